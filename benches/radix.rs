@@ -0,0 +1,73 @@
+//! Benchmarks for converting large numbers to and from decimal strings. Doubling the number of
+//! digits should less than quadruple the time taken.
+
+use copyspan::Span;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use mulch::{
+    error::PartialSpanned,
+    gc::{
+        GarbageCollector,
+        math::{DecimalFormat, GCNumber},
+    },
+};
+
+const NUM_DIGITS: [usize; 4] = [2_500, 5_000, 10_000, 20_000];
+
+/// Generates a pseudo-random string of decimal digits
+fn digits(len: usize) -> String {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+
+    (0..len)
+        .map(|i| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+
+            let digit = (state % 10) as u8;
+
+            // Avoid a leading zero
+            char::from(b'0' + if i == 0 { digit.max(1) } else { digit })
+        })
+        .collect()
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_decimal");
+
+    for len in NUM_DIGITS {
+        let literal = digits(len);
+
+        group.bench_with_input(BenchmarkId::from_parameter(len), &literal, |b, literal| {
+            b.iter(|| {
+                let gc = GarbageCollector::new();
+
+                GCNumber::parse_from_literal(&gc, PartialSpanned(literal, Span::from(0..0)), None)
+                    .unwrap();
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn to_string(c: &mut Criterion) {
+    let mut group = c.benchmark_group("to_decimal_string");
+
+    for len in NUM_DIGITS {
+        let gc = GarbageCollector::new();
+
+        let literal = digits(len);
+        let number =
+            GCNumber::parse_from_literal(&gc, PartialSpanned(&literal, Span::from(0..0)), None)
+                .unwrap();
+
+        group.bench_with_input(BenchmarkId::from_parameter(len), &number, |b, number| {
+            b.iter(|| unsafe { number.to_decimal_string(&gc, DecimalFormat::Fraction) })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, parse, to_string);
+criterion_main!(benches);
@@ -0,0 +1,124 @@
+//! Benchmarks for large sets. Sets with more than a few attributes are stored in a hash trie, so
+//! lookups should take roughly the same time regardless of the size of the set, and merging a few
+//! attributes into a large set should only copy the paths to them.
+
+use std::collections::BTreeMap;
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use mulch::Evaluator;
+
+const NUM_ATTRIBUTES: [usize; 3] = [100, 1_000, 10_000];
+
+/// Generates the attributes of a set, similarly to a generated config
+fn attributes(len: usize) -> BTreeMap<String, usize> {
+    (0..len)
+        .map(|i| (format!("service_{i}_replica_count"), i))
+        .collect()
+}
+
+fn construction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("set_construction");
+
+    for len in NUM_ATTRIBUTES {
+        let attributes = attributes(len);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(len),
+            &attributes,
+            |b, attributes| {
+                b.iter_batched(
+                    Evaluator::new,
+                    |mut evaluator| evaluator.define("set", attributes).unwrap(),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("set_lookup");
+
+    for len in NUM_ATTRIBUTES {
+        let attributes = attributes(len);
+
+        let mut evaluator = Evaluator::new();
+        evaluator.define("set", &attributes).unwrap();
+        let set = evaluator.eval_str("lookup.mulch", "set").unwrap();
+
+        // Every 7th name, so that the lookups are spread over the whole set
+        let names: Vec<&String> = attributes.keys().step_by(7).take(100).collect();
+
+        group.bench_with_input(BenchmarkId::from_parameter(len), &names, |b, names| {
+            b.iter(|| {
+                for name in names {
+                    set.get(name).unwrap();
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn merge(c: &mut Criterion) {
+    let mut group = c.benchmark_group("set_merge");
+
+    for len in NUM_ATTRIBUTES {
+        let lhs = attributes(len);
+        // Overrides a tenth of the attributes of `lhs` and adds as many new ones
+        let rhs: BTreeMap<String, usize> = attributes(len + len / 10)
+            .into_iter()
+            .skip(len - len / 10)
+            .collect();
+
+        let mut evaluator = Evaluator::new();
+        evaluator.define("lhs", &lhs).unwrap();
+        evaluator.define("rhs", &rhs).unwrap();
+
+        let mut run = 0;
+
+        group.bench_function(BenchmarkId::from_parameter(len), |b| {
+            b.iter(|| {
+                run += 1;
+
+                evaluator
+                    .eval_str(&format!("merge_{run}.mulch"), "merge(lhs, rhs)")
+                    .unwrap()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn override_attribute(c: &mut Criterion) {
+    let mut group = c.benchmark_group("set_override");
+
+    for len in NUM_ATTRIBUTES {
+        let mut evaluator = Evaluator::new();
+        evaluator.define("set", &attributes(len)).unwrap();
+
+        let mut run = 0;
+
+        group.bench_function(BenchmarkId::from_parameter(len), |b| {
+            b.iter(|| {
+                run += 1;
+
+                evaluator
+                    .eval_str(
+                        &format!("override_{run}.mulch"),
+                        "merge(set, { service_0_replica_count = 2; })",
+                    )
+                    .unwrap()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, construction, lookup, merge, override_attribute);
+criterion_main!(benches);
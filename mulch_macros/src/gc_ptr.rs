@@ -8,11 +8,19 @@ use syn::{
 use crate::util::FieldName;
 
 pub fn derive_gc_ptr(item: DeriveInput) -> TokenStream {
-    let body = match &item.data {
-        syn::Data::Struct(data_struct) => gcptr_fn_body_struct(data_struct),
-        syn::Data::Enum(data_enum) => gcptr_fn_body_enum(data_enum),
+    let (body, trace_body, verify_body) = match &item.data {
+        syn::Data::Struct(data_struct) => (
+            gcptr_fn_body_struct(data_struct),
+            trace_fn_body_struct(data_struct),
+            verify_fn_body_struct(data_struct),
+        ),
+        syn::Data::Enum(data_enum) => (
+            gcptr_fn_body_enum(data_enum),
+            trace_fn_body_enum(data_enum),
+            verify_fn_body_enum(data_enum),
+        ),
         syn::Data::Union(_) => {
-            quote! {compile_error!("`derive(GCPtr)` is not compatible with unions")}
+            return quote! {compile_error!("`derive(GCPtr)` is not compatible with unions")};
         }
     };
 
@@ -48,6 +56,17 @@ pub fn derive_gc_ptr(item: DeriveInput) -> TokenStream {
                 #body
             }
 
+            #[allow(unused_variables)]
+            unsafe fn gc_trace(self, tracer: &mut ::mulch::gc::dump::HeapTracer) {
+                #trace_body
+            }
+
+            #[cfg(feature = "verify-heap")]
+            #[allow(unused_variables)]
+            unsafe fn gc_verify(self, verifier: &mut ::mulch::gc::verify::HeapVerifier) {
+                #verify_body
+            }
+
             #gc_entry_functions
         }
     }
@@ -110,6 +129,146 @@ fn gcptr_fn_body_enum(data_enum: &DataEnum) -> TokenStream {
     }}
 }
 
+fn trace_fn_body_struct(data_struct: &DataStruct) -> TokenStream {
+    let fields: Vec<_> = match &data_struct.fields {
+        Fields::Named(fields_named) => fields_named
+            .named
+            .iter()
+            .map(|f| {
+                let field_name = f.ident.as_ref().unwrap();
+                quote! {self.#field_name}
+            })
+            .collect(),
+        Fields::Unnamed(fields_unnamed) => (0..fields_unnamed.unnamed.len())
+            .map(|i| {
+                let index = Index::from(i);
+                quote! {self.#index}
+            })
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+
+    quote! {
+        #(unsafe { ::mulch::gc::GCPtr::gc_trace(#fields, tracer) };)*
+    }
+}
+
+fn trace_fn_body_enum(data_enum: &DataEnum) -> TokenStream {
+    let per_variant = data_enum.variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
+
+        let (pattern, field_names) = match &variant.fields {
+            Fields::Named(fields_named) => {
+                let field_names: Vec<_> = fields_named
+                    .named
+                    .iter()
+                    .map(|field| field.ident.clone().unwrap())
+                    .collect();
+
+                (quote! {{#(#field_names),*}}, field_names)
+            }
+            Fields::Unnamed(fields_unnamed) => {
+                let field_names: Vec<_> = (0..fields_unnamed.unnamed.len())
+                    .map(|i| format_ident!("v{i}"))
+                    .collect();
+
+                (quote! {(#(#field_names),*)}, field_names)
+            }
+            Fields::Unit => (quote! {}, Vec::new()),
+        };
+
+        quote! {
+            Self::#variant_name #pattern => {
+                #(unsafe { ::mulch::gc::GCPtr::gc_trace(#field_names, tracer) };)*
+            }
+        }
+    });
+
+    quote! {match self {
+        #(#per_variant),*
+    }}
+}
+
+/// Verifies a field. `path` is appended to the path of the object in verification errors.
+fn verify_field(path: String, value: TokenStream) -> TokenStream {
+    quote! {
+        verifier.segment(#path, |verifier| unsafe {
+            ::mulch::gc::GCPtr::gc_verify(#value, verifier)
+        });
+    }
+}
+
+fn verify_fn_body_struct(data_struct: &DataStruct) -> TokenStream {
+    match &data_struct.fields {
+        Fields::Named(fields_named) => {
+            let per_field = fields_named.named.iter().map(|f| {
+                let field_name = f.ident.as_ref().unwrap();
+
+                verify_field(format!(".{field_name}"), quote! {self.#field_name})
+            });
+
+            quote! {#(#per_field)*}
+        }
+        Fields::Unnamed(fields_unnamed) => {
+            let per_field = (0..fields_unnamed.unnamed.len()).map(|i| {
+                let index = Index::from(i);
+
+                verify_field(format!(".{i}"), quote! {self.#index})
+            });
+
+            quote! {#(#per_field)*}
+        }
+        Fields::Unit => quote! {},
+    }
+}
+
+fn verify_fn_body_enum(data_enum: &DataEnum) -> TokenStream {
+    let per_variant = data_enum.variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
+
+        let (pattern, per_field) = match &variant.fields {
+            Fields::Named(fields_named) => {
+                let field_names = fields_named
+                    .named
+                    .iter()
+                    .map(|field| field.ident.as_ref().unwrap());
+
+                let per_field = field_names
+                    .clone()
+                    .map(|field_name| verify_field(format!(".{field_name}"), quote! {#field_name}))
+                    .collect::<Vec<_>>();
+
+                (quote! {{#(#field_names),*}}, per_field)
+            }
+            Fields::Unnamed(fields_unnamed) => {
+                let field_names =
+                    (0..fields_unnamed.unnamed.len()).map(|i| format_ident!("v{i}"));
+
+                let per_field = field_names
+                    .clone()
+                    .enumerate()
+                    .map(|(i, field_name)| verify_field(format!(".{i}"), quote! {#field_name}))
+                    .collect::<Vec<_>>();
+
+                (quote! {(#(#field_names),*)}, per_field)
+            }
+            Fields::Unit => (quote! {}, Vec::new()),
+        };
+
+        let segment = format!("::{variant_name}");
+
+        quote! {
+            Self::#variant_name #pattern => verifier.segment(#segment, |verifier| {
+                #(#per_field)*
+            })
+        }
+    });
+
+    quote! {match self {
+        #(#per_variant),*
+    }}
+}
+
 /// Calculates `GCPtr::MSB_RESERVED` for a type
 fn calculate_msb_reserved(item: &DeriveInput) -> Result<TokenStream, TokenStream> {
     let msb_reserved_attr = item.attrs.iter().find(|a| {
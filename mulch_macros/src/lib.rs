@@ -9,6 +9,7 @@ mod gc_eq;
 mod gc_fn;
 mod gc_project;
 mod gc_ptr;
+mod mulch_builtin;
 mod parser;
 
 mod util;
@@ -78,6 +79,48 @@ pub fn gc_fn(
         .into()
 }
 
+/// Turns a Rust function into a function that can be called from `mulch`. This generates a
+/// `mulch::eval::Builtin` static with the upper-case name of the function (eg `STRING_LENGTH` for
+/// `string_length`).
+///
+/// The function can take the following parameters in any order:
+/// - `&GCCtx`: the garbage-collection context. This cannot be used to trigger a GC cycle.
+/// - `FullSpan`: the span of the function call
+/// - `&str`: a string argument. The string is copied out of the garbage-collected heap, so the
+///   function can allocate while it is borrowed.
+/// - Any type that implements `mulch::eval::BuiltinArg` (eg `GC<'c, GCNumber>` or
+///   `GC<'c, MValue>`)
+///
+/// Every parameter other than the context and the span is a `mulch` argument. Calls with the wrong
+/// number of arguments or arguments with the wrong types produce a diagnostic before the function
+/// is called.
+///
+/// The function must return `DResult<GC<'c, T>>` where `GC<'c, T>` can be converted into
+/// `GC<'c, MValue>`.
+///
+/// # Attributes
+/// - `name = "..."`
+///   - Sets the name of the variable that the function is bound to. This defaults to the name of
+///     the Rust function.
+///
+/// ```
+/// #[mulch_builtin]
+/// fn string_length<'c>(ctx: &'c GCCtx, string: &str) -> DResult<GC<'c, GCNumber>> {
+///     let number = GCNumber::from_u128(ctx, string.chars().count() as u128);
+///
+///     Ok(unsafe { GC::new(ctx, number) })
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn mulch_builtin(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    mulch_builtin::mulch_builtin_impl(attr.into(), item.into())
+        .unwrap_or_else(|err| err.into_compile_error())
+        .into()
+}
+
 /// Derives the `GCDebug` trait. This is the equivalent to the standard library `Debug` trait except
 /// for garbage-collected objects.
 ///
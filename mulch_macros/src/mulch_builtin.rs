@@ -0,0 +1,145 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{FnArg, Ident, ItemFn, LitStr, Type, meta::ParseNestedMeta};
+
+/// How a parameter of a builtin is filled in
+enum Param {
+    /// The garbage-collection context (`&GCCtx`)
+    Context,
+    /// The span of the function call (`FullSpan`)
+    CallSpan,
+    /// A string argument (`&str`). This is converted to a `String` with `BuiltinArg` and passed by
+    /// reference, because strings in the heap can move when the function allocates.
+    Str,
+    /// Any other argument. These are converted with `BuiltinArg`.
+    Arg,
+}
+
+pub fn mulch_builtin_impl(attr: TokenStream, input: TokenStream) -> syn::Result<TokenStream> {
+    let input = syn::parse2::<ItemFn>(input)?;
+
+    let fn_name = &input.sig.ident;
+    let mut name = LitStr::new(&fn_name.to_string(), fn_name.span());
+
+    let attr_parser = syn::meta::parser(|meta: ParseNestedMeta| {
+        if meta.path.is_ident("name") {
+            name = meta.value()?.parse()?;
+            Ok(())
+        } else {
+            Err(meta.error("unsupported #[mulch_builtin] argument"))
+        }
+    });
+
+    syn::parse::Parser::parse2(attr_parser, attr)?;
+
+    if let Some(asyncness) = &input.sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "#[mulch_builtin] does not support async functions",
+        ));
+    }
+
+    if let Some(variadic) = &input.sig.variadic {
+        return Err(syn::Error::new_spanned(
+            variadic,
+            "#[mulch_builtin] does not support variadic functions",
+        ));
+    }
+
+    let params = input
+        .sig
+        .inputs
+        .iter()
+        .map(|input| match input {
+            FnArg::Receiver(receiver) => Err(syn::Error::new_spanned(
+                receiver,
+                "#[mulch_builtin] cannot be used on methods",
+            )),
+            FnArg::Typed(pat_type) => Ok(classify_param(&pat_type.ty)),
+        })
+        .collect::<syn::Result<Vec<Param>>>()?;
+
+    let mut arity = 0usize;
+    let mut conversions = Vec::new();
+    let mut call_args = Vec::new();
+
+    for param in &params {
+        match param {
+            Param::Context => call_args.push(quote! {ctx}),
+            Param::CallSpan => call_args.push(quote! {span}),
+            Param::Str => {
+                let arg = format_ident!("arg{arity}");
+
+                conversions.push(quote! {
+                    let #arg: ::std::string::String =
+                        ::mulch::eval::BuiltinArg::from_arg(args[#arity])?;
+                });
+                call_args.push(quote! {&#arg});
+
+                arity += 1;
+            }
+            Param::Arg => {
+                let arg = format_ident!("arg{arity}");
+
+                conversions.push(quote! {
+                    let #arg = ::mulch::eval::BuiltinArg::from_arg(args[#arity])?;
+                });
+                call_args.push(quote! {#arg});
+
+                arity += 1;
+            }
+        }
+    }
+
+    let vis = &input.vis;
+    let static_name = Ident::new(&fn_name.to_string().to_uppercase(), fn_name.span());
+    let static_doc = format!("The mulch builtin for [`{fn_name}`]");
+
+    Ok(quote! {
+        #input
+
+        #[doc = #static_doc]
+        #vis static #static_name: ::mulch::eval::Builtin = ::mulch::eval::Builtin {
+            name: #name,
+            arity: #arity,
+            function: {
+                #[allow(unused_variables)]
+                fn builtin<'c>(
+                    ctx: &'c ::mulch::gc::safety::GCCtx<'_>,
+                    args: &[::mulch::error::Spanned<::mulch::gc::safety::GC<'c, ::mulch::eval::MValue>>],
+                    span: ::mulch::error::FullSpan,
+                ) -> ::mulch::error::DResult<::mulch::gc::safety::GC<'c, ::mulch::eval::MValue>> {
+                    #(#conversions)*
+
+                    #fn_name(#(#call_args),*).map(::core::convert::Into::into)
+                }
+
+                builtin
+            },
+        };
+    })
+}
+
+fn classify_param(ty: &Type) -> Param {
+    match ty {
+        Type::Reference(reference) if is_path_ending_in(&reference.elem, "GCCtx") => {
+            Param::Context
+        }
+        Type::Reference(reference) if is_path_ending_in(&reference.elem, "str") => Param::Str,
+        ty if is_path_ending_in(ty, "FullSpan") => Param::CallSpan,
+        _ => Param::Arg,
+    }
+}
+
+fn is_path_ending_in(ty: &Type, name: &str) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+
+    path.qself.is_none()
+        && path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == name)
+}
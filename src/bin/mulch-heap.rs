@@ -0,0 +1,58 @@
+//! Prints the objects that retain the most memory in a heap dump.
+//!
+//! Usage: `mulch-heap <dump> [count]`
+
+use std::{fs::File, io::BufReader, process::ExitCode};
+
+use mulch::gc::dump::HeapDump;
+
+const DEFAULT_COUNT: usize = 20;
+
+pub fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+
+    let (Some(path), count, None) = (args.next(), args.next(), args.next()) else {
+        eprintln!("usage: mulch-heap <dump> [count]");
+        return ExitCode::FAILURE;
+    };
+
+    let count = match count.map(|count| count.parse()) {
+        None => DEFAULT_COUNT,
+        Some(Ok(count)) => count,
+        Some(Err(err)) => {
+            eprintln!("invalid count: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let dump = match File::open(&path).and_then(|file| HeapDump::read_from(BufReader::new(file))) {
+        Ok(dump) => dump,
+        Err(err) => {
+            eprintln!("failed to read `{path}`: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!(
+        "{} objects ({} bytes) reachable from {} roots",
+        dump.objects.len(),
+        dump.total_bytes(),
+        dump.roots.len()
+    );
+    println!();
+    println!("{:>12} {:>12} {:>18}  type", "retained", "shallow", "block");
+
+    for retainer in dump.largest_retainers(count) {
+        let object = &dump.objects[retainer.object];
+
+        println!(
+            "{:>12} {:>12} {:>#18x}  {}",
+            retainer.retained_bytes,
+            retainer.shallow_bytes,
+            object.block,
+            dump.type_name(object)
+        );
+    }
+
+    ExitCode::SUCCESS
+}
@@ -38,6 +38,7 @@ impl Debug for Diagnostic {
             .field("code", &self.0.code)
             .field("message", &self.0.message)
             .field("hints", &self.0.hints)
+            .field("notes", &self.0.notes)
             .finish()
     }
 }
@@ -72,6 +73,8 @@ struct RawDiagnostic {
     code: &'static str,
     message: String,
     hints: Vec<Hint>,
+    /// Additional information that is shown below the source code
+    notes: Vec<String>,
 }
 
 impl Diagnostic {
@@ -81,8 +84,25 @@ impl Diagnostic {
             code,
             message,
             hints,
+            notes: Vec::new(),
+        }))
+    }
+
+    pub fn warning(code: &'static str, message: String, hints: Vec<Hint>) -> Self {
+        Self(Box::new(RawDiagnostic {
+            severity: Severity::Warning,
+            code,
+            message,
+            hints,
+            notes: Vec::new(),
         }))
     }
+
+    /// Adds a note that is shown below the source code
+    pub fn with_note(mut self, note: String) -> Self {
+        self.0.notes.push(note);
+        self
+    }
 }
 
 impl From<Hint> for Label<usize> {
@@ -104,7 +124,7 @@ impl From<Diagnostic> for CodespanDiagnostic<usize> {
             code: Some(value.code.to_owned()),
             message: value.message,
             labels: value.hints.into_iter().map(Label::from).collect(),
-            notes: Vec::new(),
+            notes: value.notes,
         }
     }
 }
@@ -171,4 +191,26 @@ macro_rules! error {
 
 pub(crate) use error;
 
+macro_rules! warning {
+    (
+        $code:literal,
+        $msg:expr,
+        [
+            $(
+                {$hintmsg:expr, $span:expr, $type:ident}
+            ),*$(,)?
+        ]$(,)?
+    ) => {
+        $crate::error::Diagnostic::warning(
+            $code,
+            String::from($msg),
+            vec![$(
+                $crate::error::Hint::$type(String::from($hintmsg), $span)
+            ),*]
+        )
+    };
+}
+
+pub(crate) use warning;
+
 use crate::error::parse::PDResult;
@@ -85,6 +85,7 @@ impl ParseDiagnostic {
             code: self.0.code,
             message: self.0.message,
             hints,
+            notes: Vec::new(),
         }))
     }
 }
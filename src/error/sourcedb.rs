@@ -67,6 +67,11 @@ impl SourceDB {
             })
     }
 
+    /// Adds a source and returns its index.
+    ///
+    /// If a source with the same path has already been added, the path refers to the new source
+    /// from now on, but the old source keeps its index so that existing spans stay valid. Adding
+    /// the same source with the same path again returns the existing index.
     pub fn add(&self, path: OsString, src: String) -> usize {
         let sources = unsafe { &mut *self.sources.get() };
         let filename_to_index = unsafe { &mut *self.filename_to_index.get() };
@@ -74,17 +79,40 @@ impl SourceDB {
 
         let index = sources.len();
 
-        if filename_to_index.get(&*path).is_some() {
-            panic!("Path {path:?} is already in SourceDB");
-        }
+        let is_unchanged = |existing: usize| {
+            let existing = &sources[existing].src;
 
-        let path = util::RawOsString::from(path);
+            let existing = unsafe { slice::from_raw_parts(existing.ptr, existing.len) };
 
-        index_to_filename.push(util::RawOsStr {
-            data: path.0.ptr,
-            len: path.0.len,
-        });
-        filename_to_index.insert(path, index);
+            existing == src.as_bytes()
+        };
+
+        let name = match filename_to_index.get_key_value(&*path) {
+            Some((_, &existing)) if is_unchanged(existing) => return existing,
+            Some((name, _)) => {
+                let name = util::RawOsStr {
+                    data: name.0.ptr,
+                    len: name.0.len,
+                };
+
+                *filename_to_index.get_mut(&*path).unwrap() = index;
+
+                name
+            }
+            None => {
+                let path = util::RawOsString::from(path);
+                let name = util::RawOsStr {
+                    data: path.0.ptr,
+                    len: path.0.len,
+                };
+
+                filename_to_index.insert(path, index);
+
+                name
+            }
+        };
+
+        index_to_filename.push(name);
 
         let line_starts: Vec<usize> = codespan_reporting::files::line_starts(&src).collect();
 
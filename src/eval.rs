@@ -1,23 +1,38 @@
 use mulch_macros::{GCDebug, GCProject, GCPtr, gc_fn};
 
-mod error;
+mod access;
+mod builtin;
+mod de;
+pub(crate) mod error;
 mod lazyvalue;
 mod list;
 mod scope;
+mod ser;
 mod set;
 
+#[cfg(test)]
+mod test;
+
+pub use access::AccessError;
+pub(crate) use builtin::builtin_variables;
+pub use builtin::{Builtin, BuiltinArg, BuiltinFn, BuiltinFunction};
+pub use de::{DeserializeError, from_value};
+pub(crate) use lazyvalue::LazyValue;
 pub use scope::Scope;
+pub use ser::{SerializeError, to_set, to_value};
 pub use set::Set;
 
 use crate::{
     error::{DResult, Spanned},
     eval::{
+        builtin::evaluate_function_call,
         list::evaluate_list,
+        scope::evaluate_variable,
         set::{evaluate_member_access, evaluate_set},
     },
     gc::{
-        GCNumber, GCString, GCVec,
-        safety::{GC, Projected, gc_args, rebind},
+        GCNumber, GCPersistentVec, GCString,
+        safety::{GC, Projected, gc_args, rebind, root},
     },
     parser::ast,
 };
@@ -30,8 +45,24 @@ pub fn evaluate<'c>(
     let ast_span = ast.1;
     let ast = ast.0;
 
+    if ctx.heap_limit_exceeded() {
+        // The expression and its scope are rooted so that they are included in the dump
+        let _ast = root!(ctx, ast);
+        let _scope = root!(ctx, scope);
+
+        let err = error::heap_limit_exceeded(ast_span, ctx.max_heap_size().unwrap());
+
+        // SAFETY: evaluation never runs during a garbage-collection cycle
+        return Err(match unsafe { ctx.dump_heap_on_limit() } {
+            Ok(()) => err,
+            Err(dump_err) => err.with_note(format!("warning: {dump_err}")),
+        });
+    }
+
     match ast.project() {
-        Projected::<ast::Expression>::Variable(_) => todo!(),
+        Projected::<ast::Expression>::Variable(variable) => {
+            evaluate_variable(gc_args!(ctx, Spanned(variable, ast_span).into(), scope))
+        }
         Projected::<ast::Expression>::StringLiteral(string_literal) => {
             let string_literal = rebind!(ctx, string_literal);
             Ok(string_literal.project().0.into())
@@ -46,7 +77,9 @@ pub fn evaluate<'c>(
         Projected::<ast::Expression>::BinaryOperation(_binary_operation) => todo!(),
         Projected::<ast::Expression>::UnaryOperation(_unary_operation) => todo!(),
         Projected::<ast::Expression>::MethodCall(_method_call) => todo!(),
-        Projected::<ast::Expression>::FunctionCall(_function_call) => todo!(),
+        Projected::<ast::Expression>::FunctionCall(function_call) => evaluate_function_call(
+            gc_args!(ctx, Spanned(function_call, ast_span).into(), scope),
+        ),
         Projected::<ast::Expression>::MemberAccess(member_access) => evaluate_member_access(
             gc_args!(ctx, Spanned(member_access, ast_span).into(), scope),
         ),
@@ -68,9 +101,11 @@ pub enum MValue {
     #[debug_direct]
     Number(GCNumber),
     #[debug_direct]
-    List(GCVec<MValue>),
+    List(GCPersistentVec<MValue>),
     #[debug_direct]
     Set(Set),
+    #[debug_direct]
+    Builtin(BuiltinFunction),
 }
 
 impl<'c> From<GC<'c, GCString>> for GC<'c, MValue> {
@@ -85,8 +120,20 @@ impl<'c> From<GC<'c, GCNumber>> for GC<'c, MValue> {
     }
 }
 
-impl<'c> From<GC<'c, GCVec<MValue>>> for GC<'c, MValue> {
-    fn from(value: GC<'c, GCVec<MValue>>) -> Self {
+impl<'c> From<GC<'c, GCPersistentVec<MValue>>> for GC<'c, MValue> {
+    fn from(value: GC<'c, GCPersistentVec<MValue>>) -> Self {
         Projected::<MValue>::List(value).into()
     }
 }
+
+impl<'c> From<GC<'c, Set>> for GC<'c, MValue> {
+    fn from(value: GC<'c, Set>) -> Self {
+        Projected::<MValue>::Set(value).into()
+    }
+}
+
+impl<'c> From<GC<'c, BuiltinFunction>> for GC<'c, MValue> {
+    fn from(value: GC<'c, BuiltinFunction>) -> Self {
+        Projected::<MValue>::Builtin(value).into()
+    }
+}
@@ -0,0 +1,255 @@
+//! Typed accessors and attribute path queries for evaluated values
+
+use std::fmt::Display;
+
+use mulch_macros::gc_fn;
+
+use crate::{
+    error::{Diagnostic, FullSpan, Spanned},
+    eval::{self, MValue, Set, lazyvalue::LazyValue},
+    gc::{
+        GCNumber, GCPersistentVec, GCString,
+        safety::{GC, Projected, gc_args},
+    },
+};
+
+/// An error that occurs while accessing part of a value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccessError {
+    /// The value has a different type than the one that was requested
+    WrongType {
+        expected: &'static str,
+        found: &'static str,
+        span: FullSpan,
+    },
+    /// A set does not have the requested attribute
+    NoAttribute { name: String, span: FullSpan },
+    /// A list is shorter than the requested index
+    IndexOutOfBounds {
+        index: usize,
+        len: usize,
+        span: FullSpan,
+    },
+    /// An attribute could not be evaluated
+    Eval(Diagnostic),
+}
+
+impl AccessError {
+    /// The span of the offending value. This is `None` for evaluation errors, which carry their
+    /// own spans.
+    pub fn span(&self) -> Option<FullSpan> {
+        match self {
+            AccessError::WrongType { span, .. }
+            | AccessError::NoAttribute { span, .. }
+            | AccessError::IndexOutOfBounds { span, .. } => Some(*span),
+            AccessError::Eval(_) => None,
+        }
+    }
+}
+
+impl From<AccessError> for Diagnostic {
+    fn from(value: AccessError) -> Self {
+        match value {
+            AccessError::WrongType {
+                expected,
+                found,
+                span,
+            } => eval::error::wrong_type(span, expected, found),
+            AccessError::NoAttribute { name, span } => {
+                eval::error::no_attribute_with_name(span, &name)
+            }
+            AccessError::IndexOutOfBounds { index, len, span } => {
+                eval::error::index_out_of_bounds(span, index, len)
+            }
+            AccessError::Eval(diagnostic) => diagnostic,
+        }
+    }
+}
+
+impl Display for AccessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessError::WrongType {
+                expected, found, ..
+            } => write!(f, "expected {expected}, got {found}"),
+            AccessError::NoAttribute { name, .. } => {
+                write!(f, "no attribute found with name `{name}`")
+            }
+            AccessError::IndexOutOfBounds { index, len, .. } => {
+                write!(
+                    f,
+                    "index {index} is out of bounds for a list of length {len}"
+                )
+            }
+            AccessError::Eval(_) => write!(f, "failed to evaluate value"),
+        }
+    }
+}
+
+impl std::error::Error for AccessError {}
+
+/// A single step of an attribute path
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PathSegment<'p> {
+    Attribute(&'p str),
+    Index(usize),
+}
+
+/// Splits a path like `a.b[3].c` into its segments
+///
+/// # Panics
+/// Panics if the path is malformed
+fn parse_path(path: &str) -> Vec<PathSegment<'_>> {
+    let malformed = || -> ! { panic!("malformed attribute path `{path}`") };
+
+    let mut segments = Vec::new();
+    let mut rest = path;
+
+    while !rest.is_empty() {
+        if let Some(index) = rest.strip_prefix('[') {
+            let (index, remaining) = index.split_once(']').unwrap_or_else(|| malformed());
+            let index = index.parse().unwrap_or_else(|_| malformed());
+
+            segments.push(PathSegment::Index(index));
+            rest = remaining;
+        } else {
+            // Attributes after the first segment are preceded by a dot
+            if !segments.is_empty() {
+                rest = rest.strip_prefix('.').unwrap_or_else(|| malformed());
+            }
+
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+
+            if end == 0 {
+                malformed();
+            }
+
+            segments.push(PathSegment::Attribute(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+
+    segments
+}
+
+impl MValue {
+    /// Gets the value at an attribute path (eg `a.b[3].c`). `span` should be the span of the
+    /// expression that `value` was evaluated from. Only the attributes along the path are
+    /// evaluated.
+    ///
+    /// List elements don't have their own spans, so they are given the span of their list.
+    ///
+    /// # Panics
+    /// Panics if `path` is malformed
+    #[gc_fn]
+    pub fn get<'gc, 'c>(
+        ctx: &'c mut gc!('gc, value: Self),
+        span: FullSpan,
+        path: &str,
+    ) -> Result<Spanned<GC<'c, MValue>>, AccessError> {
+        let mut span = span;
+        let mut value = value.raw();
+
+        for segment in parse_path(path) {
+            // SAFETY: `value` is either rooted by the caller or was just returned by
+            // `get_or_evaluate`, and nothing has been allocated since
+            let current = Spanned(unsafe { GC::new(ctx, value) }, span);
+
+            match segment {
+                PathSegment::Attribute(name) => {
+                    let Some(lazy_value) = current.as_set()?.get_attr(name) else {
+                        return Err(AccessError::NoAttribute {
+                            name: name.to_owned(),
+                            span,
+                        });
+                    };
+
+                    span = lazy_value.span();
+                    value = LazyValue::get_or_evaluate(gc_args!(ctx, lazy_value), span)
+                        .map_err(AccessError::Eval)?
+                        .raw();
+                }
+                PathSegment::Index(index) => {
+                    let list = current.as_list()?;
+
+                    let Some(element) = list.get(index) else {
+                        return Err(AccessError::IndexOutOfBounds {
+                            index,
+                            len: list.len(),
+                            span,
+                        });
+                    };
+
+                    value = element.raw();
+                }
+            }
+        }
+
+        // SAFETY: see above
+        Ok(Spanned(unsafe { GC::new(ctx, value) }, span))
+    }
+}
+
+impl<'c> GC<'c, MValue> {
+    /// The name of this value's type as it is written in error messages
+    pub fn type_name(self) -> &'static str {
+        match self.project() {
+            Projected::<MValue>::String(_) => "string",
+            Projected::<MValue>::Number(_) => "number",
+            Projected::<MValue>::List(_) => "list",
+            Projected::<MValue>::Set(_) => "set",
+            Projected::<MValue>::Builtin(_) => "function",
+        }
+    }
+}
+
+impl<'c> Spanned<GC<'c, MValue>> {
+    fn wrong_type(self, expected: &'static str) -> AccessError {
+        AccessError::WrongType {
+            expected,
+            found: self.0.type_name(),
+            span: self.1,
+        }
+    }
+
+    /// Gets this value as a string. Its contents can be borrowed with [`GC::read`].
+    pub fn as_str(self) -> Result<GC<'c, GCString>, AccessError> {
+        match self.0.project() {
+            Projected::<MValue>::String(string) => Ok(string),
+            _ => Err(self.wrong_type("string")),
+        }
+    }
+
+    pub fn as_number(self) -> Result<GC<'c, GCNumber>, AccessError> {
+        match self.0.project() {
+            Projected::<MValue>::Number(number) => Ok(number),
+            _ => Err(self.wrong_type("number")),
+        }
+    }
+
+    /// Gets this value as a list index, which must be a non-negative integer
+    pub fn as_index(self) -> Result<usize, AccessError> {
+        self.as_number()?
+            .to_u64()
+            .and_then(|index| usize::try_from(index).ok())
+            .ok_or(AccessError::WrongType {
+                expected: "non-negative integer",
+                found: "number",
+                span: self.1,
+            })
+    }
+
+    pub fn as_list(self) -> Result<GC<'c, GCPersistentVec<MValue>>, AccessError> {
+        match self.0.project() {
+            Projected::<MValue>::List(list) => Ok(list),
+            _ => Err(self.wrong_type("list")),
+        }
+    }
+
+    pub fn as_set(self) -> Result<GC<'c, Set>, AccessError> {
+        match self.0.project() {
+            Projected::<MValue>::Set(set) => Ok(set),
+            _ => Err(self.wrong_type("set")),
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Functions that are implemented in Rust
+
+use mulch_macros::gc_fn;
+
+use crate::{
+    error::{DResult, FullSpan, PartialSpanned, Spanned},
+    eval::{self, AccessError, MValue, Scope, Set, list::evaluate_all},
+    gc::{
+        GCNumber, GCPersistentVec, GCString, GCVec,
+        safety::{GC, GCCtx, Projected, gc_args, rebind, root},
+    },
+    parser::ast,
+};
+
+mod functions;
+
+/// The signature of the Rust function behind a [`Builtin`]. The arguments have already been
+/// evaluated and their count has been checked. The span is the span of the function call.
+pub type BuiltinFn = for<'c, 'gc> fn(
+    &'c GCCtx<'gc>,
+    &[Spanned<GC<'c, MValue>>],
+    FullSpan,
+) -> DResult<GC<'c, MValue>>;
+
+/// A function that is implemented in Rust. These are created with
+/// [`#[mulch_builtin]`](mulch_macros::mulch_builtin).
+pub struct Builtin {
+    /// The name of the variable that the function is bound to
+    pub name: &'static str,
+    /// The number of arguments that the function takes
+    pub arity: usize,
+    pub function: BuiltinFn,
+}
+
+/// A reference to a [`Builtin`]. This is how builtins are stored in [`MValue`]s.
+#[derive(Clone, Copy)]
+pub struct BuiltinFunction(pub &'static Builtin);
+
+impl std::fmt::Debug for BuiltinFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<builtin {}>", self.0.name)
+    }
+}
+
+impl PartialEq for BuiltinFunction {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+/// A type that a builtin can take as an argument. Arguments of the wrong type produce an
+/// "expected X, got Y" error that points to the argument.
+///
+/// There is no implementation for `&str`, because a borrowed string could not outlive the `GC`
+/// that it's read from, and it would dangle if the builtin allocates (which can move the heap).
+/// [`#[mulch_builtin]`](mulch_macros::mulch_builtin) takes `&str` parameters as a `String` and
+/// passes a reference to it instead.
+pub trait BuiltinArg<'c>: Sized {
+    fn from_arg(arg: Spanned<GC<'c, MValue>>) -> Result<Self, AccessError>;
+}
+
+impl<'c> BuiltinArg<'c> for GC<'c, MValue> {
+    fn from_arg(arg: Spanned<GC<'c, MValue>>) -> Result<Self, AccessError> {
+        Ok(arg.0)
+    }
+}
+
+impl<'c> BuiltinArg<'c> for Spanned<GC<'c, MValue>> {
+    fn from_arg(arg: Spanned<GC<'c, MValue>>) -> Result<Self, AccessError> {
+        Ok(arg)
+    }
+}
+
+impl<'c> BuiltinArg<'c> for GC<'c, GCString> {
+    fn from_arg(arg: Spanned<GC<'c, MValue>>) -> Result<Self, AccessError> {
+        arg.as_str()
+    }
+}
+
+impl<'c> BuiltinArg<'c> for String {
+    fn from_arg(arg: Spanned<GC<'c, MValue>>) -> Result<Self, AccessError> {
+        arg.as_str().map(|string| string.read().to_owned())
+    }
+}
+
+impl<'c> BuiltinArg<'c> for GC<'c, GCNumber> {
+    fn from_arg(arg: Spanned<GC<'c, MValue>>) -> Result<Self, AccessError> {
+        arg.as_number()
+    }
+}
+
+impl<'c> BuiltinArg<'c> for GC<'c, GCPersistentVec<MValue>> {
+    fn from_arg(arg: Spanned<GC<'c, MValue>>) -> Result<Self, AccessError> {
+        arg.as_list()
+    }
+}
+
+impl<'c> BuiltinArg<'c> for usize {
+    fn from_arg(arg: Spanned<GC<'c, MValue>>) -> Result<Self, AccessError> {
+        arg.as_index()
+    }
+}
+
+impl<'c> BuiltinArg<'c> for GC<'c, Set> {
+    fn from_arg(arg: Spanned<GC<'c, MValue>>) -> Result<Self, AccessError> {
+        arg.as_set()
+    }
+}
+
+/// Creates a set that contains every builtin. This is used for the global scope.
+pub(super) fn global_variables<'c>(ctx: &'c GCCtx) -> GC<'c, Set> {
+    builtin_variables(ctx, functions::BUILTINS, FullSpan::new(0..0, 0))
+}
+
+/// Creates a set that binds each of `builtins` to its name.
+///
+/// # Panics
+/// Panics if two of `builtins` have the same name
+pub(crate) fn builtin_variables<'c>(
+    ctx: &'c GCCtx,
+    builtins: &[&'static Builtin],
+    span: FullSpan,
+) -> GC<'c, Set> {
+    let variables = builtins
+        .iter()
+        .map(|builtin| {
+            let name = GCString::intern(ctx, builtin.name);
+            let value: GC<MValue> = builtin.to_value(ctx);
+
+            (name, value)
+        })
+        .collect();
+
+    Set::from_values(ctx, variables, span)
+        .unwrap_or_else(|name| panic!("builtin `{}` is defined multiple times", name.read()))
+}
+
+impl Builtin {
+    fn to_value<'c>(&'static self, ctx: &'c GCCtx) -> GC<'c, MValue> {
+        GC::new_non_gc(ctx, BuiltinFunction(self)).into()
+    }
+}
+
+#[gc_fn]
+pub(super) fn evaluate_function_call<'c>(
+    ctx: &'c mut gc!(ast: Spanned<ast::FunctionCall>, scope: Scope),
+) -> DResult<GC<'c, MValue>> {
+    let ast_span = ast.project().1;
+    let file_id = ast_span.file_id;
+    let ast = ast.project().0;
+
+    let arg_asts: GC<GCVec<PartialSpanned<ast::Expression>>> =
+        ast.project().args.project().0.project().0.project().values;
+
+    let arg_spans: Vec<FullSpan> = arg_asts
+        .iter()
+        .map(|arg| FullSpan::new(arg.project().1, file_id))
+        .collect();
+
+    let arg_asts = root!(ctx, arg_asts);
+    let scope = root!(ctx, scope);
+
+    let function_ast = ast.project().function.get().with_file_id(file_id);
+    let function_span = function_ast.project().1;
+
+    let function = rebind!(
+        ctx,
+        eval::evaluate(gc_args!(ctx, function_ast, scope.get(ctx)))?
+    );
+
+    let Projected::<MValue>::Builtin(builtin) = function.project() else {
+        return Err(eval::error::wrong_type(
+            function_span,
+            "function",
+            function.type_name(),
+        ));
+    };
+
+    let builtin = builtin.raw().0;
+
+    if arg_spans.len() != builtin.arity {
+        return Err(eval::error::wrong_number_of_arguments(
+            ast_span,
+            builtin.name,
+            builtin.arity,
+            arg_spans.len(),
+        ));
+    }
+
+    let args = rebind!(
+        ctx,
+        evaluate_all(gc_args!(ctx, arg_asts.get(ctx), scope.get(ctx)), file_id)?
+    );
+
+    let args: Vec<Spanned<GC<MValue>>> = args
+        .iter()
+        .zip(arg_spans)
+        .map(|(arg, span)| Spanned(arg, span))
+        .collect();
+
+    (builtin.function)(ctx, &args, ast_span)
+}
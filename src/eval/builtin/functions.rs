@@ -0,0 +1,118 @@
+use mulch_macros::mulch_builtin;
+
+use crate::{
+    error::{DResult, FullSpan},
+    eval::{self, MValue, Set, builtin::Builtin},
+    gc::{
+        GCNumber, GCPersistentVec, GCString,
+        math::DecimalFormat,
+        safety::{GC, GCCtx},
+    },
+};
+
+/// Every builtin that is available in the global scope
+pub(super) static BUILTINS: &[&Builtin] = &[
+    &LENGTH,
+    &STRING_LENGTH,
+    &LINES,
+    &TO_STRING,
+    &ATTRIBUTE_NAMES,
+    &MERGE,
+    &APPEND,
+    &CONCAT,
+    &SLICE,
+];
+
+fn new_number<'c>(ctx: &'c GCCtx, value: usize) -> GC<'c, GCNumber> {
+    let number = GCNumber::from_u128(ctx, value as u128);
+
+    // SAFETY: `number` was just allocated
+    unsafe { GC::new(ctx, number) }
+}
+
+/// The number of elements in a list
+#[mulch_builtin]
+fn length<'c>(ctx: &'c GCCtx, list: GC<'c, GCPersistentVec<MValue>>) -> DResult<GC<'c, GCNumber>> {
+    Ok(new_number(ctx, list.len()))
+}
+
+/// The number of characters in a string
+#[mulch_builtin]
+fn string_length<'c>(ctx: &'c GCCtx, string: &str) -> DResult<GC<'c, GCNumber>> {
+    Ok(new_number(ctx, string.chars().count()))
+}
+
+/// Splits a string into its lines. Line endings are not included.
+#[mulch_builtin]
+fn lines<'c>(ctx: &'c GCCtx, string: &str) -> DResult<GC<'c, GCPersistentVec<MValue>>> {
+    let lines = string.lines().map(|line| GCString::new(ctx, line).into());
+
+    Ok(GCPersistentVec::from_iter_and_len(
+        ctx,
+        lines,
+        string.lines().count(),
+    ))
+}
+
+/// Formats a number as a decimal string
+#[mulch_builtin]
+fn to_string<'c>(ctx: &'c GCCtx, number: GC<'c, GCNumber>) -> DResult<GC<'c, GCString>> {
+    // SAFETY: `number` is valid because it's wrapped in `GC`
+    let string = unsafe {
+        number
+            .raw()
+            .to_decimal_string(number.gc(), DecimalFormat::default())
+    };
+
+    Ok(GCString::new(ctx, &string))
+}
+
+/// The names of the attributes of a set, sorted
+#[mulch_builtin]
+fn attribute_names<'c>(
+    ctx: &'c GCCtx,
+    set: GC<'c, Set>,
+) -> DResult<GC<'c, GCPersistentVec<MValue>>> {
+    let names = set.attributes().map(|(name, _)| name.into());
+
+    Ok(GCPersistentVec::from_iter_and_len(ctx, names, set.len()))
+}
+
+/// Merges two sets. Attributes of the second set take precedence.
+#[mulch_builtin]
+fn merge<'c>(ctx: &'c GCCtx, lhs: GC<'c, Set>, rhs: GC<'c, Set>) -> DResult<GC<'c, Set>> {
+    Ok(Set::merge(ctx, lhs, rhs))
+}
+
+/// Appends an element to the end of a list
+#[mulch_builtin]
+fn append<'c>(
+    ctx: &'c GCCtx,
+    list: GC<'c, GCPersistentVec<MValue>>,
+    element: GC<'c, MValue>,
+) -> DResult<GC<'c, GCPersistentVec<MValue>>> {
+    Ok(list.push(ctx, element))
+}
+
+/// Concatenates two lists
+#[mulch_builtin]
+fn concat<'c>(
+    ctx: &'c GCCtx,
+    lhs: GC<'c, GCPersistentVec<MValue>>,
+    rhs: GC<'c, GCPersistentVec<MValue>>,
+) -> DResult<GC<'c, GCPersistentVec<MValue>>> {
+    Ok(lhs.concat(ctx, rhs))
+}
+
+/// The elements of a list from index `start` up to, but not including, index `end`
+#[mulch_builtin]
+fn slice<'c>(
+    ctx: &'c GCCtx,
+    span: FullSpan,
+    list: GC<'c, GCPersistentVec<MValue>>,
+    start: usize,
+    end: usize,
+) -> DResult<GC<'c, GCPersistentVec<MValue>>> {
+    list.slice(ctx, start..end)
+        .ok_or_else(|| eval::error::invalid_slice(span, start, end, list.len()))
+}
@@ -0,0 +1,643 @@
+//! Deserializes evaluated values into Rust types with `serde`
+
+use std::{cell::RefCell, fmt::Display};
+
+use mulch_macros::gc_fn;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, Expected, IntoDeserializer, MapAccess,
+    SeqAccess, Unexpected, VariantAccess, Visitor,
+};
+
+use crate::{
+    error::{Diagnostic, FullSpan},
+    eval::{self, MValue, Set, lazyvalue::LazyValue},
+    gc::{
+        GCNumber, GCPersistentVec, GarbageCollector,
+        math::{ConversionError, DecimalFormat},
+        safety::{GC, GCCtx, GCRootGuard, Projected, gc_args},
+    },
+};
+
+/// Deserializes `value` into a `T`. `span` should be the span of the expression that `value` was
+/// evaluated from.
+///
+/// Attributes of sets are only evaluated when `T` requests them, so attributes that `T` ignores
+/// are never evaluated.
+#[gc_fn]
+pub fn from_value<'c, T: DeserializeOwned>(
+    ctx: &'c mut gc!(value: MValue),
+    span: FullSpan,
+) -> Result<T, DeserializeError> {
+    let value = value.raw();
+
+    let state = State {
+        gc: **ctx,
+        ctx: RefCell::new(ctx),
+    };
+
+    let deserializer = MValueDeserializer {
+        state: &state,
+        value: PendingValue::Evaluated(value),
+        path: String::new(),
+        span,
+    };
+
+    T::deserialize(deserializer)
+}
+
+/// An error that occurs while deserializing a value. This can be converted into a [`Diagnostic`]
+/// that points to the offending value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeserializeError {
+    kind: DeserializeErrorKind,
+    /// The attribute path and span of the offending value. This is filled in by the innermost
+    /// value that the error passes through.
+    location: Option<(String, FullSpan)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum DeserializeErrorKind {
+    /// The value does not match the requested type
+    Message(String),
+    /// The value could not be evaluated
+    Eval(Diagnostic),
+}
+
+impl DeserializeError {
+    /// The attribute path of the offending value (eg `a.b[3].c`). This is empty for the root
+    /// value.
+    pub fn path(&self) -> &str {
+        self.location.as_ref().map_or("", |(path, _)| path)
+    }
+
+    /// The span of the offending value
+    pub fn span(&self) -> Option<FullSpan> {
+        self.location.as_ref().map(|(_, span)| *span)
+    }
+
+    fn at(mut self, path: &str, span: FullSpan) -> Self {
+        if self.location.is_none() {
+            self.location = Some((path.to_owned(), span));
+        }
+
+        self
+    }
+}
+
+impl From<DeserializeError> for Diagnostic {
+    fn from(value: DeserializeError) -> Self {
+        match (value.kind, value.location) {
+            (DeserializeErrorKind::Eval(diagnostic), _) => diagnostic,
+            (DeserializeErrorKind::Message(message), Some((path, span))) => {
+                eval::error::invalid_value(span, &path, &message)
+            }
+            (DeserializeErrorKind::Message(message), None) => {
+                eval::error::invalid_value_without_span(&message)
+            }
+        }
+    }
+}
+
+impl Display for DeserializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path();
+
+        match &self.kind {
+            DeserializeErrorKind::Message(message) if path.is_empty() => write!(f, "{message}"),
+            DeserializeErrorKind::Message(message) => write!(f, "`{path}`: {message}"),
+            DeserializeErrorKind::Eval(_) if path.is_empty() => {
+                write!(f, "failed to evaluate value")
+            }
+            DeserializeErrorKind::Eval(_) => write!(f, "failed to evaluate `{path}`"),
+        }
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+impl de::Error for DeserializeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self {
+            kind: DeserializeErrorKind::Message(msg.to_string()),
+            location: None,
+        }
+    }
+
+    fn invalid_type(unexp: Unexpected, exp: &dyn Expected) -> Self {
+        Self::custom(format_args!("expected {exp}, got {}", mulch_name(unexp)))
+    }
+
+    fn invalid_value(unexp: Unexpected, exp: &dyn Expected) -> Self {
+        Self::custom(format_args!("expected {exp}, got {}", mulch_name(unexp)))
+    }
+}
+
+/// Visitors describe sets and lists as maps and sequences, so this renames them
+fn mulch_name(unexp: Unexpected) -> Unexpected {
+    match unexp {
+        Unexpected::Map => Unexpected::Other("set"),
+        Unexpected::Seq => Unexpected::Other("list"),
+        unexp => unexp,
+    }
+}
+
+/// State shared by all of the deserializers
+struct State<'a, 'gc> {
+    gc: &'gc GarbageCollector,
+    ctx: RefCell<&'a mut GCCtx<'gc>>,
+}
+
+enum PendingValue<'r, 'gc> {
+    /// A value that has already been evaluated. This is only valid until the next garbage
+    /// collection cycle, so it must be used before anything else is evaluated.
+    Evaluated(MValue),
+    /// The `idx`th attribute of a set. This is only evaluated when it is needed.
+    Attribute(&'r GCRootGuard<'gc, Set>, usize),
+}
+
+struct MValueDeserializer<'s, 'a, 'r, 'gc> {
+    state: &'s State<'a, 'gc>,
+    value: PendingValue<'r, 'gc>,
+    path: String,
+    span: FullSpan,
+}
+
+impl<'s, 'a, 'gc> MValueDeserializer<'s, 'a, '_, 'gc> {
+    /// Runs `f` and attaches the path and span of this value to any errors that it returns
+    fn locate<R>(
+        self,
+        f: impl FnOnce(Self) -> Result<R, DeserializeError>,
+    ) -> Result<R, DeserializeError> {
+        let path = self.path.clone();
+        let span = self.span;
+
+        f(self).map_err(|err| err.at(&path, span))
+    }
+
+    /// Evaluates the value if it hasn't been evaluated yet
+    fn force(&self) -> Result<MValue, DeserializeError> {
+        let (set, idx) = match self.value {
+            PendingValue::Evaluated(value) => return Ok(value),
+            PendingValue::Attribute(set, idx) => (set, idx),
+        };
+
+        let mut ctx = self.state.ctx.borrow_mut();
+        let ctx: &mut GCCtx = &mut ctx;
+
+        let (_, lazy_value) = set.get(ctx).attr_at(idx).unwrap();
+
+        match LazyValue::get_or_evaluate(gc_args!(ctx, lazy_value), self.span) {
+            Ok(value) => Ok(value.raw()),
+            Err(diagnostic) => Err(DeserializeError {
+                kind: DeserializeErrorKind::Eval(diagnostic),
+                location: None,
+            }),
+        }
+    }
+
+    /// Creates an error for a value that has the wrong type
+    fn invalid_type(value: GC<MValue>, expected: &dyn Expected) -> DeserializeError {
+        let unexpected = match value.project() {
+            Projected::<MValue>::String(string) => {
+                return de::Error::invalid_type(Unexpected::Str(string.read()), expected);
+            }
+            Projected::<MValue>::Number(_) => Unexpected::Other("number"),
+            Projected::<MValue>::List(_) => Unexpected::Other("list"),
+            Projected::<MValue>::Set(_) => Unexpected::Other("set"),
+            Projected::<MValue>::Builtin(_) => Unexpected::Other("function"),
+        };
+
+        de::Error::invalid_type(unexpected, expected)
+    }
+
+    /// Creates an error for a number that can't be converted to the requested type
+    fn invalid_number(
+        number: GC<GCNumber>,
+        expected: &str,
+        error: ConversionError,
+    ) -> DeserializeError {
+        let number = unsafe {
+            number
+                .raw()
+                .to_decimal_string(number.gc(), DecimalFormat::default())
+        };
+
+        let reason = match error {
+            ConversionError::NotAnInteger => "which is not an integer",
+            ConversionError::OutOfRange | ConversionError::Inexact => "which is out of range",
+        };
+
+        de::Error::custom(format_args!("expected {expected}, got {number}, {reason}"))
+    }
+
+    fn integer<T: TryFrom<i128> + TryFrom<u128>>(
+        &self,
+        expected: &str,
+    ) -> Result<T, DeserializeError> {
+        let value = self.force()?;
+
+        let ctx = self.state.ctx.borrow();
+        let value = unsafe { GC::new(&ctx, value) };
+
+        let Projected::<MValue>::Number(number) = value.project() else {
+            return Err(Self::invalid_type(value, &expected));
+        };
+
+        let integer = match i128::try_from(number) {
+            Ok(integer) => T::try_from(integer).ok(),
+            Err(ConversionError::OutOfRange) => u128::try_from(number)
+                .ok()
+                .and_then(|integer| T::try_from(integer).ok()),
+            Err(err) => return Err(Self::invalid_number(number, expected, err)),
+        };
+
+        integer.ok_or_else(|| Self::invalid_number(number, expected, ConversionError::OutOfRange))
+    }
+
+    fn float(&self, expected: &str) -> Result<f64, DeserializeError> {
+        let value = self.force()?;
+
+        let ctx = self.state.ctx.borrow();
+        let value = unsafe { GC::new(&ctx, value) };
+
+        let Projected::<MValue>::Number(number) = value.project() else {
+            return Err(Self::invalid_type(value, &expected));
+        };
+
+        number
+            .to_f64()
+            .map_err(|err| Self::invalid_number(number, expected, err))
+    }
+}
+
+macro_rules! deserialize_integers {
+    ($($method:ident => $visit:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+                self.locate(|this| visitor.$visit(this.integer::<$ty>(stringify!($ty))?))
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for MValueDeserializer<'_, '_, '_, '_> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        self.locate(|this| {
+            let value = this.force()?;
+
+            let ctx = this.state.ctx.borrow();
+            let value = unsafe { GC::new(&ctx, value) };
+
+            match value.project() {
+                Projected::<MValue>::String(string) => visitor.visit_str(string.read()),
+                Projected::<MValue>::Number(number) => {
+                    if let Some(integer) = number.to_i64() {
+                        visitor.visit_i64(integer)
+                    } else if let Some(integer) = number.to_u64() {
+                        visitor.visit_u64(integer)
+                    } else if let Some(integer) = number.to_u128() {
+                        visitor.visit_u128(integer)
+                    } else {
+                        let float = number
+                            .to_f64()
+                            .map_err(|err| Self::invalid_number(number, "a number", err))?;
+
+                        visitor.visit_f64(float)
+                    }
+                }
+                Projected::<MValue>::List(list) => {
+                    let access = ListAccess::new(&this, list);
+                    drop(ctx);
+
+                    visitor.visit_seq(access)
+                }
+                Projected::<MValue>::Set(set) => {
+                    let set = GCRootGuard::new(this.state.gc, set);
+                    drop(ctx);
+
+                    visitor.visit_map(SetAccess::new(&this, &set))
+                }
+                Projected::<MValue>::Builtin(_) => Err(Self::invalid_type(value, &"a value")),
+            }
+        })
+    }
+
+    deserialize_integers! {
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        self.locate(|this| {
+            let float = this.float("f32")? as f32;
+
+            if float.is_infinite() {
+                return Err(de::Error::custom(
+                    "expected f32, got a number which is out of range",
+                ));
+            }
+
+            visitor.visit_f32(float)
+        })
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        self.locate(|this| visitor.visit_f64(this.float("f64")?))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        // There is no null value, so missing attributes are the only way to get `None`
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        self.locate(|this| {
+            let value = this.force()?;
+
+            let ctx = this.state.ctx.borrow();
+            let value = unsafe { GC::new(&ctx, value) };
+
+            match value.project() {
+                Projected::<MValue>::List(list) if list.is_empty() => visitor.visit_unit(),
+                Projected::<MValue>::Set(set) if set.is_empty() => visitor.visit_unit(),
+                _ => Err(Self::invalid_type(value, &"an empty set or list")),
+            }
+        })
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        self.locate(|this| {
+            let value = this.force()?;
+
+            let ctx = this.state.ctx.borrow();
+            let value = unsafe { GC::new(&ctx, value) };
+
+            match value.project() {
+                // Unit variants can be written as strings (eg `"red"`)
+                Projected::<MValue>::String(string) => {
+                    let variant: de::value::StrDeserializer<DeserializeError> =
+                        string.read().into_deserializer();
+
+                    visitor.visit_enum(variant)
+                }
+                // Other variants are written as a set with a single attribute (eg `{ rgb = [...]; }`)
+                Projected::<MValue>::Set(set) if set.len() == 1 => {
+                    let set = GCRootGuard::new(this.state.gc, set);
+                    drop(ctx);
+
+                    visitor.visit_enum(SetAccess::new(&this, &set))
+                }
+                _ => Err(Self::invalid_type(
+                    value,
+                    &"a string or a set with a single attribute",
+                )),
+            }
+        })
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        // Ignored values are not evaluated
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool char str string bytes byte_buf seq tuple tuple_struct map struct identifier
+    }
+}
+
+/// Deserializes the elements of a list
+struct ListAccess<'s, 'a, 'gc> {
+    state: &'s State<'a, 'gc>,
+    list: GCRootGuard<'gc, GCPersistentVec<MValue>>,
+    idx: usize,
+    len: usize,
+    path: String,
+    /// Elements don't have their own spans, so errors point to the entire list
+    span: FullSpan,
+}
+
+impl<'s, 'a, 'gc> ListAccess<'s, 'a, 'gc> {
+    fn new(
+        deserializer: &MValueDeserializer<'s, 'a, '_, 'gc>,
+        list: GC<GCPersistentVec<MValue>>,
+    ) -> Self {
+        Self {
+            state: deserializer.state,
+            len: list.len(),
+            list: GCRootGuard::new(deserializer.state.gc, list),
+            idx: 0,
+            path: deserializer.path.clone(),
+            span: deserializer.span,
+        }
+    }
+}
+
+impl<'de> SeqAccess<'de> for ListAccess<'_, '_, '_> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DeserializeError> {
+        if self.idx == self.len {
+            return Ok(None);
+        }
+
+        let value = {
+            let ctx = self.state.ctx.borrow();
+            self.list.get(&ctx).get(self.idx).unwrap().raw()
+        };
+
+        let deserializer = MValueDeserializer {
+            state: self.state,
+            value: PendingValue::Evaluated(value),
+            path: format!("{}[{}]", self.path, self.idx),
+            span: self.span,
+        };
+
+        self.idx += 1;
+
+        seed.deserialize(deserializer).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.idx)
+    }
+}
+
+/// Deserializes the attributes of a set
+struct SetAccess<'s, 'a, 'r, 'gc> {
+    state: &'s State<'a, 'gc>,
+    set: &'r GCRootGuard<'gc, Set>,
+    idx: usize,
+    len: usize,
+    path: String,
+}
+
+impl<'s, 'a, 'r, 'gc> SetAccess<'s, 'a, 'r, 'gc> {
+    fn new(
+        deserializer: &MValueDeserializer<'s, 'a, '_, 'gc>,
+        set: &'r GCRootGuard<'gc, Set>,
+    ) -> Self {
+        let ctx = deserializer.state.ctx.borrow();
+
+        Self {
+            state: deserializer.state,
+            set,
+            idx: 0,
+            len: set.get(&ctx).len(),
+            path: deserializer.path.clone(),
+        }
+    }
+
+    /// Gets the path and span of the name of the next attribute
+    fn next_name(&self) -> (String, String, FullSpan) {
+        let ctx = self.state.ctx.borrow();
+
+        let (name, _) = self.set.get(&ctx).attr_at(self.idx).unwrap();
+        let name = name.project();
+
+        let path = if self.path.is_empty() {
+            name.0.read().to_owned()
+        } else {
+            format!("{}.{}", self.path, name.0.read())
+        };
+
+        (name.0.read().to_owned(), path, name.1)
+    }
+
+    /// Creates a deserializer for the next attribute value and advances to the next attribute
+    fn next_value_deserializer(&mut self) -> MValueDeserializer<'s, 'a, 'r, 'gc> {
+        let (_, path, _) = self.next_name();
+
+        let span = {
+            let ctx = self.state.ctx.borrow();
+            let (_, value) = self.set.get(&ctx).attr_at(self.idx).unwrap();
+
+            value.span()
+        };
+
+        let deserializer = MValueDeserializer {
+            state: self.state,
+            value: PendingValue::Attribute(self.set, self.idx),
+            path,
+            span,
+        };
+
+        self.idx += 1;
+
+        deserializer
+    }
+}
+
+impl<'de> MapAccess<'de> for SetAccess<'_, '_, '_, '_> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeserializeError> {
+        if self.idx == self.len {
+            return Ok(None);
+        }
+
+        let (name, path, span) = self.next_name();
+
+        let name: de::value::StringDeserializer<DeserializeError> = name.into_deserializer();
+
+        seed.deserialize(name)
+            .map(Some)
+            .map_err(|err| err.at(&path, span))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, DeserializeError> {
+        seed.deserialize(self.next_value_deserializer())
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.idx)
+    }
+}
+
+impl<'de> EnumAccess<'de> for SetAccess<'_, '_, '_, '_> {
+    type Error = DeserializeError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        mut self,
+        seed: V,
+    ) -> Result<(V::Value, Self), DeserializeError> {
+        let variant = self.next_key_seed(seed)?.unwrap();
+
+        // `next_key_seed` doesn't advance to the next attribute
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for SetAccess<'_, '_, '_, '_> {
+    type Error = DeserializeError;
+
+    fn unit_variant(mut self) -> Result<(), DeserializeError> {
+        de::Deserialize::deserialize(self.next_value_deserializer())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        mut self,
+        seed: T,
+    ) -> Result<T::Value, DeserializeError> {
+        seed.deserialize(self.next_value_deserializer())
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        mut self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        de::Deserializer::deserialize_tuple(self.next_value_deserializer(), len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        mut self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        de::Deserializer::deserialize_struct(self.next_value_deserializer(), "", fields, visitor)
+    }
+}
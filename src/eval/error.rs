@@ -35,3 +35,67 @@ pub fn no_attribute_with_name(span: FullSpan, name: &str) -> Diagnostic {
         {"Here", span, primary},
     ])
 }
+
+pub fn invalid_value(span: FullSpan, path: &str, message: &str) -> Diagnostic {
+    let title = if path.is_empty() {
+        "Invalid value".to_owned()
+    } else {
+        format!("Invalid value for `{path}`")
+    };
+
+    error!("EE0005", title, [{message, span, primary}])
+}
+
+pub fn undefined_variable(span: FullSpan, name: &str) -> Diagnostic {
+    error!("EE0006", format!("Undefined variable `{name}`"), [
+        {"Here", span, primary},
+    ])
+}
+
+pub fn wrong_type(span: FullSpan, expected: &str, found: &str) -> Diagnostic {
+    error!("EE0007", format!("Expected {expected}, got {found}"), [
+        {"Here", span, primary},
+    ])
+}
+
+pub fn index_out_of_bounds(span: FullSpan, index: usize, len: usize) -> Diagnostic {
+    error!("EE0008", format!("Index {index} is out of bounds for a list of length {len}"), [
+        {"Here", span, primary},
+    ])
+}
+
+pub fn wrong_number_of_arguments(
+    span: FullSpan,
+    function: &str,
+    expected: usize,
+    found: usize,
+) -> Diagnostic {
+    let plural = if expected == 1 { "" } else { "s" };
+    let were = if found == 1 { "was" } else { "were" };
+
+    error!(
+        "EE0009",
+        format!("`{function}` takes {expected} argument{plural} but {found} {were} given"),
+        [{"Here", span, primary}]
+    )
+}
+
+pub fn heap_limit_exceeded(span: FullSpan, max_heap_size: usize) -> Diagnostic {
+    error!(
+        "EE0010",
+        format!("Evaluation exceeded the heap limit of {max_heap_size} bytes"),
+        [{"While evaluating this", span, primary}]
+    )
+}
+
+pub fn invalid_slice(span: FullSpan, start: usize, end: usize, len: usize) -> Diagnostic {
+    error!(
+        "EE0011",
+        format!("Slice {start}..{end} is out of bounds for a list of length {len}"),
+        [{"Here", span, primary}]
+    )
+}
+
+pub fn invalid_value_without_span(message: &str) -> Diagnostic {
+    error!("EE0012", format!("Invalid value: {message}"), [])
+}
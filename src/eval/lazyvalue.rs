@@ -21,7 +21,7 @@ struct UnevaluatedLazyValue {
 #[msb_reserved]
 enum LazyValueData {
     Unevaluated(UnevaluatedLazyValue),
-    Evaluated(MValue),
+    Evaluated(Spanned<MValue>),
     CurrentlyBeingEvaluated(FullSpan),
 }
 
@@ -49,6 +49,18 @@ impl LazyValue {
         unsafe { GC::new(ctx, Self { inner: inner.raw() }) }
     }
 
+    /// Creates an already-evaluated value. `span` is reported as the location where the value is
+    /// defined.
+    pub fn from_value<'c>(ctx: &'c GCCtx, value: GC<'c, MValue>, span: FullSpan) -> GC<'c, Self> {
+        let data: GC<LazyValueData> =
+            Projected::<LazyValueData>::Evaluated(Spanned(value, span).into()).into();
+
+        let inner = GCBox::new(data);
+
+        // SAFETY: we know that `inner` is currently valid because it's wrapped in `GC`
+        unsafe { GC::new(ctx, Self { inner: inner.raw() }) }
+    }
+
     #[gc_fn]
     pub fn get_or_evaluate<'gc, 'c>(
         ctx: &'c mut gc!('gc, value: Self),
@@ -57,19 +69,21 @@ impl LazyValue {
         let inner = unsafe { GC::new(ctx, value.raw().inner) };
 
         match inner.get().project() {
-            Projected::<LazyValueData>::Evaluated(mvalue) => return Ok(rebind!(ctx, mvalue)),
+            Projected::<LazyValueData>::Evaluated(mvalue) => {
+                return Ok(rebind!(ctx, mvalue.project().0));
+            }
             Projected::<LazyValueData>::CurrentlyBeingEvaluated(definition_span) => Err(
                 eval::error::illegal_recursively_defined_value(definition_span.raw(), usage_span),
             ),
             Projected::<LazyValueData>::Unevaluated(unevaluated_data) => {
                 let ast = unevaluated_data.project().ast;
                 let scope = unevaluated_data.project().scope.get();
+                let span = ast.project().1;
 
                 unsafe {
                     inner
                         .raw()
-                        .as_mut(ctx)
-                        .write(LazyValueData::CurrentlyBeingEvaluated(ast.project().1))
+                        .set(ctx, LazyValueData::CurrentlyBeingEvaluated(span))
                 };
 
                 let inner_root = root!(ctx, inner);
@@ -78,11 +92,12 @@ impl LazyValue {
 
                 let inner = inner_root.get(ctx);
 
+                // `inner` may have been promoted while `ast` was evaluated, so this has to go
+                // through the write barrier
                 unsafe {
                     inner
                         .raw()
-                        .as_mut(ctx)
-                        .write(LazyValueData::Evaluated(value.raw()));
+                        .set(ctx, LazyValueData::Evaluated(Spanned(value.raw(), span)));
                 }
 
                 Ok(value)
@@ -91,4 +106,19 @@ impl LazyValue {
     }
 }
 
+impl<'c> GC<'c, LazyValue> {
+    /// Gets the span of the expression that defines this value. This does not evaluate the value.
+    pub fn span(self) -> FullSpan {
+        let inner = unsafe { GC::from_raw_parts(self.gc(), self.raw().inner) };
+
+        match inner.get().project() {
+            Projected::<LazyValueData>::Unevaluated(unevaluated_data) => {
+                unevaluated_data.project().ast.project().1
+            }
+            Projected::<LazyValueData>::Evaluated(mvalue) => mvalue.project().1,
+            Projected::<LazyValueData>::CurrentlyBeingEvaluated(span) => span.raw(),
+        }
+    }
+}
+
 impl GCRootRef<LazyValue> {}
@@ -4,7 +4,7 @@ use crate::{
     error::{DResult, PartialSpanned, Spanned},
     eval::{self, MValue, Scope},
     gc::{
-        GCVec,
+        GCPersistentVec, GCVec,
         safety::{GC, GCRootGuard, Projected, gc_args, rebind},
     },
     parser::ast,
@@ -14,12 +14,23 @@ use crate::{
 pub(super) fn evaluate_list<'c>(
     ctx: &'c mut gc!(ast: Spanned<ast::List>, scope: Scope),
 ) -> DResult<GC<'c, MValue>> {
-    let ast_span = ast.project().1;
+    let file_id = ast.project().1.file_id;
     let ast = ast.project().0;
 
     let elem_asts: GC<GCVec<PartialSpanned<ast::Expression>>> =
         ast.project().0.project().0.project().values;
 
+    let output_val = evaluate_all(gc_args!(ctx, elem_asts, scope), file_id)?;
+
+    Ok(Projected::<MValue>::List(output_val).into())
+}
+
+/// Evaluates each expression in `elem_asts` in order
+#[gc_fn]
+pub(super) fn evaluate_all<'c>(
+    ctx: &'c mut gc!(elem_asts: GCVec<PartialSpanned<ast::Expression>>, scope: Scope),
+    file_id: usize,
+) -> DResult<GC<'c, GCPersistentVec<MValue>>> {
     // Create roots for the element ASTs //
     //
     // We create individual roots so that we can discard the roots of elements that we've already evaluated.
@@ -40,7 +51,7 @@ pub(super) fn evaluate_list<'c>(
     let mut elem_value_roots: Vec<GCRootGuard<MValue>> = Vec::with_capacity(elem_asts.len());
 
     for elem_ast_root in elem_ast_roots.iter() {
-        let elem_ast = elem_ast_root.get(ctx).with_file_id(ast_span.file_id);
+        let elem_ast = elem_ast_root.get(ctx).with_file_id(file_id);
 
         let elem_value = rebind!(
             ctx,
@@ -56,7 +67,7 @@ pub(super) fn evaluate_list<'c>(
     }
 
     // Create the actual value //
-    let output_val: GC<GCVec<MValue>> = GCVec::from_iter_and_len(
+    let output_val: GC<GCPersistentVec<MValue>> = GCPersistentVec::from_iter_and_len(
         ctx,
         elem_value_roots.iter().map(|val_root| val_root.get(ctx)),
         elem_value_roots.len(),
@@ -74,5 +85,5 @@ pub(super) fn evaluate_list<'c>(
         std::mem::drop(elem_ast_root);
     }
 
-    Ok(Projected::<MValue>::List(output_val).into())
+    Ok(output_val)
 }
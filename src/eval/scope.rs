@@ -1,11 +1,13 @@
-use mulch_macros::{GCDebug, GCProject, GCPtr};
+use mulch_macros::{GCDebug, GCProject, GCPtr, gc_fn};
 
 use crate::{
-    eval::Set,
+    error::{DResult, Spanned},
+    eval::{self, MValue, Set, builtin, lazyvalue::LazyValue},
     gc::{
-        GCBox,
-        safety::{GC, GCCtx, Projected},
+        GCBox, GCString,
+        safety::{GC, GCCtx, Projected, gc_args},
     },
+    parser::Ident,
 };
 
 #[derive(Clone, Copy, GCPtr, GCDebug, GCProject)]
@@ -15,13 +17,61 @@ pub struct Scope {
 }
 
 impl Scope {
+    /// Creates the outermost scope. This contains every builtin.
     pub fn new_global<'c>(ctx: &'c GCCtx) -> GC<'c, Scope> {
         let parent = unsafe { GC::new(ctx, None) }; // TODO: make this safe
 
         Projected::<Scope> {
             parent,
-            variables: Set::new_empty(ctx),
+            variables: builtin::global_variables(ctx),
         }
         .into()
     }
+
+    /// Creates a scope that contains `variables` and all of the variables in `parent`. Variables in
+    /// `variables` shadow variables with the same name in `parent`.
+    pub fn new_child<'c>(
+        ctx: &'c GCCtx,
+        parent: GC<'c, Scope>,
+        variables: GC<'c, Set>,
+    ) -> GC<'c, Scope> {
+        let parent = GCBox::new(parent);
+
+        // SAFETY: we know that `parent` is valid because it's wrapped in `GC`
+        let parent = unsafe { GC::new(ctx, Some(parent.raw())) };
+
+        Projected::<Scope> { parent, variables }.into()
+    }
+}
+
+impl<'c> GC<'c, Scope> {
+    /// Finds the innermost definition of a variable
+    fn lookup(self, name: GC<'c, GCString>) -> Option<GC<'c, LazyValue>> {
+        let mut scope = self;
+
+        loop {
+            if let Some(value) = scope.project().variables.get_attr_by_name(name) {
+                return Some(value);
+            }
+
+            let parent = scope.raw().parent?;
+
+            // SAFETY: `parent` is valid because it's referenced by `scope`
+            scope = unsafe { GC::from_raw_parts(self.gc(), parent) }.get();
+        }
+    }
+}
+
+#[gc_fn]
+pub(super) fn evaluate_variable<'c>(
+    ctx: &'c mut gc!(ast: Spanned<Ident>, scope: Scope),
+) -> DResult<GC<'c, MValue>> {
+    let ast_span = ast.project().1;
+    let name: GC<GCString> = ast.project().0.project().0;
+
+    let Some(lazy_value) = scope.lookup(name) else {
+        return Err(eval::error::undefined_variable(ast_span, name.read()));
+    };
+
+    LazyValue::get_or_evaluate(gc_args!(ctx, lazy_value), ast_span)
 }
@@ -0,0 +1,586 @@
+//! Serializes Rust values into `mulch` values with `serde`
+
+use std::fmt::Display;
+
+use serde::ser::{
+    self, Error as _, Serialize, SerializeMap, SerializeSeq, SerializeStruct,
+    SerializeStructVariant, SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
+};
+
+use crate::{
+    error::FullSpan,
+    eval::{MValue, Set},
+    gc::{
+        GCNumber, GCPersistentVec, GCString,
+        safety::{GC, GCCtx, Projected},
+    },
+};
+
+/// Serializes `value` into a `mulch` value. `span` is used as the span of every attribute in the
+/// result, so it should point to wherever the value is considered to be defined.
+///
+/// Structs and maps become sets, sequences and tuples become lists, and unit variants become
+/// strings. Other enum variants become a set with a single attribute named after the variant.
+/// Attributes with a value of `None` are left out.
+pub fn to_value<'c, T: Serialize + ?Sized>(
+    ctx: &'c GCCtx,
+    value: &T,
+    span: FullSpan,
+) -> Result<GC<'c, MValue>, SerializeError> {
+    value
+        .serialize(MValueSerializer { ctx, span })?
+        .ok_or_else(SerializeError::unexpected_none)
+}
+
+/// Serializes `value` into a set. The attributes of the set can be bound as variables with
+/// [`Scope::new_child`](crate::eval::Scope::new_child).
+pub fn to_set<'c, T: Serialize + ?Sized>(
+    ctx: &'c GCCtx,
+    value: &T,
+    span: FullSpan,
+) -> Result<GC<'c, Set>, SerializeError> {
+    match to_value(ctx, value, span)?.project() {
+        Projected::<MValue>::Set(set) => Ok(set),
+        _ => Err(SerializeError::custom("expected a struct or map")),
+    }
+}
+
+/// An error that occurs while serializing a value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SerializeError {
+    message: String,
+    /// The attribute path of the offending value. Segments are prepended as the error is passed
+    /// to the outer values.
+    path: String,
+}
+
+impl SerializeError {
+    /// The attribute path of the offending value (eg `a.b[3].c`). This is empty for the root
+    /// value.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn unexpected_none() -> Self {
+        SerializeError::custom("`None` can only be used as the value of an attribute")
+    }
+
+    /// Adds the attribute `name` to the start of the path
+    fn in_attribute(mut self, name: &str) -> Self {
+        self.path = match self.path.as_str() {
+            "" => name.to_owned(),
+            path if path.starts_with('[') => format!("{name}{path}"),
+            path => format!("{name}.{path}"),
+        };
+
+        self
+    }
+
+    /// Adds the enum variant name to the start of the path if the value is a variant
+    fn in_variant(self, variant: Option<&str>) -> Self {
+        match variant {
+            Some(variant) => self.in_attribute(variant),
+            None => self,
+        }
+    }
+
+    /// Adds the list index `idx` to the start of the path
+    fn in_element(mut self, idx: usize) -> Self {
+        self.path = match self.path.as_str() {
+            "" => format!("[{idx}]"),
+            path if path.starts_with('[') => format!("[{idx}]{path}"),
+            path => format!("[{idx}].{path}"),
+        };
+
+        self
+    }
+}
+
+impl Display for SerializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "`{}`: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for SerializeError {}
+
+impl ser::Error for SerializeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self {
+            message: msg.to_string(),
+            path: String::new(),
+        }
+    }
+}
+
+/// Serializes a value into an `MValue`. `None` is serialized as `Ok(None)`.
+///
+/// This only borrows the context immutably, so no garbage-collection cycles can occur while the
+/// value is being built.
+#[derive(Clone, Copy)]
+struct MValueSerializer<'c, 'gc> {
+    ctx: &'c GCCtx<'gc>,
+    span: FullSpan,
+}
+
+impl<'c> MValueSerializer<'c, '_> {
+    fn string(self, value: &str) -> Result<Option<GC<'c, MValue>>, SerializeError> {
+        Ok(Some(GCString::new(self.ctx, value).into()))
+    }
+
+    fn integer(self, value: i128) -> Result<Option<GC<'c, MValue>>, SerializeError> {
+        let number = GCNumber::from_i128(self.ctx, value);
+
+        // SAFETY: `number` was just allocated
+        Ok(Some(unsafe { GC::new(self.ctx, number) }.into()))
+    }
+
+    fn float(self, value: f64) -> Result<Option<GC<'c, MValue>>, SerializeError> {
+        let Some(number) = GCNumber::from_f64(self.ctx, value) else {
+            return Err(SerializeError::custom(format_args!(
+                "`{value}` cannot be represented as a number"
+            )));
+        };
+
+        // SAFETY: `number` was just allocated
+        Ok(Some(unsafe { GC::new(self.ctx, number) }.into()))
+    }
+
+    fn list(self, elements: Vec<GC<'c, MValue>>) -> GC<'c, MValue> {
+        let len = elements.len();
+
+        GCPersistentVec::from_iter_and_len(self.ctx, elements.into_iter(), len).into()
+    }
+
+    fn set(
+        self,
+        attributes: Vec<(GC<'c, GCString>, GC<'c, MValue>)>,
+    ) -> Result<GC<'c, MValue>, SerializeError> {
+        match Set::from_values(self.ctx, attributes, self.span) {
+            Ok(set) => Ok(Projected::<MValue>::Set(set).into()),
+            Err(name) => Err(SerializeError::custom(format_args!(
+                "attribute `{}` is defined multiple times",
+                name.read()
+            ))),
+        }
+    }
+
+    /// Wraps `value` in a set with a single attribute named `variant`
+    fn variant(
+        self,
+        variant: &'static str,
+        value: GC<'c, MValue>,
+    ) -> Result<Option<GC<'c, MValue>>, SerializeError> {
+        let name = GCString::intern(self.ctx, variant);
+
+        self.set(vec![(name, value)]).map(Some)
+    }
+
+    /// Serializes `value` as an element of a list
+    fn element<T: Serialize + ?Sized>(
+        self,
+        idx: usize,
+        value: &T,
+    ) -> Result<GC<'c, MValue>, SerializeError> {
+        value
+            .serialize(self)
+            .and_then(|value| value.ok_or_else(SerializeError::unexpected_none))
+            .map_err(|err| err.in_element(idx))
+    }
+}
+
+impl<'c, 'gc> ser::Serializer for MValueSerializer<'c, 'gc> {
+    type Ok = Option<GC<'c, MValue>>;
+    type Error = SerializeError;
+
+    type SerializeSeq = ListSerializer<'c, 'gc>;
+    type SerializeTuple = ListSerializer<'c, 'gc>;
+    type SerializeTupleStruct = ListSerializer<'c, 'gc>;
+    type SerializeTupleVariant = ListSerializer<'c, 'gc>;
+    type SerializeMap = SetSerializer<'c, 'gc>;
+    type SerializeStruct = SetSerializer<'c, 'gc>;
+    type SerializeStructVariant = SetSerializer<'c, 'gc>;
+
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Self::Error> {
+        Err(SerializeError::custom("booleans cannot be represented"))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        self.integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        let number = GCNumber::from_u128(self.ctx, v);
+
+        // SAFETY: `number` was just allocated
+        Ok(Some(unsafe { GC::new(self.ctx, number) }.into()))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.float(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.float(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.string(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        self.string(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let mut list = self.serialize_seq(Some(v.len()))?;
+
+        for byte in v {
+            list.push(byte)?;
+        }
+
+        list.finish()
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.set(Vec::new()).map(Some)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.string(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        let value = value
+            .serialize(self)
+            .and_then(|value| value.ok_or_else(SerializeError::unexpected_none))
+            .map_err(|err| err.in_attribute(variant))?;
+
+        self.variant(variant, value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(ListSerializer {
+            serializer: self,
+            elements: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Ok(ListSerializer {
+            serializer: self,
+            elements: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(SetSerializer {
+            serializer: self,
+            attributes: Vec::with_capacity(len.unwrap_or(0)),
+            next_name: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Ok(SetSerializer {
+            serializer: self,
+            attributes: Vec::with_capacity(len),
+            next_name: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+/// Builds a list. If `variant` is set, the list is wrapped in a set with a single attribute.
+struct ListSerializer<'c, 'gc> {
+    serializer: MValueSerializer<'c, 'gc>,
+    elements: Vec<GC<'c, MValue>>,
+    variant: Option<&'static str>,
+}
+
+impl<'c> ListSerializer<'c, '_> {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerializeError> {
+        let element = self
+            .serializer
+            .element(self.elements.len(), value)
+            .map_err(|err| err.in_variant(self.variant))?;
+
+        self.elements.push(element);
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<GC<'c, MValue>>, SerializeError> {
+        let list = self.serializer.list(self.elements);
+
+        match self.variant {
+            Some(variant) => self.serializer.variant(variant, list),
+            None => Ok(Some(list)),
+        }
+    }
+}
+
+impl<'c> SerializeSeq for ListSerializer<'c, '_> {
+    type Ok = Option<GC<'c, MValue>>;
+    type Error = SerializeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+impl<'c> SerializeTuple for ListSerializer<'c, '_> {
+    type Ok = Option<GC<'c, MValue>>;
+    type Error = SerializeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+impl<'c> SerializeTupleStruct for ListSerializer<'c, '_> {
+    type Ok = Option<GC<'c, MValue>>;
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+impl<'c> SerializeTupleVariant for ListSerializer<'c, '_> {
+    type Ok = Option<GC<'c, MValue>>;
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+/// Builds a set. If `variant` is set, the set is wrapped in another set with a single attribute.
+struct SetSerializer<'c, 'gc> {
+    serializer: MValueSerializer<'c, 'gc>,
+    attributes: Vec<(GC<'c, GCString>, GC<'c, MValue>)>,
+    /// The name of the attribute whose value will be serialized next. This is only used for maps.
+    next_name: Option<GC<'c, GCString>>,
+    variant: Option<&'static str>,
+}
+
+impl<'c> SetSerializer<'c, '_> {
+    /// Adds an attribute. Attributes with a value of `None` are skipped.
+    fn push<T: Serialize + ?Sized>(
+        &mut self,
+        name: GC<'c, GCString>,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        let value = value
+            .serialize(self.serializer)
+            .map_err(|err| err.in_attribute(name.read()).in_variant(self.variant))?;
+
+        if let Some(value) = value {
+            self.attributes.push((name, value));
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<GC<'c, MValue>>, SerializeError> {
+        let set = self
+            .serializer
+            .set(self.attributes)
+            .map_err(|err| err.in_variant(self.variant))?;
+
+        match self.variant {
+            Some(variant) => self.serializer.variant(variant, set),
+            None => Ok(Some(set)),
+        }
+    }
+}
+
+impl<'c> SerializeMap for SetSerializer<'c, '_> {
+    type Ok = Option<GC<'c, MValue>>;
+    type Error = SerializeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        let name = key.serialize(self.serializer)?;
+
+        match name.map(|name| name.project()) {
+            Some(Projected::<MValue>::String(name)) => {
+                self.next_name = Some(name);
+                Ok(())
+            }
+            _ => {
+                Err(SerializeError::custom("attribute names must be strings")
+                    .in_variant(self.variant))
+            }
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let name = self
+            .next_name
+            .take()
+            .expect("`serialize_key` should be called before `serialize_value`");
+
+        self.push(name, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+impl<'c> SerializeStruct for SetSerializer<'c, '_> {
+    type Ok = Option<GC<'c, MValue>>;
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        let name = GCString::intern(self.serializer.ctx, key);
+
+        self.push(name, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+impl<'c> SerializeStructVariant for SetSerializer<'c, '_> {
+    type Ok = Option<GC<'c, MValue>>;
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        let name = GCString::intern(self.serializer.ctx, key);
+
+        self.push(name, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
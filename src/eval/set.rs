@@ -1,53 +1,300 @@
-use itertools::Itertools;
+use std::cmp::Ordering;
+
+use itertools::{EitherOrBoth, Itertools};
 use mulch_macros::{GCDebug, GCProject, GCPtr, gc_fn};
 
 use crate::{
-    error::{DResult, Spanned},
+    error::{DResult, FullSpan, Spanned},
     eval::{self, MValue, Scope, lazyvalue::LazyValue},
     gc::{
-        GCBox, GCString, GCVec,
+        GCBox, GCDebug, GCString, GCVec, GarbageCollector,
         safety::{GC, GCCtx, Projected, gc_args, rebind, root},
+        util::GCWrap,
     },
     parser::ast::{self, MemberAccess, NamedValue},
 };
 
-#[derive(Clone, Copy, GCDebug, GCPtr)]
+mod hamt;
+
+/// Sets with at least this many attributes are stored in a hash trie. Smaller sets are searched
+/// by binary search.
+const TRIE_THRESHOLD: usize = 32;
+
+#[derive(Clone, Copy, GCPtr)]
 pub struct Set {
-    values: GCVec<NamedMValue>,
+    /// The number of attributes
+    len: usize,
+    /// The attributes, sorted by name. Sets that are created by [`Set::merge`] compute this from
+    /// `trie` the first time that it's needed, so that merging doesn't copy every attribute.
+    sorted: GCBox<Option<GCVec<NamedMValue>>>,
+    /// The attributes of sets with at least [`TRIE_THRESHOLD`] attributes
+    trie: Option<hamt::NodeRef>,
 }
 
 impl Set {
     pub fn new_empty<'c>(ctx: &'c GCCtx) -> GC<'c, Self> {
         let values = GCVec::<NamedMValue>::new_empty(ctx);
 
+        Self::from_sorted(ctx, values)
+    }
+
+    /// Creates a set from already-evaluated attributes. `span` is used as the span of every
+    /// attribute. Fails with the name of an attribute if it is defined multiple times.
+    pub(super) fn from_values<'c>(
+        ctx: &'c GCCtx,
+        mut attributes: Vec<(GC<'c, GCString>, GC<'c, MValue>)>,
+        span: FullSpan,
+    ) -> Result<GC<'c, Self>, GC<'c, GCString>> {
+        attributes.sort_by(|(a, _), (b, _)| a.read().cmp(b.read()));
+
+        if let Some(((name, _), _)) = attributes
+            .iter()
+            .tuple_windows()
+            .find(|((prev, _), (cur, _))| prev.read() == cur.read())
+        {
+            return Err(*name);
+        }
+
+        let named_values = attributes.iter().map(|(name, value)| {
+            Projected::<NamedMValue> {
+                name: Spanned(*name, span).into(),
+                value: LazyValue::from_value(ctx, *value, span),
+            }
+            .into()
+        });
+
+        let values = GCVec::<NamedMValue>::from_iter_and_len(ctx, named_values, attributes.len());
+
+        Ok(Self::from_sorted(ctx, values))
+    }
+
+    /// Creates a set from attributes that are sorted by name and unique. The hash trie is built if
+    /// the set is large enough.
+    fn from_sorted<'c>(ctx: &'c GCCtx, values: GC<'c, GCVec<NamedMValue>>) -> GC<'c, Self> {
+        let trie = (values.len() >= TRIE_THRESHOLD).then(|| hash_trie(ctx, values));
+
+        // SAFETY: we know that `values` is valid because it's wrapped in `GC`, and `trie` was just
+        // allocated
         unsafe {
+            let sorted = GCBox::new_raw(ctx, Some(values.raw()));
+
             GC::new(
                 ctx,
                 Set {
-                    values: values.raw(),
+                    len: values.len(),
+                    sorted,
+                    trie,
                 },
             )
         }
     }
+
+    /// Merges two sets. Attributes of `rhs` take precedence over attributes of `lhs` with the same
+    /// name.
+    ///
+    /// If either set is large, their tries are merged, which only copies the nodes that both
+    /// tries have attributes in.
+    pub fn merge<'c>(ctx: &'c GCCtx, lhs: GC<'c, Self>, rhs: GC<'c, Self>) -> GC<'c, Self> {
+        if rhs.is_empty() {
+            return lhs;
+        } else if lhs.is_empty() {
+            return rhs;
+        }
+
+        if lhs.raw().trie.is_some() || rhs.raw().trie.is_some() {
+            let (lhs_trie, rhs_trie) = (lhs.trie(ctx), rhs.trie(ctx));
+
+            // SAFETY: the tries are valid because they're referenced by the sets or were just
+            // built from them
+            unsafe {
+                let trie = hamt::merge(ctx, lhs_trie, rhs_trie);
+                let sorted = GCBox::new_raw(ctx, None);
+
+                return GC::new(
+                    ctx,
+                    Set {
+                        len: hamt::len(ctx, trie),
+                        sorted,
+                        trie: Some(trie),
+                    },
+                );
+            }
+        }
+
+        let merged: Vec<GC<NamedMValue>> = lhs
+            .sorted()
+            .iter()
+            .merge_join_by(rhs.sorted().iter(), |a, b| {
+                cmp_names(a.project().name.project().0, b.project().name.project().0)
+            })
+            .map(|attrs| match attrs {
+                EitherOrBoth::Left(attr) | EitherOrBoth::Right(attr) => attr,
+                EitherOrBoth::Both(_, attr) => attr,
+            })
+            .collect();
+
+        let values = GCVec::from_iter_and_len(ctx, merged.iter().copied(), merged.len());
+
+        Self::from_sorted(ctx, values)
+    }
+
+    /// The attributes sorted by name, without the safe garbage-collection API
+    ///
+    /// # Safety
+    /// - `self` must be valid and alive
+    unsafe fn sorted_values(&self, gc: &GarbageCollector) -> Vec<NamedMValue> {
+        if let Some(values) = unsafe { self.sorted.get(gc) } {
+            return unsafe { values.as_slice(gc) }.to_vec();
+        }
+
+        let mut values = Vec::with_capacity(self.len);
+
+        unsafe {
+            hamt::collect(gc, self.trie.unwrap(), &mut values);
+
+            values.sort_by(|a, b| a.name.0.get(gc).cmp(b.name.0.get(gc)));
+        }
+
+        values
+    }
+}
+
+impl GCDebug for Set {
+    unsafe fn gc_debug(
+        &self,
+        gc: &GarbageCollector,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        // The trie is left out because it contains the same attributes
+        unsafe {
+            let values = self.sorted_values(gc);
+            let values: Vec<_> = values.iter().map(|attr| GCWrap::new(attr, gc)).collect();
+
+            f.debug_struct("Set").field("values", &values).finish()
+        }
+    }
 }
 
 impl<'c> GC<'c, Set> {
+    /// The number of attributes in the set
+    pub fn len(&self) -> usize {
+        self.raw().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets the name and value of the `idx`th attribute. Attributes are sorted by name.
+    pub(crate) fn attr_at(
+        self,
+        idx: usize,
+    ) -> Option<(GC<'c, Spanned<GCString>>, GC<'c, LazyValue>)> {
+        self.sorted()
+            .get(idx)
+            .map(|attr| (attr.project().name, attr.project().value))
+    }
+
+    /// Iterates over the names and values of the attributes, sorted by name. The values are not
+    /// evaluated until [`LazyValue::get_or_evaluate`] is called on them.
+    pub fn attributes(self) -> impl Iterator<Item = (GC<'c, GCString>, GC<'c, LazyValue>)> {
+        self.sorted()
+            .iter()
+            .map(|attr| (attr.project().name.project().0, attr.project().value))
+    }
+
+    /// The attributes, sorted by name. This is computed and stored in the set the first time
+    /// that it's needed.
+    fn sorted(self) -> GC<'c, GCVec<NamedMValue>> {
+        let gc = self.gc();
+        let sorted = self.raw().sorted;
+
+        // SAFETY: `sorted` is valid because it's referenced by `self`, and the new vector only
+        // contains attributes of `self`
+        unsafe {
+            let values = match sorted.get(gc) {
+                Some(values) => values,
+                None => {
+                    let values = GCVec::new(gc, &self.raw().sorted_values(gc));
+                    sorted.set(gc, Some(values));
+
+                    values
+                }
+            };
+
+            GC::from_raw_parts(gc, values)
+        }
+    }
+
+    /// The hash trie of the set. Small sets don't store one, so it is built from their attributes.
+    fn trie(self, ctx: &'c GCCtx) -> hamt::NodeRef {
+        self.raw()
+            .trie
+            .unwrap_or_else(|| hash_trie(ctx, self.sorted()))
+    }
+
     pub fn get_attr(self, attr_name: &str) -> Option<GC<'c, LazyValue>> {
+        self.find(attr_name, None).map(|attr| attr.project().value)
+    }
+
+    /// Like [`get_attr`](Self::get_attr), but names that are interned (like the names in the
+    /// AST) can be compared by pointer
+    pub fn get_attr_by_name(self, attr_name: GC<'c, GCString>) -> Option<GC<'c, LazyValue>> {
+        self.find(attr_name.read(), Some(attr_name.raw()))
+            .map(|attr| attr.project().value)
+    }
+
+    /// Finds the attribute named `attr_name`. `interned` is the same name as a `GCString`, if
+    /// there is one.
+    fn find(self, attr_name: &str, interned: Option<GCString>) -> Option<GC<'c, NamedMValue>> {
         let gc = self.gc();
 
-        let result_idx = unsafe {
-            self.raw()
-                .values
-                .as_slice(gc)
-                .binary_search_by_key(&attr_name, |attr| attr.name.0.get(gc))
+        let attr = if let Some(trie) = self.raw().trie {
+            // SAFETY: `trie` is valid because it's referenced by `self`
+            unsafe { hamt::find(gc, trie, gc.hash_str(attr_name), attr_name, interned)? }
+        } else {
+            // SAFETY: nothing is allocated while the slice is borrowed
+            let values = unsafe { self.sorted().raw().as_slice(gc) };
+
+            let idx = values
+                .binary_search_by(|attr| {
+                    if interned.is_some_and(|name| attr.name.0.ptr_eq(&name)) {
+                        Ordering::Equal
+                    } else {
+                        unsafe { attr.name.0.get(gc) }.cmp(attr_name)
+                    }
+                })
+                .ok()?;
+
+            values[idx]
         };
 
-        let values = unsafe { GC::from_raw_parts(gc, self.raw().values) };
+        // SAFETY: `attr` is valid because it's referenced by `self`
+        Some(unsafe { GC::from_raw_parts(gc, attr) })
+    }
+}
+
+/// Builds a hash trie from attributes that are sorted by name and unique. `values` must not be
+/// empty.
+fn hash_trie(ctx: &GCCtx, values: GC<GCVec<NamedMValue>>) -> hamt::NodeRef {
+    let entries: Vec<hamt::Entry> = values
+        .iter()
+        .map(|attr| hamt::Entry {
+            hash: ctx.hash_str(attr.project().name.project().0.read()),
+            attr: attr.raw(),
+        })
+        .collect();
+
+    // SAFETY: the attributes are valid because they're referenced by `values`
+    unsafe { hamt::build(ctx, &entries) }
+}
 
-        result_idx
-            .ok()
-            .and_then(|idx| values.get(idx))
-            .map(|attr| attr.project().value)
+/// Compares two attribute names. Interned names are compared by pointer first.
+fn cmp_names(a: GC<GCString>, b: GC<GCString>) -> Ordering {
+    if a.raw().ptr_eq(&b.raw()) {
+        Ordering::Equal
+    } else {
+        a.read().cmp(b.read())
     }
 }
 
@@ -57,6 +304,23 @@ struct NamedMValue {
     value: LazyValue,
 }
 
+impl NamedMValue {
+    /// Whether the attribute is named `name`. `interned` is the same name as a `GCString`, if
+    /// there is one.
+    ///
+    /// # Safety
+    /// - `self` must be valid and alive
+    unsafe fn has_name(
+        &self,
+        gc: &GarbageCollector,
+        name: &str,
+        interned: Option<GCString>,
+    ) -> bool {
+        interned.is_some_and(|interned| self.name.0.ptr_eq(&interned))
+            || unsafe { self.name.0.get(gc) } == name
+    }
+}
+
 #[gc_fn]
 pub(super) fn evaluate_set<'c>(
     ctx: &'c mut gc!(ast: Spanned<ast::Set>, scope: Scope),
@@ -107,15 +371,7 @@ pub(super) fn evaluate_set<'c>(
         }
     }
 
-    // SAFETY: we know that `out_attrs` is valid because it's wrapped in `GC`
-    let set = unsafe {
-        GC::new(
-            ctx,
-            Set {
-                values: out_attrs.raw(),
-            },
-        )
-    };
+    let set = Set::from_sorted(ctx, out_attrs);
 
     Ok(Projected::<MValue>::Set(set).into())
 }
@@ -146,7 +402,7 @@ pub(super) fn evaluate_member_access<'c>(
 
         let rhs = rhs.get(ctx);
 
-        lazy_value = match lhs.get_attr(rhs.read()) {
+        lazy_value = match lhs.get_attr_by_name(rhs) {
             Some(lazy_value) => lazy_value,
             None => return Err(eval::error::no_attribute_with_name(ast_span, rhs.read())),
         }
@@ -0,0 +1,347 @@
+//! A persistent hash array mapped trie that stores the attributes of large sets, keyed by the
+//! hashes of their names.
+//!
+//! Every node has a bitmap of the 5-bit hash chunks that it contains, and one slot per set bit
+//! that either holds an attribute or points to a node on the next level. Attributes whose hashes
+//! are equal end up in a collision node below the last level, which lists all of them.
+//!
+//! Nodes are never modified after they are created. Inserting copies the path from the root to
+//! the slot that changes, and merging two tries only copies the nodes that both of them have a
+//! slot in, so the result shares every other node with its inputs.
+
+use mulch_macros::GCPtr;
+
+use crate::{
+    eval::set::NamedMValue,
+    gc::{GCBox, GCString, GCVec, GarbageCollector},
+};
+
+#[cfg(test)]
+mod test;
+
+/// The number of hash bits that each level of the trie consumes
+const CHUNK_BITS: u32 = 5;
+
+pub type NodeRef = GCBox<Node>;
+
+#[derive(Clone, Copy, GCPtr)]
+pub struct Node {
+    /// The number of attributes in this subtree
+    len: usize,
+    /// The chunks that have a slot in this node. Collision nodes don't have a bitmap.
+    bitmap: Option<u32>,
+    /// One slot per set bit of `bitmap`, in the order of the chunks. Collision nodes only have
+    /// leaves.
+    slots: GCVec<Slot>,
+}
+
+#[derive(Clone, Copy, GCPtr)]
+#[repr(usize)]
+enum Slot {
+    Leaf(Entry),
+    Branch(NodeRef),
+}
+
+/// An attribute and the hash of its name
+#[derive(Clone, Copy, GCPtr)]
+pub struct Entry {
+    pub hash: u64,
+    pub attr: NamedMValue,
+}
+
+/// Builds a trie from attributes that all have different names. `entries` must not be empty.
+///
+/// # Safety
+/// - All `entries` must be valid and alive
+pub unsafe fn build(gc: &GarbageCollector, entries: &[Entry]) -> NodeRef {
+    debug_assert!(!entries.is_empty());
+
+    unsafe { build_node(gc, entries, 0) }
+}
+
+/// The number of attributes in the trie
+///
+/// # Safety
+/// - `root` must be valid and alive
+pub unsafe fn len(gc: &GarbageCollector, root: NodeRef) -> usize {
+    unsafe { root.get(gc).len }
+}
+
+/// Finds the attribute named `name`. `hash` is the hash of `name`, and `interned` is the same
+/// name as a `GCString`, if there is one.
+///
+/// # Safety
+/// - `root` must be valid and alive
+pub unsafe fn find(
+    gc: &GarbageCollector,
+    root: NodeRef,
+    hash: u64,
+    name: &str,
+    interned: Option<GCString>,
+) -> Option<NamedMValue> {
+    unsafe { find_at(gc, root, 0, hash, name, interned) }
+}
+
+/// Merges two tries. Attributes of `rhs` take precedence over attributes of `lhs` with the same
+/// name.
+///
+/// # Safety
+/// - `lhs` and `rhs` must be valid and alive
+pub unsafe fn merge(gc: &GarbageCollector, lhs: NodeRef, rhs: NodeRef) -> NodeRef {
+    unsafe { merge_nodes(gc, lhs, rhs, 0) }
+}
+
+/// Appends the attributes in the trie to `out`, in the order of their hashes
+///
+/// # Safety
+/// - `root` must be valid and alive
+pub unsafe fn collect(gc: &GarbageCollector, root: NodeRef, out: &mut Vec<NamedMValue>) {
+    for slot in unsafe { root.get(gc).slots.as_slice(gc) } {
+        match *slot {
+            Slot::Leaf(entry) => out.push(entry.attr),
+            Slot::Branch(child) => unsafe { collect(gc, child, out) },
+        }
+    }
+}
+
+/// Builds a node on the level of `shift` that contains `entries`
+///
+/// # Safety
+/// - All `entries` must be valid and alive
+unsafe fn build_node(gc: &GarbageCollector, entries: &[Entry], shift: u32) -> NodeRef {
+    if shift >= u64::BITS {
+        let slots: Vec<Slot> = entries.iter().copied().map(Slot::Leaf).collect();
+
+        return unsafe { new_node(gc, None, &slots) };
+    }
+
+    let mut buckets: [Vec<Entry>; 1 << CHUNK_BITS] = Default::default();
+
+    for &entry in entries {
+        buckets[chunk(entry.hash, shift)].push(entry);
+    }
+
+    let mut bitmap = 0u32;
+    let mut slots = Vec::new();
+
+    for (i, bucket) in buckets.iter().enumerate() {
+        let slot = match bucket.as_slice() {
+            [] => continue,
+            [entry] => Slot::Leaf(*entry),
+            _ => Slot::Branch(unsafe { build_node(gc, bucket, shift + CHUNK_BITS) }),
+        };
+
+        bitmap |= 1 << i;
+        slots.push(slot);
+    }
+
+    unsafe { new_node(gc, Some(bitmap), &slots) }
+}
+
+/// Allocates a node
+///
+/// # Safety
+/// - All `slots` must be valid and alive
+unsafe fn new_node(gc: &GarbageCollector, bitmap: Option<u32>, slots: &[Slot]) -> NodeRef {
+    let len = slots
+        .iter()
+        .map(|slot| match slot {
+            Slot::Leaf(_) => 1,
+            Slot::Branch(child) => unsafe { child.get(gc).len },
+        })
+        .sum();
+
+    unsafe {
+        let slots = GCVec::new(gc, slots);
+
+        GCBox::new_raw(gc, Node { len, bitmap, slots })
+    }
+}
+
+/// Like [`find`], but starts at a node on the level of `shift`
+///
+/// # Safety
+/// - `node_ref` must be valid and alive
+unsafe fn find_at(
+    gc: &GarbageCollector,
+    node_ref: NodeRef,
+    mut shift: u32,
+    hash: u64,
+    name: &str,
+    interned: Option<GCString>,
+) -> Option<NamedMValue> {
+    let mut node = unsafe { node_ref.get(gc) };
+
+    loop {
+        let slots = unsafe { node.slots.as_slice(gc) };
+
+        let Some(bitmap) = node.bitmap else {
+            return slots.iter().find_map(|slot| match slot {
+                Slot::Leaf(entry) if unsafe { entry.attr.has_name(gc, name, interned) } => {
+                    Some(entry.attr)
+                }
+                _ => None,
+            });
+        };
+
+        let bit = 1 << chunk(hash, shift);
+
+        if bitmap & bit == 0 {
+            return None;
+        }
+
+        match slots[slot_index(bitmap, bit)] {
+            Slot::Leaf(entry) => {
+                let found =
+                    entry.hash == hash && unsafe { entry.attr.has_name(gc, name, interned) };
+
+                return found.then_some(entry.attr);
+            }
+            Slot::Branch(child) => {
+                node = unsafe { child.get(gc) };
+                shift += CHUNK_BITS;
+            }
+        }
+    }
+}
+
+/// Merges two nodes on the level of `shift`
+///
+/// # Safety
+/// - `lhs` and `rhs` must be valid and alive
+unsafe fn merge_nodes(
+    gc: &GarbageCollector,
+    lhs_ref: NodeRef,
+    rhs_ref: NodeRef,
+    shift: u32,
+) -> NodeRef {
+    if lhs_ref.ptr() == rhs_ref.ptr() {
+        return rhs_ref;
+    }
+
+    let (lhs, rhs) = unsafe { (lhs_ref.get(gc), rhs_ref.get(gc)) };
+
+    // The slots are copied, because allocating may move them
+    let (lhs_slots, rhs_slots) = unsafe {
+        (
+            lhs.slots.as_slice(gc).to_vec(),
+            rhs.slots.as_slice(gc).to_vec(),
+        )
+    };
+
+    // Nodes on the same level are either both collision nodes or both have a bitmap
+    let (Some(lhs_bitmap), Some(rhs_bitmap)) = (lhs.bitmap, rhs.bitmap) else {
+        return rhs_slots.iter().fold(lhs_ref, |node, slot| {
+            let Slot::Leaf(entry) = *slot else {
+                unreachable!("collision nodes only have leaves")
+            };
+
+            unsafe { insert(gc, node, entry, shift) }
+        });
+    };
+
+    let bitmap = lhs_bitmap | rhs_bitmap;
+    let mut slots = Vec::with_capacity(bitmap.count_ones() as usize);
+    let (mut lhs_slots, mut rhs_slots) = (lhs_slots.into_iter(), rhs_slots.into_iter());
+
+    for i in 0..1 << CHUNK_BITS {
+        let bit = 1 << i;
+
+        let slot = match (lhs_bitmap & bit != 0, rhs_bitmap & bit != 0) {
+            (false, false) => continue,
+            (true, false) => lhs_slots.next().unwrap(),
+            (false, true) => rhs_slots.next().unwrap(),
+            (true, true) => {
+                let (lhs_slot, rhs_slot) = (lhs_slots.next().unwrap(), rhs_slots.next().unwrap());
+
+                unsafe { merge_slots(gc, lhs_slot, rhs_slot, shift + CHUNK_BITS) }
+            }
+        };
+
+        slots.push(slot);
+    }
+
+    unsafe { new_node(gc, Some(bitmap), &slots) }
+}
+
+/// Merges two slots that have the same position in their nodes. `shift` is the level of the
+/// nodes below the slots.
+///
+/// # Safety
+/// - `lhs` and `rhs` must be valid and alive
+unsafe fn merge_slots(gc: &GarbageCollector, lhs: Slot, rhs: Slot, shift: u32) -> Slot {
+    unsafe {
+        match (lhs, rhs) {
+            (Slot::Leaf(l), Slot::Leaf(r)) if l.hash == r.hash && same_name(gc, l.attr, r.attr) => {
+                rhs
+            }
+            (Slot::Leaf(l), Slot::Leaf(r)) => Slot::Branch(build_node(gc, &[l, r], shift)),
+            (Slot::Branch(l), Slot::Branch(r)) => Slot::Branch(merge_nodes(gc, l, r, shift)),
+            (Slot::Branch(node), Slot::Leaf(entry)) => Slot::Branch(insert(gc, node, entry, shift)),
+            (Slot::Leaf(entry), Slot::Branch(node)) => {
+                let name = entry.attr.name.0;
+
+                if find_at(gc, node, shift, entry.hash, name.get(gc), Some(name)).is_some() {
+                    rhs
+                } else {
+                    Slot::Branch(insert(gc, node, entry, shift))
+                }
+            }
+        }
+    }
+}
+
+/// Inserts `entry` into the node on the level of `shift`, replacing the attribute with the same
+/// name if there is one
+///
+/// # Safety
+/// - `node_ref` and `entry` must be valid and alive
+unsafe fn insert(gc: &GarbageCollector, node_ref: NodeRef, entry: Entry, shift: u32) -> NodeRef {
+    let node = unsafe { node_ref.get(gc) };
+    // The slots are copied, because allocating may move them
+    let mut slots = unsafe { node.slots.as_slice(gc) }.to_vec();
+    let leaf = Slot::Leaf(entry);
+
+    let Some(bitmap) = node.bitmap else {
+        let existing = slots.iter().position(|slot| match slot {
+            Slot::Leaf(other) => unsafe { same_name(gc, other.attr, entry.attr) },
+            Slot::Branch(_) => false,
+        });
+
+        match existing {
+            Some(idx) => slots[idx] = leaf,
+            None => slots.push(leaf),
+        }
+
+        return unsafe { new_node(gc, None, &slots) };
+    };
+
+    let bit = 1 << chunk(entry.hash, shift);
+    let idx = slot_index(bitmap, bit);
+
+    if bitmap & bit == 0 {
+        slots.insert(idx, leaf);
+    } else {
+        slots[idx] = unsafe { merge_slots(gc, slots[idx], leaf, shift + CHUNK_BITS) };
+    }
+
+    unsafe { new_node(gc, Some(bitmap | bit), &slots) }
+}
+
+/// Whether two attributes have the same name
+///
+/// # Safety
+/// - `a` and `b` must be valid and alive
+unsafe fn same_name(gc: &GarbageCollector, a: NamedMValue, b: NamedMValue) -> bool {
+    unsafe { a.has_name(gc, b.name.0.get(gc), Some(b.name.0)) }
+}
+
+/// The position of the slot for `bit` in a node with the given bitmap
+fn slot_index(bitmap: u32, bit: u32) -> usize {
+    (bitmap & (bit - 1)).count_ones() as usize
+}
+
+/// The 5-bit chunk of `hash` that is used at the level of the trie with the given shift
+fn chunk(hash: u64, shift: u32) -> usize {
+    (hash >> shift) as usize & ((1 << CHUNK_BITS) - 1)
+}
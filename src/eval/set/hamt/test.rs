@@ -0,0 +1,186 @@
+use crate::{
+    error::{FullSpan, Spanned},
+    eval::{
+        LazyValue,
+        set::{
+            NamedMValue,
+            hamt::{self, Entry, NodeRef, Slot},
+        },
+    },
+    gc::{
+        GCString, GarbageCollector,
+        safety::{GCCtx, let_gc_and_context},
+    },
+};
+
+/// Generates pseudo-random hashes
+fn hashes(len: usize) -> Vec<u64> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+
+            state
+        })
+        .collect()
+}
+
+/// Creates an attribute with the given hash. The file id of its span tells apart attributes with
+/// the same name.
+fn entry(ctx: &GCCtx, name: &str, hash: u64, file_id: usize) -> Entry {
+    let span = FullSpan::new(0..0, file_id);
+    let value = LazyValue::from_value(ctx, GCString::new(ctx, name).into(), span);
+
+    Entry {
+        hash,
+        attr: NamedMValue {
+            name: Spanned(GCString::new(ctx, name).raw(), span),
+            value: value.raw(),
+        },
+    }
+}
+
+/// Creates attributes named `attr_{i}` for every `i` in `range`
+fn entries(ctx: &GCCtx, range: std::ops::Range<usize>, file_id: usize) -> Vec<Entry> {
+    let hashes = hashes(range.end);
+
+    range
+        .map(|i| entry(ctx, &format!("attr_{i}"), hashes[i], file_id))
+        .collect()
+}
+
+/// Looks up the attribute with the name and hash of `entry` and returns the file id of its span
+fn find(gc: &GarbageCollector, trie: NodeRef, entry: &Entry) -> Option<usize> {
+    unsafe {
+        let name = entry.attr.name.0;
+
+        hamt::find(gc, trie, entry.hash, name.get(gc), None).map(|attr| attr.name.1.file_id)
+    }
+}
+
+/// The nodes that the slots of the root of `trie` point to
+fn root_branches(gc: &GarbageCollector, trie: NodeRef) -> Vec<Option<usize>> {
+    unsafe { trie.get(gc).slots.as_slice(gc) }
+        .iter()
+        .map(|slot| match slot {
+            Slot::Leaf(_) => None,
+            Slot::Branch(node) => Some(node.ptr().get()),
+        })
+        .collect()
+}
+
+#[test]
+fn lookup() {
+    let_gc_and_context!(gc, ctx);
+
+    let entries = entries(ctx, 0..10_000, 0);
+    let trie = unsafe { hamt::build(ctx, &entries) };
+
+    assert_eq!(unsafe { hamt::len(&gc, trie) }, 10_000);
+    // Every chunk has a slot in the root, which must not be mistaken for a collision node
+    assert_eq!(unsafe { trie.get(&gc) }.bitmap, Some(u32::MAX));
+
+    for entry in &entries {
+        assert_eq!(find(&gc, trie, entry), Some(0));
+    }
+
+    // The same hash with a different name
+    let missing = Entry {
+        hash: entries[42].hash,
+        ..entry(ctx, "missing", 0, 0)
+    };
+    assert_eq!(find(&gc, trie, &missing), None);
+
+    let mut attrs = Vec::new();
+    unsafe { hamt::collect(&gc, trie, &mut attrs) };
+    assert_eq!(attrs.len(), 10_000);
+}
+
+#[test]
+fn collisions() {
+    let_gc_and_context!(gc, ctx);
+
+    // The first two hashes only differ in their last chunk, and the rest are equal
+    let entries = [
+        entry(ctx, "a", 0x1f, 0),
+        entry(ctx, "b", 0x1f | 1 << 63, 0),
+        entry(ctx, "c", 42, 0),
+        entry(ctx, "d", 42, 0),
+        entry(ctx, "e", 42, 0),
+    ];
+    let trie = unsafe { hamt::build(ctx, &entries) };
+
+    for entry in &entries {
+        assert_eq!(find(&gc, trie, entry), Some(0));
+    }
+    assert_eq!(find(&gc, trie, &entry(ctx, "f", 42, 0)), None);
+
+    // Replaces `d` and adds `f` to the collision node
+    let rhs = [entry(ctx, "d", 42, 1), entry(ctx, "f", 42, 1)];
+    let rhs = unsafe { hamt::build(ctx, &rhs) };
+    let merged = unsafe { hamt::merge(ctx, trie, rhs) };
+
+    assert_eq!(unsafe { hamt::len(&gc, merged) }, 6);
+    assert_eq!(find(&gc, merged, &entries[2]), Some(0));
+    assert_eq!(find(&gc, merged, &entries[3]), Some(1));
+    assert_eq!(find(&gc, merged, &entry(ctx, "f", 42, 0)), Some(1));
+}
+
+#[test]
+fn merge() {
+    let_gc_and_context!(gc, ctx);
+
+    let lhs_entries = entries(ctx, 0..1000, 0);
+    let rhs_entries = entries(ctx, 500..1500, 1);
+
+    let (lhs, rhs) = unsafe {
+        (
+            hamt::build(ctx, &lhs_entries),
+            hamt::build(ctx, &rhs_entries),
+        )
+    };
+    let merged = unsafe { hamt::merge(ctx, lhs, rhs) };
+
+    assert_eq!(unsafe { hamt::len(&gc, merged) }, 1500);
+
+    for entry in &lhs_entries[..500] {
+        assert_eq!(find(&gc, merged, entry), Some(0));
+    }
+    for entry in &rhs_entries {
+        assert_eq!(find(&gc, merged, entry), Some(1));
+    }
+
+    // The second trie takes precedence
+    let merged = unsafe { hamt::merge(ctx, rhs, lhs) };
+
+    assert_eq!(unsafe { hamt::len(&gc, merged) }, 1500);
+    assert_eq!(find(&gc, merged, &rhs_entries[0]), Some(0));
+    assert_eq!(find(&gc, merged, &rhs_entries[999]), Some(1));
+
+    // The inputs are unchanged
+    assert_eq!(unsafe { hamt::len(&gc, lhs) }, 1000);
+    assert_eq!(find(&gc, lhs, &lhs_entries[700]), Some(0));
+    assert_eq!(find(&gc, lhs, &rhs_entries[999]), None);
+}
+
+#[test]
+fn merge_shares_nodes() {
+    let_gc_and_context!(gc, ctx);
+
+    let lhs = unsafe { hamt::build(ctx, &entries(ctx, 0..1000, 0)) };
+
+    assert_eq!(unsafe { hamt::merge(ctx, lhs, lhs) }.ptr(), lhs.ptr());
+
+    // Only the first slot of the root changes
+    let rhs = unsafe { hamt::build(ctx, &[entry(ctx, "new", 0, 1)]) };
+    let merged = unsafe { hamt::merge(ctx, lhs, rhs) };
+
+    let (lhs_branches, merged_branches) = (root_branches(&gc, lhs), root_branches(&gc, merged));
+
+    assert_eq!(lhs_branches.len(), 32);
+    assert_ne!(lhs_branches[0], merged_branches[0]);
+    assert_eq!(lhs_branches[1..], merged_branches[1..]);
+}
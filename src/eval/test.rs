@@ -0,0 +1,681 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    error::{
+        Diagnostic, FullSpan, PartialSpanned, SourceDB, Spanned, dresult_unwrap, pdresult_unwrap,
+    },
+    eval::{
+        self, AccessError, DeserializeError, MValue, Scope, SerializeError, evaluate, from_value,
+        to_set, to_value,
+    },
+    gc::{
+        GarbageCollector,
+        safety::{GC, GCCtx, gc_args, let_gc_and_context},
+    },
+    lexer::Lexer,
+    parser::{Parse, Parser, ast},
+};
+
+/// A garbage collector that collects at every safe point. This makes rooting mistakes in the
+/// evaluator fail deterministically.
+fn stressed_gc() -> GarbageCollector {
+    GarbageCollector::builder().stress(true).build()
+}
+
+/// Evaluates `source` and deserializes the result
+fn deserialize<T: DeserializeOwned>(source: &str) -> Result<T, DeserializeError> {
+    deserialize_with_variables(source, &())
+}
+
+/// Evaluates `source` with the attributes of `variables` bound as variables and deserializes the
+/// result
+fn deserialize_with_variables<T: DeserializeOwned, V: Serialize>(
+    source: &str,
+    variables: &V,
+) -> Result<T, DeserializeError> {
+    evaluate_with_variables(source, variables, |ctx, value, span| {
+        // SAFETY: `value` was just evaluated
+        let value = unsafe { GC::new(ctx, value) };
+
+        from_value(gc_args!(ctx, value), span)
+    })
+}
+
+/// Evaluates `source` with the attributes of `variables` bound as variables and passes the result
+/// and its span to `f`
+fn evaluate_with_variables<R, V: Serialize>(
+    source: &str,
+    variables: &V,
+    f: impl FnOnce(&mut GCCtx, MValue, FullSpan) -> R,
+) -> R {
+    let db = SourceDB::new();
+    db.add("test.mulch".into(), source.to_owned());
+
+    let tokens = dresult_unwrap(Lexer::new(source, 0).lex(), &db);
+
+    let_gc_and_context!(gc = stressed_gc(), ctx);
+
+    let parser = Parser::new_default(&gc);
+
+    let ast = pdresult_unwrap(
+        PartialSpanned::<ast::Expression>::parse(&parser, &tokens),
+        0,
+        &db,
+    )
+    .unwrap();
+
+    let span = FullSpan::new(ast.1, 0);
+    let ast = unsafe { GC::new(ctx, ast) };
+
+    let variables = to_set(ctx, variables, FullSpan::new(0..0, 0)).unwrap();
+    let scope = Scope::new_child(ctx, Scope::new_global(ctx), variables);
+
+    let value = dresult_unwrap(evaluate(gc_args!(ctx, ast.with_file_id(0), scope)), &db).raw();
+
+    f(ctx, value, span)
+}
+
+/// Serializes `value` and deserializes it back
+fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> Result<T, SerializeError> {
+    let_gc_and_context!(gc = stressed_gc(), ctx);
+
+    let span = FullSpan::new(0..0, 0);
+    let value = to_value(ctx, value, span)?;
+
+    Ok(from_value(gc_args!(ctx, value), span).unwrap())
+}
+
+/// The span of the first occurrence of `needle` in `source`
+fn span_of(source: &str, needle: &str) -> FullSpan {
+    let start = source.find(needle).unwrap();
+
+    FullSpan::new(start..start + needle.len(), 0)
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Server {
+    name: String,
+    port: u16,
+    ratio: f64,
+    tags: Vec<String>,
+    limits: Limits,
+    backup: Option<Box<Server>>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Limits {
+    connections: u32,
+    timeout: i64,
+}
+
+#[test]
+fn deserialize_struct() {
+    let server: Server = deserialize(
+        r#"{
+            name = "main";
+            port = 8080;
+            ratio = 0.25;
+            tags = ["a", "b"];
+            limits = { connections = 100; timeout = 30; };
+            unused = "ignored";
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        server,
+        Server {
+            name: "main".into(),
+            port: 8080,
+            ratio: 0.25,
+            tags: vec!["a".into(), "b".into()],
+            limits: Limits {
+                connections: 100,
+                timeout: 30,
+            },
+            backup: None,
+        }
+    );
+}
+
+#[test]
+fn deserialize_collections() {
+    let map: BTreeMap<String, Vec<u128>> =
+        deserialize("{ b = [340282366920938463463374607431768211455]; a = []; }").unwrap();
+
+    assert_eq!(
+        map,
+        BTreeMap::from([("a".into(), vec![]), ("b".into(), vec![u128::MAX])])
+    );
+
+    // Below `i64::MIN`
+    for value in [i128::from(i64::MIN) - 1, i128::MIN] {
+        let variables = BTreeMap::from([("x", value)]);
+        assert_eq!(
+            deserialize_with_variables::<i128, _>("x", &variables).unwrap(),
+            value
+        );
+    }
+
+    let err = deserialize::<i128>("340282366920938463463374607431768211455").unwrap_err();
+    assert!(err.to_string().ends_with("which is out of range"));
+
+    let nested: Vec<(String, f32)> = deserialize(r#"[["x", 1.5], ["y", 2]]"#).unwrap();
+
+    assert_eq!(nested, vec![("x".into(), 1.5), ("y".into(), 2.0)]);
+}
+
+#[test]
+fn deserialize_enums() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Color {
+        Red,
+        Rgb(u8, u8, u8),
+        Named { name: String },
+        Gray(u8),
+    }
+
+    let colors: Vec<Color> = deserialize(
+        r#"["Red", { Rgb = [1, 2, 3]; }, { Named = { name = "teal"; }; }, { Gray = 128; }]"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        colors,
+        vec![
+            Color::Red,
+            Color::Rgb(1, 2, 3),
+            Color::Named {
+                name: "teal".into()
+            },
+            Color::Gray(128),
+        ]
+    );
+}
+
+#[test]
+fn ignored_attributes_are_not_evaluated() {
+    #[derive(Debug, Deserialize)]
+    struct Used {
+        used: u8,
+    }
+
+    let value: Used = deserialize("{ used = 1; unused = {}.missing; }").unwrap();
+    assert_eq!(value.used, 1);
+
+    let source = "{ used = {}.missing; }";
+    let err = deserialize::<Used>(source).unwrap_err();
+
+    assert_eq!(err.path(), "used");
+    assert_eq!(
+        Diagnostic::from(err),
+        eval::error::no_attribute_with_name(span_of(source, "{}.missing"), "missing")
+    );
+}
+
+#[test]
+fn invalid_values() {
+    let source = r#"{ name = "a"; port = "http"; }"#;
+    let err = deserialize::<Limits>(source).unwrap_err();
+
+    assert_eq!(err.path(), "");
+    assert_eq!(err.span(), Some(span_of(source, source)));
+    assert_eq!(err.to_string(), "missing field `connections`");
+
+    let source = r#"{ connections = "many"; timeout = 0; }"#;
+    let err = deserialize::<Limits>(source).unwrap_err();
+
+    assert_eq!(err.path(), "connections");
+    assert_eq!(err.span(), Some(span_of(source, r#""many""#)));
+    assert_eq!(
+        err.to_string(),
+        r#"`connections`: expected u32, got string "many""#
+    );
+    assert_eq!(
+        Diagnostic::from(err),
+        eval::error::invalid_value(
+            span_of(source, r#""many""#),
+            "connections",
+            r#"expected u32, got string "many""#
+        )
+    );
+
+    let source = "{ limits = { connections = 1; timeout = 0.5; }; }";
+    let err = deserialize::<BTreeMap<String, Limits>>(source).unwrap_err();
+
+    assert_eq!(err.path(), "limits.timeout");
+    assert_eq!(err.span(), Some(span_of(source, "0.5")));
+    assert_eq!(
+        err.to_string(),
+        "`limits.timeout`: expected i64, got 0.5, which is not an integer"
+    );
+
+    let source = "{ values = [1, 2, 300]; }";
+    let err = deserialize::<BTreeMap<String, Vec<u8>>>(source).unwrap_err();
+
+    assert_eq!(err.path(), "values[2]");
+    assert_eq!(err.span(), Some(span_of(source, "[1, 2, 300]")));
+    assert_eq!(
+        err.to_string(),
+        "`values[2]`: expected u8, got 300, which is out of range"
+    );
+
+    // Errors that aren't tied to a value
+    let err = <DeserializeError as serde::de::Error>::custom("no value");
+    assert_eq!(
+        Diagnostic::from(err),
+        eval::error::invalid_value_without_span("no value")
+    );
+}
+
+#[test]
+fn undefined_variables() {
+    let source = "{ a = missing; }";
+    let err = deserialize::<BTreeMap<String, u8>>(source).unwrap_err();
+
+    assert_eq!(err.path(), "a");
+    assert_eq!(
+        Diagnostic::from(err),
+        eval::error::undefined_variable(span_of(source, "missing"), "missing")
+    );
+}
+
+#[test]
+fn serialize_round_trip() {
+    let server = Server {
+        name: "main".into(),
+        port: 8080,
+        ratio: 0.1,
+        tags: vec!["a".into(), "b".into()],
+        limits: Limits {
+            connections: 100,
+            timeout: -30,
+        },
+        backup: Some(Box::new(Server {
+            name: "backup".into(),
+            port: 0,
+            ratio: -1.5,
+            tags: vec![],
+            limits: Limits {
+                connections: u32::MAX,
+                timeout: i64::MIN,
+            },
+            backup: None,
+        })),
+    };
+
+    assert_eq!(round_trip(&server), Ok(server));
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Point,
+        Circle(u128),
+        Rect(u8, u8),
+        Named { name: char },
+    }
+
+    let shapes = vec![
+        Shape::Point,
+        Shape::Circle(u128::MAX),
+        Shape::Rect(3, 4),
+        Shape::Named { name: 'x' },
+    ];
+
+    assert_eq!(round_trip(&shapes), Ok(shapes));
+}
+
+#[test]
+fn variables_from_rust_values() {
+    #[derive(Serialize)]
+    struct Git {
+        commit: String,
+        dirty_files: Vec<String>,
+    }
+
+    #[derive(Serialize)]
+    struct Globals {
+        git: Git,
+        args: BTreeMap<String, String>,
+        #[serde(rename = "jobs")]
+        job_count: u32,
+    }
+
+    let globals = Globals {
+        git: Git {
+            commit: "abc123".into(),
+            dirty_files: vec!["src/main.rs".into()],
+        },
+        args: BTreeMap::from([("profile".into(), "release".into())]),
+        job_count: 8,
+    };
+
+    let value: BTreeMap<String, Vec<String>> = deserialize_with_variables(
+        "{ commit = [git.commit]; profile = [args.profile]; files = git.dirty_files; }",
+        &globals,
+    )
+    .unwrap();
+
+    assert_eq!(
+        value,
+        BTreeMap::from([
+            ("commit".into(), vec!["abc123".into()]),
+            ("files".into(), vec!["src/main.rs".into()]),
+            ("profile".into(), vec!["release".into()]),
+        ])
+    );
+
+    let jobs: u32 = deserialize_with_variables("{ jobs = jobs; }.jobs", &globals).unwrap();
+    assert_eq!(jobs, 8);
+}
+
+#[test]
+fn serialize_errors() {
+    fn error<T: Serialize + ?Sized>(value: &T) -> SerializeError {
+        let_gc_and_context!(gc = stressed_gc(), ctx);
+
+        to_value(ctx, value, FullSpan::new(0..0, 0)).unwrap_err()
+    }
+
+    let err = error(&true);
+    assert_eq!(err.path(), "");
+    assert_eq!(err.to_string(), "booleans cannot be represented");
+
+    let err = error(&BTreeMap::from([("a", vec![Some(1), None])]));
+    assert_eq!(err.path(), "a[1]");
+    assert_eq!(
+        err.to_string(),
+        "`a[1]`: `None` can only be used as the value of an attribute"
+    );
+
+    let err = error(&vec![BTreeMap::from([(1, 2)])]);
+    assert_eq!(err.path(), "[0]");
+    assert_eq!(err.to_string(), "`[0]`: attribute names must be strings");
+
+    let err = error(&[(f64::NAN,)]);
+    assert_eq!(err.path(), "[0][0]");
+    assert_eq!(
+        err.to_string(),
+        "`[0][0]`: `NaN` cannot be represented as a number"
+    );
+
+    let_gc_and_context!(gc = stressed_gc(), ctx);
+
+    let span = FullSpan::new(0..0, 0);
+
+    assert_eq!(
+        to_set(ctx, &[1, 2], span).unwrap_err().to_string(),
+        "expected a struct or map"
+    );
+
+    // `None` attributes are left out
+    let set = to_set(ctx, &BTreeMap::from([("a", Some(1)), ("b", None)]), span).unwrap();
+    assert_eq!(set.len(), 1);
+}
+
+#[test]
+fn path_queries() {
+    let source = r#"{ a = { b = [0, 1, 2, { c = "found"; }]; }; unused = {}.missing; }"#;
+
+    evaluate_with_variables(source, &(), |ctx, value, span| {
+        let value = unsafe { GC::new(ctx, value) };
+        let found = MValue::get(gc_args!(ctx, value), span, "a.b[3].c").unwrap();
+
+        assert_eq!(found.1, span_of(source, r#""found""#));
+        assert_eq!(found.as_str().unwrap().read(), "found");
+    });
+
+    let errors = [
+        (
+            "a.b[4]",
+            AccessError::IndexOutOfBounds {
+                index: 4,
+                len: 4,
+                span: span_of(source, r#"[0, 1, 2, { c = "found"; }]"#),
+            },
+        ),
+        (
+            "a.b[1].c",
+            AccessError::WrongType {
+                expected: "set",
+                found: "number",
+                span: span_of(source, r#"[0, 1, 2, { c = "found"; }]"#),
+            },
+        ),
+        (
+            "a.d",
+            AccessError::NoAttribute {
+                name: "d".to_owned(),
+                span: span_of(source, r#"{ b = [0, 1, 2, { c = "found"; }]; }"#),
+            },
+        ),
+        (
+            "unused",
+            AccessError::Eval(eval::error::no_attribute_with_name(
+                span_of(source, "{}.missing"),
+                "missing",
+            )),
+        ),
+    ];
+
+    for (path, expected) in errors {
+        let err = evaluate_with_variables(source, &(), |ctx, value, span| {
+            let value = unsafe { GC::new(ctx, value) };
+            MValue::get(gc_args!(ctx, value), span, path).unwrap_err()
+        });
+
+        assert_eq!(err, expected, "{path}");
+    }
+}
+
+#[test]
+fn accessors() {
+    let source = r#"{ string = "a"; number = 1; list = [1, 2]; set = { x = 1; y = 2; }; }"#;
+
+    evaluate_with_variables(source, &(), |ctx, value, span| {
+        let value = Spanned(unsafe { GC::new(ctx, value) }, span);
+        let set = value.as_set().unwrap();
+
+        let names: Vec<String> = set
+            .attributes()
+            .map(|(name, _)| name.read().to_owned())
+            .collect();
+        assert_eq!(names, ["list", "number", "set", "string"]);
+
+        let err = value.as_list().unwrap_err();
+        assert_eq!(err.span(), Some(span));
+        assert_eq!(err.to_string(), "expected list, got set");
+        assert_eq!(
+            Diagnostic::from(err),
+            eval::error::wrong_type(span, "list", "set")
+        );
+    });
+
+    let found_types = ["string", "number", "list", "set"].map(|path| {
+        evaluate_with_variables(source, &(), |ctx, value, span| {
+            let value = unsafe { GC::new(ctx, value) };
+            let value = MValue::get(gc_args!(ctx, value), span, path).unwrap();
+
+            [
+                value.as_str().is_ok(),
+                value.as_number().is_ok(),
+                value.as_list().is_ok(),
+                value.as_set().is_ok(),
+            ]
+        })
+    });
+
+    assert_eq!(
+        found_types,
+        [
+            [true, false, false, false],
+            [false, true, false, false],
+            [false, false, true, false],
+            [false, false, false, true],
+        ]
+    );
+}
+
+#[test]
+fn builtins() {
+    let value: (u8, u8, String, Vec<String>) = deserialize(
+        r#"[length([1, 2, 3]), string_length("héllo"), to_string(1.5), attribute_names({ b = 1; a = 2; })]"#,
+    )
+    .unwrap();
+    assert_eq!(
+        value,
+        (3, 5, "1.5".to_owned(), vec!["a".to_owned(), "b".to_owned()])
+    );
+
+    let value: Vec<String> = deserialize(r#"lines("a\nb c\r\n\nd")"#).unwrap();
+    assert_eq!(value, ["a", "b c", "", "d"]);
+
+    // Every line is allocated while the string is borrowed
+    #[derive(Serialize)]
+    struct Text {
+        text: String,
+    }
+
+    let expected: Vec<String> = (0..2000)
+        .map(|i| format!("a line that is too long to be stored inline {i}"))
+        .collect();
+    let text = Text {
+        text: expected.join("\n"),
+    };
+    let value: Vec<String> = deserialize_with_variables("lines(text)", &text).unwrap();
+    assert_eq!(value, expected);
+
+    let source = "{ a = lines(1); }";
+    let err = deserialize::<BTreeMap<String, Vec<String>>>(source).unwrap_err();
+    assert_eq!(
+        Diagnostic::from(err),
+        eval::error::wrong_type(span_of(source, "1"), "string", "number")
+    );
+
+    let source = r#"{ a = length("abc"); }"#;
+    let err = deserialize::<BTreeMap<String, u8>>(source).unwrap_err();
+    assert_eq!(
+        Diagnostic::from(err),
+        eval::error::wrong_type(span_of(source, r#""abc""#), "list", "string")
+    );
+
+    let source = "{ a = length([1], [2]); }";
+    let err = deserialize::<BTreeMap<String, u8>>(source).unwrap_err();
+    assert_eq!(
+        Diagnostic::from(err),
+        eval::error::wrong_number_of_arguments(span_of(source, "length([1], [2])"), "length", 1, 2)
+    );
+
+    let source = "{ a = {}(1); }";
+    let err = deserialize::<BTreeMap<String, u8>>(source).unwrap_err();
+    assert_eq!(
+        Diagnostic::from(err),
+        eval::error::wrong_type(span_of(source, "{}"), "function", "set")
+    );
+
+    let source = "{ a = length; }";
+    let err = deserialize::<BTreeMap<String, u8>>(source).unwrap_err();
+    assert_eq!(err.to_string(), "`a`: expected u8, got function");
+}
+
+#[test]
+fn large_sets() {
+    // Large enough to be looked up through a hash index
+    let names: Vec<String> = (0..100)
+        .map(|i| format!("an_attribute_with_a_long_name_{i}"))
+        .collect();
+
+    let attributes: String = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| format!("{name} = {i}; "))
+        .collect();
+
+    let source = format!("{{ {attributes}}}.an_attribute_with_a_long_name_42");
+    assert_eq!(deserialize::<u8>(&source).unwrap(), 42);
+
+    let large_set = format!("{{ {attributes}}}");
+    let source = format!("{{ a = {large_set}.missing; }}");
+    let err = deserialize::<BTreeMap<String, u8>>(&source).unwrap_err();
+    assert_eq!(
+        Diagnostic::from(err),
+        eval::error::no_attribute_with_name(
+            span_of(&source, &format!("{large_set}.missing")),
+            "missing"
+        )
+    );
+
+    // Sets that are created from Rust values
+    let variables: BTreeMap<String, usize> = names.iter().cloned().zip(0..).collect();
+    let value: usize =
+        deserialize_with_variables("an_attribute_with_a_long_name_99", &variables).unwrap();
+    assert_eq!(value, 99);
+
+    let value: BTreeMap<String, usize> = deserialize(&large_set).unwrap();
+    assert_eq!(value, variables);
+}
+
+#[test]
+fn merge() {
+    let value: BTreeMap<String, u8> =
+        deserialize("merge({ a = 1; b = 2; }, { b = 3; c = 4; })").unwrap();
+    assert_eq!(
+        value,
+        BTreeMap::from([("a".into(), 1), ("b".into(), 3), ("c".into(), 4)])
+    );
+
+    let lhs: BTreeMap<String, usize> = (0..100).map(|i| (format!("lhs_{i}"), i)).collect();
+    let rhs: String = (50..150)
+        .map(|i| format!("lhs_{i} = {}; ", i * 2))
+        .collect();
+
+    let value: BTreeMap<String, usize> =
+        deserialize_with_variables(&format!("merge({{ {rhs}}}, {{}})"), &lhs).unwrap();
+    assert_eq!(value.len(), 100);
+
+    #[derive(Serialize)]
+    struct Variables {
+        lhs: BTreeMap<String, usize>,
+    }
+
+    let value: BTreeMap<String, usize> = deserialize_with_variables(
+        &format!("merge(lhs, {{ {rhs}}})"),
+        &Variables { lhs: lhs.clone() },
+    )
+    .unwrap();
+
+    let mut expected = lhs;
+    expected.extend((50..150).map(|i| (format!("lhs_{i}"), i * 2)));
+
+    assert_eq!(value, expected);
+}
+
+#[test]
+fn list_builtins() {
+    let value: Vec<u8> = deserialize("append([1, 2], 3)").unwrap();
+    assert_eq!(value, vec![1, 2, 3]);
+
+    let value: Vec<u8> = deserialize("concat([1, 2], concat([], [3, 4]))").unwrap();
+    assert_eq!(value, vec![1, 2, 3, 4]);
+
+    let value: Vec<u8> = deserialize("slice([1, 2, 3, 4, 5], 1, 4)").unwrap();
+    assert_eq!(value, vec![2, 3, 4]);
+
+    let long: Vec<usize> = (0..200).collect();
+    let value: Vec<usize> =
+        deserialize_with_variables("slice(concat(long, long), 150, 250)", &Long { long }).unwrap();
+    assert_eq!(value, (150..200).chain(0..50).collect::<Vec<_>>());
+
+    #[derive(Serialize)]
+    struct Long {
+        long: Vec<usize>,
+    }
+
+    let source = "{ a = slice([1, 2], 1, 3); }";
+    let err = deserialize::<BTreeMap<String, Vec<u8>>>(source).unwrap_err();
+    assert_eq!(
+        Diagnostic::from(err),
+        eval::error::invalid_slice(span_of(source, "slice([1, 2], 1, 3)"), 1, 3, 2)
+    );
+}
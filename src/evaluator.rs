@@ -0,0 +1,529 @@
+//! A high-level API for embedding `mulch`
+
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    ffi::OsString,
+    fmt::{Debug, Display},
+    mem::ManuallyDrop,
+    path::Path,
+    rc::Rc,
+};
+
+use copyspan::Span;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    error::{Diagnostic, FullSpan, PartialSpanned, SourceDB, Spanned, span_of},
+    eval::{self, Builtin, MValue, Scope, SerializeError, Set, evaluate, from_value, to_set},
+    gc::{
+        CollectionEvent, GCHandle, GCStats, GarbageCollector,
+        dump::HeapDump,
+        safety::{GC, GCCtx, Projected, gc_args, rebind},
+    },
+    lexer::{self, Lexer},
+    parser::{Parse, Parser, ast},
+};
+
+#[cfg(test)]
+mod test;
+
+/// Evaluates `mulch` source code.
+///
+/// This owns the garbage collector, the source files that have been evaluated, and the prelude
+/// (the variables that are available to every file).
+///
+/// ```
+/// use mulch::Evaluator;
+///
+/// let mut evaluator = Evaluator::new();
+/// evaluator.define("version", "1.2.3").unwrap();
+///
+/// let config = evaluator
+///     .eval_str("config.mulch", "{ server = { port = 8080; version = version; }; }")
+///     .unwrap();
+///
+/// let port: u16 = config.get_path(&["server", "port"]).unwrap().deserialize().unwrap();
+/// assert_eq!(port, 8080);
+/// ```
+pub struct Evaluator {
+    shared: Rc<Shared>,
+    /// The file that values defined with [`Evaluator::define`] are attributed to
+    prelude_file_id: usize,
+    warnings: Vec<Diagnostic>,
+}
+
+/// The state that is shared between an [`Evaluator`] and its [`Value`]s
+struct Shared {
+    gc: GarbageCollector,
+    db: SourceDB,
+    prelude: Cell<Option<GCHandle<Scope>>>,
+    /// Whether a context currently exists for `gc`
+    in_use: Cell<bool>,
+}
+
+impl Shared {
+    /// Runs `f` with a garbage-collection context.
+    ///
+    /// # Panics
+    /// Panics if this is called while `f` is running (eg from a `Deserialize` implementation)
+    fn with_ctx<R>(&self, f: impl FnOnce(&mut GCCtx) -> R) -> R {
+        struct InUse<'a>(&'a Cell<bool>);
+
+        impl Drop for InUse<'_> {
+            fn drop(&mut self) {
+                self.0.set(false);
+            }
+        }
+
+        assert!(
+            !self.in_use.replace(true),
+            "an evaluator cannot be used while it is already evaluating something"
+        );
+        let _in_use = InUse(&self.in_use);
+
+        // SAFETY: `in_use` guarantees that this is the only context for `self.gc`
+        let mut ctx = unsafe { GCCtx::new(&self.gc) };
+
+        f(&mut ctx)
+    }
+
+    fn prelude<'c>(&self, ctx: &'c GCCtx) -> GC<'c, Scope> {
+        let handle = self.prelude.take().unwrap();
+
+        // SAFETY: the prelude handle is valid until it is replaced
+        let prelude = unsafe { GC::new(ctx, handle.get(&self.gc)) };
+
+        self.prelude.set(Some(handle));
+
+        prelude
+    }
+
+    /// Replaces the prelude with a child scope that contains `variables`
+    fn extend_prelude(&self, ctx: &GCCtx, variables: GC<Set>) {
+        let prelude = Scope::new_child(ctx, self.prelude(ctx), variables);
+
+        // SAFETY: `prelude` was just allocated, and the old prelude handle is valid
+        unsafe {
+            let old_prelude = self
+                .prelude
+                .replace(Some(self.gc.push_handle(prelude.raw())));
+            old_prelude.unwrap().free(&self.gc);
+        }
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        if let Some(prelude) = self.prelude.take() {
+            // SAFETY: the prelude handle is valid until it is replaced
+            unsafe { prelude.free(&self.gc) }
+        }
+    }
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Evaluator {
+    pub fn new() -> Self {
+        Self::with_garbage_collector(GarbageCollector::new())
+    }
+
+    /// Creates an evaluator that stores its values in `gc`. This allows the heap to be configured
+    /// with [`GarbageCollector::builder`].
+    pub fn with_garbage_collector(gc: GarbageCollector) -> Self {
+        let shared = Rc::new(Shared {
+            gc,
+            db: SourceDB::new(),
+            prelude: Cell::new(None),
+            in_use: Cell::new(false),
+        });
+
+        let prelude_file_id = shared.db.add("<prelude>".into(), String::new());
+
+        shared.with_ctx(|ctx| {
+            // SAFETY: the global scope was just allocated
+            let prelude = unsafe { shared.gc.push_handle(Scope::new_global(ctx).raw()) };
+            shared.prelude.set(Some(prelude));
+        });
+
+        Self {
+            shared,
+            prelude_file_id,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// The source files that have been evaluated. This is needed to display diagnostics.
+    pub fn source_db(&self) -> &SourceDB {
+        &self.shared.db
+    }
+
+    /// Statistics about the garbage collector that stores the evaluated values
+    pub fn gc_stats(&self) -> GCStats {
+        self.shared.gc.stats()
+    }
+
+    /// Sets a function that is called after every garbage-collection cycle. This can be used to
+    /// tune memory usage for large evaluations.
+    pub fn set_collection_callback(&self, callback: impl FnMut(&CollectionEvent) + 'static) {
+        self.shared
+            .gc
+            .set_collection_callback(Some(Box::new(callback)));
+    }
+
+    /// Does a bounded amount of garbage-collection work, copying at least `budget` bytes unless
+    /// there is nothing left to do. This can be called while the program is idle to spread the
+    /// cost of collecting a large heap over several short pauses. Returns whether a major
+    /// collection is still in progress, in which case calling this again makes further progress.
+    ///
+    /// # Panics
+    /// Panics if this evaluator is already evaluating something
+    pub fn collect_slice(&self, budget: usize) -> bool {
+        self.shared.with_ctx(|ctx| ctx.collect_slice(budget))
+    }
+
+    /// Records every value that this evaluator and its [`Value`]s keep alive. The dump can be
+    /// written to a file with [`HeapDump::write_to`] and analyzed with the `mulch-heap` command.
+    ///
+    /// # Panics
+    /// Panics if this evaluator is already evaluating something
+    pub fn heap_dump(&self) -> HeapDump {
+        self.shared.with_ctx(|ctx| ctx.heap_dump())
+    }
+
+    /// Takes the warnings that have been produced since the last call to this function
+    pub fn take_warnings(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Adds a variable to the prelude. This shadows any existing variable with the same name.
+    pub fn define<T: Serialize + ?Sized>(
+        &mut self,
+        name: &str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.define_all(&BTreeMap::from([(name, value)]))
+    }
+
+    /// Adds every attribute of `variables` to the prelude. `variables` must serialize to a set (eg
+    /// a struct or a map).
+    pub fn define_all<T: Serialize + ?Sized>(
+        &mut self,
+        variables: &T,
+    ) -> Result<(), SerializeError> {
+        let span = FullSpan::new(0..0, self.prelude_file_id);
+        let shared = &*self.shared;
+
+        shared.with_ctx(|ctx| {
+            let variables = to_set(ctx, variables, span)?;
+            shared.extend_prelude(ctx, variables);
+
+            Ok(())
+        })
+    }
+
+    /// Adds a function that is implemented in Rust to the prelude. This shadows any existing
+    /// variable with the same name. Builtins are created with [`#[mulch_builtin]`](mulch_builtin).
+    ///
+    /// ```
+    /// use mulch::{
+    ///     Evaluator,
+    ///     error::DResult,
+    ///     gc::{GCString, safety::{GC, GCCtx}},
+    ///     mulch_builtin,
+    /// };
+    ///
+    /// /// Greets someone
+    /// #[mulch_builtin]
+    /// fn greet<'c>(ctx: &'c GCCtx, name: &str) -> DResult<GC<'c, GCString>> {
+    ///     Ok(GCString::new(ctx, &format!("Hello, {name}!")))
+    /// }
+    ///
+    /// let mut evaluator = Evaluator::new();
+    /// evaluator.define_builtin(&GREET);
+    ///
+    /// let greeting: String = evaluator
+    ///     .eval_str("greet.mulch", r#"greet("world")"#)
+    ///     .unwrap()
+    ///     .deserialize()
+    ///     .unwrap();
+    /// assert_eq!(greeting, "Hello, world!");
+    /// ```
+    pub fn define_builtin(&mut self, builtin: &'static Builtin) {
+        let span = FullSpan::new(0..0, self.prelude_file_id);
+        let shared = &*self.shared;
+
+        shared.with_ctx(|ctx| {
+            let variables = eval::builtin_variables(ctx, &[builtin], span);
+            shared.extend_prelude(ctx, variables);
+        });
+    }
+
+    /// Reads and evaluates a file. The file can be evaluated again after it changes.
+    pub fn eval_file(&mut self, path: impl AsRef<Path>) -> Result<Value, EvalError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(EvalError::Io)?;
+
+        self.eval_source(path.as_os_str().to_owned(), source)
+            .map_err(EvalError::Diagnostic)
+    }
+
+    /// Evaluates `source`. `name` is used to refer to the source in diagnostics.
+    ///
+    /// Sources are kept for as long as the evaluator, because values and diagnostics may refer to
+    /// them. Evaluating the same source under the same name again reuses the stored source, and
+    /// evaluating a different source under a name that was already used replaces it for new
+    /// diagnostics.
+    pub fn eval_str(&mut self, name: &str, source: &str) -> Result<Value, Diagnostic> {
+        self.eval_source(name.into(), source.to_owned())
+    }
+
+    fn eval_source(&mut self, name: OsString, source: String) -> Result<Value, Diagnostic> {
+        let shared = &self.shared;
+
+        let file_id = shared.db.add(name, source);
+        let (source, _) = shared.db.source(file_id).unwrap();
+
+        let tokens = Lexer::new(source, file_id).lex()?;
+
+        self.warnings
+            .extend(lexer::lint_identifiers(&tokens, file_id));
+
+        shared.with_ctx(|ctx| {
+            // Everything that is still in use is referenced by a handle
+            ctx.collect();
+
+            let parser = Parser::new_default(&shared.gc);
+
+            let ast = PartialSpanned::<ast::Expression>::parse(&parser, &tokens)
+                .map_err(|err| err.with_file_id(file_id))?
+                .ok_or_else(|| {
+                    let span = span_of(&tokens).unwrap_or(Span::from(0..0));
+
+                    PartialSpanned::<ast::Expression>::EXPECTED_ERROR_FUNCTION(span)
+                        .with_file_id(file_id)
+                })?;
+
+            let span = FullSpan::new(ast.1, file_id);
+
+            // SAFETY: the AST was just allocated
+            let ast = unsafe { GC::new(ctx, ast) };
+            let prelude = shared.prelude(ctx);
+
+            let value = evaluate(gc_args!(ctx, ast.with_file_id(file_id), prelude))?;
+            let value = rebind!(ctx, value);
+
+            Ok(Value::new(shared, value, span))
+        })
+    }
+}
+
+/// A value that was produced by an [`Evaluator`]. This keeps the value alive until it is dropped,
+/// and it can be used after the evaluator is dropped.
+///
+/// Attributes of sets are evaluated lazily, so accessing them may fail.
+pub struct Value {
+    shared: Rc<Shared>,
+    handle: ManuallyDrop<GCHandle<MValue>>,
+    span: FullSpan,
+}
+
+impl Value {
+    fn new(shared: &Rc<Shared>, value: GC<MValue>, span: FullSpan) -> Self {
+        // SAFETY: `value` is valid because it's wrapped in `GC`
+        let handle = unsafe { shared.gc.push_handle(value.raw()) };
+
+        Self {
+            shared: Rc::clone(shared),
+            handle: ManuallyDrop::new(handle),
+            span,
+        }
+    }
+
+    /// The span of the expression that this value was evaluated from
+    pub fn span(&self) -> FullSpan {
+        self.span
+    }
+
+    /// Gets a nested attribute (eg `["a", "b"]` for `value.a.b`). This evaluates every attribute
+    /// along the way.
+    pub fn get_path(&self, path: &[&str]) -> Result<Value, Diagnostic> {
+        self.shared.with_ctx(|ctx| {
+            let mut span = self.span;
+            let mut value = self.get_gc(ctx).raw();
+
+            for name in path {
+                // SAFETY: `value` is either held by a handle or was just returned by
+                // `get_or_evaluate`
+                let Projected::<MValue>::Set(set) = unsafe { GC::new(ctx, value) }.project() else {
+                    return Err(eval::error::member_access_on_non_set(span));
+                };
+
+                let Some(lazy_value) = set.get_attr(name) else {
+                    return Err(eval::error::no_attribute_with_name(span, name));
+                };
+
+                span = lazy_value.span();
+                value = eval::LazyValue::get_or_evaluate(gc_args!(ctx, lazy_value), span)?.raw();
+            }
+
+            // SAFETY: `value` is either held by a handle or was just returned by `get_or_evaluate`
+            Ok(Value::new(
+                &self.shared,
+                unsafe { GC::new(ctx, value) },
+                span,
+            ))
+        })
+    }
+
+    /// Gets the value at an attribute path (eg `a.b[3].c`). Only the attributes along the path are
+    /// evaluated.
+    ///
+    /// # Panics
+    /// Panics if `path` is malformed
+    pub fn get(&self, path: &str) -> Result<Value, Diagnostic> {
+        self.shared.with_ctx(|ctx| {
+            let value = self.get_gc(ctx);
+            let Spanned(value, span) = MValue::get(gc_args!(ctx, value), self.span, path)?;
+
+            Ok(Value::new(&self.shared, value, span))
+        })
+    }
+
+    /// Iterates over the attributes of a set, sorted by name. Each attribute is evaluated when the
+    /// iterator reaches it.
+    pub fn attributes(&self) -> Result<Attributes, Diagnostic> {
+        self.shared.with_ctx(|ctx| {
+            let len = Spanned(self.get_gc(ctx), self.span).as_set()?.len();
+
+            Ok(Attributes {
+                set: self.clone_in(ctx),
+                idx: 0,
+                len,
+            })
+        })
+    }
+
+    /// Converts this value into a Rust type
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, Diagnostic> {
+        self.shared.with_ctx(|ctx| {
+            let value = self.get_gc(ctx);
+
+            from_value(gc_args!(ctx, value), self.span).map_err(Diagnostic::from)
+        })
+    }
+
+    fn clone_in(&self, ctx: &GCCtx) -> Value {
+        Value::new(&self.shared, self.get_gc(ctx), self.span)
+    }
+
+    fn get_gc<'c>(&self, ctx: &'c GCCtx) -> GC<'c, MValue> {
+        // SAFETY: `handle` is valid until `self` is dropped
+        unsafe { GC::new(ctx, self.handle.get(&self.shared.gc)) }
+    }
+}
+
+impl Clone for Value {
+    fn clone(&self) -> Self {
+        self.shared.with_ctx(|ctx| self.clone_in(ctx))
+    }
+}
+
+impl Debug for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let gc = &self.shared.gc;
+
+        // SAFETY: `handle` is valid until `self` is dropped
+        let value = unsafe { GC::from_raw_parts(gc, self.handle.get(gc)) };
+
+        Debug::fmt(&value, f)
+    }
+}
+
+impl Drop for Value {
+    fn drop(&mut self) {
+        // SAFETY: `handle` is valid until `self` is dropped
+        unsafe { ManuallyDrop::take(&mut self.handle).free(&self.shared.gc) }
+    }
+}
+
+/// An iterator over the attributes of a set. See [`Value::attributes`].
+#[derive(Debug)]
+pub struct Attributes {
+    set: Value,
+    idx: usize,
+    len: usize,
+}
+
+impl Iterator for Attributes {
+    type Item = (String, Result<Value, Diagnostic>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.idx == self.len {
+            return None;
+        }
+
+        let set = &self.set;
+        let idx = self.idx;
+        self.idx += 1;
+
+        set.shared.with_ctx(|ctx| {
+            let Projected::<MValue>::Set(attributes) = set.get_gc(ctx).project() else {
+                unreachable!()
+            };
+
+            let (name, lazy_value) = attributes.attr_at(idx).unwrap();
+            let name = name.project().0.read().to_owned();
+            let span = lazy_value.span();
+
+            let value = eval::LazyValue::get_or_evaluate(gc_args!(ctx, lazy_value), span)
+                .map(|value| Value::new(&set.shared, value, span));
+
+            Some((name, value))
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len - self.idx, Some(self.len - self.idx))
+    }
+}
+
+impl ExactSizeIterator for Attributes {}
+
+/// An error that occurs while evaluating a file with [`Evaluator::eval_file`]
+#[derive(Debug)]
+pub enum EvalError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The file could not be lexed, parsed, or evaluated
+    Diagnostic(Diagnostic),
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::Io(err) => write!(f, "unable to read file: {err}"),
+            EvalError::Diagnostic(_) => write!(f, "unable to evaluate file"),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EvalError::Io(err) => Some(err),
+            EvalError::Diagnostic(_) => None,
+        }
+    }
+}
+
+impl From<Diagnostic> for EvalError {
+    fn from(value: Diagnostic) -> Self {
+        EvalError::Diagnostic(value)
+    }
+}
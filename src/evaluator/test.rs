@@ -0,0 +1,507 @@
+use std::collections::BTreeMap;
+
+use serde::{Serialize, de::IgnoredAny};
+
+use crate::{
+    Evaluator,
+    error::{DResult, Diagnostic, FullSpan},
+    eval,
+    evaluator::EvalError,
+    gc::{
+        GCString, GarbageCollector,
+        dump::HeapDump,
+        safety::{GC, GCCtx},
+    },
+    mulch_builtin,
+};
+
+/// The span of the first occurrence of `needle` in `source`
+fn span_of(source: &str, needle: &str, file_id: usize) -> FullSpan {
+    let start = source.find(needle).unwrap();
+
+    FullSpan::new(start..start + needle.len(), file_id)
+}
+
+#[test]
+fn eval_str() {
+    let mut evaluator = Evaluator::new();
+
+    let source = r#"{ server = { port = 8080; hosts = ["a", "b"]; }; name = "main"; }"#;
+    let value = evaluator.eval_str("test.mulch", source).unwrap();
+
+    let port: u16 = value
+        .get_path(&["server", "port"])
+        .unwrap()
+        .deserialize()
+        .unwrap();
+    assert_eq!(port, 8080);
+
+    let hosts = value.get_path(&["server", "hosts"]).unwrap();
+    assert_eq!(hosts.span(), span_of(source, r#"["a", "b"]"#, 1));
+    assert_eq!(
+        hosts.deserialize::<Vec<String>>().unwrap(),
+        vec!["a".to_owned(), "b".to_owned()]
+    );
+    drop(hosts);
+
+    let name: String = value.get_path(&["name"]).unwrap().deserialize().unwrap();
+    assert_eq!(name, "main");
+
+    let root: BTreeMap<String, IgnoredAny> = value.deserialize().unwrap();
+    assert_eq!(root.len(), 2);
+}
+
+#[test]
+fn prelude() {
+    #[derive(Serialize)]
+    struct Git {
+        commit: &'static str,
+    }
+
+    let mut evaluator = Evaluator::new();
+
+    evaluator.define("version", "1.0").unwrap();
+    evaluator
+        .define_all(&BTreeMap::from([("git", Git { commit: "abc123" })]))
+        .unwrap();
+
+    let values: Vec<String> = evaluator
+        .eval_str("a.mulch", "[version, git.commit]")
+        .unwrap()
+        .deserialize()
+        .unwrap();
+    assert_eq!(values, vec!["1.0".to_owned(), "abc123".to_owned()]);
+
+    // Newer definitions shadow older ones
+    evaluator.define("version", "2.0").unwrap();
+
+    let version: String = evaluator
+        .eval_str("b.mulch", "version")
+        .unwrap()
+        .deserialize()
+        .unwrap();
+    assert_eq!(version, "2.0");
+
+    assert_eq!(
+        evaluator.define_all(&[1, 2]).unwrap_err().to_string(),
+        "expected a struct or map"
+    );
+}
+
+/// Repeats a string
+#[mulch_builtin]
+fn repeat<'c>(ctx: &'c GCCtx, string: &str, count: usize) -> DResult<GC<'c, GCString>> {
+    Ok(GCString::new(ctx, &string.repeat(count)))
+}
+
+#[test]
+fn define_builtin() {
+    let mut evaluator = Evaluator::new();
+    evaluator.define("repeat", "shadowed").unwrap();
+    evaluator.define_builtin(&REPEAT);
+
+    let value: String = evaluator
+        .eval_str("a.mulch", r#"repeat("ab", length([1, 2, 3]))"#)
+        .unwrap()
+        .deserialize()
+        .unwrap();
+    assert_eq!(value, "ababab");
+
+    // Builtins are shadowed by later definitions like any other variable
+    evaluator.define("repeat", "defined later").unwrap();
+
+    let value: String = evaluator
+        .eval_str("b.mulch", "repeat")
+        .unwrap()
+        .deserialize()
+        .unwrap();
+    assert_eq!(value, "defined later");
+}
+
+#[test]
+fn errors() {
+    let mut evaluator = Evaluator::new();
+
+    let source = "{ a = {}; }";
+    let value = evaluator.eval_str("a.mulch", source).unwrap();
+
+    assert_eq!(
+        value.get_path(&["a", "b"]).unwrap_err(),
+        eval::error::no_attribute_with_name(span_of(source, "{}", 1), "b")
+    );
+    assert_eq!(
+        value.get_path(&["b"]).unwrap_err(),
+        eval::error::no_attribute_with_name(span_of(source, source, 1), "b")
+    );
+
+    let err = value.deserialize::<Vec<u8>>().unwrap_err();
+    assert_eq!(
+        err,
+        eval::error::invalid_value(
+            span_of(source, source, 1),
+            "",
+            "expected a sequence, got set"
+        )
+    );
+    drop(value);
+
+    let source = r#"{ a = "string"; }"#;
+    let value = evaluator.eval_str("b.mulch", source).unwrap();
+
+    assert_eq!(
+        value.get_path(&["a", "b"]).unwrap_err(),
+        eval::error::member_access_on_non_set(span_of(source, r#""string""#, 2))
+    );
+    drop(value);
+
+    let source = "{ a = missing; }.a";
+    assert_eq!(
+        evaluator.eval_str("c.mulch", source).unwrap_err(),
+        eval::error::undefined_variable(span_of(source, "missing", 3), "missing")
+    );
+
+    let err: Diagnostic = evaluator.eval_str("d.mulch", "").unwrap_err();
+    assert!(
+        err.display(evaluator.source_db())
+            .to_string()
+            .contains("EP0001")
+    );
+
+    assert!(evaluator.eval_str("e.mulch", "{ a = ; }").is_err());
+}
+
+#[test]
+fn eval_file() {
+    let path = std::env::temp_dir().join(format!("mulch-eval-file-{}.mulch", std::process::id()));
+    std::fs::write(&path, "{ a = 1; }").unwrap();
+
+    let mut evaluator = Evaluator::new();
+    let result = evaluator
+        .eval_file(&path)
+        .map(|value| value.get_path(&["a"]).unwrap().deserialize::<u8>().unwrap());
+
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(result.unwrap(), 1);
+
+    assert!(matches!(
+        evaluator.eval_file(path.with_extension("missing")),
+        Err(EvalError::Io(_))
+    ));
+}
+
+#[test]
+fn many_evaluations() {
+    let mut evaluator = Evaluator::new();
+    evaluator.define("prefix", "item").unwrap();
+
+    for i in 0..2000 {
+        let source = format!(
+            r#"{{ name = prefix; values = [{i}, "{}"]; }}.values"#,
+            "x".repeat(i % 50)
+        );
+
+        let (number, string): (usize, String) = evaluator
+            .eval_str(&format!("{i}.mulch"), &source)
+            .unwrap()
+            .deserialize()
+            .unwrap();
+
+        assert_eq!(number, i);
+        assert_eq!(string.len(), i % 50);
+    }
+}
+
+#[test]
+fn same_name_twice() {
+    let mut evaluator = Evaluator::new();
+
+    let first = evaluator.eval_str("config.mulch", "{ a = 1; }").unwrap();
+    let second = evaluator.eval_str("config.mulch", "{ a = 1; }").unwrap();
+    assert_eq!(first.span(), second.span());
+
+    // Retrying after an error
+    assert!(evaluator.eval_str("config.mulch", "{ a = ; }").is_err());
+
+    let third = evaluator.eval_str("config.mulch", "{ a = 2; }").unwrap();
+    assert_eq!(third.get("a").unwrap().deserialize::<u8>().unwrap(), 2);
+
+    // Old values still refer to the source that they came from
+    let db = evaluator.source_db();
+    assert_eq!(first.get("a").unwrap().deserialize::<u8>().unwrap(), 1);
+    assert_eq!(db.source(first.span().file_id).unwrap().0, "{ a = 1; }");
+    assert_eq!(
+        db.index("config.mulch".as_ref()),
+        Some(third.span().file_id)
+    );
+}
+
+#[test]
+fn values_outlive_evaluations() {
+    let mut evaluator = Evaluator::new();
+
+    let a = evaluator.eval_str("a.mulch", "{ x = [1, 2]; }").unwrap();
+    let b = evaluator.eval_str("b.mulch", r#"{ y = "b"; }"#).unwrap();
+    let x = a.get_path(&["x"]).unwrap();
+    let a_clone = a.clone();
+
+    // Values can be dropped in any order
+    drop(a);
+
+    let c = evaluator.eval_str("c.mulch", "{ z = 3; }").unwrap();
+    drop(b);
+
+    for i in 0..500 {
+        evaluator
+            .eval_str(&format!("{i}.mulch"), &format!("[{i}, {{ a = {i}; }}]"))
+            .unwrap();
+    }
+
+    assert_eq!(x.deserialize::<Vec<u8>>().unwrap(), vec![1, 2]);
+    assert_eq!(
+        a_clone
+            .get_path(&["x"])
+            .unwrap()
+            .deserialize::<Vec<u8>>()
+            .unwrap(),
+        vec![1, 2]
+    );
+
+    // Values can be used after the evaluator is dropped
+    drop(evaluator);
+    assert_eq!(c.get_path(&["z"]).unwrap().deserialize::<u8>().unwrap(), 3);
+}
+
+#[test]
+fn path_queries_and_attributes() {
+    let mut evaluator = Evaluator::new();
+
+    let source = r#"{ servers = [{ port = 80; }, { port = 443; }]; name = "a"; broken = {}.x; }"#;
+    let value = evaluator.eval_str("a.mulch", source).unwrap();
+
+    let port = value.get("servers[1].port").unwrap();
+    assert_eq!(port.span(), span_of(source, "443", 1));
+    assert_eq!(port.deserialize::<u16>().unwrap(), 443);
+
+    assert_eq!(
+        value.get("name.first").unwrap_err(),
+        eval::error::wrong_type(span_of(source, r#""a""#, 1), "set", "string")
+    );
+
+    let attributes: Vec<_> = value.attributes().unwrap().collect();
+    let names: Vec<&str> = attributes.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["broken", "name", "servers"]);
+
+    assert_eq!(
+        attributes[0].1.as_ref().unwrap_err(),
+        &eval::error::no_attribute_with_name(span_of(source, "{}.x", 1), "x")
+    );
+    assert_eq!(
+        attributes[1]
+            .1
+            .as_ref()
+            .unwrap()
+            .deserialize::<String>()
+            .unwrap(),
+        "a"
+    );
+
+    assert_eq!(
+        port.attributes().unwrap_err(),
+        eval::error::wrong_type(span_of(source, "443", 1), "set", "number")
+    );
+}
+
+#[test]
+fn large_set_attributes() {
+    let mut evaluator = Evaluator::new();
+
+    let source: String = (0..10_000).map(|i| format!("a{i:05} = {i}; ")).collect();
+    let value = evaluator
+        .eval_str("a.mulch", &format!("{{ {source}}}"))
+        .unwrap();
+
+    let attributes = value.attributes().unwrap();
+    assert_eq!(attributes.len(), 10_000);
+
+    for (i, (name, value)) in attributes.enumerate() {
+        assert_eq!(name, format!("a{i:05}"));
+        assert_eq!(value.unwrap().deserialize::<usize>().unwrap(), i);
+    }
+}
+
+#[test]
+fn merged_set_attributes() {
+    let mut evaluator = Evaluator::new();
+
+    let lhs: String = (0..1000).map(|i| format!("a{i:04} = {i}; ")).collect();
+    let rhs: String = (500..1500)
+        .map(|i| format!("a{i:04} = {}; ", i * 2))
+        .collect();
+    let value = evaluator
+        .eval_str("a.mulch", &format!("merge({{ {lhs}}}, {{ {rhs}}})"))
+        .unwrap();
+
+    // The sorted attributes of the merged set are stored in it after it has been promoted
+    while evaluator.collect_slice(usize::MAX) {}
+
+    for _ in 0..2 {
+        let attributes = value.attributes().unwrap();
+        assert_eq!(attributes.len(), 1500);
+
+        for (i, (name, value)) in attributes.enumerate() {
+            let expected = if i < 500 { i } else { i * 2 };
+
+            assert_eq!(name, format!("a{i:04}"));
+            assert_eq!(value.unwrap().deserialize::<usize>().unwrap(), expected);
+        }
+
+        while evaluator.collect_slice(usize::MAX) {}
+    }
+
+    assert_eq!(
+        value.get("a0700").unwrap().deserialize::<usize>().unwrap(),
+        1400
+    );
+}
+
+#[test]
+fn heap_limit() {
+    let max_heap_size = 16 * 1024;
+
+    let gc = GarbageCollector::builder()
+        .initial_size(1024)
+        .growth_factor(1.5)
+        .max_heap_size(max_heap_size)
+        .build();
+    let mut evaluator = Evaluator::with_garbage_collector(gc);
+
+    assert_eq!(
+        evaluator.gc_stats().old_capacity,
+        1024 / GarbageCollector::BLOCK_SIZE
+    );
+
+    let elements = vec!["\"a string that is too long to be stored inline\""; 1000];
+    let source = format!("[{}]", elements.join(", "));
+
+    assert_eq!(
+        evaluator.eval_str("large.mulch", &source).unwrap_err(),
+        eval::error::heap_limit_exceeded(span_of(&source, &source, 1), max_heap_size)
+    );
+
+    // The garbage from the failed evaluation is collected before the next one
+    let value = evaluator.eval_str("small.mulch", "[1, 2, 3]").unwrap();
+    assert_eq!(value.deserialize::<Vec<u8>>().unwrap(), vec![1, 2, 3]);
+}
+
+#[test]
+fn stress_mode() {
+    let gc = GarbageCollector::builder().stress(true).build();
+    assert!(gc.is_stressed());
+
+    let mut evaluator = Evaluator::with_garbage_collector(gc);
+
+    let value = evaluator
+        .eval_str(
+            "stress.mulch",
+            r#"[to_string(length([1, 2, 3])), "a string that is too long to be stored inline"]"#,
+        )
+        .unwrap();
+    assert_eq!(
+        value.deserialize::<Vec<String>>().unwrap(),
+        ["3", "a string that is too long to be stored inline"]
+    );
+
+    assert!(evaluator.gc_stats().major_collections > 1);
+}
+
+#[test]
+fn incremental_collection() {
+    // A small growth factor keeps the old generation nearly full, so the collections between
+    // evaluations start major collections
+    let gc = GarbageCollector::builder()
+        .initial_size(1024)
+        .growth_factor(1.05)
+        .incremental(256)
+        .build();
+    let mut evaluator = Evaluator::with_garbage_collector(gc);
+
+    let values: Vec<_> = (0..20)
+        .map(|i| {
+            let source =
+                format!(r#"["value {i} is a string that is too long to be stored inline"]"#);
+            evaluator.eval_str(&format!("{i}.mulch"), &source).unwrap()
+        })
+        .collect();
+
+    while evaluator.collect_slice(256) {}
+    assert!(evaluator.gc_stats().major_collections > 0);
+
+    for (i, value) in values.iter().enumerate() {
+        assert_eq!(
+            value.deserialize::<Vec<String>>().unwrap(),
+            [format!(
+                "value {i} is a string that is too long to be stored inline"
+            )]
+        );
+    }
+}
+
+#[test]
+fn heap_dump_on_limit() {
+    let path = std::env::temp_dir().join(format!("mulch-heap-dump-{}.bin", std::process::id()));
+
+    let gc = GarbageCollector::builder()
+        .max_heap_size(16 * 1024)
+        .heap_dump_on_limit(&path)
+        .build();
+    let mut evaluator = Evaluator::with_garbage_collector(gc);
+
+    let elements = vec!["\"a string that is too long to be stored inline\""; 1000];
+    let source = format!("[{}]", elements.join(", "));
+    evaluator.eval_str("large.mulch", &source).unwrap_err();
+
+    let dump = HeapDump::read_from(std::fs::File::open(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // The strings are kept alive by the list literal that is being evaluated
+    assert!(dump.total_bytes() > 16 * 1024);
+    assert!(
+        dump.objects
+            .iter()
+            .any(|object| dump.type_name(object).ends_with("GCString"))
+    );
+    let top = dump.largest_retainers(1)[0];
+    assert!(
+        dump.type_name(&dump.objects[top.object])
+            .ends_with("GCBox<mulch::parser::ast::Expression>")
+    );
+    assert!(top.retained_bytes > dump.total_bytes() * 9 / 10);
+}
+
+#[test]
+fn heap_dump_on_limit_error() {
+    let path = std::env::temp_dir()
+        .join(format!("mulch-missing-{}", std::process::id()))
+        .join("dump.bin");
+
+    let gc = GarbageCollector::builder()
+        .max_heap_size(16 * 1024)
+        .heap_dump_on_limit(&path)
+        .build();
+    let mut evaluator = Evaluator::with_garbage_collector(gc);
+
+    let elements = vec!["\"a string that is too long to be stored inline\""; 1000];
+    let source = format!("[{}]", elements.join(", "));
+    let err = evaluator.eval_str("large.mulch", &source).unwrap_err();
+
+    // The heap limit is still reported, along with why the dump is missing
+    let expected = eval::error::heap_limit_exceeded(span_of(&source, &source, 1), 16 * 1024);
+    assert_ne!(err, expected);
+
+    let message = err.display(evaluator.source_db()).to_string();
+    assert!(message.contains("EE0010"));
+    assert!(message.contains(&format!(
+        "warning: unable to write heap dump to `{}`",
+        path.display()
+    )));
+}
@@ -1,23 +1,42 @@
-use std::marker::PhantomData;
+use std::{
+    cell::{Cell, RefCell},
+    marker::PhantomData,
+};
 
+mod barrier;
 mod collection;
 mod gcspace;
+mod handles;
+mod incremental;
+mod intern;
+mod policy;
 mod primitives;
 mod roots;
+mod stats;
 mod traits;
 
+pub mod dump;
 pub mod safety;
 pub mod util;
+#[cfg(feature = "verify-heap")]
+pub mod verify;
 
 pub use gcspace::GCSpace;
-use gmp_mpfr_sys::gmp;
+pub use handles::GCHandle;
+pub use policy::GarbageCollectorBuilder;
 pub use primitives::math;
 pub use primitives::*;
 pub use roots::GCRootInfo;
 pub use roots::GCRootRef;
+pub use stats::{CollectionCallback, CollectionEvent, CollectionKind, GCStats};
 pub use traits::*;
 
 use crate::error::PartialSpanned;
+use crate::gc::barrier::RememberedSet;
+use crate::gc::handles::GCHandleTable;
+use crate::gc::incremental::{MajorCycle, WorkList};
+use crate::gc::intern::InternTable;
+use crate::gc::policy::GCPolicy;
 use crate::gc::roots::GCRootList;
 use crate::gc::util::GCWrap;
 
@@ -26,11 +45,32 @@ mod test;
 
 /// The garbage collector.
 ///
+/// Objects are allocated in the nursery. Objects that survive a minor collection are promoted to
+/// the old generation, which is only collected by major collections. Block indices are shared
+/// between the generations: nursery indices start at [`GarbageCollector::NURSERY_START`].
+///
 /// This should typically be interacted with through an immutable reference.
 pub struct GarbageCollector {
-    from_space: GCSpace,
+    nursery: GCSpace,
+    old: GCSpace,
+    /// The from-space of a major collection. Objects are evacuated from it into a fresh old
+    /// generation. This is empty outside of major collections.
     to_space: GCSpace,
+    /// Whether objects are being evacuated out of the from-space
+    major: Cell<bool>,
+    /// The progress of the major collection that is in progress, if any
+    cycle: Cell<Option<MajorCycle>>,
+    /// The objects that the major collection in progress has copied but not scanned yet
+    work_list: WorkList,
+    /// Old objects that may contain references to the nursery
+    remembered: RememberedSet,
     roots: GCRootList,
+    handles: GCHandleTable,
+    /// Interned strings. This does not keep them alive.
+    interned: InternTable,
+    policy: GCPolicy,
+    stats: Cell<GCStats>,
+    collection_callback: RefCell<Option<CollectionCallback>>,
 }
 
 impl Default for GarbageCollector {
@@ -46,20 +86,72 @@ impl GarbageCollector {
         std::mem::align_of::<usize>(),
         std::mem::align_of::<u64>(),
         std::mem::align_of::<copyspan::Span>(),
-        std::mem::align_of::<gmp::limb_t>(),
+        std::mem::align_of::<math::mpn::limb_t>(),
         std::mem::size_of::<usize>(),
     ));
 
+    /// The index of the first block of the nursery. Every block index below this belongs to the
+    /// old generation. This leaves the most-significant-bit of block indices free.
+    pub const NURSERY_START: usize = 1 << (usize::BITS - 2);
+
+    /// Creates a garbage collector with the default policy. Use [`GarbageCollector::builder`] to
+    /// configure it.
     pub fn new() -> Self {
+        Self::with_policy(GCPolicy::default())
+    }
+
+    fn with_policy(policy: GCPolicy) -> Self {
         GarbageCollector {
-            from_space: GCSpace::new(),
-            to_space: GCSpace::new(),
+            nursery: GCSpace::with_policy(Self::NURSERY_START, &policy),
+            old: GCSpace::with_policy(0, &policy),
+            to_space: GCSpace::with_policy(0, &policy),
+            major: Cell::new(false),
+            cycle: Cell::new(None),
+            work_list: WorkList::new(),
+            remembered: RememberedSet::new(),
             roots: GCRootList::new(),
+            handles: GCHandleTable::new(),
+            interned: InternTable::new(),
+            policy,
+            stats: Cell::new(GCStats::default()),
+            collection_callback: RefCell::new(None),
         }
     }
 
+    /// Gets a pointer to a block in whichever generation it belongs to
     pub fn block_ptr(&self, block: impl Into<usize>) -> *mut u8 {
-        self.from_space.block_ptr(block)
+        let block = block.into();
+
+        self.space_of(block).block_ptr(block)
+    }
+
+    /// The generation that the block at `block` belongs to. While objects are being evacuated,
+    /// old blocks are in the from-space.
+    fn space_of(&self, block: usize) -> &GCSpace {
+        if block >= Self::NURSERY_START {
+            &self.nursery
+        } else if self.major.get() {
+            &self.to_space
+        } else {
+            &self.old
+        }
+    }
+
+    /// Whether the object at `block` is in the nursery
+    pub(crate) fn is_young(&self, block: usize) -> bool {
+        block >= Self::NURSERY_START
+    }
+
+    /// Whether the object at `block` is moved by the current collection. `gc_copy`
+    /// implementations return the object unchanged otherwise.
+    fn is_evacuating(&self, block: usize) -> bool {
+        self.major.get() || self.is_young(block)
+    }
+
+    /// The space that surviving objects are copied to. Both kinds of collection copy into the old
+    /// generation.
+    fn copy_target(&self) -> &GCSpace {
+        &self.old
     }
 }
 
@@ -118,6 +210,7 @@ gc_trivial_impl! {
     f64,
     copyspan::Span,
     crate::error::FullSpan,
+    crate::eval::BuiltinFunction,
 }
 
 unsafe impl<T: ?Sized> GCPtr for PhantomData<T> {
@@ -160,6 +253,19 @@ unsafe impl<T: GCPtr> GCPtr for Option<T> {
     unsafe fn gc_copy(self, gc: &GarbageCollector) -> Self {
         self.map(|val| unsafe { val.gc_copy(gc) })
     }
+
+    unsafe fn gc_trace(self, tracer: &mut dump::HeapTracer) {
+        if let Some(val) = self {
+            unsafe { val.gc_trace(tracer) };
+        }
+    }
+
+    #[cfg(feature = "verify-heap")]
+    unsafe fn gc_verify(self, verifier: &mut verify::HeapVerifier) {
+        if let Some(val) = self {
+            unsafe { val.gc_verify(verifier) };
+        }
+    }
 }
 
 unsafe impl<T: NonGC> NonGC for Option<T> {}
@@ -0,0 +1,47 @@
+use std::{cell::UnsafeCell, num::NonZeroUsize};
+
+use crate::gc::GarbageCollector;
+
+/// An old object that was written to since the last minor collection. Its references into the
+/// nursery are updated by `rescan_fn`.
+#[derive(Clone, Copy)]
+pub struct RememberedEntry {
+    pub ptr: NonZeroUsize,
+    pub rescan_fn: unsafe fn(NonZeroUsize, &GarbageCollector),
+}
+
+/// The objects recorded by the write barrier. Minor collections treat these as roots.
+pub struct RememberedSet {
+    entries: UnsafeCell<Vec<RememberedEntry>>,
+}
+
+impl RememberedSet {
+    pub fn new() -> Self {
+        Self {
+            entries: UnsafeCell::new(Vec::new()),
+        }
+    }
+
+    // NOTE: all of this interior mutability is safe because we don't provide a way to get a
+    // reference to any element.
+
+    pub fn push(&self, entry: RememberedEntry) {
+        unsafe { self.entries.get().as_mut_unchecked().push(entry) };
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { self.entries.get().as_ref_unchecked().len() }
+    }
+
+    pub unsafe fn get_unchecked(&self, index: usize) -> RememberedEntry {
+        let vec = unsafe { self.entries.get().as_ref_unchecked() };
+
+        debug_assert!(index < vec.len());
+
+        unsafe { vec.as_ptr().add(index).read() }
+    }
+
+    pub fn clear(&self) {
+        unsafe { self.entries.get().as_mut_unchecked().clear() };
+    }
+}
@@ -1,9 +1,13 @@
-use crate::gc::GarbageCollector;
+use std::time::Instant;
+
+use crate::gc::{CollectionKind, GarbageCollector};
 
 #[cfg(test)]
 mod test;
 
 impl GarbageCollector {
+    /// Copies the objects that are referenced by roots and handles. Roots that are still pending
+    /// in a major collection are skipped: they only point into the from-space.
     unsafe fn copy_roots(&self) {
         let num_roots = self.roots.len();
 
@@ -12,6 +16,10 @@ impl GarbageCollector {
                 continue;
             };
 
+            if old_entry.pending {
+                continue;
+            }
+
             let mut new_entry = old_entry;
             new_entry.data_ptr = unsafe { (old_entry.copy_fn)(old_entry.data_ptr, self) };
 
@@ -19,45 +27,182 @@ impl GarbageCollector {
         }
 
         assert_eq!(self.roots.len(), num_roots);
+
+        for i in 0..self.handles.len() {
+            let Some(old_entry) = (unsafe { self.handles.get_unchecked(i) }) else {
+                continue;
+            };
+
+            if old_entry.pending {
+                continue;
+            }
+
+            let mut new_entry = old_entry;
+            new_entry.data_ptr = unsafe { (old_entry.copy_fn)(old_entry.data_ptr, self) };
+
+            self.handles.set(i, Some(new_entry));
+        }
+    }
+
+    /// Updates the references held by the old objects that were recorded by the write barrier
+    unsafe fn rescan_remembered(&self) {
+        for i in 0..self.remembered.len() {
+            let entry = unsafe { self.remembered.get_unchecked(i) };
+
+            unsafe { (entry.rescan_fn)(entry.ptr, self) };
+        }
     }
 
     /// Does a garbage collection cycle if it is deemed neccessary.
     ///
+    /// If [`GarbageCollectorBuilder::incremental`] is set, major collections are done in slices:
+    /// each call only does a bounded amount of work on the major collection that is in progress.
+    ///
     /// NOTE: All objects contained in `root` will be moved and all references inside of `root` will
     /// be updated. **Any other references will become invalid even if they point to an object that
     /// survives the garbage-collection cycle**.
     ///
     /// # Safety
     /// - This function should not be called directly while the safe garbage-collection API is in use.
+    ///
+    /// [`GarbageCollectorBuilder::incremental`]: crate::gc::GarbageCollectorBuilder::incremental
     pub unsafe fn collect(&self) {
-        if self.from_space.len() < self.from_space.capacity() * 15 / 16 {
+        // Collecting as soon as the heap limit is exceeded gives evaluation the best chance of
+        // staying within it
+        let heap_limit_exceeded = self.heap_limit_exceeded();
+
+        if self.is_collecting() {
+            if self.nursery.used() >= self.nursery.capacity() * 15 / 16 || heap_limit_exceeded {
+                unsafe { self.force_minor_collect() };
+            }
+
+            let budget = match self.policy.slice_budget {
+                Some(budget) if !heap_limit_exceeded => budget,
+                _ => usize::MAX,
+            };
+
+            if unsafe { self.continue_major(budget) } {
+                self.grow_old();
+            }
+
+            return;
+        }
+
+        if self.nursery.used() < self.nursery.capacity() * 15 / 16 && !heap_limit_exceeded {
             return;
         }
 
-        unsafe { self.force_collect() };
+        unsafe { self.force_minor_collect() };
 
-        if self.from_space.len() < self.from_space.capacity() * 12 / 16 {
+        if self.old.used() < self.old.capacity() * 15 / 16 && !self.heap_limit_exceeded() {
             return;
         }
 
-        self.to_space
-            .expand_capacity_to_exact(self.from_space.capacity() * 2);
-        self.from_space
-            .expand_capacity_to_exact(self.from_space.capacity() * 2);
+        self.start_major();
+
+        let budget = match self.policy.slice_budget {
+            Some(budget) if !self.heap_limit_exceeded() => budget,
+            _ => usize::MAX,
+        };
+
+        if unsafe { self.continue_major(budget) } {
+            self.grow_old();
+        }
     }
 
-    /// Forcefully does a garbage collection cycle.
+    /// Does a bounded amount of garbage-collection work. This is meant to be called when the
+    /// program is idle, eg between keystrokes in an editor.
+    ///
+    /// A minor collection is done if the nursery is not empty. Then, a major collection is
+    /// continued (or started) until at least `budget` bytes have been copied. Returns whether a
+    /// major collection is still in progress.
+    ///
+    /// See documentation of [`GarbageCollector::collect`] for safety and any other information.
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn collect_slice(&self, budget: usize) -> bool {
+        if self.nursery.used() > 0 {
+            unsafe { self.force_minor_collect() };
+        }
+
+        if !self.is_collecting() {
+            self.start_major();
+        }
+
+        if unsafe { self.continue_major(budget) } {
+            self.grow_old();
+        }
+
+        self.is_collecting()
+    }
+
+    /// Grows the old generation if it is mostly full after a major collection
+    fn grow_old(&self) {
+        if self.old.used() < self.old.capacity() * 12 / 16 {
+            return;
+        }
+
+        let mut new_capacity = self.old.grown_capacity(self.old.capacity());
+
+        if let Some(max_heap_size) = self.max_heap_size() {
+            // Growing beyond the heap limit would only waste memory
+            new_capacity = new_capacity.min(max_heap_size.div_ceil(Self::BLOCK_SIZE));
+        }
+
+        self.to_space.expand_capacity_to_exact(new_capacity);
+        self.old.expand_capacity_to_exact(new_capacity);
+    }
+
+    /// Forcefully does a minor garbage collection cycle. Live objects in the nursery are promoted
+    /// to the old generation. Objects that are already in the old generation are not moved.
     ///
     /// See documentation of [`GarbageCollector::collect`] for safety and any other information.
     #[allow(clippy::missing_safety_doc)]
     #[cold]
-    pub unsafe fn force_collect(&self) {
+    pub unsafe fn force_minor_collect(&self) {
+        let start = Instant::now();
+        let old_len = self.old.len();
+
         unsafe {
             self.copy_roots();
+            self.rescan_remembered();
+            self.sweep_interned(CollectionKind::Minor);
         }
 
+        let bytes_copied = (self.old.len() - old_len) * Self::BLOCK_SIZE;
+
+        self.remembered.clear();
+        self.nursery.clear();
+
+        self.finish_cycle(
+            CollectionKind::Minor,
+            start.elapsed(),
+            bytes_copied,
+            self.num_roots(),
+        );
+
+        #[cfg(feature = "verify-heap")]
         unsafe {
-            self.from_space.swap(&self.to_space);
+            self.verify_heap()
+        };
+    }
+
+    /// Forcefully does a major garbage collection cycle. All live objects are moved into a freshly
+    /// compacted old generation. If a major collection is already in progress, it is finished
+    /// instead. The nursery is first emptied with a minor collection if needed.
+    ///
+    /// See documentation of [`GarbageCollector::collect`] for safety and any other information.
+    #[allow(clippy::missing_safety_doc)]
+    #[cold]
+    pub unsafe fn force_collect(&self) {
+        if self.nursery.used() > 0 {
+            // Pending roots must only point into the from-space
+            unsafe { self.force_minor_collect() };
+        }
+
+        if !self.is_collecting() {
+            self.start_major();
         }
+
+        unsafe { self.continue_major(usize::MAX) };
     }
 }
@@ -1,9 +1,11 @@
-use std::mem;
+use std::{cell::RefCell, mem, rc::Rc};
+
+use mulch_macros::GCPtr;
 
 use crate::{
     eval::MValue,
     gc::{
-        GCString, GCVec,
+        CollectionKind, GCBox, GCPersistentVec, GCString, GCVec, GarbageCollector,
         safety::{GC, GCCtx, GCRootGuard, Projected, let_gc_and_context, rebind, root},
     },
 };
@@ -19,7 +21,7 @@ fn string_list_test() {
         panic!()
     };
 
-    let list: GC<GCVec<MValue>> = list;
+    let list: GC<GCPersistentVec<MValue>> = list;
 
     assert_eq!(list.len(), expected.len());
 
@@ -63,7 +65,10 @@ fn create_string_list<'c>(ctx: &'c mut GCCtx, strings: &[&'static str]) -> GC<'c
     // Test that the `GCVec` was moved. This indicates that the unused string was not copied during the GC cycle.
     assert_ne!(old_ptr, vec_root.get(ctx).raw().ptr());
 
-    Projected::<MValue>::List(vec_root.get(ctx)).into()
+    let vec = vec_root.get(ctx);
+    let list = GCPersistentVec::from_iter_and_len(ctx, vec.iter(), vec.len());
+
+    Projected::<MValue>::List(list).into()
 }
 
 fn create_string_val<'gc, 'c>(ctx: &'c mut GCCtx<'gc>, val: &str) -> GC<'c, MValue> {
@@ -73,3 +78,303 @@ fn create_string_val<'gc, 'c>(ctx: &'c mut GCCtx<'gc>, val: &str) -> GC<'c, MVal
 
     Projected::<MValue>::String(string.get(ctx)).into()
 }
+
+#[test]
+fn handles() {
+    let_gc_and_context!(gc, ctx);
+
+    let strings = ["first", "second", "third"];
+    let handles: Vec<_> = strings
+        .iter()
+        .map(|s| {
+            let string = GCString::new(ctx, s);
+
+            // SAFETY: `string` was just allocated
+            unsafe { gc.push_handle(string.raw()) }
+        })
+        .collect();
+
+    let mut handles = handles.into_iter();
+    let first = handles.next().unwrap();
+    let second = handles.next().unwrap();
+    let third = handles.next().unwrap();
+
+    // Handles can be freed in any order
+    // SAFETY: `second` has not been freed
+    unsafe { second.free(&gc) };
+    assert_eq!(gc.num_handles(), 2);
+
+    create_string_val(ctx, "unused");
+    ctx.force_collect();
+
+    for (handle, expected) in [(&first, "first"), (&third, "third")] {
+        // SAFETY: `handle` has not been freed
+        let string = unsafe { GC::new(ctx, handle.get(&gc)) };
+        assert_eq!(string.read(), expected);
+    }
+
+    // SAFETY: neither handle has been freed
+    unsafe {
+        first.free(&gc);
+        third.free(&gc);
+    }
+    assert_eq!(gc.num_handles(), 0);
+}
+
+#[test]
+fn minor_collection_promotes_survivors() {
+    let_gc_and_context!(gc, ctx);
+
+    let expected = ["alpha", "beta", "abcdefghijklmnopqrstuvwxyz"];
+    let strings = expected
+        .iter()
+        .map(|s| Projected::<MValue>::String(GCString::new(ctx, s)).into());
+
+    let list = GCVec::from_iter_and_len(ctx, strings, expected.len());
+    assert!(gc.is_young(list.raw().ptr().get()));
+
+    let list = root!(ctx, list);
+
+    ctx.force_minor_collect();
+
+    let promoted_ptr = list.get(ctx).raw().ptr();
+    assert!(!gc.is_young(promoted_ptr.get()));
+
+    ctx.force_minor_collect();
+
+    // Old objects are not moved by minor collections
+    assert_eq!(list.get(ctx).raw().ptr(), promoted_ptr);
+
+    for (s, expected) in list.get(ctx).iter().zip(expected) {
+        let Projected::<MValue>::String(gcstr) = s.project() else {
+            panic!()
+        };
+
+        assert_eq!(gcstr.read(), expected);
+    }
+}
+
+#[test]
+fn write_barrier() {
+    let_gc_and_context!(gc, ctx);
+
+    let initial: GC<MValue> = Projected::<MValue>::String(GCString::new(ctx, "initial")).into();
+    let gc_box = root!(ctx, GCBox::new(initial));
+
+    ctx.force_minor_collect();
+    assert!(!gc.is_young(gc_box.get(ctx).raw().ptr().get()));
+
+    let expected = "a string that is too long to be stored inline";
+    let young: GC<MValue> = Projected::<MValue>::String(GCString::new(ctx, expected)).into();
+
+    // SAFETY: both the box and the value are alive
+    unsafe { gc_box.get(ctx).raw().set(ctx, young.raw()) };
+
+    // The young string is only reachable through the old box
+    ctx.force_minor_collect();
+
+    // Overwrite the nursery so that a string that wasn't promoted would be clobbered
+    GCString::new(ctx, &"x".repeat(expected.len()));
+
+    let Projected::<MValue>::String(string) = gc_box.get(ctx).get().project() else {
+        panic!()
+    };
+
+    assert_eq!(string.read(), expected);
+}
+
+#[test]
+fn stats_and_collection_callback() {
+    let_gc_and_context!(gc, ctx);
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let callback_events = events.clone();
+    gc.set_collection_callback(Some(Box::new(move |event| {
+        callback_events.borrow_mut().push(*event)
+    })));
+
+    let expected = "a string that is too long to be stored inline";
+    let string = root!(ctx, GCString::new(ctx, expected));
+    GCString::new(ctx, "garbage that is too long to be stored inline");
+
+    ctx.force_minor_collect();
+    ctx.force_collect();
+
+    let events = events.borrow();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].kind, CollectionKind::Minor);
+    assert_eq!(events[1].kind, CollectionKind::Major);
+
+    // The string (and the box that roots it) are copied by both cycles, but the garbage isn't
+    let live_blocks = events[0].live_blocks;
+    assert!(live_blocks >= expected.len().div_ceil(GarbageCollector::BLOCK_SIZE));
+    assert!(live_blocks < 2 * expected.len().div_ceil(GarbageCollector::BLOCK_SIZE));
+
+    for event in events.iter() {
+        assert_eq!(event.roots, 1);
+        assert_eq!(event.live_blocks, live_blocks);
+        assert_eq!(
+            event.bytes_copied,
+            live_blocks * GarbageCollector::BLOCK_SIZE
+        );
+    }
+
+    let stats = gc.stats();
+    assert_eq!(stats.minor_collections, 1);
+    assert_eq!(stats.major_collections, 1);
+    assert_eq!(
+        stats.bytes_copied,
+        2 * live_blocks * GarbageCollector::BLOCK_SIZE
+    );
+    assert_eq!(stats.live_blocks, live_blocks);
+    assert_eq!(stats.roots, 1);
+
+    assert_eq!(string.get(ctx).read(), expected);
+}
+
+#[test]
+fn incremental_collection() {
+    let_gc_and_context!(gc, ctx);
+
+    let expected = [
+        "the first string that is too long to be stored inline",
+        "the second string that is too long to be stored inline",
+        "the third string that is too long to be stored inline",
+    ];
+
+    let first = root!(ctx, GCString::new(ctx, expected[0]));
+
+    // SAFETY: the string was just allocated
+    let garbage = unsafe { gc.push_handle(GCString::new(ctx, &"garbage".repeat(100)).raw()) };
+
+    let second = root!(ctx, GCString::new(ctx, expected[1]));
+
+    // SAFETY: the string was just allocated
+    let third = unsafe { gc.push_handle(GCString::new(ctx, expected[2]).raw()) };
+
+    // The garbage is promoted before it becomes unreachable
+    ctx.force_minor_collect();
+    // SAFETY: the handle has not been freed
+    unsafe { garbage.free(&gc) };
+
+    let heap_size = gc.heap_size();
+
+    // Each slice evacuates at least one root
+    assert!(ctx.collect_slice(1));
+    assert!(gc.is_collecting());
+
+    // Reading a root that hasn't been evacuated yet evacuates it
+    assert_eq!(second.get(ctx).read(), expected[1]);
+
+    // Roots that are created during the collection already point out of the from-space
+    let young = root!(
+        ctx,
+        GCString::new(ctx, "a young string that is too long to be stored inline")
+    );
+    ctx.force_minor_collect();
+
+    let mut slices = 1;
+    while ctx.collect_slice(1) {
+        slices += 1;
+    }
+
+    assert!(slices > 1);
+    assert!(!gc.is_collecting());
+    assert_eq!(gc.stats().major_collections, 1);
+
+    // The garbage string was freed along with the from-space
+    assert!(gc.heap_size() < heap_size);
+
+    assert_eq!(first.get(ctx).read(), expected[0]);
+    assert_eq!(second.get(ctx).read(), expected[1]);
+    assert_eq!(
+        young.get(ctx).read(),
+        "a young string that is too long to be stored inline"
+    );
+
+    // SAFETY: the handle has not been freed
+    unsafe {
+        assert_eq!(GC::new(ctx, third.get(&gc)).read(), expected[2]);
+        third.free(&gc);
+    }
+}
+
+#[derive(Clone, Copy, GCPtr)]
+struct Node {
+    value: usize,
+    next: Option<GCBox<Node>>,
+}
+
+#[test]
+fn incremental_slices_stay_within_budget() {
+    let_gc_and_context!(gc, ctx);
+
+    let len = 2000;
+    let budget = 256;
+
+    // A single root that keeps a long linked list alive
+    let mut list = None;
+    for value in (0..len).rev() {
+        // SAFETY: `list` was just allocated
+        list = Some(unsafe { GCBox::new_raw(&gc, Node { value, next: list }) });
+    }
+
+    // SAFETY: the list was just allocated
+    let list = root!(ctx, unsafe { GC::new(ctx, list) });
+    ctx.force_minor_collect();
+
+    // A slice stops after the object that uses up its budget. A box may need an extra block for
+    // its forward pointer.
+    let max_slice_bytes = budget
+        + mem::size_of::<Node>().next_multiple_of(GarbageCollector::BLOCK_SIZE)
+        + GarbageCollector::BLOCK_SIZE;
+    let mut slices = 0;
+
+    loop {
+        // The first slice starts with an empty old generation
+        let old_used = if gc.is_collecting() { gc.old.used() } else { 0 };
+        let collecting = ctx.collect_slice(budget);
+        let bytes_copied = (gc.old.used() - old_used) * GarbageCollector::BLOCK_SIZE;
+
+        assert!(
+            bytes_copied <= max_slice_bytes,
+            "a slice copied {bytes_copied} bytes"
+        );
+        slices += 1;
+
+        if slices == 10 {
+            // Reading the root while it is being scanned finishes the scan
+            assert!(collecting);
+            assert_list(&gc, list.get(ctx).raw(), len);
+        }
+
+        if !collecting {
+            break;
+        }
+    }
+
+    assert!(slices > 10);
+    assert_list(&gc, list.get(ctx).raw(), len);
+}
+
+/// Checks that `list` holds the numbers from 0 to `len` and has been moved into the old
+/// generation
+fn assert_list(gc: &GarbageCollector, list: Option<GCBox<Node>>, len: usize) {
+    let mut node = list;
+
+    for value in 0..len {
+        let gc_box = node.unwrap();
+        assert!(!gc.is_young(gc_box.ptr().get()));
+
+        // SAFETY: the list is kept alive by a root
+        let Node {
+            value: actual,
+            next,
+        } = unsafe { gc_box.get(gc) };
+
+        assert_eq!(actual, value);
+        node = next;
+    }
+
+    assert!(node.is_none());
+}
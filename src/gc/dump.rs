@@ -0,0 +1,525 @@
+//! Heap dumps, which record every object that is reachable from a root along with its type, its
+//! size, and the objects that it references. They are used to find out what is keeping memory
+//! alive, eg with the `mulch-heap` command.
+//!
+//! # Format
+//! Dumps are written by [`HeapDump::write_to`] in a simple binary format. Every integer is a
+//! little-endian `u64`, and every string is its length in bytes followed by its UTF-8 bytes.
+//!
+//! 1. The magic bytes `MULCHHD` followed by a version byte (currently 1)
+//! 2. The block size in bytes
+//! 3. The number of types, followed by the name of each type
+//! 4. The number of objects, followed by each object:
+//!    - Its block index in the heap
+//!    - The index of its type
+//!    - Its size in blocks
+//!    - The number of references, followed by the index of each referenced object
+//! 5. The number of roots, followed by each root:
+//!    - Its name, eg `roots[3]` or `handles[0]`
+//!    - The number of references, followed by the index of each referenced object
+//!
+//! An object can appear in the references of many objects, but it is only listed once.
+
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+};
+
+use crate::gc::{GarbageCollector, roots::GCRootEntry};
+
+#[cfg(test)]
+mod test;
+
+const MAGIC: &[u8; 7] = b"MULCHHD";
+const VERSION: u8 = 1;
+
+/// An object in a [`HeapDump`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeapObject {
+    /// The index of the first block of the object
+    pub block: usize,
+    /// An index into [`HeapDump::types`]
+    pub type_index: usize,
+    pub size_blocks: usize,
+    /// Indices into [`HeapDump::objects`]
+    pub references: Vec<usize>,
+}
+
+/// A root or handle in a [`HeapDump`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeapRoot {
+    pub name: String,
+    /// Indices into [`HeapDump::objects`]
+    pub references: Vec<usize>,
+}
+
+/// Every object that was reachable when the dump was taken. This is created with
+/// [`GarbageCollector::heap_dump`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HeapDump {
+    pub block_size: usize,
+    pub types: Vec<String>,
+    pub objects: Vec<HeapObject>,
+    pub roots: Vec<HeapRoot>,
+}
+
+/// An object along with the memory that it keeps alive. See [`HeapDump::largest_retainers`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Retainer {
+    /// An index into [`HeapDump::objects`]
+    pub object: usize,
+    /// The size of the object itself
+    pub shallow_bytes: usize,
+    /// The size of the object and of every object that can only be reached through it
+    pub retained_bytes: usize,
+}
+
+impl HeapDump {
+    /// The name of the type of an object
+    pub fn type_name(&self, object: &HeapObject) -> &str {
+        &self.types[object.type_index]
+    }
+
+    /// The number of bytes that are used by every object in the dump
+    pub fn total_bytes(&self) -> usize {
+        self.objects
+            .iter()
+            .map(|object| object.size_blocks * self.block_size)
+            .sum()
+    }
+
+    /// Gets the `count` objects that retain the most memory, largest first. An object retains
+    /// itself and every object that can only be reached from a root by going through it (the
+    /// objects that it dominates). Freeing the object would free all of that memory.
+    pub fn largest_retainers(&self, count: usize) -> Vec<Retainer> {
+        let retained = self.retained_blocks();
+
+        let mut retainers: Vec<_> = self
+            .objects
+            .iter()
+            .enumerate()
+            .map(|(i, object)| Retainer {
+                object: i,
+                shallow_bytes: object.size_blocks * self.block_size,
+                retained_bytes: retained[i] * self.block_size,
+            })
+            .collect();
+
+        retainers.sort_by(|a, b| {
+            b.retained_bytes
+                .cmp(&a.retained_bytes)
+                .then(a.object.cmp(&b.object))
+        });
+        retainers.truncate(count);
+
+        retainers
+    }
+
+    /// The number of blocks retained by each object. This builds the dominator tree of the object
+    /// graph with the algorithm from "A Simple, Fast Dominance Algorithm" by Cooper, Harvey, and
+    /// Kennedy.
+    fn retained_blocks(&self) -> Vec<usize> {
+        // Node 0 is a virtual root that references every root. Object `i` is node `i + 1`.
+        let num_nodes = self.objects.len() + 1;
+
+        let successors = |node: usize| -> Box<dyn Iterator<Item = usize> + '_> {
+            if node == 0 {
+                Box::new(
+                    self.roots
+                        .iter()
+                        .flat_map(|root| root.references.iter().map(|&object| object + 1)),
+                )
+            } else {
+                Box::new(
+                    self.objects[node - 1]
+                        .references
+                        .iter()
+                        .map(|&object| object + 1),
+                )
+            }
+        };
+
+        // Number the nodes in postorder with an iterative depth-first search
+        let mut postorder = Vec::with_capacity(num_nodes);
+        let mut visited = vec![false; num_nodes];
+        let mut stack = vec![(0, successors(0))];
+        visited[0] = true;
+
+        while let Some((node, children)) = stack.last_mut() {
+            match children.find(|&child| !visited[child]) {
+                Some(child) => {
+                    visited[child] = true;
+                    stack.push((child, successors(child)));
+                }
+                None => {
+                    postorder.push(*node);
+                    stack.pop();
+                }
+            }
+        }
+
+        let mut postorder_index = vec![usize::MAX; num_nodes];
+        for (i, &node) in postorder.iter().enumerate() {
+            postorder_index[node] = i;
+        }
+
+        let mut predecessors = vec![Vec::new(); num_nodes];
+        for &node in &postorder {
+            for successor in successors(node) {
+                predecessors[successor].push(node);
+            }
+        }
+
+        let mut idom = vec![usize::MAX; num_nodes];
+        idom[0] = 0;
+
+        let intersect = |idom: &[usize], mut a: usize, mut b: usize| {
+            while a != b {
+                while postorder_index[a] < postorder_index[b] {
+                    a = idom[a];
+                }
+                while postorder_index[b] < postorder_index[a] {
+                    b = idom[b];
+                }
+            }
+
+            a
+        };
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            // Every node but the virtual root, in reverse postorder
+            for &node in postorder.iter().rev().skip(1) {
+                let new_idom = predecessors[node]
+                    .iter()
+                    .copied()
+                    .filter(|&predecessor| idom[predecessor] != usize::MAX)
+                    .reduce(|a, b| intersect(&idom, a, b))
+                    .unwrap();
+
+                if idom[node] != new_idom {
+                    idom[node] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        // A node always comes before its immediate dominator in postorder
+        let mut retained = vec![0; num_nodes];
+        for &node in &postorder[..postorder.len() - 1] {
+            retained[node] += self.objects[node - 1].size_blocks;
+            retained[idom[node]] += retained[node];
+        }
+
+        retained.remove(0);
+        retained
+    }
+
+    /// Writes the dump in the format that is described in the [module documentation](self)
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        let writer = &mut writer;
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        write_usize(writer, self.block_size)?;
+
+        write_usize(writer, self.types.len())?;
+        for type_name in &self.types {
+            write_str(writer, type_name)?;
+        }
+
+        write_usize(writer, self.objects.len())?;
+        for object in &self.objects {
+            write_usize(writer, object.block)?;
+            write_usize(writer, object.type_index)?;
+            write_usize(writer, object.size_blocks)?;
+            write_references(writer, &object.references)?;
+        }
+
+        write_usize(writer, self.roots.len())?;
+        for root in &self.roots {
+            write_str(writer, &root.name)?;
+            write_references(writer, &root.references)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a dump that was written with [`HeapDump::write_to`]
+    pub fn read_from(mut reader: impl Read) -> io::Result<Self> {
+        let reader = &mut reader;
+
+        let mut header = [0; MAGIC.len() + 1];
+        reader.read_exact(&mut header)?;
+
+        if &header[..MAGIC.len()] != MAGIC {
+            return Err(invalid_data("not a heap dump"));
+        }
+
+        if header[MAGIC.len()] != VERSION {
+            return Err(invalid_data(format!(
+                "unsupported heap dump version {}",
+                header[MAGIC.len()]
+            )));
+        }
+
+        let block_size = read_usize(reader)?;
+
+        let types = (0..read_usize(reader)?)
+            .map(|_| read_string(reader))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let objects = (0..read_usize(reader)?)
+            .map(|_| {
+                Ok(HeapObject {
+                    block: read_usize(reader)?,
+                    type_index: read_usize(reader)?,
+                    size_blocks: read_usize(reader)?,
+                    references: read_references(reader)?,
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let roots = (0..read_usize(reader)?)
+            .map(|_| {
+                Ok(HeapRoot {
+                    name: read_string(reader)?,
+                    references: read_references(reader)?,
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let dump = Self {
+            block_size,
+            types,
+            objects,
+            roots,
+        };
+
+        let num_objects = dump.objects.len();
+        let references = dump
+            .objects
+            .iter()
+            .flat_map(|object| &object.references)
+            .chain(dump.roots.iter().flat_map(|root| &root.references));
+
+        if dump
+            .objects
+            .iter()
+            .any(|object| object.type_index >= dump.types.len())
+        {
+            return Err(invalid_data("object has an invalid type index"));
+        }
+
+        for &reference in references {
+            if reference >= num_objects {
+                return Err(invalid_data(format!(
+                    "reference to object {reference}, but there are only {num_objects} objects"
+                )));
+            }
+        }
+
+        Ok(dump)
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn write_usize(writer: &mut impl Write, value: usize) -> io::Result<()> {
+    writer.write_all(&(value as u64).to_le_bytes())
+}
+
+fn write_str(writer: &mut impl Write, value: &str) -> io::Result<()> {
+    write_usize(writer, value.len())?;
+    writer.write_all(value.as_bytes())
+}
+
+fn write_references(writer: &mut impl Write, references: &[usize]) -> io::Result<()> {
+    write_usize(writer, references.len())?;
+
+    for &reference in references {
+        write_usize(writer, reference)?;
+    }
+
+    Ok(())
+}
+
+fn read_usize(reader: &mut impl Read) -> io::Result<usize> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+
+    usize::try_from(u64::from_le_bytes(bytes)).map_err(|_| invalid_data("integer is too large"))
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    let len = read_usize(reader)?;
+
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+
+    if bytes.len() != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    String::from_utf8(bytes).map_err(|_| invalid_data("string is not valid UTF-8"))
+}
+
+fn read_references(reader: &mut impl Read) -> io::Result<Vec<usize>> {
+    (0..read_usize(reader)?)
+        .map(|_| read_usize(reader))
+        .collect()
+}
+
+/// Walks the heap from the garbage-collection roots and records every object that it finds
+pub struct HeapTracer<'gc> {
+    gc: &'gc GarbageCollector,
+    dump: HeapDump,
+    type_indices: HashMap<&'static str, usize>,
+    /// The index of every object that has been recorded, by block
+    object_indices: HashMap<usize, usize>,
+    /// The references of the root or object that is currently being traced
+    references: Vec<usize>,
+    /// Objects that have been recorded, but whose references haven't been traced yet. The heap
+    /// can be too deep to trace recursively.
+    stack: Vec<UntracedObject>,
+}
+
+/// The function that traces the references of the object at a block. See [`HeapTracer::object`].
+pub type TraceFn = unsafe fn(usize, &mut HeapTracer);
+
+/// An object whose references still need to be traced
+struct UntracedObject {
+    index: usize,
+    ptr: usize,
+    trace_references: TraceFn,
+}
+
+impl<'gc> HeapTracer<'gc> {
+    pub fn gc(&self) -> &'gc GarbageCollector {
+        self.gc
+    }
+
+    /// Records a reference to the object at `ptr` from the root or object that is currently being
+    /// traced. The first time that an object is found, it is recorded, and `trace_references` is
+    /// later called with `ptr` to trace the objects that it references.
+    pub fn object(
+        &mut self,
+        ptr: usize,
+        type_name: &'static str,
+        size_blocks: usize,
+        trace_references: TraceFn,
+    ) {
+        if let Some(&index) = self.object_indices.get(&ptr) {
+            self.references.push(index);
+            return;
+        }
+
+        let type_index = *self.type_indices.entry(type_name).or_insert_with(|| {
+            self.dump.types.push(type_name.to_owned());
+            self.dump.types.len() - 1
+        });
+
+        let index = self.dump.objects.len();
+        self.object_indices.insert(ptr, index);
+        self.references.push(index);
+
+        // The object is recorded before its references so that cycles end here
+        self.dump.objects.push(HeapObject {
+            block: ptr,
+            type_index,
+            size_blocks,
+            references: Vec::new(),
+        });
+
+        self.stack.push(UntracedObject {
+            index,
+            ptr,
+            trace_references,
+        });
+    }
+
+    /// Traces the objects that are reachable from a root or handle
+    unsafe fn root(&mut self, name: String, entry: Option<GCRootEntry>) {
+        let Some(entry) = entry else {
+            return;
+        };
+
+        unsafe { (entry.trace_fn)(entry.data_ptr, self) };
+
+        let references = std::mem::take(&mut self.references);
+        self.dump.roots.push(HeapRoot { name, references });
+
+        while let Some(object) = self.stack.pop() {
+            unsafe { (object.trace_references)(object.ptr, self) };
+
+            self.dump.objects[object.index].references = std::mem::take(&mut self.references);
+        }
+    }
+}
+
+impl GarbageCollector {
+    /// Records every object that is reachable from a root or a handle. If a major collection is
+    /// in progress, it is finished first. This only moves objects that haven't been seen by the
+    /// program yet, so it can be done in the middle of an evaluation.
+    ///
+    /// # Safety
+    /// - This must not be called during a garbage-collection cycle
+    pub unsafe fn heap_dump(&self) -> HeapDump {
+        if self.is_collecting() {
+            unsafe { self.continue_major(usize::MAX) };
+        }
+
+        let mut tracer = HeapTracer {
+            gc: self,
+            dump: HeapDump {
+                block_size: Self::BLOCK_SIZE,
+                ..HeapDump::default()
+            },
+            type_indices: HashMap::new(),
+            object_indices: HashMap::new(),
+            references: Vec::new(),
+            stack: Vec::new(),
+        };
+
+        for i in 0..self.roots.len() {
+            unsafe { tracer.root(format!("roots[{i}]"), self.roots.get_unchecked(i)) };
+        }
+
+        for i in 0..self.handles.len() {
+            unsafe { tracer.root(format!("handles[{i}]"), self.handles.get_unchecked(i)) };
+        }
+
+        tracer.dump
+    }
+
+    /// Writes a heap dump to the path set with [`GarbageCollectorBuilder::heap_dump_on_limit`],
+    /// if any. This is called when evaluation exceeds the heap limit.
+    ///
+    /// # Safety
+    /// - This must not be called during a garbage-collection cycle
+    ///
+    /// [`GarbageCollectorBuilder::heap_dump_on_limit`]: crate::gc::GarbageCollectorBuilder::heap_dump_on_limit
+    pub(crate) unsafe fn dump_heap_on_limit(&self) -> io::Result<()> {
+        let Some(path) = &self.policy.limit_dump_path else {
+            return Ok(());
+        };
+
+        let dump = unsafe { self.heap_dump() };
+
+        std::fs::File::create(path)
+            .and_then(|file| {
+                let mut writer = io::BufWriter::new(file);
+                dump.write_to(&mut writer)?;
+                writer.flush()
+            })
+            .map_err(|err| {
+                let message = format!("unable to write heap dump to `{}`: {err}", path.display());
+
+                io::Error::new(err.kind(), message)
+            })
+    }
+}
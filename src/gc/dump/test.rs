@@ -0,0 +1,163 @@
+use std::io;
+
+use mulch_macros::GCPtr;
+
+use crate::{
+    eval::MValue,
+    gc::{
+        GCBox, GCString, GCVec, GarbageCollector,
+        dump::{HeapDump, HeapObject, HeapRoot, Retainer},
+        safety::{GC, Projected, let_gc_and_context, root},
+    },
+};
+
+fn object(size_blocks: usize, references: &[usize]) -> HeapObject {
+    HeapObject {
+        block: 0,
+        type_index: 0,
+        size_blocks,
+        references: references.to_vec(),
+    }
+}
+
+#[test]
+fn largest_retainers() {
+    // 0 -> 1 -> 3 <- 2 <- 0, and 3 -> 0 makes a cycle
+    let dump = HeapDump {
+        block_size: 8,
+        types: vec!["T".into()],
+        objects: vec![
+            object(1, &[1, 2]),
+            object(2, &[3]),
+            object(3, &[3]),
+            object(4, &[0]),
+        ],
+        roots: vec![HeapRoot {
+            name: "roots[0]".into(),
+            references: vec![0],
+        }],
+    };
+
+    let retainer = |object, shallow_blocks: usize, retained_blocks: usize| Retainer {
+        object,
+        shallow_bytes: shallow_blocks * 8,
+        retained_bytes: retained_blocks * 8,
+    };
+
+    // The object that is reachable from both 1 and 2 is only retained by 0
+    assert_eq!(
+        dump.largest_retainers(3),
+        [retainer(0, 1, 10), retainer(3, 4, 4), retainer(2, 3, 3)]
+    );
+}
+
+#[test]
+fn dump_round_trip() {
+    let_gc_and_context!(gc, ctx);
+
+    let shared = "a string that is too long to be stored inline";
+    let string = root!(ctx, GCString::new(ctx, shared));
+
+    let elements = [
+        string.get(ctx),
+        string.get(ctx),
+        GCString::new(ctx, "inline"),
+    ]
+    .map(|s| GC::from(Projected::<MValue>::String(s)));
+    let list = root!(ctx, GCVec::from_iter_and_len(ctx, elements.into_iter(), 3));
+
+    let dump = ctx.heap_dump();
+    assert_eq!(dump.block_size, GarbageCollector::BLOCK_SIZE);
+    assert_eq!(dump.roots.len(), 2);
+
+    // The string is rooted through a box. It is only recorded once, and inline strings aren't
+    // objects.
+    let string_box = dump.roots[0].references[0];
+    let string_object = dump.objects[string_box].references[0];
+    let list_object = dump.roots[1].references[0];
+
+    assert_eq!(dump.objects.len(), 3);
+    assert_eq!(
+        dump.objects[list_object].block,
+        list.get(ctx).raw().ptr().get()
+    );
+    assert_eq!(
+        dump.objects[list_object].references,
+        [string_object, string_object]
+    );
+    assert!(dump.type_name(&dump.objects[string_box]).contains("GCBox"));
+    assert!(
+        dump.type_name(&dump.objects[string_object])
+            .ends_with("GCString")
+    );
+    assert!(dump.type_name(&dump.objects[list_object]).contains("GCVec"));
+
+    // The string is reachable from both roots, so neither of them retains it
+    let size = |object: usize| dump.objects[object].size_blocks * dump.block_size;
+    let retainers = dump.largest_retainers(3);
+    let retained = |object| {
+        retainers
+            .iter()
+            .find(|retainer| retainer.object == object)
+            .unwrap()
+            .retained_bytes
+    };
+
+    assert_eq!(retained(string_box), size(string_box));
+    assert_eq!(retained(list_object), size(list_object));
+    assert_eq!(retained(string_object), size(string_object));
+    assert_eq!(
+        dump.total_bytes(),
+        size(string_box) + size(list_object) + size(string_object)
+    );
+
+    let mut bytes = Vec::new();
+    dump.write_to(&mut bytes).unwrap();
+    assert_eq!(HeapDump::read_from(bytes.as_slice()).unwrap(), dump);
+
+    let err = HeapDump::read_from(&bytes[..bytes.len() - 1]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    let err = HeapDump::read_from(&b"not a heap dump"[..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[derive(Clone, Copy, GCPtr)]
+struct Node {
+    next: Option<GCBox<Node>>,
+}
+
+#[test]
+fn deep_heap() {
+    let_gc_and_context!(gc, ctx);
+
+    // Tracing this recursively would overflow the stack
+    let len = 100_000;
+    let mut list = None;
+    for _ in 0..len {
+        // SAFETY: `list` was just allocated
+        list = Some(unsafe { GCBox::new_raw(&gc, Node { next: list }) });
+    }
+
+    // SAFETY: the list was just allocated
+    let _list = root!(ctx, unsafe { GC::new(ctx, list) });
+
+    let dump = ctx.heap_dump();
+
+    // The root stores the list in another box
+    assert_eq!(dump.objects.len(), len + 1);
+
+    let mut object = dump.roots[0].references[0];
+    for _ in 0..len {
+        let [next] = dump.objects[object].references[..] else {
+            panic!("every box in the list references the next one")
+        };
+        object = next;
+    }
+    assert!(dump.objects[object].references.is_empty());
+
+    assert_eq!(
+        dump.largest_retainers(1)[0].retained_bytes,
+        dump.total_bytes()
+    );
+}
@@ -1,13 +1,18 @@
 use std::{alloc::Layout, cell::Cell};
 
-use crate::gc::GarbageCollector;
+use crate::gc::{GarbageCollector, policy::GCPolicy};
 
 pub struct GCSpace {
     data: Cell<*mut u8>,
-    /// Currently occupied space (in blocks)
+    /// The index of the first block of the space. Block indices are global across all spaces of a
+    /// garbage collector, so this is used to tell which space an object lives in.
+    base: Cell<usize>,
+    /// The index after the last occupied block (in blocks). This includes `base`.
     len: Cell<usize>,
     /// Capacity (in blocks)
     capacity: Cell<usize>,
+    /// The factor that the capacity is multiplied by when the space grows
+    growth_factor: Cell<f64>,
 }
 
 impl Default for GCSpace {
@@ -17,8 +22,6 @@ impl Default for GCSpace {
 }
 
 impl GCSpace {
-    const STARTING_BLOCKS: usize = 128;
-
     pub(super) fn ptr(&self) -> *mut u8 {
         self.data.get()
     }
@@ -28,10 +31,25 @@ impl GCSpace {
     pub(super) fn len(&self) -> usize {
         self.len.get()
     }
+    pub(super) fn base(&self) -> usize {
+        self.base.get()
+    }
+
+    /// The index of the first block that can be allocated
+    fn start(&self) -> usize {
+        // We reserve the first block. This allows us to use `NonZeroUsize` for many of our
+        // datastructures.
+        self.base().max(1)
+    }
+
+    /// The number of occupied blocks
+    pub(super) fn used(&self) -> usize {
+        self.len() - self.start()
+    }
 
     /// Sets the length and increases the capacity if needed.
     pub(super) fn set_len(&self, len: usize) {
-        self.expand_capacity_to(len);
+        self.expand_capacity_to(len - self.base());
         self.len.set(len);
     }
 
@@ -68,7 +86,7 @@ impl GCSpace {
         let mut new_exact_size_blocks = self.capacity();
 
         while new_exact_size_blocks < new_size_blocks {
-            new_exact_size_blocks *= 2;
+            new_exact_size_blocks = self.grown_capacity(new_exact_size_blocks);
         }
 
         if new_exact_size_blocks == self.capacity() {
@@ -78,30 +96,45 @@ impl GCSpace {
         self.expand_capacity_to_exact(new_exact_size_blocks);
     }
 
+    /// The capacity that a space with `capacity` blocks grows to
+    pub(super) fn grown_capacity(&self, capacity: usize) -> usize {
+        ((capacity as f64 * self.growth_factor.get()).ceil() as usize).max(capacity + 1)
+    }
+
     /// Clears the GCSpace. All objects in the space are "forgotten".
-    pub fn clear(&mut self) {
-        self.len.set(1);
+    pub fn clear(&self) {
+        self.len.set(self.start());
     }
 
     pub fn new() -> Self {
+        Self::with_policy(0, &GCPolicy::default())
+    }
+
+    /// Creates a `GCSpace` whose blocks start at the index `base`
+    pub(super) fn with_policy(base: usize, policy: &GCPolicy) -> Self {
         let data = unsafe {
             std::alloc::alloc(Layout::from_size_align_unchecked(
-                Self::STARTING_BLOCKS * GarbageCollector::BLOCK_SIZE,
+                policy.initial_blocks * GarbageCollector::BLOCK_SIZE,
                 GarbageCollector::BLOCK_SIZE,
             ))
         };
 
-        Self {
+        let space = Self {
             data: Cell::new(data),
-            len: Cell::new(1), // We reserve the first block. This allows us to use `NonZeroUsize` for many of our datastructures.
-            capacity: Cell::new(Self::STARTING_BLOCKS),
-        }
+            base: Cell::new(base),
+            len: Cell::new(0),
+            capacity: Cell::new(policy.initial_blocks),
+            growth_factor: Cell::new(policy.growth_factor),
+        };
+
+        space.clear();
+        space
     }
 
     /// Gets a pointer to the block at `idx`
     pub(super) fn block_ptr(&self, idx: impl Into<usize>) -> *mut u8 {
         self.ptr()
-            .wrapping_byte_add(idx.into() * GarbageCollector::BLOCK_SIZE)
+            .wrapping_byte_add((idx.into() - self.base()) * GarbageCollector::BLOCK_SIZE)
     }
 
     /// Swaps this `GCSpace` with another `GCSpace`. This should only be done as a part of a
@@ -109,8 +142,10 @@ impl GCSpace {
     #[allow(unreachable_code)]
     pub(super) unsafe fn swap(&self, other: &GCSpace) {
         self.data.swap(&other.data);
+        self.base.swap(&other.base);
         self.len.swap(&other.len);
         self.capacity.swap(&other.capacity);
+        self.growth_factor.swap(&other.growth_factor);
 
         return;
 
@@ -119,8 +154,10 @@ impl GCSpace {
         #[allow(clippy::diverging_sub_expression)]
         let _ = Self {
             data: unreachable!(),
+            base: unreachable!(),
             len: unreachable!(),
             capacity: unreachable!(),
+            growth_factor: unreachable!(),
         };
     }
 }
@@ -0,0 +1,147 @@
+use std::{cell::UnsafeCell, marker::PhantomData};
+
+use crate::gc::{GCPtr, GarbageCollector, incremental::RootSlot, roots::GCRootEntry};
+
+pub use handletable::GCHandleTable;
+
+mod handletable {
+    use super::*;
+
+    /// Garbage-collection roots that can be freed in any order. Freed slots are reused by later
+    /// handles, so the index of a handle is stable for as long as it is alive.
+    pub struct GCHandleTable {
+        entries: UnsafeCell<Vec<Option<GCRootEntry>>>,
+        free: UnsafeCell<Vec<usize>>,
+    }
+
+    impl GCHandleTable {
+        pub fn new() -> Self {
+            Self {
+                entries: UnsafeCell::new(Vec::new()),
+                free: UnsafeCell::new(Vec::new()),
+            }
+        }
+
+        // NOTE: all of this interior mutability is safe because we don't provide a safe way to get
+        // a reference to any element.
+
+        pub unsafe fn get_unchecked(&self, index: usize) -> Option<GCRootEntry> {
+            let vec = unsafe { self.entries.get().as_ref_unchecked() };
+
+            debug_assert!(index < vec.len());
+
+            unsafe { vec.as_ptr().add(index).read() }
+        }
+
+        pub fn set(&self, index: usize, value: Option<GCRootEntry>) {
+            unsafe { self.entries.get().as_mut_unchecked()[index] = value }
+        }
+
+        /// The number of slots in the table, including free ones
+        pub fn len(&self) -> usize {
+            unsafe { self.entries.get().as_ref_unchecked().len() }
+        }
+
+        /// The number of handles that are alive
+        pub fn num_live(&self) -> usize {
+            self.len() - unsafe { self.free.get().as_ref_unchecked().len() }
+        }
+
+        pub(super) fn insert(&self, entry: GCRootEntry) -> usize {
+            let entries = unsafe { self.entries.get().as_mut_unchecked() };
+
+            match unsafe { self.free.get().as_mut_unchecked() }.pop() {
+                Some(index) => {
+                    entries[index] = Some(entry);
+                    index
+                }
+                None => {
+                    entries.push(Some(entry));
+                    entries.len() - 1
+                }
+            }
+        }
+
+        pub(super) fn remove(&self, index: usize) {
+            self.set(index, None);
+            unsafe { self.free.get().as_mut_unchecked() }.push(index);
+        }
+    }
+}
+
+/// A raw reference to a garbage-collection root in the handle table. Unlike [`GCRootRef`], these
+/// can be freed in any order.
+///
+/// Dropping a `GCHandle` without freeing it leaks the root, so the value that it points to is
+/// never collected.
+///
+/// [`GCRootRef`]: crate::gc::GCRootRef
+pub struct GCHandle<T> {
+    index: usize,
+    _phantomdata: PhantomData<T>,
+}
+
+impl<T: GCPtr> GCHandle<T> {
+    /// Gets the value that this handle points to. If a major collection is in progress and the
+    /// handle has not been evacuated (or scanned) yet, that is finished first.
+    ///
+    /// # Safety
+    /// - `self` must point to a valid handle in `gc`
+    pub unsafe fn get(&self, gc: &GarbageCollector) -> T {
+        let mut entry =
+            unsafe { gc.handles.get_unchecked(self.index) }.expect("GC handle should not be freed");
+
+        if entry.pending || gc.is_scanning(RootSlot::Handle(self.index)) {
+            entry = unsafe { gc.evacuate_entry(entry) };
+            gc.handles.set(self.index, Some(entry));
+        }
+
+        #[cfg(debug_assertions)]
+        assert_eq!(entry.type_name, core::any::type_name::<T>());
+
+        unsafe { <T as GCPtr>::from_gc_root_entry(gc, entry.data_ptr) }
+    }
+
+    /// Frees a handle so that its value can be collected.
+    ///
+    /// # Safety
+    /// - `self` must point to a valid handle in `gc`
+    pub unsafe fn free(self, gc: &GarbageCollector) {
+        gc.handles.remove(self.index);
+    }
+}
+
+impl GarbageCollector {
+    /// Adds an object as a garbage-collection root that can be freed at any time. This may create
+    /// another allocation on the GC heap.
+    ///
+    /// # Safety
+    /// `value` must be a valid object in `self`
+    #[must_use = "The GC handle should be freed at some point"]
+    pub unsafe fn push_handle<T: GCPtr>(&self, value: T) -> GCHandle<T> {
+        let info = unsafe { value.to_gc_root_entry(self) };
+
+        let index = self.handles.insert(GCRootEntry {
+            copy_fn: info.copy_fn,
+            data_ptr: info.data_ptr,
+            pending: false,
+            trace_fn: info.trace_fn,
+
+            #[cfg(debug_assertions)]
+            type_name: ::core::any::type_name::<T>(),
+
+            #[cfg(feature = "verify-heap")]
+            verify_fn: crate::gc::verify::verify_root::<T>,
+        });
+
+        GCHandle {
+            index,
+            _phantomdata: PhantomData,
+        }
+    }
+
+    /// The number of [`GCHandle`]s that haven't been freed
+    pub fn num_handles(&self) -> usize {
+        self.handles.num_live()
+    }
+}
@@ -0,0 +1,295 @@
+//! Major collections that are done in slices of bounded work.
+//!
+//! When a major collection starts, the old generation becomes the from-space and every root and
+//! handle is marked as pending. Slices evacuate the pending roots one at a time. Copying an object
+//! out of the from-space does not copy the objects that it references: that happens when the copy
+//! is scanned. Copies are scanned in the order that they were made, like with the scan pointer of
+//! Cheney's algorithm. Objects don't record their types, so instead of a pointer into the
+//! to-space, the collector keeps a work list of the copies and the functions that scan them. A
+//! slice can stop after any object, so it only goes over its budget by the size of the objects
+//! that a single object references.
+//!
+//! The program runs between slices, and it must never see an object that still references the
+//! from-space. Objects can only be reached through roots and handles, so reading those is the read
+//! barrier: reading a pending root evacuates it and scans everything that it references. A slice
+//! only moves on to the next root once the work list is empty, so the unscanned copies can only
+//! be reached from the root that is being scanned, and reading it finishes the scan. Every other
+//! object that the program can reach has been scanned, which is why reading through `GC` or
+//! `GCBox` needs no barrier.
+
+use std::{
+    cell::UnsafeCell,
+    collections::VecDeque,
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
+
+use crate::gc::{CollectionKind, GarbageCollector, roots::GCRootEntry};
+
+/// The progress of a major collection that is in progress
+#[derive(Clone, Copy, Debug)]
+pub(super) struct MajorCycle {
+    /// Every root below this index has been evacuated
+    next_root: usize,
+    /// Every handle below this index has been evacuated
+    next_handle: usize,
+    /// The root or handle that the objects in the work list were copied for
+    scanning: Option<RootSlot>,
+    bytes_copied: usize,
+    /// The number of roots and handles when the collection started
+    roots: usize,
+    /// The time spent in slices so far
+    duration: Duration,
+}
+
+/// The position of an entry in the root list or the handle table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum RootSlot {
+    Root(usize),
+    Handle(usize),
+}
+
+/// An object that has been copied out of the from-space, but whose references still point into it
+#[derive(Clone, Copy)]
+pub(super) struct UnscannedEntry {
+    pub ptr: NonZeroUsize,
+    /// Copies the objects that the object at `ptr` references and updates its references
+    pub scan_fn: unsafe fn(NonZeroUsize, &GarbageCollector),
+}
+
+/// The objects that a major collection has copied but not scanned yet, in the order that they
+/// were copied
+pub(super) struct WorkList {
+    entries: UnsafeCell<VecDeque<UnscannedEntry>>,
+}
+
+impl WorkList {
+    pub fn new() -> Self {
+        Self {
+            entries: UnsafeCell::new(VecDeque::new()),
+        }
+    }
+
+    // NOTE: all of this interior mutability is safe because we don't provide a way to get a
+    // reference to any element.
+
+    pub fn push(&self, entry: UnscannedEntry) {
+        unsafe { self.entries.get().as_mut_unchecked().push_back(entry) };
+    }
+
+    pub fn pop(&self) -> Option<UnscannedEntry> {
+        unsafe { self.entries.get().as_mut_unchecked().pop_front() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        unsafe { self.entries.get().as_ref_unchecked().is_empty() }
+    }
+}
+
+impl GarbageCollector {
+    /// Whether a major collection has been started but not finished
+    pub fn is_collecting(&self) -> bool {
+        self.cycle.get().is_some()
+    }
+
+    /// Starts a major collection without copying anything. The nursery must be empty.
+    pub(super) fn start_major(&self) {
+        debug_assert!(!self.is_collecting());
+        debug_assert_eq!(self.nursery.used(), 0);
+
+        // SAFETY: the to-space is empty outside of major collections
+        unsafe { self.old.swap(&self.to_space) };
+
+        for i in 0..self.roots.len() {
+            if let Some(entry) = self.roots.get(i) {
+                self.roots.set(
+                    i,
+                    Some(GCRootEntry {
+                        pending: true,
+                        ..entry
+                    }),
+                );
+            }
+        }
+
+        for i in 0..self.handles.len() {
+            if let Some(entry) = unsafe { self.handles.get_unchecked(i) } {
+                self.handles.set(
+                    i,
+                    Some(GCRootEntry {
+                        pending: true,
+                        ..entry
+                    }),
+                );
+            }
+        }
+
+        self.interned.mark_pending();
+
+        self.cycle.set(Some(MajorCycle {
+            next_root: 0,
+            next_handle: 0,
+            scanning: None,
+            bytes_copied: 0,
+            roots: self.num_roots(),
+            duration: Duration::ZERO,
+        }));
+    }
+
+    /// Whether objects that are copied out of the from-space are added to the work list instead of
+    /// being scanned right away
+    pub(super) fn defers_scanning(&self) -> bool {
+        self.major.get()
+    }
+
+    /// Adds an object that was just copied out of the from-space to the work list
+    pub(super) fn defer_scan(&self, entry: UnscannedEntry) {
+        debug_assert!(self.defers_scanning());
+
+        self.work_list.push(entry);
+    }
+
+    /// Whether reading the root or handle at `slot` must finish scanning the work list
+    pub(super) fn is_scanning(&self, slot: RootSlot) -> bool {
+        !self.work_list.is_empty()
+            && self
+                .cycle
+                .get()
+                .is_some_and(|cycle| cycle.scanning == Some(slot))
+    }
+
+    /// Moves everything that is reachable from a root or handle out of the from-space. This is
+    /// the read barrier for pending entries and for the entry that is being scanned.
+    ///
+    /// # Safety
+    /// - `entry` must be a live entry of the root list or handle table
+    pub(super) unsafe fn evacuate_entry(&self, entry: GCRootEntry) -> GCRootEntry {
+        self.evacuate(|| unsafe { self.copy_entry(entry) })
+    }
+
+    /// Copies the object that a pending root or handle points to, without scanning it
+    ///
+    /// # Safety
+    /// - `entry` must be a live entry of the root list or handle table
+    /// - Objects must be being evacuated out of the from-space
+    unsafe fn copy_entry(&self, entry: GCRootEntry) -> GCRootEntry {
+        if !entry.pending {
+            return entry;
+        }
+
+        GCRootEntry {
+            data_ptr: unsafe { (entry.copy_fn)(entry.data_ptr, self) },
+            pending: false,
+            ..entry
+        }
+    }
+
+    /// Runs `copy` with old objects being evacuated out of the from-space, and scans everything
+    /// that is copied. The copied bytes count towards the current major collection.
+    pub(super) fn evacuate<T>(&self, copy: impl FnOnce() -> T) -> T {
+        self.copy_from_space(|| {
+            let result = copy();
+
+            while let Some(entry) = self.work_list.pop() {
+                unsafe { (entry.scan_fn)(entry.ptr, self) };
+            }
+
+            result
+        })
+    }
+
+    /// Runs `copy` with old objects being evacuated out of the from-space, and counts the copied
+    /// bytes towards the current major collection. Copies that are not scanned by `copy` are left
+    /// in the work list.
+    fn copy_from_space<T>(&self, copy: impl FnOnce() -> T) -> T {
+        let old_len = self.old.len();
+
+        self.major.set(true);
+        let result = copy();
+        self.major.set(false);
+
+        let mut cycle = self
+            .cycle
+            .get()
+            .expect("a major collection should be in progress");
+        cycle.bytes_copied += (self.old.len() - old_len) * Self::BLOCK_SIZE;
+        self.cycle.set(Some(cycle));
+
+        result
+    }
+
+    /// Continues the current major collection until `budget` bytes have been copied or the
+    /// collection finishes. A slice scans at least one object, and it stops after the object that
+    /// uses up its budget. Returns whether the collection finished.
+    ///
+    /// # Safety
+    /// - This function should not be called directly while the safe garbage-collection API is in
+    ///   use.
+    pub(super) unsafe fn continue_major(&self, budget: usize) -> bool {
+        let start = Instant::now();
+        let start_bytes = self.cycle.get().unwrap().bytes_copied;
+        let budget_left = || self.cycle.get().unwrap().bytes_copied - start_bytes < budget;
+
+        loop {
+            let mut cycle = self.cycle.get().unwrap();
+
+            if let Some(entry) = self.work_list.pop() {
+                self.copy_from_space(|| unsafe { (entry.scan_fn)(entry.ptr, self) });
+            } else if cycle.next_root < self.roots.len() {
+                let i = cycle.next_root;
+                cycle.next_root += 1;
+                cycle.scanning = Some(RootSlot::Root(i));
+                self.cycle.set(Some(cycle));
+
+                if let Some(entry) = self.roots.get(i) {
+                    let entry = self.copy_from_space(|| unsafe { self.copy_entry(entry) });
+                    self.roots.set(i, Some(entry));
+                }
+            } else if cycle.next_handle < self.handles.len() {
+                let i = cycle.next_handle;
+                cycle.next_handle += 1;
+                cycle.scanning = Some(RootSlot::Handle(i));
+                self.cycle.set(Some(cycle));
+
+                if let Some(entry) = unsafe { self.handles.get_unchecked(i) } {
+                    let entry = self.copy_from_space(|| unsafe { self.copy_entry(entry) });
+                    self.handles.set(i, Some(entry));
+                }
+            } else {
+                self.finish_major(start);
+                return true;
+            }
+
+            if !budget_left() {
+                break;
+            }
+        }
+
+        let mut cycle = self.cycle.get().unwrap();
+        cycle.duration += start.elapsed();
+        self.cycle.set(Some(cycle));
+
+        false
+    }
+
+    /// Frees the from-space once nothing points into it anymore
+    fn finish_major(&self, slice_start: Instant) {
+        unsafe { self.sweep_interned(CollectionKind::Major) };
+
+        let cycle = self.cycle.take().unwrap();
+
+        self.to_space.clear();
+
+        self.finish_cycle(
+            CollectionKind::Major,
+            cycle.duration + slice_start.elapsed(),
+            cycle.bytes_copied,
+            cycle.roots,
+        );
+
+        #[cfg(feature = "verify-heap")]
+        unsafe {
+            self.verify_heap()
+        };
+    }
+}
@@ -0,0 +1,155 @@
+//! The interning table for strings, which is used for names.
+//!
+//! Interned strings with the same contents share their storage, so large configs that repeat the
+//! same attribute names use less memory, and names can be compared by pointer. Strings that are
+//! short enough to be stored inline are never interned: comparing them is already cheap.
+//!
+//! The table is weak: it does not keep strings alive. After a collection has copied everything
+//! that is reachable, entries that were copied are updated and entries that were not are removed.
+//! While a major collection is in progress, entries that have not been evacuated yet are pending,
+//! just like roots. Looking up a pending entry evacuates it first.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    hash::{BuildHasher, RandomState},
+};
+
+use crate::gc::{CollectionKind, GCPtr, GCString, GarbageCollector};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Clone, Copy)]
+struct InternEntry {
+    string: GCString,
+    /// Whether `string` points into the from-space of the major collection that is in progress
+    pending: bool,
+}
+
+/// Interned strings, grouped by the hash of their contents
+pub(super) struct InternTable {
+    hasher: RandomState,
+    entries: RefCell<HashMap<u64, Vec<InternEntry>>>,
+}
+
+impl InternTable {
+    pub fn new() -> Self {
+        Self {
+            hasher: RandomState::new(),
+            entries: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The number of interned strings
+    pub fn len(&self) -> usize {
+        self.entries.borrow().values().map(Vec::len).sum()
+    }
+
+    /// Marks every entry as pending. This is called when a major collection starts.
+    pub fn mark_pending(&self) {
+        for entry in self.entries.borrow_mut().values_mut().flatten() {
+            entry.pending = true;
+        }
+    }
+}
+
+impl GarbageCollector {
+    /// Gets the interned string with the same contents as `string`, or interns a new one. The
+    /// string must be too long to be stored inline.
+    pub(super) fn intern(&self, string: &str) -> GCString {
+        debug_assert!(!GCString::fits_inline(string));
+
+        let hash = self.hash_str(string);
+        let mut entries = self.interned.entries.borrow_mut();
+        let bucket = entries.entry(hash).or_default();
+
+        for entry in bucket.iter_mut() {
+            if entry.pending {
+                // SAFETY: pending entries are alive, because the from-space is only freed once
+                // they are removed
+                entry.string = self.evacuate(|| unsafe { entry.string.gc_copy(self) });
+                entry.pending = false;
+            }
+
+            // SAFETY: `entry` is not pending, so it is in the nursery or the old generation
+            if unsafe { entry.string.get(self) } == string {
+                return entry.string;
+            }
+        }
+
+        let interned = GCString::new_raw(self, string);
+
+        bucket.push(InternEntry {
+            string: interned,
+            pending: false,
+        });
+
+        interned
+    }
+
+    /// Updates the entries that were copied by a collection and removes the ones that were not.
+    /// A minor collection handles the entries in the nursery, and a major collection handles the
+    /// pending entries.
+    ///
+    /// # Safety
+    /// - This must be called after everything that is reachable has been copied, but before the
+    ///   space that was collected is freed
+    pub(super) unsafe fn sweep_interned(&self, kind: CollectionKind) {
+        let sweep = |entry: &mut InternEntry| {
+            let forward = match kind {
+                CollectionKind::Minor if entry.pending => return true,
+                CollectionKind::Minor => unsafe { entry.string.get_forwarded_value(self) },
+                CollectionKind::Major if !entry.pending => return true,
+                CollectionKind::Major => {
+                    self.major.set(true);
+                    let forward = unsafe { entry.string.get_forwarded_value(self) };
+                    self.major.set(false);
+
+                    forward
+                }
+            };
+
+            match forward {
+                Some(string) => {
+                    *entry = InternEntry {
+                        string,
+                        pending: false,
+                    };
+
+                    true
+                }
+                None => false,
+            }
+        };
+
+        let mut entries = self.interned.entries.borrow_mut();
+
+        entries.retain(|_, bucket| {
+            bucket.retain_mut(sweep);
+
+            !bucket.is_empty()
+        });
+    }
+
+    /// Hashes a string with the hasher of the interning table. Hashes are stable for the lifetime
+    /// of the garbage collector, but differ between garbage collectors.
+    pub fn hash_str(&self, string: &str) -> u64 {
+        self.interned.hasher.hash_one(string)
+    }
+
+    /// The number of strings in the interning table
+    pub fn num_interned(&self) -> usize {
+        self.interned.len()
+    }
+
+    /// Calls `f` with every interned string that is not pending
+    #[cfg(feature = "verify-heap")]
+    pub(super) fn for_each_interned(&self, mut f: impl FnMut(GCString)) {
+        for entry in self.interned.entries.borrow().values().flatten() {
+            if !entry.pending {
+                f(entry.string);
+            }
+        }
+    }
+}
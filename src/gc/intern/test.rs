@@ -0,0 +1,91 @@
+use crate::gc::{
+    GCString,
+    safety::{let_gc_and_context, root},
+};
+
+const NAME: &str = "an attribute name that is too long to be stored inline";
+
+#[test]
+fn interned_strings_are_shared() {
+    let_gc_and_context!(gc, ctx);
+
+    let a = GCString::intern(ctx, NAME).raw();
+    let b = GCString::intern(ctx, NAME).raw();
+    let other = GCString::intern(ctx, "another name that is too long to be stored inline").raw();
+    let not_interned = GCString::new(ctx, NAME).raw();
+
+    assert!(a.ptr_eq(&b));
+    assert!(!a.ptr_eq(&other));
+    assert!(!a.ptr_eq(&not_interned));
+    assert_eq!(gc.num_interned(), 2);
+
+    // Inline strings are compared by value and never take up space in the table
+    let short = GCString::intern(ctx, "short").raw();
+    assert!(short.ptr_eq(&GCString::new(ctx, "short").raw()));
+    assert_eq!(gc.num_interned(), 2);
+}
+
+#[test]
+fn interning_table_is_weak() {
+    let_gc_and_context!(gc, ctx);
+
+    {
+        let kept = root!(ctx, GCString::intern(ctx, NAME));
+        GCString::intern(ctx, "a name that is only used once and then thrown away");
+        assert_eq!(gc.num_interned(), 2);
+
+        ctx.force_minor_collect();
+
+        // Only the reachable string survives, and the table follows it to the old generation
+        assert_eq!(gc.num_interned(), 1);
+        assert!(
+            kept.get(ctx)
+                .raw()
+                .ptr_eq(&GCString::intern(ctx, NAME).raw())
+        );
+
+        ctx.force_collect();
+
+        assert_eq!(gc.num_interned(), 1);
+        assert_eq!(kept.get(ctx).read(), NAME);
+        assert!(
+            kept.get(ctx)
+                .raw()
+                .ptr_eq(&GCString::intern(ctx, NAME).raw())
+        );
+    }
+
+    ctx.force_collect();
+
+    assert_eq!(gc.num_interned(), 0);
+}
+
+#[test]
+fn interning_during_major_collection() {
+    let_gc_and_context!(gc, ctx);
+
+    let first = root!(ctx, GCString::intern(ctx, NAME));
+    let second = root!(
+        ctx,
+        GCString::new(
+            ctx,
+            "a string that keeps the collection from finishing at once"
+        )
+    );
+    ctx.force_minor_collect();
+
+    assert!(ctx.collect_slice(1));
+
+    // The entry is pending, so looking it up evacuates it before its root is
+    let interned = root!(ctx, GCString::intern(ctx, NAME));
+    assert_eq!(interned.get(ctx).read(), NAME);
+
+    while ctx.collect_slice(1) {}
+
+    assert_eq!(gc.num_interned(), 1);
+    assert!(first.get(ctx).raw().ptr_eq(&interned.get(ctx).raw()));
+    assert_eq!(
+        second.get(ctx).read(),
+        "a string that keeps the collection from finishing at once"
+    );
+}
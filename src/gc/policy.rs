@@ -0,0 +1,142 @@
+use std::path::PathBuf;
+
+use crate::gc::GarbageCollector;
+
+/// How the heap of a [`GarbageCollector`] is sized
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct GCPolicy {
+    /// The starting capacity of each space (in blocks)
+    pub initial_blocks: usize,
+    /// The factor that a space's capacity is multiplied by when it grows
+    pub growth_factor: f64,
+    /// The number of bytes that can be in use before evaluation is aborted
+    pub max_heap_size: Option<usize>,
+    /// Whether a collection is done at every safe point
+    pub stress: bool,
+    /// The number of bytes that a slice of a major collection copies. Major collections are not
+    /// done in slices if this is `None`.
+    pub slice_budget: Option<usize>,
+    /// Where a heap dump is written when the heap limit is exceeded
+    pub limit_dump_path: Option<PathBuf>,
+}
+
+impl Default for GCPolicy {
+    fn default() -> Self {
+        Self {
+            initial_blocks: 128,
+            growth_factor: 2.0,
+            max_heap_size: None,
+            stress: false,
+            slice_budget: None,
+            limit_dump_path: None,
+        }
+    }
+}
+
+/// Configures a [`GarbageCollector`]. This is created with [`GarbageCollector::builder`].
+///
+/// ```
+/// use mulch::gc::GarbageCollector;
+///
+/// let gc = GarbageCollector::builder()
+///     .initial_size(64 * 1024)
+///     .growth_factor(1.5)
+///     .max_heap_size(256 * 1024 * 1024)
+///     .build();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct GarbageCollectorBuilder {
+    policy: GCPolicy,
+}
+
+impl GarbageCollectorBuilder {
+    /// Sets the starting size (in bytes) of each space. Defaults to 128 blocks.
+    pub fn initial_size(mut self, bytes: usize) -> Self {
+        // The first block of a space is reserved, so a space needs at least 2 blocks
+        self.policy.initial_blocks = bytes.div_ceil(GarbageCollector::BLOCK_SIZE).max(2);
+        self
+    }
+
+    /// Sets the factor that the size of a space is multiplied by when it grows. Defaults to 2.
+    ///
+    /// # Panics
+    /// Panics if `factor` is not greater than 1
+    pub fn growth_factor(mut self, factor: f64) -> Self {
+        assert!(factor > 1.0, "the growth factor must be greater than 1");
+
+        self.policy.growth_factor = factor;
+        self
+    }
+
+    /// Limits the number of bytes that can be in use on the heap. When the limit is exceeded,
+    /// evaluation is aborted with an error. By default, there is no limit.
+    ///
+    /// The limit is checked before each expression is evaluated, so the heap can briefly exceed it
+    /// by the allocations of a single expression.
+    pub fn max_heap_size(mut self, bytes: usize) -> Self {
+        self.policy.max_heap_size = Some(bytes);
+        self
+    }
+
+    /// Writes a heap dump to `path` when evaluation exceeds the heap limit, so that the objects
+    /// that used up the memory can be analyzed. See [`crate::gc::dump`] for the format. This has
+    /// no effect without [`GarbageCollectorBuilder::max_heap_size`].
+    ///
+    /// If the dump cannot be written, a warning is added as a note to the heap limit error.
+    pub fn heap_dump_on_limit(mut self, path: impl Into<PathBuf>) -> Self {
+        self.policy.limit_dump_path = Some(path.into());
+        self
+    }
+
+    /// Enables stress mode, where a garbage-collection cycle is done at every safe point (every
+    /// call to a [`#[gc_fn]`](mulch_macros::gc_fn)). This is extremely slow, but it makes values
+    /// that are used without being rooted fail deterministically. It is intended for tests.
+    pub fn stress(mut self, stress: bool) -> Self {
+        self.policy.stress = stress;
+        self
+    }
+
+    /// Makes [`GarbageCollector::collect`] do major collections in slices that copy about
+    /// `slice_bytes` bytes each, rather than all at once. This bounds the pauses of interactive
+    /// programs. By default, major collections are not done in slices.
+    ///
+    /// A slice stops after the object that uses up its budget, so it can copy more than
+    /// `slice_bytes` by the size of the objects that a single object references (eg the elements
+    /// of a large list).
+    pub fn incremental(mut self, slice_bytes: usize) -> Self {
+        self.policy.slice_budget = Some(slice_bytes);
+        self
+    }
+
+    pub fn build(self) -> GarbageCollector {
+        GarbageCollector::with_policy(self.policy)
+    }
+}
+
+impl GarbageCollector {
+    pub fn builder() -> GarbageCollectorBuilder {
+        GarbageCollectorBuilder::default()
+    }
+
+    /// The number of bytes that are currently in use on the heap. This includes the from-space of
+    /// a major collection that is in progress.
+    pub fn heap_size(&self) -> usize {
+        (self.nursery.used() + self.old.used() + self.to_space.used()) * Self::BLOCK_SIZE
+    }
+
+    /// The limit set with [`GarbageCollectorBuilder::max_heap_size`]
+    pub fn max_heap_size(&self) -> Option<usize> {
+        self.policy.max_heap_size
+    }
+
+    /// Whether stress mode is enabled. See [`GarbageCollectorBuilder::stress`].
+    pub fn is_stressed(&self) -> bool {
+        self.policy.stress
+    }
+
+    /// Whether the heap is larger than [`GarbageCollector::max_heap_size`]
+    pub fn heap_limit_exceeded(&self) -> bool {
+        self.max_heap_size()
+            .is_some_and(|max_heap_size| self.heap_size() > max_heap_size)
+    }
+}
@@ -1,11 +1,13 @@
 mod boxed;
 mod buffer;
 pub mod math;
+mod persistent_vec;
 mod string;
 mod vec;
 
 pub use boxed::GCBox;
 pub(super) use buffer::GCBuffer;
 pub use math::GCNumber;
+pub use persistent_vec::GCPersistentVec;
 pub use string::GCString;
 pub use vec::GCVec;
@@ -1,7 +1,8 @@
 use std::{marker::PhantomData, mem, num::NonZeroUsize};
 
 use crate::gc::{
-    GCDebug, GCEq, GCGet, GCPtr, GCSpace, GarbageCollector, roots::GCRootInfo, safety::GC,
+    GCDebug, GCEq, GCGet, GCPtr, GCSpace, GarbageCollector, barrier::RememberedEntry,
+    dump::HeapTracer, incremental::UnscannedEntry, roots::GCRootInfo, safety::GC,
 };
 
 /// Analogous to `std::boxed::Box`. This can be useful for recursively-defined datastructures.
@@ -41,8 +42,8 @@ impl<T: GCPtr> GCBox<T> {
     /// # Safety
     /// - `value` must point to a valid, non-frozen object in `gc`
     pub unsafe fn new_raw(gc: &GarbageCollector, value: T) -> Self {
-        let ptr = Self::alloc_uninit_in_space(&gc.from_space);
-        unsafe { ptr.ptr_in_space(&gc.from_space).write(value) };
+        let ptr = Self::alloc_uninit_in_space(&gc.nursery);
+        unsafe { ptr.ptr_in_space(&gc.nursery).write(value) };
 
         ptr
     }
@@ -62,8 +63,23 @@ impl<T: GCPtr> GCBox<T> {
         self.ptr
     }
 
-    pub(crate) fn as_mut(&self, gc: &GarbageCollector) -> *mut T {
-        gc.from_space.block_ptr(self.ptr).cast::<T>()
+    /// Replaces the value stored in the `GCBox`. This is the write barrier: if the box has been
+    /// promoted to the old generation, it is remembered so that the next minor collection can
+    /// update its reference to `value`.
+    ///
+    /// # Safety
+    /// - `self` must point to a valid, non-frozen `GCBox<T>` in `gc`
+    /// - `value` must point to a valid, non-frozen object in `gc`
+    /// - This cannot be used during a GC cycle
+    pub unsafe fn set(&self, gc: &GarbageCollector, value: T) {
+        unsafe { self.ptr_in_space(gc.space_of(self.ptr.get())).write(value) };
+
+        if !gc.is_young(self.ptr.get()) {
+            gc.remembered.push(RememberedEntry {
+                ptr: self.ptr,
+                rescan_fn: scan::<T>,
+            });
+        }
     }
 
     /// Reads the value stored in the `GCBox`
@@ -72,7 +88,7 @@ impl<T: GCPtr> GCBox<T> {
     /// - `self` must point to a valid, non-frozen `GCBox<T>` in `gc`
     /// - This cannot be used during a GC cycle
     pub unsafe fn get(&self, gc: &GarbageCollector) -> T {
-        unsafe { self.ptr_in_space(&gc.from_space).read() }
+        unsafe { self.ptr_in_space(gc.space_of(self.ptr.get())).read() }
     }
 
     /// Allocates an uninitialized `GCBox` in a given `GCSpace`
@@ -129,17 +145,36 @@ impl<T: GCPtr> GCBox<T> {
     }
 }
 
+/// Copies the objects that the value of a `GCBox` in the old generation references. This is used
+/// for boxes that were written to since the last minor collection, and for boxes that a major
+/// collection copied without scanning.
+unsafe fn scan<T: GCPtr>(ptr: NonZeroUsize, gc: &GarbageCollector) {
+    let gc_box = GCBox::<T>::from_ptr(ptr);
+
+    let old_value = unsafe { gc_box.ptr_in_space(gc.copy_target()).read() };
+    let new_value = unsafe { old_value.gc_copy(gc) };
+
+    // Copying may have grown the old generation, so the pointer is recalculated
+    unsafe { gc_box.ptr_in_space(gc.copy_target()).write(new_value) };
+}
+
 unsafe impl<T: GCPtr> GCPtr for GCBox<T> {
     const MSB_RESERVED: bool = true;
 
     unsafe fn gc_copy(self, gc: &GarbageCollector) -> Self {
-        if let Some(fwd) = unsafe { self.get_forwarded_value(&gc.from_space) } {
+        if !gc.is_evacuating(self.ptr.get()) {
+            return self;
+        }
+
+        let from_space = gc.space_of(self.ptr.get());
+
+        if let Some(fwd) = unsafe { self.get_forwarded_value(from_space) } {
             return fwd;
         }
 
-        let old_value = unsafe { self.ptr_in_space(&gc.from_space).read() };
+        let old_value = unsafe { self.ptr_in_space(from_space).read() };
 
-        let new_box = Self::alloc_uninit_in_space(&gc.to_space);
+        let new_box = Self::alloc_uninit_in_space(gc.copy_target());
 
         let fwd_storage_ptr = if T::MSB_RESERVED {
             self.ptr.get()
@@ -148,18 +183,65 @@ unsafe impl<T: GCPtr> GCPtr for GCBox<T> {
         };
 
         unsafe {
-            gc.from_space
+            from_space
                 .block_ptr(fwd_storage_ptr)
                 .cast::<usize>()
                 .write(new_box.ptr.get() | 1usize.rotate_right(1))
         };
 
+        if gc.defers_scanning() {
+            unsafe { new_box.ptr_in_space(gc.copy_target()).write(old_value) };
+
+            gc.defer_scan(UnscannedEntry {
+                ptr: new_box.ptr,
+                scan_fn: scan::<T>,
+            });
+
+            return new_box;
+        }
+
         let new_value = unsafe { old_value.gc_copy(gc) };
-        unsafe { new_box.ptr_in_space(&gc.to_space).write(new_value) };
+        unsafe { new_box.ptr_in_space(gc.copy_target()).write(new_value) };
 
         new_box
     }
 
+    unsafe fn gc_trace(self, tracer: &mut HeapTracer) {
+        let size_blocks = mem::size_of::<T>().div_ceil(GarbageCollector::BLOCK_SIZE)
+            + usize::from(!T::MSB_RESERVED);
+
+        unsafe fn trace_references<T: GCPtr>(ptr: usize, tracer: &mut HeapTracer) {
+            let gc_box = GCBox::<T>::from_ptr(NonZeroUsize::new(ptr).unwrap());
+
+            unsafe { gc_box.get(tracer.gc()).gc_trace(tracer) };
+        }
+
+        tracer.object(
+            self.ptr.get(),
+            std::any::type_name::<Self>(),
+            size_blocks,
+            trace_references::<T>,
+        );
+    }
+
+    #[cfg(feature = "verify-heap")]
+    unsafe fn gc_verify(self, verifier: &mut crate::gc::verify::HeapVerifier) {
+        let value_blocks = mem::size_of::<T>().div_ceil(GarbageCollector::BLOCK_SIZE);
+        let size_blocks = value_blocks + usize::from(!T::MSB_RESERVED);
+
+        if !verifier.check_object(self.ptr.get(), size_blocks) {
+            return;
+        }
+
+        if T::MSB_RESERVED {
+            verifier.check_not_forwarded(self.ptr.get());
+        } else {
+            verifier.check_not_forwarded(self.ptr.get() + value_blocks);
+        }
+
+        unsafe { self.get(verifier.gc()).gc_verify(verifier) };
+    }
+
     unsafe fn to_gc_root_entry(self, _gc: &GarbageCollector) -> GCRootInfo {
         unsafe fn copy_fn<T: GCPtr>(data: NonZeroUsize, gc: &GarbageCollector) -> NonZeroUsize {
             let old = GCBox::<T>::from_ptr(data);
@@ -167,8 +249,13 @@ unsafe impl<T: GCPtr> GCPtr for GCBox<T> {
             new.ptr()
         }
 
+        unsafe fn trace_fn<T: GCPtr>(data: NonZeroUsize, tracer: &mut HeapTracer) {
+            unsafe { GCBox::<T>::from_ptr(data).gc_trace(tracer) };
+        }
+
         GCRootInfo {
             copy_fn: copy_fn::<T>,
+            trace_fn: trace_fn::<T>,
             data_ptr: self.ptr(),
         }
     }
@@ -192,7 +279,7 @@ impl<T: GCPtr> GCGet for GCBox<T> {
     type Borrowed = T;
 
     unsafe fn get<'a>(&'a self, gc: &'a GarbageCollector) -> &'a Self::Borrowed {
-        unsafe { &*self.ptr_in_space(&gc.from_space) }
+        unsafe { &*self.ptr_in_space(gc.space_of(self.ptr.get())) }
     }
 }
 
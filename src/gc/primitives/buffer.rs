@@ -34,7 +34,7 @@ impl<T> GCBuffer<T> {
     }
 
     pub fn new_uninit(gc: &GarbageCollector, len_items: usize) -> Self {
-        Self::new_uninit_in_space(&gc.from_space, len_items)
+        Self::new_uninit_in_space(&gc.nursery, len_items)
     }
 
     pub fn as_mut_ptr(self, gc: &GarbageCollector) -> *mut T {
@@ -84,7 +84,7 @@ impl<T> GCBuffer<T> {
 
         debug_assert_eq!(
             self.ptr.get() + cur_allocation_size,
-            gc.from_space.len(),
+            gc.nursery.len(),
             "set_length_at_end can only be used when the allocation is the last allocation on the GC heap"
         );
 
@@ -94,8 +94,8 @@ impl<T> GCBuffer<T> {
 
         let new_allocation_size = Self::allocation_size_blocks(new_length);
 
-        gc.from_space
-            .set_len(gc.from_space.len() - cur_allocation_size + new_allocation_size);
+        gc.nursery
+            .set_len(gc.nursery.len() - cur_allocation_size + new_allocation_size);
 
         self.len = new_length;
     }
@@ -112,11 +112,11 @@ impl<T> GCBuffer<T> {
 
         debug_assert_eq!(
             self.ptr.get() + allocation_size,
-            gc.from_space.len(),
+            gc.nursery.len(),
             "freeze_from_end can only be used when the allocation is the last allocation on the GC heap"
         );
 
-        gc.from_space.set_len(gc.from_space.len() - allocation_size);
+        gc.nursery.set_len(gc.nursery.len() - allocation_size);
     }
 
     pub fn new_uninit_in_space(gcspace: &GCSpace, len: usize) -> Self {
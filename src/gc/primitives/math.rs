@@ -1,7 +1,15 @@
+mod convert;
+mod decimal;
+pub(crate) mod mpn;
 mod number;
 mod rational;
 mod uint;
 mod util;
 
+#[cfg(test)]
+mod test;
+
+pub use convert::ConversionError;
+pub use decimal::{DecimalFormat, RoundingMode};
 pub use number::GCNumber;
 pub use util::*;
@@ -0,0 +1,238 @@
+//! Conversions between numbers and Rust types
+
+use std::fmt::Display;
+
+use super::{
+    DecimalFormat, RoundingMode,
+    decimal::{Limbs, format_decimal},
+    mpn::{limb_t, mpn_lshift, size_t},
+};
+
+/// The reason that a number could not be converted to a Rust type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConversionError {
+    /// The number is not an integer, but the target type can only store integers
+    NotAnInteger,
+    /// The number is too large or too small for the target type
+    OutOfRange,
+    /// The number cannot be written exactly with the requested number of decimal places
+    Inexact,
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConversionError::NotAnInteger => write!(f, "number is not an integer"),
+            ConversionError::OutOfRange => write!(f, "number is out of range"),
+            ConversionError::Inexact => write!(f, "number cannot be represented exactly"),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// Gets the magnitude of `numerator/denominator` as a `u128`. The fraction must be reduced.
+pub(super) fn integer_magnitude(
+    numerator: &[limb_t],
+    denominator: &[limb_t],
+) -> Result<u128, ConversionError> {
+    if Limbs::new(denominator).0 != [1] {
+        return Err(ConversionError::NotAnInteger);
+    }
+
+    Limbs::new(numerator)
+        .to_u128()
+        .ok_or(ConversionError::OutOfRange)
+}
+
+/// Converts `numerator/denominator` to the nearest `f64`. Ties are rounded to the value with an
+/// even mantissa. `denominator` must not be zero.
+pub(super) fn to_f64(
+    is_negative: bool,
+    numerator: &[limb_t],
+    denominator: &[limb_t],
+) -> Result<f64, ConversionError> {
+    // The exponent of the smallest subnormal `f64`
+    const MIN_UNIT_EXPONENT: i64 = f64::MIN_EXP as i64 - f64::MANTISSA_DIGITS as i64;
+
+    let numerator = Limbs::new(numerator);
+    let denominator = Limbs::new(denominator);
+
+    debug_assert!(!denominator.is_zero());
+
+    if numerator.is_zero() {
+        return Ok(0.0);
+    }
+
+    // `2^(k - 1) < numerator/denominator < 2^(k + 1)`
+    let k = numerator.bit_len() as i64 - denominator.bit_len() as i64;
+
+    // Scale the fraction so that its integer part has 55 or 56 bits. That is two more than the
+    // precision of an `f64`, so the remainder is only needed to break ties.
+    let scale = f64::MANTISSA_DIGITS as i64 + 2 - k;
+
+    let (quotient, remainder) = if scale >= 0 {
+        numerator.shl(scale as u64).div_rem(&denominator)
+    } else {
+        numerator.div_rem(&denominator.shl(scale.unsigned_abs()))
+    };
+
+    let quotient = quotient.to_u128().unwrap();
+    let is_exact = remainder.is_zero();
+
+    let num_bits = (u128::BITS - quotient.leading_zeros()) as i64;
+
+    // The exponent of the last bit that fits in the mantissa. Subnormal values have fewer bits.
+    let unit_exponent =
+        (num_bits - 1 - scale - (f64::MANTISSA_DIGITS as i64 - 1)).max(MIN_UNIT_EXPONENT);
+
+    // This is at-least two. If it is clamped, all of the bits are dropped.
+    let num_dropped = (unit_exponent + scale).min(u128::BITS as i64 - 1) as u32;
+
+    let mut mantissa = quotient >> num_dropped;
+    let dropped = quotient & ((1 << num_dropped) - 1);
+    let half = 1 << (num_dropped - 1);
+
+    if dropped > half || (dropped == half && (!is_exact || mantissa & 1 != 0)) {
+        mantissa += 1;
+    }
+
+    // `mantissa` has at-most 53 bits, so this is exact
+    let value = mantissa as f64 * pow2(unit_exponent);
+
+    if value.is_infinite() {
+        return Err(ConversionError::OutOfRange);
+    }
+
+    Ok(if is_negative { -value } else { value })
+}
+
+/// Renders `numerator/denominator` with exactly `scale` digits after the decimal point. Fails if
+/// the value cannot be written exactly with that many digits.
+pub(super) fn to_fixed_point_string(
+    is_negative: bool,
+    numerator: &[limb_t],
+    denominator: &[limb_t],
+    scale: usize,
+) -> Result<String, ConversionError> {
+    let mut scaled = Limbs::new(numerator);
+    scaled.mul_pow10(scale);
+
+    let (_, remainder) = scaled.div_rem(&Limbs::new(denominator));
+
+    if !remainder.is_zero() {
+        return Err(ConversionError::Inexact);
+    }
+
+    Ok(format_decimal(
+        is_negative,
+        numerator,
+        denominator,
+        DecimalFormat::Rounded {
+            precision: scale,
+            rounding: RoundingMode::Trunc,
+        },
+    ))
+}
+
+/// Splits `value` into limbs, starting with the least significant limb
+pub(super) fn u128_to_limbs(value: u128) -> Vec<limb_t> {
+    (0..u128::BITS.div_ceil(limb_t::BITS))
+        .map(|i| value.unbounded_shr(i * limb_t::BITS) as limb_t)
+        .collect()
+}
+
+/// Gets the sign, numerator, and denominator of the exact value of `value`. Returns `None` if
+/// `value` is infinite or NaN.
+pub(super) fn f64_to_fraction(value: f64) -> Option<(bool, Vec<limb_t>, Vec<limb_t>)> {
+    const MANTISSA_BITS: u32 = f64::MANTISSA_DIGITS - 1;
+    const EXPONENT_BIAS: i64 = f64::MAX_EXP as i64 - 1;
+
+    if !value.is_finite() {
+        return None;
+    }
+
+    let bits = value.to_bits();
+    let biased_exponent = ((bits >> MANTISSA_BITS) & 0x7FF) as i64;
+    let fraction = bits & ((1 << MANTISSA_BITS) - 1);
+
+    // Subnormal values don't have an implicit leading one, and they have the same exponent as the
+    // smallest normal values
+    let (mantissa, exponent) = if biased_exponent == 0 {
+        (fraction, 1 - EXPONENT_BIAS)
+    } else {
+        (
+            fraction | (1 << MANTISSA_BITS),
+            biased_exponent - EXPONENT_BIAS,
+        )
+    };
+
+    // `value` is `mantissa * 2^exponent`
+    let exponent = exponent - MANTISSA_BITS as i64;
+
+    let mantissa = Limbs::new(&u128_to_limbs(mantissa.into()));
+    let one = Limbs::new(&[1]);
+
+    let (numerator, denominator) = if exponent >= 0 {
+        (mantissa.shl(exponent as u64), one)
+    } else {
+        (mantissa, one.shl(exponent.unsigned_abs()))
+    };
+
+    Some((value.is_sign_negative(), numerator.0, denominator.0))
+}
+
+/// Calculates `2^exponent`. Returns infinity if the result is too large for an `f64`.
+fn pow2(exponent: i64) -> f64 {
+    const MANTISSA_BITS: u32 = f64::MANTISSA_DIGITS - 1;
+    const EXPONENT_BIAS: i64 = f64::MAX_EXP as i64 - 1;
+
+    if exponent > EXPONENT_BIAS {
+        f64::INFINITY
+    } else if exponent > -EXPONENT_BIAS {
+        f64::from_bits(((exponent + EXPONENT_BIAS) as u64) << MANTISSA_BITS)
+    } else {
+        // Subnormal
+        f64::from_bits(1 << (exponent + EXPONENT_BIAS - 1 + MANTISSA_BITS as i64))
+    }
+}
+
+impl Limbs {
+    fn bit_len(&self) -> u64 {
+        match self.0.last() {
+            Some(last) => self.0.len() as u64 * limb_t::BITS as u64 - last.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    fn shl(&self, shift: u64) -> Self {
+        let limb_shift = (shift / limb_t::BITS as u64) as usize;
+        let bit_shift = (shift % limb_t::BITS as u64) as u32;
+
+        let mut limbs = vec![0; limb_shift];
+        limbs.extend_from_slice(&self.0);
+        limbs.push(0);
+
+        if bit_shift != 0 {
+            let shifted = &mut limbs[limb_shift..];
+            let ptr = shifted.as_mut_ptr();
+
+            unsafe { mpn_lshift(ptr, ptr, shifted.len() as size_t, bit_shift) };
+        }
+
+        Limbs::new(&limbs)
+    }
+
+    fn to_u128(&self) -> Option<u128> {
+        if self.bit_len() > u128::BITS as u64 {
+            return None;
+        }
+
+        Some(
+            self.0
+                .iter()
+                .rev()
+                .fold(0, |value, limb| (value << limb_t::BITS) | u128::from(*limb)),
+        )
+    }
+}
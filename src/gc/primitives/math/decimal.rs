@@ -0,0 +1,426 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use super::mpn::{limb_t, mpn_divmod_1, mpn_get_str, mpn_mul_1, mpn_tdiv_qr, size_t};
+
+/// The rounding mode used by [`DecimalFormat::Rounded`] and for rounding numbers to integers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Rounds to the nearest value. Ties are rounded to the value with an even last digit.
+    HalfEven,
+    /// Rounds towards negative infinity
+    Floor,
+    /// Rounds towards positive infinity
+    Ceil,
+    /// Rounds towards zero
+    Trunc,
+}
+
+impl RoundingMode {
+    /// Determines whether a truncated quotient should be rounded away from zero given that the
+    /// remainder is non-zero. `cmp_half` should compare twice the remainder with the divisor.
+    pub(super) fn rounds_away_from_zero(
+        self,
+        is_negative: bool,
+        quotient_is_odd: bool,
+        cmp_half: impl FnOnce() -> Ordering,
+    ) -> bool {
+        match self {
+            RoundingMode::Floor => is_negative,
+            RoundingMode::Ceil => !is_negative,
+            RoundingMode::Trunc => false,
+            RoundingMode::HalfEven => match cmp_half() {
+                Ordering::Less => false,
+                Ordering::Equal => quotient_is_odd,
+                Ordering::Greater => true,
+            },
+        }
+    }
+}
+
+/// Determines how a number is rendered as a decimal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecimalFormat {
+    /// Renders the exact value of the number. If the number does not have a finite decimal
+    /// representation, the repeating digits are put in parentheses (eg `0.1(6)` for `1/6`).
+    ///
+    /// If there are more than `max_period` repeating digits, the number is rendered as a fraction
+    /// instead.
+    Repeating { max_period: usize },
+    /// Renders the exact value of the number. If the number does not have a finite decimal
+    /// representation, it is rendered as a fraction (eg `1/3`).
+    Fraction,
+    /// Rounds the number to exactly `precision` digits after the decimal point
+    Rounded {
+        precision: usize,
+        rounding: RoundingMode,
+    },
+}
+
+impl Default for DecimalFormat {
+    fn default() -> Self {
+        Self::Repeating { max_period: 64 }
+    }
+}
+
+/// Renders `numerator/denominator` as a decimal.
+///
+/// `numerator` and `denominator` are little-endian limbs. The fraction does not need to be
+/// reduced (it is reduced before its exact digits are calculated), but `denominator` must not be
+/// zero.
+pub(super) fn format_decimal(
+    is_negative: bool,
+    numerator: &[limb_t],
+    denominator: &[limb_t],
+    format: DecimalFormat,
+) -> String {
+    let numerator = Limbs::new(numerator);
+    let denominator = Limbs::new(denominator);
+
+    assert!(!denominator.is_zero(), "denominator must not be zero");
+
+    let sign = if is_negative && !numerator.is_zero() {
+        "-"
+    } else {
+        ""
+    };
+
+    match format {
+        DecimalFormat::Rounded {
+            precision,
+            rounding,
+        } => {
+            let digits = rounded_digits(is_negative, numerator, &denominator, precision, rounding);
+
+            // Rounding can produce zero from a non-zero value (eg `-0.001` -> `-0.00`)
+            let sign = if is_negative && !digits.bytes().all(|d| d == b'0') {
+                "-"
+            } else {
+                ""
+            };
+
+            let (integer, fraction) = digits.split_at(digits.len() - precision);
+
+            if precision == 0 {
+                format!("{sign}{integer}")
+            } else {
+                format!("{sign}{integer}.{fraction}")
+            }
+        }
+        DecimalFormat::Fraction => format_exact(sign, numerator, denominator, 0),
+        DecimalFormat::Repeating { max_period } => {
+            format_exact(sign, numerator, denominator, max_period)
+        }
+    }
+}
+
+/// Renders the exact value of `numerator/denominator`, or the reduced fraction if there are more
+/// than `max_period` repeating digits
+fn format_exact(sign: &str, numerator: Limbs, denominator: Limbs, max_period: usize) -> String {
+    let (numerator, denominator) = reduce(numerator, denominator);
+
+    match exact_digits(&numerator, &denominator, max_period) {
+        Some((integer, fraction, repeating)) => join_digits(sign, &integer, &fraction, &repeating),
+        None => format!("{sign}{numerator}/{denominator}"),
+    }
+}
+
+/// Divides `numerator` and `denominator` by their greatest common divisor
+fn reduce(numerator: Limbs, denominator: Limbs) -> (Limbs, Limbs) {
+    let mut gcd = denominator.clone();
+    let mut rest = numerator.clone();
+
+    while !rest.is_zero() {
+        let (_, remainder) = gcd.div_rem(&rest);
+        gcd = rest;
+        rest = remainder;
+    }
+
+    (numerator.div_rem(&gcd).0, denominator.div_rem(&gcd).0)
+}
+
+fn join_digits(sign: &str, integer: &str, fraction: &str, repeating: &str) -> String {
+    match (fraction.is_empty(), repeating.is_empty()) {
+        (true, true) => format!("{sign}{integer}"),
+        (_, true) => format!("{sign}{integer}.{fraction}"),
+        (_, false) => format!("{sign}{integer}.{fraction}({repeating})"),
+    }
+}
+
+/// Calculates the digits of `|numerator/denominator| * 10^precision` rounded to an integer. The
+/// result is left-padded with zeroes so that it has at-least `precision + 1` digits.
+fn rounded_digits(
+    is_negative: bool,
+    mut numerator: Limbs,
+    denominator: &Limbs,
+    precision: usize,
+    rounding: RoundingMode,
+) -> String {
+    numerator.mul_pow10(precision);
+
+    let (mut quotient, remainder) = numerator.div_rem(denominator);
+
+    if !remainder.is_zero() {
+        let round_up = rounding.rounds_away_from_zero(is_negative, quotient.is_odd(), || {
+            let mut twice_remainder = remainder;
+            twice_remainder.mul_limb(2);
+
+            twice_remainder.cmp(denominator)
+        });
+
+        if round_up {
+            quotient.add_limb(1);
+        }
+    }
+
+    let digits = quotient.to_string();
+
+    format!("{digits:0>width$}", width = precision + 1)
+}
+
+/// Calculates the exact decimal digits of `numerator/denominator`, which must be reduced. Returns
+/// the integer digits, the non-repeating fractional digits, and the repeating fractional digits.
+///
+/// Returns `None` if there are more than `max_period` repeating digits.
+fn exact_digits(
+    numerator: &Limbs,
+    denominator: &Limbs,
+    max_period: usize,
+) -> Option<(String, String, String)> {
+    // If the denominator is `2^a * 5^b * m` where `m` is coprime to 10, then the non-repeating part
+    // has `max(a, b)` digits and the repeating part starts afterwards. This only holds if the
+    // fraction is reduced: the factors of 2 and 5 that cancel out don't produce digits.
+    let (pow_2, pow_5) = denominator.pow_2_and_pow_5();
+    let num_non_repeating = pow_2.max(pow_5);
+
+    let (integer, mut remainder) = numerator.div_rem(denominator);
+
+    let next_digit = |remainder: &mut Limbs| {
+        remainder.mul_limb(10);
+        let (digit, new_remainder) = remainder.div_rem(denominator);
+        *remainder = new_remainder;
+
+        char::from(b'0' + digit.0.first().copied().unwrap_or(0) as u8)
+    };
+
+    let mut fraction = String::new();
+
+    for _ in 0..num_non_repeating {
+        if remainder.is_zero() {
+            break;
+        }
+
+        fraction.push(next_digit(&mut remainder));
+    }
+
+    let mut repeating = String::new();
+
+    if !remainder.is_zero() {
+        let period_start = remainder.clone();
+
+        loop {
+            if repeating.len() == max_period {
+                return None;
+            }
+
+            repeating.push(next_digit(&mut remainder));
+
+            if remainder == period_start {
+                break;
+            }
+        }
+    }
+
+    Some((integer.to_string(), fraction, repeating))
+}
+
+/// A temporary unsigned integer used for rendering. This never has leading zero limbs, so zero is
+/// represented by an empty `Vec`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(super) struct Limbs(pub(super) Vec<limb_t>);
+
+impl Limbs {
+    pub(super) fn new(limbs: &[limb_t]) -> Self {
+        let mut limbs = Self(limbs.to_vec());
+        limbs.trim();
+        limbs
+    }
+
+    fn trim(&mut self) {
+        let len = self.0.iter().rposition(|l| *l != 0).map_or(0, |i| i + 1);
+        self.0.truncate(len);
+    }
+
+    pub(super) fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn is_odd(&self) -> bool {
+        self.0.first().is_some_and(|l| l & 1 != 0)
+    }
+
+    fn mul_limb(&mut self, multiplier: limb_t) {
+        if self.is_zero() {
+            return;
+        }
+
+        let ptr = self.0.as_mut_ptr();
+        let carry = unsafe { mpn_mul_1(ptr, ptr, self.0.len() as size_t, multiplier) };
+
+        if carry != 0 {
+            self.0.push(carry);
+        }
+
+        self.trim();
+    }
+
+    pub(super) fn mul_pow10(&mut self, pow: usize) {
+        // The largest power of ten that fits in a single limb
+        const MAX_POW_10: u32 = limb_t::MAX.ilog10();
+
+        let mut pow = pow;
+
+        while pow != 0 {
+            let step = pow.min(MAX_POW_10 as usize);
+            self.mul_limb((10 as limb_t).pow(step as u32));
+            pow -= step;
+        }
+    }
+
+    fn add_limb(&mut self, addend: limb_t) {
+        let mut carry = addend;
+
+        for limb in &mut self.0 {
+            let (sum, overflowed) = limb.overflowing_add(carry);
+            *limb = sum;
+            carry = limb_t::from(overflowed);
+
+            if carry == 0 {
+                return;
+            }
+        }
+
+        if carry != 0 {
+            self.0.push(carry);
+        }
+    }
+
+    /// Divides `self` by `limb` in-place and returns the remainder. `self` must not be zero.
+    fn div_rem_limb(&mut self, divisor: limb_t) -> limb_t {
+        let ptr = self.0.as_mut_ptr();
+        let remainder = unsafe { mpn_divmod_1(ptr, ptr, self.0.len() as size_t, divisor) };
+
+        self.trim();
+
+        remainder
+    }
+
+    /// Returns the quotient and remainder of `self / divisor`. `divisor` must not be zero.
+    pub(super) fn div_rem(&self, divisor: &Self) -> (Self, Self) {
+        debug_assert!(!divisor.is_zero());
+
+        if self.0.len() < divisor.0.len() {
+            return (Self(Vec::new()), self.clone());
+        }
+
+        let mut quotient = vec![0; self.0.len() - divisor.0.len() + 1];
+        let mut remainder = vec![0; divisor.0.len()];
+
+        unsafe {
+            mpn_tdiv_qr(
+                quotient.as_mut_ptr(),
+                remainder.as_mut_ptr(),
+                0,
+                self.0.as_ptr(),
+                self.0.len() as size_t,
+                divisor.0.as_ptr(),
+                divisor.0.len() as size_t,
+            )
+        };
+
+        let mut quotient = Self(quotient);
+        let mut remainder = Self(remainder);
+
+        quotient.trim();
+        remainder.trim();
+
+        (quotient, remainder)
+    }
+
+    /// Counts the number of times that `self` is divisible by two and by five. `self` must not be
+    /// zero.
+    fn pow_2_and_pow_5(&self) -> (usize, usize) {
+        let mut pow_2 = 0;
+
+        for limb in &self.0 {
+            pow_2 += limb.trailing_zeros() as usize;
+
+            if *limb != 0 {
+                break;
+            }
+        }
+
+        let mut value = self.clone();
+        let mut pow_5 = 0;
+
+        loop {
+            let mut quotient = value.clone();
+
+            if quotient.div_rem_limb(5) != 0 {
+                break;
+            }
+
+            value = quotient;
+            pow_5 += 1;
+        }
+
+        (pow_2, pow_5)
+    }
+}
+
+impl PartialOrd for Limbs {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Limbs {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .len()
+            .cmp(&other.0.len())
+            .then_with(|| self.0.iter().rev().cmp(other.0.iter().rev()))
+    }
+}
+
+impl Display for Limbs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // `mpn_get_str` clobbers its input and requires an extra limb
+        let mut input = self.0.clone();
+        input.push(0);
+
+        let mut digits = vec![0u8; super::maximum_digits_from_num_limbs(self.0.len()) + 1];
+
+        let num_digits = unsafe {
+            mpn_get_str(
+                digits.as_mut_ptr(),
+                10,
+                input.as_mut_ptr(),
+                self.0.len() as size_t,
+            )
+        };
+
+        digits.truncate(num_digits);
+
+        let start = digits.iter().position(|d| *d != 0).unwrap_or(digits.len());
+
+        for digit in &digits[start..] {
+            write!(f, "{}", char::from(b'0' + digit))?;
+        }
+
+        Ok(())
+    }
+}
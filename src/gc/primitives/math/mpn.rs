@@ -0,0 +1,45 @@
+//! Low-level natural number routines with the same interface as GMP's `mpn` functions.
+//!
+//! By default, these are implemented in pure Rust. If the `gmp` feature is enabled, GMP's
+//! implementations are used instead, and the pure-Rust ones are only compiled for differential
+//! testing.
+
+#![allow(non_camel_case_types)]
+
+#[cfg(any(not(feature = "gmp"), test))]
+#[cfg_attr(feature = "gmp", allow(dead_code))]
+mod native;
+
+#[cfg(test)]
+mod test;
+
+#[cfg(not(feature = "gmp"))]
+pub use native::*;
+
+#[cfg(feature = "gmp")]
+pub use gmp::*;
+
+#[cfg(feature = "gmp")]
+mod gmp {
+    pub use gmp_mpfr_sys::gmp::*;
+
+    /// Writes `{np, nn} / {dp, dn}` to `{qp, nn - dn + 1}`. The division must be exact.
+    ///
+    /// GMP doesn't expose its exact division publicly, so this just uses `mpn_tdiv_qr`.
+    ///
+    /// # Safety
+    /// Has the same requirements as `mpn_tdiv_qr`
+    pub unsafe fn mpn_divexact(
+        qp: *mut limb_t,
+        np: *const limb_t,
+        nn: size_t,
+        dp: *const limb_t,
+        dn: size_t,
+    ) {
+        let mut remainder = vec![0; dn as usize];
+
+        unsafe { mpn_tdiv_qr(qp, remainder.as_mut_ptr(), 0, np, nn, dp, dn) };
+
+        debug_assert!(remainder.iter().all(|limb| *limb == 0));
+    }
+}
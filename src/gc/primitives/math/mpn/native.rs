@@ -0,0 +1,1035 @@
+//! Pure-Rust implementations of the `mpn` functions that we use.
+//!
+//! These follow the same conventions as GMP: numbers are little-endian arrays of limbs given by a
+//! pointer and a length, and the requirements on lengths and overlapping operands are the same as
+//! the corresponding GMP function.
+
+use std::{
+    cmp::Ordering,
+    ffi::{c_int, c_uint},
+};
+
+mod radix;
+
+#[cfg(target_pointer_width = "64")]
+pub type limb_t = u64;
+#[cfg(target_pointer_width = "64")]
+type dlimb_t = u128;
+
+#[cfg(not(target_pointer_width = "64"))]
+pub type limb_t = u32;
+#[cfg(not(target_pointer_width = "64"))]
+type dlimb_t = u64;
+
+pub type size_t = isize;
+
+const LIMB_BITS: u32 = limb_t::BITS;
+
+/// Operands with fewer limbs than this are multiplied with the schoolbook algorithm
+const KARATSUBA_THRESHOLD: usize = 32;
+
+/// Divisors and quotients with fewer limbs than this are calculated with the schoolbook algorithm
+const BURNIKEL_ZIEGLER_THRESHOLD: usize = 40;
+
+/// Sets `{rp, n}` to zero
+///
+/// # Safety
+/// `{rp, n}` must be valid for writes
+pub unsafe fn mpn_zero(rp: *mut limb_t, n: size_t) {
+    unsafe { rp.write_bytes(0, n as usize) };
+}
+
+/// Returns `1` if `{sp, n}` is zero and `0` otherwise
+///
+/// # Safety
+/// `{sp, n}` must be valid for reads
+pub unsafe fn mpn_zero_p(sp: *const limb_t, n: size_t) -> c_int {
+    let limbs = unsafe { std::slice::from_raw_parts(sp, n as usize) };
+
+    limbs.iter().all(|limb| *limb == 0).into()
+}
+
+/// Copies `{s1p, n}` to `{rp, n}`. The operands may overlap.
+///
+/// # Safety
+/// `{s1p, n}` must be valid for reads, and `{rp, n}` must be valid for writes
+pub unsafe fn mpn_copyi(rp: *mut limb_t, s1p: *const limb_t, n: size_t) {
+    unsafe { std::ptr::copy(s1p, rp, n as usize) };
+}
+
+/// Copies `{s1p, n}` to `{rp, n}`. The operands may overlap.
+///
+/// # Safety
+/// `{s1p, n}` must be valid for reads, and `{rp, n}` must be valid for writes
+pub unsafe fn mpn_copyd(rp: *mut limb_t, s1p: *const limb_t, n: size_t) {
+    unsafe { std::ptr::copy(s1p, rp, n as usize) };
+}
+
+/// Writes `{s1p, s1n} + {s2p, s2n}` to `{rp, s1n}` and returns the carry
+///
+/// # Safety
+/// - `s1n >= s2n`
+/// - `rp` may be equal to `s1p` or `s2p`, but must not otherwise overlap with them
+pub unsafe fn mpn_add(
+    rp: *mut limb_t,
+    s1p: *const limb_t,
+    s1n: size_t,
+    s2p: *const limb_t,
+    s2n: size_t,
+) -> limb_t {
+    debug_assert!(s1n >= s2n);
+
+    let mut carry = false;
+
+    for i in 0..s1n as usize {
+        let lhs = unsafe { *s1p.add(i) };
+        let rhs = if i < s2n as usize {
+            unsafe { *s2p.add(i) }
+        } else {
+            0
+        };
+
+        let (sum, overflowed) = lhs.carrying_add(rhs, carry);
+        unsafe { rp.add(i).write(sum) };
+        carry = overflowed;
+    }
+
+    carry.into()
+}
+
+/// Writes `{s1p, s1n} - {s2p, s2n}` to `{rp, s1n}` and returns the borrow
+///
+/// # Safety
+/// - `s1n >= s2n`
+/// - `rp` may be equal to `s1p` or `s2p`, but must not otherwise overlap with them
+pub unsafe fn mpn_sub(
+    rp: *mut limb_t,
+    s1p: *const limb_t,
+    s1n: size_t,
+    s2p: *const limb_t,
+    s2n: size_t,
+) -> limb_t {
+    debug_assert!(s1n >= s2n);
+
+    let mut borrow = false;
+
+    for i in 0..s1n as usize {
+        let lhs = unsafe { *s1p.add(i) };
+        let rhs = if i < s2n as usize {
+            unsafe { *s2p.add(i) }
+        } else {
+            0
+        };
+
+        let (difference, overflowed) = lhs.borrowing_sub(rhs, borrow);
+        unsafe { rp.add(i).write(difference) };
+        borrow = overflowed;
+    }
+
+    borrow.into()
+}
+
+/// Writes `{s1p, n} * s2limb` to `{rp, n}` and returns the most significant limb of the product
+///
+/// # Safety
+/// `rp` may be equal to `s1p`, but must not otherwise overlap with it
+pub unsafe fn mpn_mul_1(rp: *mut limb_t, s1p: *const limb_t, n: size_t, s2limb: limb_t) -> limb_t {
+    let mut carry = 0;
+
+    for i in 0..n as usize {
+        let (low, high) = unsafe { *s1p.add(i) }.carrying_mul(s2limb, carry);
+        unsafe { rp.add(i).write(low) };
+        carry = high;
+    }
+
+    carry
+}
+
+/// Adds `{s1p, n} * s2limb` to `{rp, n}` and returns the most significant limb of the result
+///
+/// # Safety
+/// `rp` may be equal to `s1p`, but must not otherwise overlap with it
+pub unsafe fn mpn_addmul_1(
+    rp: *mut limb_t,
+    s1p: *const limb_t,
+    n: size_t,
+    s2limb: limb_t,
+) -> limb_t {
+    let mut carry = 0;
+
+    for i in 0..n as usize {
+        let (low, high) =
+            unsafe { *s1p.add(i) }.carrying_mul_add(s2limb, unsafe { *rp.add(i) }, carry);
+        unsafe { rp.add(i).write(low) };
+        carry = high;
+    }
+
+    carry
+}
+
+/// Shifts `{sp, n}` left by `count` bits, writes the result to `{rp, n}`, and returns the bits
+/// that were shifted out in the least significant bits of the return value.
+///
+/// # Safety
+/// - `0 < count < limb_t::BITS`
+/// - `rp` must be greater-than or equal-to `sp` if the operands overlap
+pub unsafe fn mpn_lshift(rp: *mut limb_t, sp: *const limb_t, n: size_t, count: c_uint) -> limb_t {
+    debug_assert!(count > 0 && count < LIMB_BITS);
+
+    let n = n as usize;
+    let shifted_out = unsafe { *sp.add(n - 1) } >> (LIMB_BITS - count);
+
+    for i in (1..n).rev() {
+        let limb = unsafe { (*sp.add(i) << count) | (*sp.add(i - 1) >> (LIMB_BITS - count)) };
+        unsafe { rp.add(i).write(limb) };
+    }
+
+    unsafe { rp.write(*sp << count) };
+
+    shifted_out
+}
+
+/// Shifts `{sp, n}` right by `count` bits, writes the result to `{rp, n}`, and returns the bits
+/// that were shifted out in the most significant bits of the return value.
+///
+/// # Safety
+/// - `0 < count < limb_t::BITS`
+/// - `rp` must be less-than or equal-to `sp` if the operands overlap
+pub unsafe fn mpn_rshift(rp: *mut limb_t, sp: *const limb_t, n: size_t, count: c_uint) -> limb_t {
+    debug_assert!(count > 0 && count < LIMB_BITS);
+
+    let n = n as usize;
+    let shifted_out = unsafe { *sp } << (LIMB_BITS - count);
+
+    for i in 0..n - 1 {
+        let limb = unsafe { (*sp.add(i) >> count) | (*sp.add(i + 1) << (LIMB_BITS - count)) };
+        unsafe { rp.add(i).write(limb) };
+    }
+
+    unsafe { rp.add(n - 1).write(*sp.add(n - 1) >> count) };
+
+    shifted_out
+}
+
+/// Writes `{s1p, s1n} * {s2p, s2n}` to `{rp, s1n + s2n}` and returns the most significant limb of
+/// the product.
+///
+/// # Safety
+/// - `s1n >= s2n > 0`
+/// - `rp` must not overlap with either operand
+pub unsafe fn mpn_mul(
+    rp: *mut limb_t,
+    s1p: *const limb_t,
+    s1n: size_t,
+    s2p: *const limb_t,
+    s2n: size_t,
+) -> limb_t {
+    debug_assert!(s1n >= s2n && s2n > 0);
+
+    let (s1n, s2n) = (s1n as usize, s2n as usize);
+
+    let output = unsafe { std::slice::from_raw_parts_mut(rp, s1n + s2n) };
+    let lhs = unsafe { std::slice::from_raw_parts(s1p, s1n) };
+    let rhs = unsafe { std::slice::from_raw_parts(s2p, s2n) };
+
+    mul(output, lhs, rhs);
+
+    output[s1n + s2n - 1]
+}
+
+/// Divides `{s2p, s2n}` by `s3limb`, writes the quotient to `{r1p, s2n}`, and returns the
+/// remainder.
+///
+/// # Safety
+/// - `s3limb` must not be zero
+/// - `r1p` may be equal to `s2p`, but must not otherwise overlap with it
+pub unsafe fn mpn_divmod_1(
+    r1p: *mut limb_t,
+    s2p: *const limb_t,
+    s2n: size_t,
+    s3limb: limb_t,
+) -> limb_t {
+    unsafe { LimbDivisor::new(s3limb).div_rem(r1p, s2p, s2n as usize) }
+}
+
+/// Divides `{np, nn}` by `{dp, dn}`, writes the quotient to `{qp, nn - dn + 1}`, and writes the
+/// remainder to `{rp, dn}`.
+///
+/// # Safety
+/// - `qxn` must be zero
+/// - `nn >= dn > 0`, and the most significant limb of `{dp, dn}` must not be zero
+/// - The outputs must not overlap with each other or with the inputs
+pub unsafe fn mpn_tdiv_qr(
+    qp: *mut limb_t,
+    rp: *mut limb_t,
+    qxn: size_t,
+    np: *const limb_t,
+    nn: size_t,
+    dp: *const limb_t,
+    dn: size_t,
+) {
+    debug_assert_eq!(qxn, 0);
+    debug_assert!(nn >= dn && dn > 0);
+
+    let (nn, dn) = (nn as usize, dn as usize);
+
+    let quotient = unsafe { std::slice::from_raw_parts_mut(qp, nn - dn + 1) };
+    let remainder = unsafe { std::slice::from_raw_parts_mut(rp, dn) };
+    let dividend = unsafe { std::slice::from_raw_parts(np, nn) };
+    let divisor = unsafe { std::slice::from_raw_parts(dp, dn) };
+
+    div_rem(quotient, remainder, dividend, divisor);
+}
+
+/// Divides `{np, nn}` by `{dp, dn}` and writes the quotient to `{qp, nn - dn + 1}`. The division
+/// must be exact.
+///
+/// # Safety
+/// - `nn >= dn > 0`, and the most significant limb of `{dp, dn}` must not be zero
+/// - `qp` must not overlap with either input
+pub unsafe fn mpn_divexact(
+    qp: *mut limb_t,
+    np: *const limb_t,
+    nn: size_t,
+    dp: *const limb_t,
+    dn: size_t,
+) {
+    debug_assert!(nn >= dn && dn > 0);
+
+    let (nn, dn) = (nn as usize, dn as usize);
+
+    let quotient = unsafe { std::slice::from_raw_parts_mut(qp, nn - dn + 1) };
+    let dividend = unsafe { std::slice::from_raw_parts(np, nn) };
+    let divisor = unsafe { std::slice::from_raw_parts(dp, dn) };
+
+    div_exact(quotient, dividend, divisor);
+}
+
+/// Writes the greatest common divisor of `{xp, xn}` and `{yp, yn}` to `rp` and returns its length.
+/// Both inputs are destroyed.
+///
+/// # Safety
+/// - `xn >= yn > 0`, and `{xp, xn}` must be greater-than or equal-to `{yp, yn}`
+/// - the most significant limb of `{yp, yn}` must not be zero
+/// - at-least one of the inputs must be odd
+/// - `{rp, yn}` must be valid for writes and must not overlap with the inputs
+pub unsafe fn mpn_gcd(
+    rp: *mut limb_t,
+    xp: *mut limb_t,
+    xn: size_t,
+    yp: *mut limb_t,
+    yn: size_t,
+) -> size_t {
+    debug_assert!(xn >= yn && yn > 0);
+
+    let lhs = unsafe { std::slice::from_raw_parts(xp, xn as usize) };
+    let rhs = unsafe { std::slice::from_raw_parts(yp, yn as usize) };
+
+    let gcd = gcd(lhs, rhs);
+
+    unsafe { std::ptr::copy_nonoverlapping(gcd.as_ptr(), rp, gcd.len()) };
+
+    gcd.len() as size_t
+}
+
+/// Writes the digits of `{s1p, s1n}` in the given base to `str`, starting with the most significant
+/// one. The digits are written as their values (ie `0..base`) rather than ASCII characters.
+/// Returns the number of digits written.
+///
+/// # Safety
+/// - `2 <= base <= 256`
+/// - `str` must have room for the maximum number of digits in an `s1n` limb number, plus one
+pub unsafe fn mpn_get_str(str: *mut u8, base: c_int, s1p: *mut limb_t, s1n: size_t) -> usize {
+    debug_assert!((2..=256).contains(&base));
+
+    let input = unsafe { std::slice::from_raw_parts(s1p, s1n as usize) };
+    let digits = radix::to_radix_digits(input, base as limb_t);
+
+    unsafe { std::ptr::copy_nonoverlapping(digits.as_ptr(), str, digits.len()) };
+
+    digits.len()
+}
+
+/// Converts the digits `{str, strsize}` in the given base to limbs, writes them to `rp`, and
+/// returns the number of limbs written. `str` starts with the most significant digit, and the
+/// digits are their values (ie `0..base`) rather than ASCII characters.
+///
+/// If the first digit is not zero, then the last limb written will not be zero either.
+///
+/// # Safety
+/// - `2 <= base <= 256`, and every digit must be less than `base`
+/// - `strsize` must not be zero
+/// - `rp` must have room for the maximum number of limbs in a `strsize` digit number, plus one
+pub unsafe fn mpn_set_str(rp: *mut limb_t, str: *const u8, strsize: usize, base: c_int) -> size_t {
+    debug_assert!((2..=256).contains(&base));
+
+    let digits = unsafe { std::slice::from_raw_parts(str, strsize) };
+    let limbs = radix::from_radix_digits(digits, base as limb_t);
+
+    unsafe { std::ptr::copy_nonoverlapping(limbs.as_ptr(), rp, limbs.len()) };
+
+    limbs.len() as size_t
+}
+
+/// Divides numbers by a single limb. The reciprocal of the divisor is precomputed so that dividing
+/// by the same limb repeatedly (eg for radix conversion) doesn't require any hardware divisions.
+///
+/// See "Improved division by invariant integers" by Niels Möller and Torbjörn Granlund.
+#[derive(Clone, Copy)]
+struct LimbDivisor {
+    /// The divisor shifted so that its most significant bit is set
+    normalized: limb_t,
+    shift: u32,
+    /// `floor((B^2 - 1) / normalized) - B` where `B` is `2^limb_t::BITS`
+    reciprocal: limb_t,
+}
+
+impl LimbDivisor {
+    fn new(divisor: limb_t) -> Self {
+        assert_ne!(divisor, 0, "division by zero");
+
+        let shift = divisor.leading_zeros();
+        let normalized = divisor << shift;
+
+        let reciprocal = ((dlimb_t::from(!normalized) << LIMB_BITS) | dlimb_t::from(limb_t::MAX))
+            / dlimb_t::from(normalized);
+
+        Self {
+            normalized,
+            shift,
+            reciprocal: reciprocal as limb_t,
+        }
+    }
+
+    /// Divides `high * B + low` by the normalized divisor. Requires that `high < normalized`.
+    fn div_2by1(self, high: limb_t, low: limb_t) -> (limb_t, limb_t) {
+        debug_assert!(high < self.normalized);
+
+        let product = dlimb_t::from(self.reciprocal) * dlimb_t::from(high);
+        let product = product.wrapping_add((dlimb_t::from(high) << LIMB_BITS) | dlimb_t::from(low));
+
+        let mut quotient = ((product >> LIMB_BITS) as limb_t).wrapping_add(1);
+        let mut remainder = low.wrapping_sub(quotient.wrapping_mul(self.normalized));
+
+        if remainder > product as limb_t {
+            quotient = quotient.wrapping_sub(1);
+            remainder = remainder.wrapping_add(self.normalized);
+        }
+
+        if remainder >= self.normalized {
+            quotient += 1;
+            remainder -= self.normalized;
+        }
+
+        (quotient, remainder)
+    }
+
+    /// Writes `{sp, n} / divisor` to `{rp, n}` and returns the remainder. `rp` may be equal to
+    /// `sp`.
+    unsafe fn div_rem(self, rp: *mut limb_t, sp: *const limb_t, n: usize) -> limb_t {
+        if n == 0 {
+            return 0;
+        }
+
+        if self.shift == 0 {
+            let mut remainder = 0;
+
+            for i in (0..n).rev() {
+                let quotient;
+                (quotient, remainder) = self.div_2by1(remainder, unsafe { *sp.add(i) });
+                unsafe { rp.add(i).write(quotient) };
+            }
+
+            return remainder;
+        }
+
+        // We divide `{sp, n} << shift` by the normalized divisor instead. The quotient is the
+        // same, but the remainder is shifted.
+        let mut remainder = unsafe { *sp.add(n - 1) } >> (LIMB_BITS - self.shift);
+
+        for i in (0..n).rev() {
+            let mut limb = unsafe { *sp.add(i) } << self.shift;
+
+            if i != 0 {
+                limb |= unsafe { *sp.add(i - 1) } >> (LIMB_BITS - self.shift);
+            }
+
+            let quotient;
+            (quotient, remainder) = self.div_2by1(remainder, limb);
+            unsafe { rp.add(i).write(quotient) };
+        }
+
+        remainder >> self.shift
+    }
+
+    /// Divides `limbs` by the divisor in-place and returns the remainder
+    fn div_rem_in_place(self, limbs: &mut [limb_t]) -> limb_t {
+        let ptr = limbs.as_mut_ptr();
+
+        unsafe { self.div_rem(ptr, ptr, limbs.len()) }
+    }
+}
+
+/// Returns `limbs` without any leading zero limbs
+fn trimmed(limbs: &[limb_t]) -> &[limb_t] {
+    let len = limbs
+        .iter()
+        .rposition(|limb| *limb != 0)
+        .map_or(0, |i| i + 1);
+
+    &limbs[..len]
+}
+
+fn cmp(lhs: &[limb_t], rhs: &[limb_t]) -> Ordering {
+    let (lhs, rhs) = (trimmed(lhs), trimmed(rhs));
+
+    lhs.len()
+        .cmp(&rhs.len())
+        .then_with(|| lhs.iter().rev().cmp(rhs.iter().rev()))
+}
+
+/// Adds `rhs` to `lhs` in-place and returns the carry. Requires that `rhs` is no longer than `lhs`.
+fn add_assign(lhs: &mut [limb_t], rhs: &[limb_t]) -> limb_t {
+    let ptr = lhs.as_mut_ptr();
+
+    unsafe {
+        mpn_add(
+            ptr,
+            ptr,
+            lhs.len() as size_t,
+            rhs.as_ptr(),
+            rhs.len() as size_t,
+        )
+    }
+}
+
+/// Subtracts `rhs` from `lhs` in-place and returns the borrow. Requires that `rhs` is no longer
+/// than `lhs`.
+fn sub_assign(lhs: &mut [limb_t], rhs: &[limb_t]) -> limb_t {
+    let ptr = lhs.as_mut_ptr();
+
+    unsafe {
+        mpn_sub(
+            ptr,
+            ptr,
+            lhs.len() as size_t,
+            rhs.as_ptr(),
+            rhs.len() as size_t,
+        )
+    }
+}
+
+/// Subtracts `rhs * multiplier` from `lhs` in-place and returns the borrow. `lhs` and `rhs` must
+/// have the same length.
+fn submul_1(lhs: &mut [limb_t], rhs: &[limb_t], multiplier: limb_t) -> limb_t {
+    debug_assert_eq!(lhs.len(), rhs.len());
+
+    let mut borrow = 0;
+
+    for (lhs, rhs) in lhs.iter_mut().zip(rhs) {
+        let (low, high) = rhs.carrying_mul(multiplier, borrow);
+        let (difference, overflowed) = lhs.overflowing_sub(low);
+
+        *lhs = difference;
+        borrow = high + limb_t::from(overflowed);
+    }
+
+    borrow
+}
+
+/// Writes `lhs * rhs` to `output`. Requires that `lhs.len() >= rhs.len() > 0` and that
+/// `output.len() == lhs.len() + rhs.len()`.
+fn mul(output: &mut [limb_t], lhs: &[limb_t], rhs: &[limb_t]) {
+    debug_assert!(lhs.len() >= rhs.len() && !rhs.is_empty());
+    debug_assert_eq!(output.len(), lhs.len() + rhs.len());
+
+    if rhs.len() < KARATSUBA_THRESHOLD {
+        mul_schoolbook(output, lhs, rhs);
+    } else if lhs.len() >= 2 * rhs.len() {
+        mul_unbalanced(output, lhs, rhs);
+    } else {
+        mul_karatsuba(output, lhs, rhs);
+    }
+}
+
+fn mul_schoolbook(output: &mut [limb_t], lhs: &[limb_t], rhs: &[limb_t]) {
+    let output_ptr = output.as_mut_ptr();
+    let len = lhs.len() as size_t;
+
+    unsafe {
+        let carry = mpn_mul_1(output_ptr, lhs.as_ptr(), len, rhs[0]);
+        output[lhs.len()] = carry;
+
+        for (i, limb) in rhs.iter().enumerate().skip(1) {
+            let carry = mpn_addmul_1(output_ptr.add(i), lhs.as_ptr(), len, *limb);
+            output[lhs.len() + i] = carry;
+        }
+    }
+}
+
+/// Multiplies a long `lhs` by a much shorter `rhs` by splitting `lhs` into chunks that are the
+/// same size as `rhs`
+fn mul_unbalanced(output: &mut [limb_t], lhs: &[limb_t], rhs: &[limb_t]) {
+    output.fill(0);
+
+    let mut product = vec![0; 2 * rhs.len()];
+
+    for (i, chunk) in lhs.chunks(rhs.len()).enumerate() {
+        let product = &mut product[..chunk.len() + rhs.len()];
+        mul(product, rhs, chunk);
+
+        let carry = add_assign(&mut output[i * rhs.len()..], product);
+        debug_assert_eq!(carry, 0);
+    }
+}
+
+/// Multiplies using Karatsuba's algorithm. Requires that `rhs` is more than half as long as `lhs`.
+fn mul_karatsuba(output: &mut [limb_t], lhs: &[limb_t], rhs: &[limb_t]) {
+    // lhs = lhs_high * B^split + lhs_low
+    // rhs = rhs_high * B^split + rhs_low
+    let split = lhs.len() / 2;
+
+    debug_assert!(rhs.len() > split);
+
+    let (lhs_low, lhs_high) = lhs.split_at(split);
+    let (rhs_low, rhs_high) = rhs.split_at(split);
+
+    // output = lhs_high * rhs_high * B^(2 * split) + lhs_low * rhs_low
+    let (output_low, output_high) = output.split_at_mut(2 * split);
+
+    mul(output_low, lhs_low, rhs_low);
+    mul_any_order(output_high, lhs_high, rhs_high);
+
+    // middle = (lhs_low + lhs_high)(rhs_low + rhs_high) - lhs_low * rhs_low - lhs_high * rhs_high
+    let lhs_sum = sum(lhs_low, lhs_high);
+    let rhs_sum = sum(rhs_low, rhs_high);
+
+    let mut middle = vec![0; lhs_sum.len() + rhs_sum.len()];
+    mul_any_order(&mut middle, &lhs_sum, &rhs_sum);
+
+    let borrow = sub_assign(&mut middle, output_low) + sub_assign(&mut middle, output_high);
+    debug_assert_eq!(borrow, 0);
+
+    let carry = add_assign(&mut output[split..], trimmed(&middle));
+    debug_assert_eq!(carry, 0);
+}
+
+/// Like [`mul`] but the operands may be in any order and may have leading zero limbs
+fn mul_any_order(output: &mut [limb_t], lhs: &[limb_t], rhs: &[limb_t]) {
+    let (lhs, rhs) = (trimmed(lhs), trimmed(rhs));
+    let (lhs, rhs) = if lhs.len() >= rhs.len() {
+        (lhs, rhs)
+    } else {
+        (rhs, lhs)
+    };
+
+    if rhs.is_empty() {
+        output.fill(0);
+        return;
+    }
+
+    let len = lhs.len() + rhs.len();
+
+    mul(&mut output[..len], lhs, rhs);
+    output[len..].fill(0);
+}
+
+/// Calculates `lhs + rhs`. The result has one more limb than the longest operand.
+fn sum(lhs: &[limb_t], rhs: &[limb_t]) -> Vec<limb_t> {
+    let (lhs, rhs) = if lhs.len() >= rhs.len() {
+        (lhs, rhs)
+    } else {
+        (rhs, lhs)
+    };
+
+    let mut sum = Vec::with_capacity(lhs.len() + 1);
+    sum.extend_from_slice(lhs);
+
+    let carry = add_assign(&mut sum, rhs);
+    sum.push(carry);
+
+    sum
+}
+
+/// Writes `dividend / divisor` to `quotient` and `dividend % divisor` to `remainder`. The most
+/// significant limb of `divisor` must not be zero.
+fn div_rem(
+    quotient: &mut [limb_t],
+    remainder: &mut [limb_t],
+    dividend: &[limb_t],
+    divisor: &[limb_t],
+) {
+    if divisor.len() >= BURNIKEL_ZIEGLER_THRESHOLD && quotient.len() >= BURNIKEL_ZIEGLER_THRESHOLD {
+        div_rem_burnikel_ziegler(quotient, remainder, dividend, divisor);
+    } else {
+        div_rem_schoolbook(quotient, remainder, dividend, divisor);
+    }
+}
+
+/// Writes `dividend / divisor` to `quotient` and `dividend % divisor` to `remainder`.
+///
+/// This uses algorithm D from section 4.3.1 of "The Art of Computer Programming" by Donald Knuth.
+fn div_rem_schoolbook(
+    quotient: &mut [limb_t],
+    remainder: &mut [limb_t],
+    dividend: &[limb_t],
+    divisor: &[limb_t],
+) {
+    let dn = divisor.len();
+
+    debug_assert_ne!(divisor[dn - 1], 0);
+    debug_assert_eq!(quotient.len(), dividend.len() - dn + 1);
+    debug_assert_eq!(remainder.len(), dn);
+
+    if dn == 1 {
+        let divisor = LimbDivisor::new(divisor[0]);
+
+        remainder[0] =
+            unsafe { divisor.div_rem(quotient.as_mut_ptr(), dividend.as_ptr(), dividend.len()) };
+
+        return;
+    }
+
+    // Normalize the divisor so that its most significant bit is set. This guarantees that our
+    // estimate for each quotient limb is at-most two too large.
+    let shift = divisor[dn - 1].leading_zeros();
+
+    let divisor = shifted_left(divisor, shift);
+    let mut dividend = shifted_left(dividend, shift);
+
+    debug_assert_eq!(divisor[dn], 0);
+    let divisor = &divisor[..dn];
+
+    let divisor_high = divisor[dn - 1];
+    let divisor_next = divisor[dn - 2];
+
+    for j in (0..quotient.len()).rev() {
+        let high = dividend[j + dn];
+        let next = dividend[j + dn - 1];
+        let low = dividend[j + dn - 2];
+
+        let numerator = (dlimb_t::from(high) << LIMB_BITS) | dlimb_t::from(next);
+
+        // `high <= divisor_high` is an invariant of this algorithm
+        let mut estimate = if high >= divisor_high {
+            limb_t::MAX
+        } else {
+            (numerator / dlimb_t::from(divisor_high)) as limb_t
+        };
+
+        let mut estimate_remainder =
+            numerator - dlimb_t::from(estimate) * dlimb_t::from(divisor_high);
+
+        while estimate_remainder <= dlimb_t::from(limb_t::MAX)
+            && dlimb_t::from(estimate) * dlimb_t::from(divisor_next)
+                > (estimate_remainder << LIMB_BITS) | dlimb_t::from(low)
+        {
+            estimate -= 1;
+            estimate_remainder += dlimb_t::from(divisor_high);
+        }
+
+        let borrow = submul_1(&mut dividend[j..j + dn], divisor, estimate);
+        let (high, overflowed) = dividend[j + dn].overflowing_sub(borrow);
+        dividend[j + dn] = high;
+
+        // The estimate was one too large
+        if overflowed {
+            estimate -= 1;
+
+            let carry = add_assign(&mut dividend[j..j + dn], divisor);
+            dividend[j + dn] = dividend[j + dn].wrapping_add(carry);
+        }
+
+        quotient[j] = estimate;
+    }
+
+    let remainder_limbs = &mut dividend[..dn];
+
+    if shift != 0 {
+        let ptr = remainder_limbs.as_mut_ptr();
+        unsafe { mpn_rshift(ptr, ptr, dn as size_t, shift) };
+    }
+
+    remainder.copy_from_slice(remainder_limbs);
+}
+
+/// Writes `dividend / divisor` to `quotient` and `dividend % divisor` to `remainder`.
+///
+/// This uses the recursive division algorithm from "Fast Recursive Division" by Christoph
+/// Burnikel and Joachim Ziegler. The dividend is split into blocks that are the same size as the
+/// divisor, and each pair of blocks is divided recursively. This makes division as fast as
+/// Karatsuba multiplication (up to a logarithmic factor).
+fn div_rem_burnikel_ziegler(
+    quotient: &mut [limb_t],
+    remainder: &mut [limb_t],
+    dividend: &[limb_t],
+    divisor: &[limb_t],
+) {
+    let dn = divisor.len();
+
+    // The recursion halves the divisor until it is below the threshold, so it is padded with zero
+    // limbs (ie multiplied by `B^padding`) to a length of `m * 2^k` where `m` is below the
+    // threshold. It is also normalized so that its most significant bit is set.
+    let mut block_len = dn;
+    let mut num_halvings = 0;
+
+    while block_len >= BURNIKEL_ZIEGLER_THRESHOLD {
+        block_len = block_len.div_ceil(2);
+        num_halvings += 1;
+    }
+
+    let block_len = block_len << num_halvings;
+    let padding = block_len - dn;
+    let shift = divisor[dn - 1].leading_zeros();
+
+    let padded = |limbs: &[limb_t]| {
+        let mut padded = vec![0; padding];
+        padded.extend(shifted_left(limbs, shift));
+        padded
+    };
+
+    let mut divisor = padded(divisor);
+    debug_assert_eq!(divisor.last(), Some(&0));
+    divisor.pop();
+
+    // The most significant block of the dividend must be less than the divisor, so we make sure
+    // that its most significant limb is zero
+    let mut dividend = padded(dividend);
+    let mut num_blocks = dividend.len().div_ceil(block_len);
+
+    if dividend.len() == num_blocks * block_len && dividend.last() != Some(&0) {
+        num_blocks += 1;
+    }
+
+    dividend.resize(num_blocks * block_len, 0);
+
+    let blocks: Vec<_> = dividend.chunks(block_len).collect();
+
+    let mut padded_quotient = vec![0; (num_blocks - 1) * block_len];
+    let mut padded_remainder = blocks[num_blocks - 1].to_vec();
+
+    for i in (0..num_blocks - 1).rev() {
+        let mut block = blocks[i].to_vec();
+        block.extend_from_slice(&padded_remainder);
+
+        let block_quotient;
+        (block_quotient, padded_remainder) = div_2n_1n(&block, &divisor);
+
+        padded_quotient[i * block_len..(i + 1) * block_len].copy_from_slice(&block_quotient);
+    }
+
+    let padded_quotient = trimmed(&padded_quotient);
+
+    quotient.fill(0);
+    quotient[..padded_quotient.len()].copy_from_slice(padded_quotient);
+
+    // Undo the padding and the normalization
+    debug_assert!(padded_remainder[..padding].iter().all(|limb| *limb == 0));
+    remainder.copy_from_slice(&padded_remainder[padding..]);
+
+    if shift != 0 {
+        let ptr = remainder.as_mut_ptr();
+        unsafe { mpn_rshift(ptr, ptr, dn as size_t, shift) };
+    }
+}
+
+/// Divides a `2n` limb `dividend` by an `n` limb `divisor` and returns the `n` limb quotient and
+/// remainder. The most significant bit of `divisor` must be set, and `dividend` must be less than
+/// `divisor * B^n`.
+fn div_2n_1n(dividend: &[limb_t], divisor: &[limb_t]) -> (Vec<limb_t>, Vec<limb_t>) {
+    let n = divisor.len();
+
+    debug_assert_eq!(dividend.len(), 2 * n);
+    debug_assert!(cmp(&dividend[n..], divisor).is_lt());
+
+    if n % 2 == 1 || n < BURNIKEL_ZIEGLER_THRESHOLD {
+        let mut quotient = vec![0; n + 1];
+        let mut remainder = vec![0; n];
+
+        div_rem_schoolbook(&mut quotient, &mut remainder, dividend, divisor);
+
+        debug_assert_eq!(quotient[n], 0);
+        quotient.pop();
+
+        return (quotient, remainder);
+    }
+
+    let half = n / 2;
+
+    let (quotient_high, remainder) = div_3n_2n(&dividend[half..], divisor);
+
+    let mut dividend_low = dividend[..half].to_vec();
+    dividend_low.extend_from_slice(&remainder);
+
+    let (mut quotient, remainder) = div_3n_2n(&dividend_low, divisor);
+    quotient.extend_from_slice(&quotient_high);
+
+    (quotient, remainder)
+}
+
+/// Divides a `3n` limb `dividend` by a `2n` limb `divisor` and returns the `n` limb quotient and
+/// the `2n` limb remainder. The most significant bit of `divisor` must be set, and `dividend` must
+/// be less than `divisor * B^n`.
+fn div_3n_2n(dividend: &[limb_t], divisor: &[limb_t]) -> (Vec<limb_t>, Vec<limb_t>) {
+    let n = divisor.len() / 2;
+
+    let (divisor_low, divisor_high) = divisor.split_at(n);
+    let (dividend_low, dividend_high) = dividend.split_at(n);
+
+    // Estimate the quotient by dividing the high limbs. This is at-most two too large.
+    let (mut quotient, estimate_remainder) = if cmp(&dividend_high[n..], divisor_high).is_lt() {
+        div_2n_1n(dividend_high, divisor_high)
+    } else {
+        // The quotient is too big to calculate, so we use `B^n - 1`. In this case, the high limbs
+        // of the dividend and the divisor are equal, so the remainder is
+        // `dividend_high - (B^n - 1) * divisor_high = dividend_high[..n] + divisor_high`.
+        (vec![limb_t::MAX; n], sum(&dividend_high[..n], divisor_high))
+    };
+
+    // remainder = estimate_remainder * B^n + dividend_low - quotient * divisor_low
+    let mut remainder = vec![0; 2 * n + 1];
+    remainder[..n].copy_from_slice(dividend_low);
+
+    let carry = add_assign(&mut remainder[n..], trimmed(&estimate_remainder));
+    debug_assert_eq!(carry, 0);
+
+    let mut product = vec![0; 2 * n];
+    mul_any_order(&mut product, &quotient, divisor_low);
+
+    while cmp(&remainder, &product).is_lt() {
+        sub_assign(&mut quotient, &[1]);
+        add_assign(&mut remainder, divisor);
+    }
+
+    let borrow = sub_assign(&mut remainder, trimmed(&product));
+    debug_assert_eq!(borrow, 0);
+
+    debug_assert_eq!(remainder[2 * n], 0);
+    remainder.pop();
+
+    (quotient, remainder)
+}
+
+/// Returns `limbs << shift` with an extra limb for the shifted out bits. `shift` must be less than
+/// `limb_t::BITS`.
+fn shifted_left(limbs: &[limb_t], shift: u32) -> Vec<limb_t> {
+    let mut shifted = limbs.to_vec();
+
+    let carry = if shift != 0 {
+        let ptr = shifted.as_mut_ptr();
+        unsafe { mpn_lshift(ptr, ptr, shifted.len() as size_t, shift) }
+    } else {
+        0
+    };
+
+    shifted.push(carry);
+
+    shifted
+}
+
+/// Writes `dividend / divisor` to `quotient`. The division must be exact.
+///
+/// Because the remainder is known to be zero, the quotient can be calculated starting from the
+/// least significant limb using the inverse of the divisor modulo `B`. This avoids estimating and
+/// correcting quotient limbs like in [`div_rem`].
+fn div_exact(quotient: &mut [limb_t], dividend: &[limb_t], divisor: &[limb_t]) {
+    quotient.fill(0);
+
+    // Strip the common factors of two so that the divisor is odd (and therefore invertible)
+    let zero_limbs = divisor.iter().position(|limb| *limb != 0).unwrap();
+    let shift = divisor[zero_limbs].trailing_zeros();
+
+    let shifted_right = |limbs: &[limb_t]| {
+        let mut limbs = limbs[zero_limbs..].to_vec();
+
+        if shift != 0 {
+            let ptr = limbs.as_mut_ptr();
+            unsafe { mpn_rshift(ptr, ptr, limbs.len() as size_t, shift) };
+        }
+
+        limbs
+    };
+
+    debug_assert!(dividend[..zero_limbs].iter().all(|limb| *limb == 0));
+
+    let divisor = shifted_right(divisor);
+    let divisor = trimmed(&divisor);
+    let mut dividend = shifted_right(dividend);
+
+    let inverse = limb_inverse(divisor[0]);
+
+    // The quotient may have fewer limbs than `quotient` if the divisor was shortened by the shift.
+    // The remaining limbs are zero.
+    let num_limbs = (dividend.len() - divisor.len() + 1).min(quotient.len());
+
+    for i in 0..num_limbs {
+        let limb = dividend[i].wrapping_mul(inverse);
+        quotient[i] = limb;
+
+        // Subtract `limb * divisor * B^i`. This clears `dividend[i]`. The limbs past the end of
+        // the quotient don't matter, so the divisor may be truncated.
+        let dividend = &mut dividend[i..];
+        let len = divisor.len().min(dividend.len());
+
+        let borrow = submul_1(&mut dividend[..len], &divisor[..len], limb);
+
+        if len < dividend.len() {
+            sub_assign(&mut dividend[len..], &[borrow]);
+        }
+    }
+}
+
+/// Calculates the inverse of `limb` modulo `B`. `limb` must be odd.
+fn limb_inverse(limb: limb_t) -> limb_t {
+    debug_assert_eq!(limb & 1, 1);
+
+    // Every odd number is its own inverse modulo 8. Each Newton iteration doubles the number of
+    // correct bits.
+    let mut inverse = limb;
+    let mut correct_bits = 3;
+
+    while correct_bits < LIMB_BITS {
+        inverse = inverse.wrapping_mul((2 as limb_t).wrapping_sub(limb.wrapping_mul(inverse)));
+        correct_bits *= 2;
+    }
+
+    debug_assert_eq!(limb.wrapping_mul(inverse), 1);
+
+    inverse
+}
+
+/// Calculates the greatest common divisor of two non-zero numbers using Euclid's algorithm
+fn gcd(lhs: &[limb_t], rhs: &[limb_t]) -> Vec<limb_t> {
+    let mut lhs = trimmed(lhs).to_vec();
+    let mut rhs = trimmed(rhs).to_vec();
+
+    if cmp(&lhs, &rhs).is_lt() {
+        std::mem::swap(&mut lhs, &mut rhs);
+    }
+
+    while !rhs.is_empty() {
+        if let ([lhs_limb], [rhs_limb]) = (lhs.as_slice(), rhs.as_slice()) {
+            return vec![limb_gcd(*lhs_limb, *rhs_limb)];
+        }
+
+        let mut quotient = vec![0; lhs.len() - rhs.len() + 1];
+        let mut remainder = vec![0; rhs.len()];
+
+        div_rem(&mut quotient, &mut remainder, &lhs, &rhs);
+
+        let len = trimmed(&remainder).len();
+        remainder.truncate(len);
+
+        lhs = std::mem::replace(&mut rhs, remainder);
+    }
+
+    lhs
+}
+
+fn limb_gcd(mut lhs: limb_t, mut rhs: limb_t) -> limb_t {
+    while rhs != 0 {
+        (lhs, rhs) = (rhs, lhs % rhs);
+    }
+
+    lhs
+}
@@ -0,0 +1,215 @@
+//! Radix conversion for [`mpn_get_str`](super::mpn_get_str) and
+//! [`mpn_set_str`](super::mpn_set_str).
+//!
+//! Small numbers are converted one limb at a time. Large numbers are split at a power of the base,
+//! and then both halves are converted recursively. Because the split uses Karatsuba
+//! multiplication and Burnikel-Ziegler division, this is subquadratic.
+
+use super::{LimbDivisor, add_assign, div_rem, limb_t, mpn_mul_1, mul_any_order, size_t, trimmed};
+
+/// Numbers with fewer limbs than this are converted one limb at a time
+const DIVIDE_AND_CONQUER_THRESHOLD: usize = 30;
+
+/// The powers of a base that numbers are split at. The `i`th power is `base^(k * 2^i)` where
+/// `base^k` is the largest power of the base that fits in a single limb.
+struct RadixPowers {
+    base: limb_t,
+    digits_per_limb: usize,
+    divisor: LimbDivisor,
+    powers: Vec<Vec<limb_t>>,
+}
+
+impl RadixPowers {
+    fn new(base: limb_t) -> Self {
+        let digits_per_limb = limb_t::MAX.ilog(base);
+        let big_base = base.pow(digits_per_limb);
+
+        Self {
+            base,
+            digits_per_limb: digits_per_limb as usize,
+            divisor: LimbDivisor::new(big_base),
+            powers: vec![vec![big_base]],
+        }
+    }
+
+    /// Gets the `i`th power. The powers are calculated by repeated squaring as they are needed.
+    fn power(&mut self, i: usize) -> &[limb_t] {
+        while self.powers.len() <= i {
+            let last = self.powers.last().unwrap();
+
+            let mut square = vec![0; 2 * last.len()];
+            mul_any_order(&mut square, last, last);
+
+            let len = trimmed(&square).len();
+            square.truncate(len);
+
+            self.powers.push(square);
+        }
+
+        &self.powers[i]
+    }
+
+    /// The number of trailing zeroes of the `i`th power when it is written in the base
+    fn num_digits(&self, i: usize) -> usize {
+        self.digits_per_limb << i
+    }
+}
+
+/// Converts `limbs` to digits in `base`, starting with the most significant digit. Zero has no
+/// digits.
+pub(super) fn to_radix_digits(limbs: &[limb_t], base: limb_t) -> Vec<u8> {
+    let mut powers = RadixPowers::new(base);
+    let mut digits = Vec::new();
+
+    write_digits(trimmed(limbs), &mut powers, None, &mut digits);
+
+    digits
+}
+
+/// Appends the digits of `limbs` to `digits`. If `width` is given, the digits are left-padded with
+/// zeroes so that exactly `width` digits are written.
+fn write_digits(
+    limbs: &[limb_t],
+    powers: &mut RadixPowers,
+    width: Option<usize>,
+    digits: &mut Vec<u8>,
+) {
+    if limbs.len() < DIVIDE_AND_CONQUER_THRESHOLD {
+        let start = digits.len();
+
+        write_digits_basic(limbs, powers, digits);
+
+        if let Some(width) = width {
+            let len = digits.len() - start;
+            debug_assert!(len <= width);
+
+            digits.splice(start..start, std::iter::repeat_n(0, width - len));
+        }
+
+        return;
+    }
+
+    // Split at the largest power that is at-most about half as long as `limbs`
+    let mut i = 0;
+
+    while 2 * powers.power(i + 1).len() - 1 <= limbs.len() {
+        i += 1;
+    }
+
+    let power = powers.power(i).to_vec();
+
+    let mut quotient = vec![0; limbs.len() - power.len() + 1];
+    let mut remainder = vec![0; power.len()];
+
+    div_rem(&mut quotient, &mut remainder, limbs, &power);
+
+    let low_width = powers.num_digits(i);
+
+    write_digits(
+        trimmed(&quotient),
+        powers,
+        width.map(|width| width - low_width),
+        digits,
+    );
+    write_digits(trimmed(&remainder), powers, Some(low_width), digits);
+}
+
+/// Appends the digits of `limbs` to `digits` by repeatedly dividing by the largest power of the
+/// base that fits in a limb
+fn write_digits_basic(limbs: &[limb_t], powers: &RadixPowers, digits: &mut Vec<u8>) {
+    let start = digits.len();
+    let mut limbs = limbs.to_vec();
+
+    while !limbs.is_empty() {
+        let mut remainder = powers.divisor.div_rem_in_place(&mut limbs);
+
+        let len = trimmed(&limbs).len();
+        limbs.truncate(len);
+
+        for _ in 0..powers.digits_per_limb {
+            if limbs.is_empty() && remainder == 0 {
+                break;
+            }
+
+            digits.push((remainder % powers.base) as u8);
+            remainder /= powers.base;
+        }
+    }
+
+    digits[start..].reverse();
+}
+
+/// Converts digits in `base` to limbs. `digits` starts with the most significant digit. The result
+/// has no leading zero limbs.
+pub(super) fn from_radix_digits(digits: &[u8], base: limb_t) -> Vec<limb_t> {
+    let mut powers = RadixPowers::new(base);
+
+    read_digits(digits, &mut powers)
+}
+
+fn read_digits(digits: &[u8], powers: &mut RadixPowers) -> Vec<limb_t> {
+    if digits.len() < DIVIDE_AND_CONQUER_THRESHOLD * powers.digits_per_limb {
+        return read_digits_basic(digits, powers);
+    }
+
+    // Split so that the low digits are the zeroes of the largest power that is shorter than
+    // `digits`. This means that there are at-least as many low digits as high digits.
+    let mut i = 0;
+
+    while powers.num_digits(i + 1) < digits.len() {
+        i += 1;
+    }
+
+    let (high, low) = digits.split_at(digits.len() - powers.num_digits(i));
+
+    let high = read_digits(high, powers);
+    let low = read_digits(low, powers);
+    let power = powers.power(i);
+
+    // result = high * power + low
+    let mut result = vec![0; high.len() + power.len() + 1];
+    mul_any_order(&mut result, &high, power);
+
+    let carry = add_assign(&mut result, &low);
+    debug_assert_eq!(carry, 0);
+
+    let len = trimmed(&result).len();
+    result.truncate(len);
+
+    result
+}
+
+/// Converts digits to limbs by multiplying by the largest power of the base that fits in a limb
+fn read_digits_basic(digits: &[u8], powers: &RadixPowers) -> Vec<limb_t> {
+    let mut limbs: Vec<limb_t> = Vec::new();
+
+    // The first chunk is shorter so that the rest of the chunks have exactly `digits_per_limb`
+    // digits
+    let first_chunk_len = match digits.len() % powers.digits_per_limb {
+        0 => powers.digits_per_limb,
+        len => len,
+    };
+
+    let (first_chunk, rest) = digits.split_at(first_chunk_len.min(digits.len()));
+
+    for chunk in std::iter::once(first_chunk).chain(rest.chunks(powers.digits_per_limb)) {
+        let multiplier = powers.base.pow(chunk.len() as u32);
+        let value = chunk
+            .iter()
+            .fold(0, |value, digit| value * powers.base + limb_t::from(*digit));
+
+        // limbs = limbs * multiplier + value
+        let ptr = limbs.as_mut_ptr();
+        let carry = unsafe { mpn_mul_1(ptr, ptr, limbs.len() as size_t, multiplier) };
+        limbs.push(carry);
+
+        let carry = add_assign(&mut limbs, &[value]);
+        debug_assert_eq!(carry, 0);
+
+        if limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+    }
+
+    limbs
+}
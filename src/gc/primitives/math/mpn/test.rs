@@ -0,0 +1,577 @@
+//! Tests for the pure-Rust `mpn` implementation. Known answers and round trips always run, and
+//! the results are also compared with GMP if the `gmp` feature is enabled.
+
+use proptest::prelude::*;
+
+use super::limb_t;
+
+/// Defines safe wrappers around the functions of an `mpn` backend
+macro_rules! backend {
+    ($backend:ident) => {
+        mod $backend {
+            use super::super::$backend::*;
+
+            pub fn add(lhs: &[limb_t], rhs: &[limb_t]) -> (Vec<limb_t>, limb_t) {
+                let mut output = vec![0; lhs.len()];
+                let carry = unsafe {
+                    mpn_add(
+                        output.as_mut_ptr(),
+                        lhs.as_ptr(),
+                        lhs.len() as size_t,
+                        rhs.as_ptr(),
+                        rhs.len() as size_t,
+                    )
+                };
+
+                (output, carry)
+            }
+
+            pub fn sub(lhs: &[limb_t], rhs: &[limb_t]) -> (Vec<limb_t>, limb_t) {
+                let mut output = vec![0; lhs.len()];
+                let borrow = unsafe {
+                    mpn_sub(
+                        output.as_mut_ptr(),
+                        lhs.as_ptr(),
+                        lhs.len() as size_t,
+                        rhs.as_ptr(),
+                        rhs.len() as size_t,
+                    )
+                };
+
+                (output, borrow)
+            }
+
+            pub fn mul(lhs: &[limb_t], rhs: &[limb_t]) -> (Vec<limb_t>, limb_t) {
+                let mut output = vec![0; lhs.len() + rhs.len()];
+                let high = unsafe {
+                    mpn_mul(
+                        output.as_mut_ptr(),
+                        lhs.as_ptr(),
+                        lhs.len() as size_t,
+                        rhs.as_ptr(),
+                        rhs.len() as size_t,
+                    )
+                };
+
+                (output, high)
+            }
+
+            /// Calculates `lhs * rhs` in-place and `addend + lhs * rhs`
+            pub fn mul_1(
+                lhs: &[limb_t],
+                rhs: limb_t,
+                addend: &[limb_t],
+            ) -> ((Vec<limb_t>, limb_t), (Vec<limb_t>, limb_t)) {
+                let mut product = lhs.to_vec();
+                let ptr = product.as_mut_ptr();
+                let carry = unsafe { mpn_mul_1(ptr, ptr, lhs.len() as size_t, rhs) };
+
+                let mut sum = addend.to_vec();
+                let sum_carry = unsafe {
+                    mpn_addmul_1(sum.as_mut_ptr(), lhs.as_ptr(), lhs.len() as size_t, rhs)
+                };
+
+                ((product, carry), (sum, sum_carry))
+            }
+
+            /// Shifts `limbs` left and right in-place
+            pub fn shift(
+                limbs: &[limb_t],
+                count: u32,
+            ) -> ((Vec<limb_t>, limb_t), (Vec<limb_t>, limb_t)) {
+                let len = limbs.len() as size_t;
+
+                let mut left = limbs.to_vec();
+                let ptr = left.as_mut_ptr();
+                let left_out = unsafe { mpn_lshift(ptr, ptr, len, count) };
+
+                let mut right = limbs.to_vec();
+                let ptr = right.as_mut_ptr();
+                let right_out = unsafe { mpn_rshift(ptr, ptr, len, count) };
+
+                ((left, left_out), (right, right_out))
+            }
+
+            /// Divides `dividend` by `divisor` in-place
+            pub fn divmod_1(dividend: &[limb_t], divisor: limb_t) -> (Vec<limb_t>, limb_t) {
+                let mut quotient = dividend.to_vec();
+                let ptr = quotient.as_mut_ptr();
+                let remainder =
+                    unsafe { mpn_divmod_1(ptr, ptr, dividend.len() as size_t, divisor) };
+
+                (quotient, remainder)
+            }
+
+            pub fn tdiv_qr(dividend: &[limb_t], divisor: &[limb_t]) -> (Vec<limb_t>, Vec<limb_t>) {
+                let mut quotient = vec![0; dividend.len() - divisor.len() + 1];
+                let mut remainder = vec![0; divisor.len()];
+
+                unsafe {
+                    mpn_tdiv_qr(
+                        quotient.as_mut_ptr(),
+                        remainder.as_mut_ptr(),
+                        0,
+                        dividend.as_ptr(),
+                        dividend.len() as size_t,
+                        divisor.as_ptr(),
+                        divisor.len() as size_t,
+                    )
+                };
+
+                (quotient, remainder)
+            }
+
+            pub fn divexact(dividend: &[limb_t], divisor: &[limb_t]) -> Vec<limb_t> {
+                let mut quotient = vec![0; dividend.len() - divisor.len() + 1];
+
+                unsafe {
+                    mpn_divexact(
+                        quotient.as_mut_ptr(),
+                        dividend.as_ptr(),
+                        dividend.len() as size_t,
+                        divisor.as_ptr(),
+                        divisor.len() as size_t,
+                    )
+                };
+
+                quotient
+            }
+
+            pub fn gcd(greater: &[limb_t], less: &[limb_t]) -> Vec<limb_t> {
+                let mut greater = greater.to_vec();
+                let mut less = less.to_vec();
+                let mut output = vec![0; less.len()];
+
+                let len = unsafe {
+                    mpn_gcd(
+                        output.as_mut_ptr(),
+                        greater.as_mut_ptr(),
+                        greater.len() as size_t,
+                        less.as_mut_ptr(),
+                        less.len() as size_t,
+                    )
+                };
+
+                output.truncate(len as usize);
+                output
+            }
+
+            pub fn get_str(limbs: &[limb_t], base: i32) -> Vec<u8> {
+                let mut input = limbs.to_vec();
+                input.push(0);
+
+                let mut digits = vec![0; limbs.len() * limb_t::BITS as usize + 1];
+
+                let len = unsafe {
+                    mpn_get_str(
+                        digits.as_mut_ptr(),
+                        base,
+                        input.as_mut_ptr(),
+                        limbs.len() as size_t,
+                    )
+                };
+
+                digits.truncate(len);
+                digits
+            }
+
+            pub fn set_str(digits: &[u8], base: i32) -> Vec<limb_t> {
+                let bits_per_digit = (base as f64).log2().ceil() as usize;
+                let mut limbs = vec![0; digits.len() * bits_per_digit / limb_t::BITS as usize + 2];
+
+                let len =
+                    unsafe { mpn_set_str(limbs.as_mut_ptr(), digits.as_ptr(), digits.len(), base) };
+
+                limbs.truncate(len as usize);
+                limbs
+            }
+        }
+    };
+}
+
+backend!(native);
+#[cfg(feature = "gmp")]
+backend!(gmp);
+
+/// The number of limbs in a `u128`
+const U128_LIMBS: usize = (u128::BITS / limb_t::BITS) as usize;
+
+/// Converts a `u128` to exactly `len` limbs
+fn from_u128(value: u128, len: usize) -> Vec<limb_t> {
+    (0..len)
+        .map(|i| (value.checked_shr(i as u32 * limb_t::BITS).unwrap_or(0)) as limb_t)
+        .collect()
+}
+
+fn to_u128(limbs: &[limb_t]) -> u128 {
+    limbs
+        .iter()
+        .rev()
+        .fold(0, |acc, limb| acc << limb_t::BITS | u128::from(*limb))
+}
+
+/// Limbs that are likely to trigger edge cases are generated more often
+fn arb_limb() -> impl Strategy<Value = limb_t> {
+    prop_oneof![
+        Just(0),
+        Just(1),
+        Just(limb_t::MAX),
+        Just(limb_t::MAX >> 1),
+        Just(1 << (limb_t::BITS - 1)),
+        any::<limb_t>(),
+        any::<limb_t>(),
+        any::<limb_t>(),
+    ]
+}
+
+/// Generates a number with `len` limbs and a non-zero most significant limb
+fn arb_natural(len: impl Into<prop::collection::SizeRange>) -> impl Strategy<Value = Vec<limb_t>> {
+    (prop::collection::vec(arb_limb(), len), 1..=limb_t::MAX).prop_map(|(mut limbs, high)| {
+        limbs.push(high);
+        limbs
+    })
+}
+
+fn without_leading_zeroes(mut limbs: Vec<limb_t>) -> Vec<limb_t> {
+    let len = limbs
+        .iter()
+        .rposition(|limb| *limb != 0)
+        .map_or(0, |i| i + 1);
+    limbs.truncate(len);
+    limbs
+}
+
+/// Calculates `lhs * rhs` without leading zero limbs
+fn product(lhs: &[limb_t], rhs: &[limb_t]) -> Vec<limb_t> {
+    let (product, _) = if lhs.len() >= rhs.len() {
+        native::mul(lhs, rhs)
+    } else {
+        native::mul(rhs, lhs)
+    };
+
+    without_leading_zeroes(product)
+}
+
+/// Orders `lhs` and `rhs` by length and then by value
+fn greater_first(lhs: Vec<limb_t>, rhs: Vec<limb_t>) -> (Vec<limb_t>, Vec<limb_t>) {
+    let greater = lhs
+        .len()
+        .cmp(&rhs.len())
+        .then_with(|| lhs.iter().rev().cmp(rhs.iter().rev()))
+        .is_ge();
+
+    if greater { (lhs, rhs) } else { (rhs, lhs) }
+}
+
+#[test]
+fn known_answers() {
+    assert_eq!(native::add(&[limb_t::MAX], &[1]), (vec![0], 1));
+    assert_eq!(
+        native::sub(&from_u128(1 << 64, U128_LIMBS), &[1]),
+        (from_u128((1 << 64) - 1, U128_LIMBS), 0)
+    );
+    assert_eq!(native::sub(&[0], &[1]), (vec![limb_t::MAX], 1));
+
+    let (product, _) = native::mul(
+        &from_u128(u64::MAX.into(), U128_LIMBS / 2),
+        &from_u128(u64::MAX.into(), U128_LIMBS / 2),
+    );
+    assert_eq!(to_u128(&product), 0xfffffffffffffffe0000000000000001);
+
+    assert_eq!(
+        native::shift(
+            &from_u128(0x8000_0000_0000_0000_0000_0000_0000_0001, U128_LIMBS),
+            1
+        ),
+        (
+            (from_u128(2, U128_LIMBS), 1),
+            (
+                from_u128(0x4000_0000_0000_0000_0000_0000_0000_0000, U128_LIMBS),
+                1 << (limb_t::BITS - 1)
+            )
+        )
+    );
+
+    let dividend = from_u128(0xfedcba9876543210_0123456789abcdef, U128_LIMBS);
+    let (quotient, remainder) = native::tdiv_qr(
+        &dividend,
+        &from_u128(0x1_0000000000000001, 64 / limb_t::BITS as usize + 1),
+    );
+    assert_eq!(to_u128(&quotient), 0xfedcba987654320f);
+    assert_eq!(to_u128(&remainder), 0x2468acf13579be0);
+
+    let (quotient, remainder) = native::divmod_1(&dividend, 10);
+    assert_eq!(to_u128(&quotient), 0xfedcba9876543210_0123456789abcdef / 10);
+    assert_eq!(remainder, 5);
+
+    let gcd = native::gcd(
+        &from_u128(0x115c7530e30eb390f838d, U128_LIMBS),
+        &from_u128(0x115c5e6f76f7feba36ce1, U128_LIMBS),
+    );
+    assert_eq!(to_u128(&gcd), 0x1234567890abcdef);
+
+    let digits: Vec<u8> = "340282366920938463463374607431768211455"
+        .bytes()
+        .map(|digit| digit - b'0')
+        .collect();
+    assert_eq!(
+        native::get_str(&from_u128(u128::MAX, U128_LIMBS), 10),
+        digits
+    );
+    assert_eq!(
+        native::set_str(&digits, 10),
+        from_u128(u128::MAX, U128_LIMBS)
+    );
+    assert_eq!(native::get_str(&[255], 16), vec![15, 15]);
+    assert_eq!(native::get_str(&[35], 36), vec![35]);
+}
+
+/// Generates a number that fits in half of a `u128`, so that sums and products fit in a `u128`
+fn arb_half_natural() -> impl Strategy<Value = Vec<limb_t>> {
+    arb_natural(0..U128_LIMBS / 2)
+}
+
+/// Calculates `value % divisor` without leading zero limbs
+fn modulo(value: &[limb_t], divisor: &[limb_t]) -> Vec<limb_t> {
+    if value.len() < divisor.len() {
+        return without_leading_zeroes(value.to_vec());
+    }
+
+    without_leading_zeroes(native::tdiv_qr(value, divisor).1)
+}
+
+/// Appends `high` to `limbs` as the most significant limb
+fn with_high(mut limbs: Vec<limb_t>, high: limb_t) -> Vec<limb_t> {
+    limbs.push(high);
+    limbs
+}
+
+proptest! {
+    #[test]
+    fn matches_u128_arithmetic(lhs in arb_half_natural(), rhs in arb_half_natural()) {
+        let (lhs, rhs) = greater_first(lhs, rhs);
+        let (lhs_value, rhs_value) = (to_u128(&lhs), to_u128(&rhs));
+
+        let (sum, carry) = native::add(&lhs, &rhs);
+        prop_assert_eq!(to_u128(&with_high(sum, carry)), lhs_value + rhs_value);
+
+        let (difference, borrow) = native::sub(&lhs, &rhs);
+        prop_assert_eq!((to_u128(&difference), borrow), (lhs_value - rhs_value, 0));
+
+        let (product, _) = native::mul(&lhs, &rhs);
+        prop_assert_eq!(to_u128(&product), lhs_value * rhs_value);
+
+        let (quotient, remainder) = native::tdiv_qr(&lhs, &rhs);
+        prop_assert_eq!(to_u128(&quotient), lhs_value / rhs_value);
+        prop_assert_eq!(to_u128(&remainder), lhs_value % rhs_value);
+    }
+
+    #[test]
+    fn add_sub_round_trip(lhs in arb_natural(0..20), rhs in arb_natural(0..20)) {
+        let (lhs, rhs) = greater_first(lhs, rhs);
+
+        let (sum, carry) = native::add(&lhs, &rhs);
+        let (difference, borrow) = native::sub(&with_high(sum, carry), &rhs);
+
+        prop_assert_eq!(difference, with_high(lhs, 0));
+        prop_assert_eq!(borrow, 0);
+    }
+
+    #[test]
+    fn mul_div_round_trip(
+        quotient in arb_natural(0..40),
+        divisor in arb_natural(0..40),
+        remainder in arb_natural(0..40),
+    ) {
+        let dividend = product(&quotient, &divisor);
+        prop_assert_eq!(without_leading_zeroes(native::divexact(&dividend, &divisor)), quotient.clone());
+
+        let remainder = modulo(&remainder, &divisor);
+        let (dividend, carry) = native::add(&dividend, &remainder);
+        let dividend = without_leading_zeroes(with_high(dividend, carry));
+
+        let (actual_quotient, actual_remainder) = native::tdiv_qr(&dividend, &divisor);
+        prop_assert_eq!(without_leading_zeroes(actual_quotient), quotient);
+        prop_assert_eq!(without_leading_zeroes(actual_remainder), remainder);
+    }
+
+    #[test]
+    fn mul_1_matches_mul(lhs in arb_natural(0..20), rhs in arb_limb(), addend in arb_natural(0..20)) {
+        let mut addend = addend;
+        addend.resize(lhs.len(), 0);
+
+        let ((product, carry), (sum, sum_carry)) = native::mul_1(&lhs, rhs, &addend);
+        let (expected, _) = native::mul(&lhs, &[rhs]);
+        prop_assert_eq!(with_high(product, carry), expected.clone());
+
+        let (expected_sum, overflow) = native::add(&expected, &addend);
+        prop_assert_eq!(overflow, 0);
+        prop_assert_eq!(with_high(sum, sum_carry), expected_sum);
+    }
+
+    #[test]
+    fn divmod_1_matches_tdiv_qr(dividend in arb_natural(0..20), divisor in 1..=limb_t::MAX) {
+        let (quotient, remainder) = native::divmod_1(&dividend, divisor);
+
+        prop_assert_eq!(native::tdiv_qr(&dividend, &[divisor]), (quotient, vec![remainder]));
+    }
+
+    #[test]
+    fn shift_round_trip(limbs in arb_natural(0..20), count in 1..limb_t::BITS) {
+        let ((shifted, out), _) = native::shift(&with_high(limbs.clone(), 0), count);
+        prop_assert_eq!(out, 0);
+
+        let (_, (unshifted, out)) = native::shift(&shifted, count);
+        prop_assert_eq!(out, 0);
+        prop_assert_eq!(unshifted, with_high(limbs, 0));
+    }
+
+    #[test]
+    fn gcd_divides_both(
+        factor in arb_natural(0..10),
+        lhs in arb_natural(0..10),
+        rhs in arb_natural(0..10),
+    ) {
+        // `mpn_gcd` requires that at-least one input is odd
+        let mut factor = factor;
+        factor[0] |= 1;
+
+        let mut rhs = rhs;
+        rhs[0] |= 1;
+
+        let lhs = product(&lhs, &factor);
+        let rhs = product(&rhs, &factor);
+
+        let (greater, less) = greater_first(lhs, rhs);
+        let gcd = native::gcd(&greater, &less);
+
+        prop_assert!(modulo(&greater, &gcd).is_empty());
+        prop_assert!(modulo(&less, &gcd).is_empty());
+        prop_assert!(modulo(&gcd, &factor).is_empty());
+    }
+
+    #[test]
+    fn str_round_trip(limbs in arb_natural(0..100), base in prop_oneof![Just(10), 2..=36i32]) {
+        let digits = native::get_str(&limbs, base);
+
+        prop_assert_eq!(native::set_str(&digits, base), limbs);
+    }
+}
+
+#[cfg(feature = "gmp")]
+mod differential {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn add_and_sub(lhs in arb_natural(0..20), rhs in arb_natural(0..20)) {
+            let (lhs, rhs) = greater_first(lhs, rhs);
+
+            prop_assert_eq!(native::add(&lhs, &rhs), gmp::add(&lhs, &rhs));
+            prop_assert_eq!(native::sub(&lhs, &rhs), gmp::sub(&lhs, &rhs));
+        }
+
+        #[test]
+        fn mul(lhs in arb_natural(0..150), rhs in arb_natural(0..150)) {
+            let (lhs, rhs) = greater_first(lhs, rhs);
+
+            prop_assert_eq!(native::mul(&lhs, &rhs), gmp::mul(&lhs, &rhs));
+        }
+
+        #[test]
+        fn mul_1(lhs in arb_natural(0..20), rhs in arb_limb(), addend in arb_natural(0..20)) {
+            let mut addend = addend;
+            addend.resize(lhs.len(), 0);
+
+            prop_assert_eq!(native::mul_1(&lhs, rhs, &addend), gmp::mul_1(&lhs, rhs, &addend));
+        }
+
+        #[test]
+        fn shift(limbs in arb_natural(0..20), count in 1..limb_t::BITS) {
+            prop_assert_eq!(native::shift(&limbs, count), gmp::shift(&limbs, count));
+        }
+
+        #[test]
+        fn divmod_1(dividend in arb_natural(0..20), divisor in 1..=limb_t::MAX) {
+            prop_assert_eq!(native::divmod_1(&dividend, divisor), gmp::divmod_1(&dividend, divisor));
+        }
+
+        #[test]
+        fn tdiv_qr(dividend in arb_natural(0..40), divisor in arb_natural(0..20)) {
+            let (dividend, divisor) = greater_first(dividend, divisor);
+
+            prop_assert_eq!(native::tdiv_qr(&dividend, &divisor), gmp::tdiv_qr(&dividend, &divisor));
+        }
+
+        #[test]
+        fn tdiv_qr_large(dividend in arb_natural(80..400), divisor in arb_natural(40..200)) {
+            let (dividend, divisor) = greater_first(dividend, divisor);
+
+            prop_assert_eq!(native::tdiv_qr(&dividend, &divisor), gmp::tdiv_qr(&dividend, &divisor));
+        }
+
+        #[test]
+        fn divexact(quotient in arb_natural(0..30), divisor in arb_natural(0..30)) {
+            let dividend = product(&quotient, &divisor);
+
+            let expected = gmp::divexact(&dividend, &divisor);
+
+            prop_assert_eq!(without_leading_zeroes(expected.clone()), quotient);
+            prop_assert_eq!(native::divexact(&dividend, &divisor), expected);
+        }
+
+        #[test]
+        fn gcd(
+            factor in arb_natural(0..10),
+            lhs in arb_natural(0..10),
+            rhs in arb_natural(0..10),
+        ) {
+            // `mpn_gcd` requires that at-least one input is odd
+            let mut factor = factor;
+            factor[0] |= 1;
+
+            let mut rhs = rhs;
+            rhs[0] |= 1;
+
+            let lhs = product(&lhs, &factor);
+            let rhs = product(&rhs, &factor);
+
+            let (greater, less) = greater_first(lhs, rhs);
+
+            prop_assert_eq!(native::gcd(&greater, &less), gmp::gcd(&greater, &less));
+        }
+
+        #[test]
+        fn get_str_large(limbs in arb_natural(30..300)) {
+            prop_assert_eq!(native::get_str(&limbs, 10), gmp::get_str(&limbs, 10));
+        }
+
+        #[test]
+        fn set_str(
+            (base, digits) in (2..=36i32).prop_flat_map(|base| {
+                (Just(base), prop::collection::vec(0..base as u8, 0..100))
+            }),
+            leading_digit in 1..10u8,
+        ) {
+            let mut digits = digits;
+            digits.insert(0, leading_digit % base as u8 + u8::from(leading_digit % base as u8 == 0));
+
+            prop_assert_eq!(native::set_str(&digits, base), gmp::set_str(&digits, base));
+        }
+
+        #[test]
+        fn set_str_large(digits in prop::collection::vec(0..10u8, 500..6000), leading_digit in 1..10u8) {
+            let mut digits = digits;
+            digits.insert(0, leading_digit);
+
+            prop_assert_eq!(native::set_str(&digits, 10), gmp::set_str(&digits, 10));
+        }
+
+        #[test]
+        fn get_str(limbs in arb_natural(0..30), base in prop_oneof![Just(10), 2..=36i32]) {
+            prop_assert_eq!(native::get_str(&limbs, base), gmp::get_str(&limbs, base));
+        }
+    }
+}
@@ -1,14 +1,23 @@
 use std::{
+    cmp::Ordering,
     fmt::Debug,
     marker::PhantomData,
     num::{IntErrorKind, NonZeroUsize},
 };
 
+use super::mpn::limb_t;
+
 use crate::{
     error::{PartialSpanned, parse::PDResult},
     gc::{
-        GCDebug, GCEq, GCPtr, GCWrap, GarbageCollector, math::rational::GCRational,
+        GCDebug, GCEq, GCPtr, GCWrap, GarbageCollector,
+        dump::HeapTracer,
+        math::{
+            ArithmeticError, ConversionError, DecimalFormat, DivisionMode, RoundingMode, convert,
+            decimal::format_decimal, rational::GCRational,
+        },
         roots::GCRootInfo,
+        safety::GC,
     },
 };
 
@@ -26,16 +35,30 @@ pub struct GCNumber {
 }
 
 enum GetGCNumber {
-    Inline(usize),
+    Inline(isize),
     Rational(GCRational),
 }
 
 impl GCNumber {
+    const INLINE_TAG: usize = 1usize.rotate_right(1);
+    const INLINE_MIN: isize = isize::MIN >> 1;
+    const INLINE_MAX: isize = isize::MAX >> 1;
+
+    /// Parses a number literal. If `multiplier` is specified, the value will be multiplied by the
+    /// fraction `numerator/denominator` (this is used for unit suffixes).
     pub fn parse_from_literal(
         gc: &GarbageCollector,
         decimal: PartialSpanned<&str>,
+        multiplier: Option<(u64, u64)>,
     ) -> PDResult<Self> {
-        let rational = GCRational::parse_from_literal(gc, decimal)?;
+        let mut rational = GCRational::parse_from_literal(gc, decimal)?;
+
+        if let Some((numerator, denominator)) = multiplier {
+            let multiplier = GCRational::from_u64_fraction(gc, numerator, denominator);
+
+            // The unscaled value and multiplier will be cleaned up by the next collection
+            rational = unsafe { rational.mul(gc, multiplier) };
+        }
 
         if let Some(num) = unsafe { rational.as_usize(gc) }
             && let Some(num) = Self::from_usize(num)
@@ -74,25 +97,371 @@ impl GCNumber {
     }
 
     pub fn from_usize(usize: usize) -> Option<Self> {
-        if usize & 1usize.rotate_right(1) != 0 {
-            Some(Self {
-                value: unsafe { NonZeroUsize::new_unchecked(usize | 1usize.rotate_right(1)) },
-                _phantomdata: PhantomData,
-            })
+        isize::try_from(usize).ok().and_then(Self::from_isize)
+    }
+
+    /// Creates an inline `GCNumber`. Returns `None` if `isize` is too large to be stored inline.
+    pub fn from_isize(isize: isize) -> Option<Self> {
+        if !(Self::INLINE_MIN..=Self::INLINE_MAX).contains(&isize) {
+            return None;
+        }
+
+        Some(Self {
+            value: unsafe { NonZeroUsize::new_unchecked(isize as usize | Self::INLINE_TAG) },
+            _phantomdata: PhantomData,
+        })
+    }
+
+    /// Creates a `GCNumber`. This will only allocate if `isize` is too large to be stored inline.
+    pub fn from_isize_or_rational(gc: &GarbageCollector, isize: isize) -> Self {
+        Self::from_isize(isize).unwrap_or_else(|| GCRational::from_isize(gc, isize).into())
+    }
+
+    /// Creates a `GCNumber` from an integer. This will only allocate if `value` is too large to be
+    /// stored inline.
+    pub fn from_i128(gc: &GarbageCollector, value: i128) -> Self {
+        if let Ok(value) = isize::try_from(value)
+            && let Some(num) = Self::from_isize(value)
+        {
+            return num;
+        }
+
+        let magnitude = convert::u128_to_limbs(value.unsigned_abs());
+
+        GCRational::from_limbs(gc, value < 0, &magnitude, &[1]).into()
+    }
+
+    /// Creates a `GCNumber` from an unsigned integer. This will only allocate if `value` is too
+    /// large to be stored inline.
+    pub fn from_u128(gc: &GarbageCollector, value: u128) -> Self {
+        match i128::try_from(value) {
+            Ok(value) => Self::from_i128(gc, value),
+            Err(_) => {
+                GCRational::from_limbs(gc, false, &convert::u128_to_limbs(value), &[1]).into()
+            }
+        }
+    }
+
+    /// Creates a `GCNumber` with the exact value of `value`. Returns `None` if `value` is infinite
+    /// or NaN.
+    pub fn from_f64(gc: &GarbageCollector, value: f64) -> Option<Self> {
+        let (is_negative, numerator, denominator) = convert::f64_to_fraction(value)?;
+
+        let rational = GCRational::from_limbs(gc, is_negative, &numerator, &denominator);
+
+        Some(unsafe { Self::from_rational(gc, rational) })
+    }
+
+    /// Converts a `GCRational` into a `GCNumber`. If the rational is a small enough integer, it will
+    /// be stored inline.
+    ///
+    /// # Safety
+    /// `rational` must be a valid object in `gc`
+    pub unsafe fn from_rational(gc: &GarbageCollector, rational: GCRational) -> Self {
+        unsafe { rational.as_isize(gc) }
+            .and_then(Self::from_isize)
+            .unwrap_or(rational.into())
+    }
+
+    /// Gets `self` as a `GCRational`. This will allocate if `self` is stored inline.
+    pub fn to_rational(self, gc: &GarbageCollector) -> GCRational {
+        match self.get() {
+            GetGCNumber::Inline(int) => GCRational::from_isize(gc, int),
+            GetGCNumber::Rational(rat) => rat,
+        }
+    }
+
+    /// Determines whether `self` is an integer
+    ///
+    /// # Safety
+    /// `self` must be a valid object in `gc`
+    pub unsafe fn is_integer(self, gc: &GarbageCollector) -> bool {
+        match self.get() {
+            GetGCNumber::Inline(_) => true,
+            GetGCNumber::Rational(rat) => unsafe { rat.is_integer(gc) },
+        }
+    }
+
+    /// Calculates `-self`
+    ///
+    /// # Safety
+    /// `self` must be a valid object in `gc`
+    pub unsafe fn neg(self, gc: &GarbageCollector) -> Self {
+        match self.get() {
+            GetGCNumber::Inline(int) => Self::from_isize_or_rational(gc, -int),
+            GetGCNumber::Rational(rat) => unsafe { Self::from_rational(gc, rat.neg(gc)) },
+        }
+    }
+
+    /// Calculates `|self|`
+    ///
+    /// # Safety
+    /// `self` must be a valid object in `gc`
+    pub unsafe fn abs(self, gc: &GarbageCollector) -> Self {
+        match self.get() {
+            GetGCNumber::Inline(int) => Self::from_isize_or_rational(gc, int.abs()),
+            GetGCNumber::Rational(rat) => unsafe { Self::from_rational(gc, rat.abs(gc)) },
+        }
+    }
+
+    /// Compares `self` with `rhs`
+    ///
+    /// # Safety
+    /// `self` and `rhs` must be valid objects in `gc`
+    pub unsafe fn cmp(self, gc: &GarbageCollector, rhs: Self) -> Ordering {
+        match (self.get(), rhs.get()) {
+            (GetGCNumber::Inline(lhs), GetGCNumber::Inline(rhs)) => lhs.cmp(&rhs),
+            _ => unsafe { self.to_rational(gc).cmp(gc, rhs.to_rational(gc)) },
+        }
+    }
+
+    /// Returns the smaller of `self` and `rhs`
+    ///
+    /// # Safety
+    /// `self` and `rhs` must be valid objects in `gc`
+    pub unsafe fn min(self, gc: &GarbageCollector, rhs: Self) -> Self {
+        if unsafe { self.cmp(gc, rhs) }.is_gt() {
+            rhs
         } else {
-            None
+            self
+        }
+    }
+
+    /// Returns the larger of `self` and `rhs`
+    ///
+    /// # Safety
+    /// `self` and `rhs` must be valid objects in `gc`
+    pub unsafe fn max(self, gc: &GarbageCollector, rhs: Self) -> Self {
+        if unsafe { self.cmp(gc, rhs) }.is_lt() {
+            rhs
+        } else {
+            self
+        }
+    }
+
+    /// Rounds `self` to an integer (ie `floor`, `ceil`, `round`, or `trunc`)
+    ///
+    /// # Safety
+    /// `self` must be a valid object in `gc`
+    pub unsafe fn round(self, gc: &GarbageCollector, mode: RoundingMode) -> Self {
+        match self.get() {
+            GetGCNumber::Inline(_) => self,
+            GetGCNumber::Rational(rat) => unsafe { Self::from_rational(gc, rat.round(gc, mode)) },
+        }
+    }
+
+    /// Calculates the integer quotient and the remainder of `self / rhs` (ie `div` and `mod`)
+    ///
+    /// # Safety
+    /// `self` and `rhs` must be valid objects in `gc`
+    pub unsafe fn div_rem(
+        self,
+        gc: &GarbageCollector,
+        rhs: Self,
+        mode: DivisionMode,
+    ) -> Result<(Self, Self), ArithmeticError> {
+        if let (GetGCNumber::Inline(lhs), GetGCNumber::Inline(rhs)) = (self.get(), rhs.get()) {
+            if rhs == 0 {
+                return Err(ArithmeticError::DivisionByZero);
+            }
+
+            // Inline values are one bit smaller than `isize`, so this can't overflow
+            let (mut quotient, mut remainder) = (lhs / rhs, lhs % rhs);
+
+            if mode == DivisionMode::Floored && remainder != 0 && (remainder < 0) != (rhs < 0) {
+                quotient -= 1;
+                remainder += rhs;
+            }
+
+            return Ok((
+                Self::from_isize_or_rational(gc, quotient),
+                Self::from_isize_or_rational(gc, remainder),
+            ));
+        }
+
+        let (quotient, remainder) = unsafe {
+            self.to_rational(gc)
+                .div_rem(gc, rhs.to_rational(gc), mode)?
+        };
+
+        unsafe {
+            Ok((
+                Self::from_rational(gc, quotient),
+                Self::from_rational(gc, remainder),
+            ))
+        }
+    }
+
+    /// Calculates the greatest common divisor of `self` and `rhs`. See [`GCRational::gcd`] for
+    /// how this is defined for non-integers.
+    ///
+    /// # Safety
+    /// `self` and `rhs` must be valid objects in `gc`
+    pub unsafe fn gcd(self, gc: &GarbageCollector, rhs: Self) -> Self {
+        if let (GetGCNumber::Inline(lhs), GetGCNumber::Inline(rhs)) = (self.get(), rhs.get()) {
+            let gcd = usize_gcd(lhs.unsigned_abs(), rhs.unsigned_abs());
+
+            // This can't overflow because `gcd <= max(|lhs|, |rhs|) <= 2^(isize::BITS - 2)`
+            return Self::from_isize_or_rational(gc, gcd as isize);
+        }
+
+        unsafe { Self::from_rational(gc, self.to_rational(gc).gcd(gc, rhs.to_rational(gc))) }
+    }
+
+    /// Calculates the least common multiple of `self` and `rhs`. See [`GCRational::lcm`] for how
+    /// this is defined for non-integers.
+    ///
+    /// # Safety
+    /// `self` and `rhs` must be valid objects in `gc`
+    pub unsafe fn lcm(self, gc: &GarbageCollector, rhs: Self) -> Self {
+        if let (GetGCNumber::Inline(lhs), GetGCNumber::Inline(rhs)) = (self.get(), rhs.get()) {
+            let (lhs, rhs) = (lhs.unsigned_abs(), rhs.unsigned_abs());
+
+            if lhs == 0 || rhs == 0 {
+                return Self::from_isize(0).unwrap();
+            }
+
+            if let Some(lcm) = (lhs / usize_gcd(lhs, rhs)).checked_mul(rhs)
+                && let Ok(lcm) = isize::try_from(lcm)
+            {
+                return Self::from_isize_or_rational(gc, lcm);
+            }
+        }
+
+        unsafe { Self::from_rational(gc, self.to_rational(gc).lcm(gc, rhs.to_rational(gc))) }
+    }
+
+    /// Renders `self` as a decimal (eg `0.25` or `0.(3)`). See [`DecimalFormat`] for more info.
+    ///
+    /// # Safety
+    /// `self` must be a valid object in `gc`
+    pub unsafe fn to_decimal_string(self, gc: &GarbageCollector, format: DecimalFormat) -> String {
+        match self.get() {
+            GetGCNumber::Inline(int) => {
+                format_decimal(int < 0, &[int.unsigned_abs() as limb_t], &[1], format)
+            }
+            GetGCNumber::Rational(rat) => unsafe { rat.to_decimal_string(gc, format) },
         }
     }
 
     fn get(&self) -> GetGCNumber {
-        if self.value.get() & 1usize.rotate_right(1) != 0 {
-            GetGCNumber::Inline(self.value.get() & !1usize.rotate_right(1))
+        if self.value.get() & Self::INLINE_TAG != 0 {
+            // Sign-extend the remaining bits
+            GetGCNumber::Inline(((self.value.get() << 1) as isize) >> 1)
         } else {
             GetGCNumber::Rational(GCRational::from_raw(self.value))
         }
     }
 }
 
+impl<'c> GC<'c, GCNumber> {
+    /// Converts `self` to an `i64`. Returns `None` if `self` is not an integer or is out of range.
+    /// Use `i64::try_from` to find out why the conversion failed.
+    pub fn to_i64(self) -> Option<i64> {
+        i64::try_from(self).ok()
+    }
+
+    /// Converts `self` to a `u64`. Returns `None` if `self` is not an integer or is out of range.
+    /// Use `u64::try_from` to find out why the conversion failed.
+    pub fn to_u64(self) -> Option<u64> {
+        u64::try_from(self).ok()
+    }
+
+    /// Converts `self` to a `u128`. Returns `None` if `self` is not an integer or is out of range.
+    /// Use `u128::try_from` to find out why the conversion failed.
+    pub fn to_u128(self) -> Option<u128> {
+        u128::try_from(self).ok()
+    }
+
+    /// Converts `self` to the nearest `f64`. Ties are rounded to the value with an even mantissa.
+    ///
+    /// Fails with [`ConversionError::OutOfRange`] if the magnitude of `self` rounds to a value
+    /// larger than `f64::MAX`. Values too small for an `f64` are rounded to zero.
+    pub fn to_f64(self) -> Result<f64, ConversionError> {
+        self.with_limbs(convert::to_f64)
+    }
+
+    /// Renders `self` with exactly `scale` digits after the decimal point (eg `2.50` for `2.5` with
+    /// a scale of two).
+    ///
+    /// Fails with [`ConversionError::Inexact`] if `self` cannot be written exactly with that many
+    /// digits. Use [`GCNumber::to_decimal_string`] with [`DecimalFormat::Rounded`] to round
+    /// instead.
+    pub fn to_fixed_point_string(self, scale: usize) -> Result<String, ConversionError> {
+        self.with_limbs(|is_negative, numerator, denominator| {
+            convert::to_fixed_point_string(is_negative, numerator, denominator, scale)
+        })
+    }
+
+    /// Gets the sign and magnitude of `self` if it is an integer
+    fn integer_parts(self) -> Result<(bool, u128), ConversionError> {
+        self.with_limbs(|is_negative, numerator, denominator| {
+            Ok((
+                is_negative,
+                convert::integer_magnitude(numerator, denominator)?,
+            ))
+        })
+    }
+
+    fn with_limbs<R>(self, f: impl FnOnce(bool, &[limb_t], &[limb_t]) -> R) -> R {
+        match self.raw().get() {
+            GetGCNumber::Inline(int) => f(int < 0, &[int.unsigned_abs() as limb_t], &[1]),
+            GetGCNumber::Rational(rat) => unsafe { rat.with_limbs(self.gc(), f) },
+        }
+    }
+}
+
+impl TryFrom<GC<'_, GCNumber>> for i64 {
+    type Error = ConversionError;
+
+    fn try_from(value: GC<'_, GCNumber>) -> Result<Self, Self::Error> {
+        let (is_negative, magnitude) = value.integer_parts()?;
+        let magnitude = u64::try_from(magnitude).map_err(|_| ConversionError::OutOfRange)?;
+
+        if is_negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+        .ok_or(ConversionError::OutOfRange)
+    }
+}
+
+impl TryFrom<GC<'_, GCNumber>> for i128 {
+    type Error = ConversionError;
+
+    fn try_from(value: GC<'_, GCNumber>) -> Result<Self, Self::Error> {
+        let (is_negative, magnitude) = value.integer_parts()?;
+
+        if is_negative {
+            0i128.checked_sub_unsigned(magnitude)
+        } else {
+            i128::try_from(magnitude).ok()
+        }
+        .ok_or(ConversionError::OutOfRange)
+    }
+}
+
+impl TryFrom<GC<'_, GCNumber>> for u64 {
+    type Error = ConversionError;
+
+    fn try_from(value: GC<'_, GCNumber>) -> Result<Self, Self::Error> {
+        u128::try_from(value)
+            .and_then(|value| u64::try_from(value).map_err(|_| ConversionError::OutOfRange))
+    }
+}
+
+impl TryFrom<GC<'_, GCNumber>> for u128 {
+    type Error = ConversionError;
+
+    fn try_from(value: GC<'_, GCNumber>) -> Result<Self, Self::Error> {
+        match value.integer_parts()? {
+            (true, magnitude) if magnitude != 0 => Err(ConversionError::OutOfRange),
+            (_, magnitude) => Ok(magnitude),
+        }
+    }
+}
+
 impl From<GCRational> for GCNumber {
     fn from(value: GCRational) -> Self {
         Self {
@@ -112,6 +481,19 @@ unsafe impl GCPtr for GCNumber {
         }
     }
 
+    unsafe fn gc_trace(self, tracer: &mut HeapTracer) {
+        if let GetGCNumber::Rational(rat) = self.get() {
+            unsafe { rat.gc_trace(tracer) };
+        }
+    }
+
+    #[cfg(feature = "verify-heap")]
+    unsafe fn gc_verify(self, verifier: &mut crate::gc::verify::HeapVerifier) {
+        if let GetGCNumber::Rational(rat) = self.get() {
+            unsafe { rat.gc_verify(verifier) };
+        }
+    }
+
     unsafe fn to_gc_root_entry(self, _gc: &GarbageCollector) -> GCRootInfo {
         unsafe fn copy_fn(data: NonZeroUsize, gc: &GarbageCollector) -> NonZeroUsize {
             let old = GCNumber {
@@ -123,8 +505,18 @@ unsafe impl GCPtr for GCNumber {
             new.value
         }
 
+        unsafe fn trace_fn(data: NonZeroUsize, tracer: &mut HeapTracer) {
+            let number = GCNumber {
+                value: data,
+                _phantomdata: PhantomData,
+            };
+
+            unsafe { number.gc_trace(tracer) };
+        }
+
         GCRootInfo {
             copy_fn,
+            trace_fn,
             data_ptr: self.value,
         }
     }
@@ -168,3 +560,11 @@ impl GCEq<GCNumber> for GCNumber {
         }
     }
 }
+
+fn usize_gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
@@ -1,6 +1,6 @@
 use std::{cmp::Ordering, marker::PhantomData, num::NonZeroUsize};
 
-use gmp_mpfr_sys::gmp::{limb_t, mpn_gcd, mpn_tdiv_qr, size_t};
+use super::mpn::{limb_t, mpn_divexact, mpn_gcd, size_t};
 
 use crate::{
     error::{PartialSpanned, parse::PDResult},
@@ -8,14 +8,17 @@ use crate::{
         GCBuffer, GCDebug, GCEq, GCPtr, GCWrap, GarbageCollector,
         math::{literal_type, num_integer_digits, strip_integer_zeroes},
         primitives::math::{
-            Digit, PowerOfTenFactorization, decimal_literal_info, strip_decimal_zeroes,
-            uint::GCUInt,
+            DecimalFormat, Digit, PowerOfTenFactorization, decimal_literal_info, split_exponent,
+            strip_decimal_zeroes, uint::GCUInt,
         },
     },
     parser,
 };
 
-use super::NumLiteralType;
+use super::{
+    ArithmeticError, DivisionMode, NumLiteralType, RoundingMode, convert::u128_to_limbs,
+    decimal::format_decimal,
+};
 
 /// A garbage-collected, infinite precision rational number.
 ///
@@ -24,7 +27,7 @@ use super::NumLiteralType;
 /// zero. In which case, it there should be exactly one limb.
 ///
 /// Pointed to data:
-/// ```
+/// ```text
 /// union {
 ///     struct has_value {
 ///         usize {
@@ -109,10 +112,70 @@ impl GCRational {
         gc: &GarbageCollector,
         literal: PartialSpanned<&str>,
     ) -> PDResult<Self> {
-        match literal_type(literal)? {
-            NumLiteralType::Decimal => Self::parse_from_decimal_literal(gc, literal),
+        let literal_type = literal_type(literal)?;
+
+        if let Some(bits_per_digit) = literal_type.bits_per_digit() {
+            return Self::parse_from_pow2_radix_literal(gc, literal, bits_per_digit);
+        }
+
+        match literal_type {
             NumLiteralType::Fraction => Self::parse_from_fraction_literal(gc, literal),
+            _ => Self::parse_from_decimal_literal(gc, literal),
+        }
+    }
+
+    /// Parses a hexadecimal, octal, or binary literal (eg `0xFF`)
+    fn parse_from_pow2_radix_literal(
+        gc: &GarbageCollector,
+        literal: PartialSpanned<&str>,
+        bits_per_digit: u32,
+    ) -> PDResult<Self> {
+        let radix = 1 << bits_per_digit;
+        let digits = &literal[2..];
+
+        let mut num_digits = 0;
+
+        for c in digits.chars() {
+            match c {
+                '_' => {}
+                _ if c.is_digit(radix) => num_digits += 1,
+                _ => return Err(parser::error::unexpected_character_in_number(c, literal.1)),
+            }
+        }
+
+        let metadata_ptr = gc.nursery.len();
+        gc.nursery
+            .set_len(metadata_ptr + Self::METADATA_SIZE_BLOCKS);
+
+        let numerator = unsafe {
+            GCUInt::parse_from_pow2_radix_digits(
+                gc,
+                digits
+                    .chars()
+                    .filter_map(|c| c.to_digit(radix).map(limb_t::from)),
+                bits_per_digit,
+                num_digits,
+            )
+        };
+
+        let denominator = GCBuffer::<limb_t>::new_uninit(gc, 1);
+        unsafe { denominator.as_mut_ptr(gc).write(1) };
+
+        let metadata = RationalMetadata {
+            numerator_len: numerator.data.len(),
+            is_negative: false,
+            denominator_len: denominator.len(),
+        };
+
+        unsafe {
+            gc.block_ptr(metadata_ptr)
+                .cast::<[usize; 2]>()
+                .write(metadata.to_raw_unchecked());
         }
+
+        Ok(Self::from_raw(unsafe {
+            NonZeroUsize::new_unchecked(metadata_ptr)
+        }))
     }
 
     fn parse_from_fraction_literal(
@@ -135,8 +198,8 @@ impl GCRational {
         let numerator_len = num_integer_digits(numerator, literal.1)?;
         let denominator_len = num_integer_digits(denominator, literal.1)?;
 
-        let metadata_ptr = gc.from_space.len();
-        gc.from_space
+        let metadata_ptr = gc.nursery.len();
+        gc.nursery
             .set_len(metadata_ptr + Self::METADATA_SIZE_BLOCKS);
 
         let numerator = unsafe {
@@ -190,12 +253,16 @@ impl GCRational {
         gc: &GarbageCollector,
         decimal: PartialSpanned<&str>,
     ) -> PDResult<Self> {
+        let (decimal, exponent) = split_exponent(decimal)?;
         let decimal = decimal.map(strip_decimal_zeroes);
 
         let (num_digits_after_decimal_point, num_digits) = decimal_literal_info(decimal)?;
 
-        let metadata_ptr = gc.from_space.len();
-        gc.from_space
+        // The value of the literal is `digits * 10^pow_10`
+        let pow_10 = exponent - num_digits_after_decimal_point as isize;
+
+        let metadata_ptr = gc.nursery.len();
+        gc.nursery
             .set_len(metadata_ptr + Self::METADATA_SIZE_BLOCKS);
 
         let mut numerator = unsafe {
@@ -208,9 +275,20 @@ impl GCRational {
             )
         };
 
+        if pow_10 > 0 {
+            let multiplier = PowerOfTenFactorization {
+                pow_5: pow_10 as usize,
+                pow_2: pow_10 as usize,
+            };
+
+            unsafe { numerator.mul_pow10_factorization_at_end(gc, multiplier) };
+        }
+
+        let denominator_pow_10 = pow_10.min(0).unsigned_abs();
+
         let mut denominator = PowerOfTenFactorization {
-            pow_5: num_digits_after_decimal_point,
-            pow_2: num_digits_after_decimal_point,
+            pow_5: denominator_pow_10,
+            pow_2: denominator_pow_10,
         };
 
         unsafe { numerator.reduce_pow_10_at_end(gc, &mut denominator) };
@@ -234,6 +312,173 @@ impl GCRational {
         }))
     }
 
+    /// Creates a reduced `GCRational` from a numerator and a denominator.
+    ///
+    /// # Panics
+    /// Panics if `denominator` is zero.
+    pub fn from_u64_fraction(gc: &GarbageCollector, numerator: u64, denominator: u64) -> Self {
+        Self::from_limbs(
+            gc,
+            false,
+            &u128_to_limbs(numerator.into()),
+            &u128_to_limbs(denominator.into()),
+        )
+    }
+
+    /// Creates a reduced `GCRational` from its sign and the limbs of its numerator and
+    /// denominator. The limbs may have leading zeroes.
+    ///
+    /// # Panics
+    /// Panics if `denominator` is zero.
+    pub(super) fn from_limbs(
+        gc: &GarbageCollector,
+        is_negative: bool,
+        numerator: &[limb_t],
+        denominator: &[limb_t],
+    ) -> Self {
+        assert!(denominator.iter().any(|limb| *limb != 0));
+
+        let ptr = gc.nursery.len();
+        gc.nursery.set_len(ptr + Self::METADATA_SIZE_BLOCKS);
+
+        let [numerator, denominator] = [numerator, denominator].map(|limbs| {
+            // Leading zeroes are removed, but a zero numerator still needs a limb
+            let len = limbs
+                .iter()
+                .rposition(|limb| *limb != 0)
+                .map_or(1, |i| i + 1);
+            let limbs = limbs.get(..len).unwrap_or(&[0]);
+
+            let buffer = GCBuffer::<limb_t>::new_uninit(gc, limbs.len());
+
+            for (i, limb) in limbs.iter().enumerate() {
+                unsafe { buffer.element_ptr(gc, i).write(*limb) };
+            }
+
+            buffer
+        });
+
+        unsafe {
+            gc.block_ptr(ptr).cast::<[usize; 2]>().write(
+                RationalMetadata {
+                    numerator_len: numerator.len(),
+                    is_negative,
+                    denominator_len: denominator.len(),
+                }
+                .to_raw_unchecked(),
+            )
+        }
+
+        let rational = Self::from_raw(unsafe { NonZeroUsize::new_unchecked(ptr) });
+
+        unsafe { rational.reduce_from_end(gc) };
+
+        rational
+    }
+
+    /// Multiplies `self` by `rhs`. The reduced product is allocated at the end of the GC heap.
+    ///
+    /// # Safety
+    /// `self` and `rhs` must be valid objects in `gc`
+    pub unsafe fn mul(self, gc: &GarbageCollector, rhs: Self) -> Self {
+        let metadata = unsafe { self.metadata(gc) };
+        let [numerator, denominator] = self
+            .numerator_and_denominator_from_metadata(metadata)
+            .map(GCUInt::from);
+
+        let rhs_metadata = unsafe { rhs.metadata(gc) };
+        let [rhs_numerator, rhs_denominator] = rhs
+            .numerator_and_denominator_from_metadata(rhs_metadata)
+            .map(GCUInt::from);
+
+        let ptr = gc.nursery.len();
+        gc.nursery.set_len(ptr + Self::METADATA_SIZE_BLOCKS);
+
+        let product_numerator =
+            GCBuffer::<limb_t>::new_uninit(gc, numerator.data.len() + rhs_numerator.data.len());
+        let product_denominator =
+            GCBuffer::<limb_t>::new_uninit(gc, denominator.data.len() + rhs_denominator.data.len());
+
+        unsafe {
+            GCUInt::mul_into(gc, product_numerator, numerator, rhs_numerator);
+            GCUInt::mul_into(gc, product_denominator, denominator, rhs_denominator);
+
+            gc.block_ptr(ptr).cast::<[usize; 2]>().write(
+                RationalMetadata {
+                    numerator_len: product_numerator.len(),
+                    is_negative: metadata.is_negative != rhs_metadata.is_negative,
+                    denominator_len: product_denominator.len(),
+                }
+                .to_raw_unchecked(),
+            );
+        }
+
+        let rational = Self::from_raw(unsafe { NonZeroUsize::new_unchecked(ptr) });
+
+        unsafe { rational.reduce_from_end(gc) };
+
+        rational
+    }
+
+    /// Creates a reduced `GCRational` from its sign, numerator, and denominator. The numerator and
+    /// denominator are copied, so they may be anywhere in the GC heap.
+    ///
+    /// # Safety
+    /// `numerator` and `denominator` must be valid, and `denominator` must not be zero
+    pub(super) unsafe fn from_parts(
+        gc: &GarbageCollector,
+        is_negative: bool,
+        numerator: GCUInt,
+        denominator: GCUInt,
+    ) -> Self {
+        let [numerator, denominator] =
+            [numerator, denominator].map(|value| unsafe { value.without_leading_zero_limbs(gc) });
+
+        let ptr = gc.nursery.len();
+        gc.nursery.set_len(ptr + Self::METADATA_SIZE_BLOCKS);
+
+        unsafe {
+            let numerator = numerator.copy(gc);
+            let denominator = denominator.copy(gc);
+
+            gc.block_ptr(ptr).cast::<[usize; 2]>().write(
+                RationalMetadata {
+                    numerator_len: numerator.data.len(),
+                    is_negative,
+                    denominator_len: denominator.data.len(),
+                }
+                .to_raw_unchecked(),
+            );
+        }
+
+        let rational = Self::from_raw(unsafe { NonZeroUsize::new_unchecked(ptr) });
+
+        unsafe { rational.reduce_from_end(gc) };
+
+        rational
+    }
+
+    pub fn from_isize(gc: &GarbageCollector, value: isize) -> Self {
+        let ptr = gc.nursery.len();
+        gc.nursery.set_len(ptr + Self::METADATA_SIZE_BLOCKS);
+
+        GCUInt::from_limb(gc, value.unsigned_abs() as limb_t);
+        GCUInt::from_limb(gc, 1);
+
+        unsafe {
+            gc.block_ptr(ptr).cast::<[usize; 2]>().write(
+                RationalMetadata {
+                    numerator_len: 1,
+                    is_negative: value < 0,
+                    denominator_len: 1,
+                }
+                .to_raw_unchecked(),
+            )
+        }
+
+        Self::from_raw(unsafe { NonZeroUsize::new_unchecked(ptr) })
+    }
+
     /// Parses a `GCRational` from a numerator and denominator. This will not reduce the fraction.
     /// Panics on failiure. This is solely intended for writing tests.
     pub(crate) fn parse_from_numerator_and_denominator_panicking(
@@ -241,8 +486,8 @@ impl GCRational {
         numerator: &str,
         denominator: Option<&str>,
     ) -> Self {
-        let ptr = gc.from_space.len();
-        gc.from_space.set_len(ptr + Self::METADATA_SIZE_BLOCKS);
+        let ptr = gc.nursery.len();
+        gc.nursery.set_len(ptr + Self::METADATA_SIZE_BLOCKS);
 
         let numerator = unsafe {
             GCUInt::parse_from_digits(
@@ -297,6 +542,203 @@ impl GCRational {
         numerator.as_usize(gc)
     }
 
+    /// Renders `self` as a decimal (eg `0.25` or `0.(3)`). See [`DecimalFormat`] for more info.
+    ///
+    /// # Safety
+    /// `self` must be a valid object in `gc`
+    pub unsafe fn to_decimal_string(self, gc: &GarbageCollector, format: DecimalFormat) -> String {
+        unsafe {
+            self.with_limbs(gc, |is_negative, numerator, denominator| {
+                format_decimal(is_negative, numerator, denominator, format)
+            })
+        }
+    }
+
+    /// Calls `f` with the sign, numerator limbs, and denominator limbs of `self`
+    ///
+    /// # Safety
+    /// `self` must be a valid object in `gc`, and `f` must not allocate in `gc`
+    pub(super) unsafe fn with_limbs<R>(
+        self,
+        gc: &GarbageCollector,
+        f: impl FnOnce(bool, &[limb_t], &[limb_t]) -> R,
+    ) -> R {
+        let metadata = unsafe { self.metadata(gc) };
+        let [numerator, denominator] = self.numerator_and_denominator_from_metadata(metadata);
+
+        unsafe {
+            f(
+                metadata.is_negative,
+                numerator.as_slice(gc),
+                denominator.as_slice(gc),
+            )
+        }
+    }
+
+    pub unsafe fn as_isize(&self, gc: &GarbageCollector) -> Option<isize> {
+        let (is_negative, numerator, denominator) = unsafe { self.parts(gc) };
+
+        if unsafe { GCWrap::new(&denominator, gc) != (1 as limb_t) } {
+            return None;
+        }
+
+        let magnitude = numerator.as_usize(gc)?;
+
+        if is_negative {
+            0isize.checked_sub_unsigned(magnitude)
+        } else {
+            isize::try_from(magnitude).ok()
+        }
+    }
+
+    pub unsafe fn is_integer(self, gc: &GarbageCollector) -> bool {
+        let (_, _, denominator) = unsafe { self.parts(gc) };
+
+        unsafe { GCWrap::new(&denominator, gc) == (1 as limb_t) }
+    }
+
+    /// Calculates `-self`. The result is allocated at the end of the GC heap.
+    pub unsafe fn neg(self, gc: &GarbageCollector) -> Self {
+        let (is_negative, numerator, denominator) = unsafe { self.parts(gc) };
+
+        unsafe { Self::from_parts(gc, !is_negative, numerator, denominator) }
+    }
+
+    /// Calculates `|self|`. The result is allocated at the end of the GC heap.
+    pub unsafe fn abs(self, gc: &GarbageCollector) -> Self {
+        let (_, numerator, denominator) = unsafe { self.parts(gc) };
+
+        unsafe { Self::from_parts(gc, false, numerator, denominator) }
+    }
+
+    /// Compares `self` with `rhs`. This may allocate temporary values in the GC heap.
+    pub unsafe fn cmp(self, gc: &GarbageCollector, rhs: Self) -> Ordering {
+        let (is_negative, numerator, denominator) = unsafe { self.parts(gc) };
+        let (rhs_is_negative, rhs_numerator, rhs_denominator) = unsafe { rhs.parts(gc) };
+
+        // Zero is never negative, so we only need to compare magnitudes if the signs are the same
+        match (is_negative, rhs_is_negative) {
+            (false, true) => return Ordering::Greater,
+            (true, false) => return Ordering::Less,
+            _ => {}
+        }
+
+        let ordering = unsafe {
+            let lhs = numerator.mul(gc, rhs_denominator);
+            let rhs = rhs_numerator.mul(gc, denominator);
+
+            lhs.cmp(gc, rhs)
+        };
+
+        if is_negative {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    /// Rounds `self` to an integer. The result is allocated at the end of the GC heap.
+    pub unsafe fn round(self, gc: &GarbageCollector, mode: RoundingMode) -> Self {
+        let (is_negative, numerator, denominator) = unsafe { self.parts(gc) };
+
+        unsafe {
+            let (mut quotient, remainder) = numerator.div_rem(gc, denominator);
+
+            if !remainder.is_zero(gc)
+                && mode.rounds_away_from_zero(is_negative, quotient.is_odd(gc), || {
+                    remainder.add(gc, remainder).cmp(gc, denominator)
+                })
+            {
+                quotient = quotient.add(gc, GCUInt::from_limb(gc, 1));
+            }
+
+            Self::from_parts(gc, is_negative, quotient, GCUInt::from_limb(gc, 1))
+        }
+    }
+
+    /// Calculates the integer quotient and the remainder of `self / rhs`. The results are
+    /// allocated in the GC heap.
+    pub unsafe fn div_rem(
+        self,
+        gc: &GarbageCollector,
+        rhs: Self,
+        mode: DivisionMode,
+    ) -> Result<(Self, Self), ArithmeticError> {
+        let (is_negative, numerator, denominator) = unsafe { self.parts(gc) };
+        let (rhs_is_negative, rhs_numerator, rhs_denominator) = unsafe { rhs.parts(gc) };
+
+        if unsafe { rhs_numerator.is_zero(gc) } {
+            return Err(ArithmeticError::DivisionByZero);
+        }
+
+        let quotient_is_negative = is_negative != rhs_is_negative;
+
+        unsafe {
+            // `self / rhs = (numerator * rhs_denominator) / (denominator * rhs_numerator)`. The
+            // remainder of this division is over `denominator * rhs_denominator`.
+            let dividend = numerator.mul(gc, rhs_denominator);
+            let divisor = denominator.mul(gc, rhs_numerator);
+            let remainder_denominator = denominator.mul(gc, rhs_denominator);
+
+            let (quotient, remainder) = dividend.div_rem(gc, divisor);
+
+            if mode == DivisionMode::Floored && quotient_is_negative && !remainder.is_zero(gc) {
+                // Round the quotient towards negative infinity. The remainder will then have the
+                // sign of `rhs`
+                let quotient = quotient.add(gc, GCUInt::from_limb(gc, 1));
+                let remainder = divisor.sub(gc, remainder);
+
+                return Ok((
+                    Self::from_parts(gc, true, quotient, GCUInt::from_limb(gc, 1)),
+                    Self::from_parts(gc, rhs_is_negative, remainder, remainder_denominator),
+                ));
+            }
+
+            Ok((
+                Self::from_parts(gc, quotient_is_negative, quotient, GCUInt::from_limb(gc, 1)),
+                Self::from_parts(gc, is_negative, remainder, remainder_denominator),
+            ))
+        }
+    }
+
+    /// Calculates the greatest common divisor of `self` and `rhs`, which is the largest rational
+    /// `d` such that `self / d` and `rhs / d` are both integers. The result is never negative.
+    pub unsafe fn gcd(self, gc: &GarbageCollector, rhs: Self) -> Self {
+        let (_, numerator, denominator) = unsafe { self.parts(gc) };
+        let (_, rhs_numerator, rhs_denominator) = unsafe { rhs.parts(gc) };
+
+        unsafe {
+            let gcd_numerator = numerator.gcd(gc, rhs_numerator);
+            let gcd_denominator = denominator.lcm(gc, rhs_denominator);
+
+            Self::from_parts(gc, false, gcd_numerator, gcd_denominator)
+        }
+    }
+
+    /// Calculates the least common multiple of `self` and `rhs`, which is the smallest
+    /// non-negative rational `m` such that `m / self` and `m / rhs` are both integers.
+    pub unsafe fn lcm(self, gc: &GarbageCollector, rhs: Self) -> Self {
+        let (_, numerator, denominator) = unsafe { self.parts(gc) };
+        let (_, rhs_numerator, rhs_denominator) = unsafe { rhs.parts(gc) };
+
+        unsafe {
+            let lcm_numerator = numerator.lcm(gc, rhs_numerator);
+            let lcm_denominator = denominator.gcd(gc, rhs_denominator);
+
+            Self::from_parts(gc, false, lcm_numerator, lcm_denominator)
+        }
+    }
+
+    /// Gets the sign, numerator, and denominator of `self`
+    unsafe fn parts(self, gc: &GarbageCollector) -> (bool, GCUInt, GCUInt) {
+        let metadata = unsafe { self.metadata(gc) };
+        let [numerator, denominator] = self
+            .numerator_and_denominator_from_metadata(metadata)
+            .map(GCUInt::from);
+
+        (metadata.is_negative, numerator, denominator)
+    }
+
     pub(in crate::gc::primitives) unsafe fn deallocate_from_end(self, gc: &GarbageCollector) {
         let [numerator, denominator] = unsafe { self.numerator_and_denominator(gc) };
 
@@ -305,11 +747,11 @@ impl GCRational {
 
         debug_assert_eq!(
             self.ptr.get() + total_len,
-            gc.from_space.len(),
+            gc.nursery.len(),
             "deallocate_from_end can only be called if the current allocation is the last allocation made"
         );
 
-        gc.from_space.set_len(self.ptr.get());
+        gc.nursery.set_len(self.ptr.get());
     }
 
     unsafe fn numerator_and_denominator(&self, gc: &GarbageCollector) -> [GCBuffer<limb_t>; 2] {
@@ -443,8 +885,8 @@ impl GCRational {
             GCUInt::from(gcd_buf).trim_leading_zero_limbs_at_end(gc);
         }
 
-        // Now we need to divide, but the buffers cannot overlap (aside from the remainder and the
-        // dividend), so we need to copy our numerator and denominator into tmp1 and tmp2 again.
+        // Now we need to divide, but the buffers cannot overlap, so we need to copy our numerator and
+        // denominator into tmp1 and tmp2 again.
 
         unsafe {
             std::ptr::copy_nonoverlapping(
@@ -458,10 +900,8 @@ impl GCRational {
                 tmp2.data.len(),
             );
 
-            mpn_tdiv_qr(
+            mpn_divexact(
                 numerator.data.as_mut_ptr(gc),
-                tmp1.data.as_mut_ptr(gc),
-                0,
                 tmp1.data.as_ptr(gc),
                 tmp1.data.len() as size_t,
                 gcd_buf.as_ptr(gc),
@@ -472,10 +912,8 @@ impl GCRational {
                 .data
                 .set_length(tmp1.data.len() - gcd_buf.len() + 1);
 
-            mpn_tdiv_qr(
+            mpn_divexact(
                 denominator.data.as_mut_ptr(gc),
-                tmp2.data.as_mut_ptr(gc),
-                0,
                 tmp2.data.as_ptr(gc),
                 tmp2.data.len() as size_t,
                 gcd_buf.as_ptr(gc),
@@ -506,7 +944,7 @@ impl GCRational {
                 denominator.data.len(),
             );
 
-            gc.from_space
+            gc.nursery
                 .set_len(new_denominator_ptr + denominator.size_blocks());
 
             // Adjust metadata to use new sizes
@@ -529,12 +967,11 @@ unsafe impl GCPtr for GCRational {
     unsafe fn gc_copy(self, gc: &crate::gc::GarbageCollector) -> Self {
         const FORWARD_BIT: usize = 1usize.rotate_right(1);
 
-        let raw_metadata = unsafe {
-            gc.from_space
-                .block_ptr(self.ptr)
-                .cast::<[usize; 2]>()
-                .read()
-        };
+        if !gc.is_evacuating(self.ptr.get()) {
+            return self;
+        }
+
+        let raw_metadata = unsafe { gc.block_ptr(self.ptr).cast::<[usize; 2]>().read() };
 
         if raw_metadata[0] & FORWARD_BIT != 0 {
             let forward = raw_metadata[0] & !FORWARD_BIT;
@@ -547,34 +984,81 @@ unsafe impl GCPtr for GCRational {
         let [old_numerator_buf, old_denominator_buf] =
             self.numerator_and_denominator_from_metadata(metadata);
 
-        let new_ptr = gc.to_space.len();
-        gc.to_space.set_len(new_ptr + Self::METADATA_SIZE_BLOCKS);
+        let to_space = gc.copy_target();
+
+        let new_ptr = to_space.len();
+        to_space.set_len(new_ptr + Self::METADATA_SIZE_BLOCKS);
 
         let new_numerator_buf =
-            GCBuffer::<limb_t>::new_uninit_in_space(&gc.to_space, old_numerator_buf.len());
+            GCBuffer::<limb_t>::new_uninit_in_space(to_space, old_numerator_buf.len());
         let new_denominator_buf =
-            GCBuffer::<limb_t>::new_uninit_in_space(&gc.to_space, old_denominator_buf.len());
+            GCBuffer::<limb_t>::new_uninit_in_space(to_space, old_denominator_buf.len());
 
-        let new_metadata_ptr = gc.to_space.block_ptr(new_ptr).cast::<[usize; 2]>();
+        let new_metadata_ptr = to_space.block_ptr(new_ptr).cast::<[usize; 2]>();
 
         unsafe { new_metadata_ptr.write(raw_metadata) };
 
         unsafe {
             std::ptr::copy_nonoverlapping(
-                old_numerator_buf.as_ptr_in_space(&gc.from_space),
-                new_numerator_buf.as_mut_ptr_in_space(&gc.to_space),
+                old_numerator_buf.as_ptr(gc),
+                new_numerator_buf.as_mut_ptr_in_space(to_space),
                 new_numerator_buf.len(),
             );
 
             std::ptr::copy_nonoverlapping(
-                old_denominator_buf.as_ptr_in_space(&gc.from_space),
-                new_denominator_buf.as_mut_ptr_in_space(&gc.to_space),
+                old_denominator_buf.as_ptr(gc),
+                new_denominator_buf.as_mut_ptr_in_space(to_space),
                 new_denominator_buf.len(),
             );
         };
 
         Self::from_raw(unsafe { NonZeroUsize::new_unchecked(new_ptr) })
     }
+
+    unsafe fn gc_trace(self, tracer: &mut crate::gc::dump::HeapTracer) {
+        let raw_metadata = unsafe { tracer.gc().block_ptr(self.ptr).cast::<[usize; 2]>().read() };
+
+        let [numerator, denominator] = self.numerator_and_denominator_from_metadata(
+            RationalMetadata::from_raw_unchecked(raw_metadata),
+        );
+
+        tracer.object(
+            self.ptr.get(),
+            std::any::type_name::<Self>(),
+            Self::METADATA_SIZE_BLOCKS + numerator.size_blocks() + denominator.size_blocks(),
+            |_, _| {},
+        );
+    }
+
+    #[cfg(feature = "verify-heap")]
+    unsafe fn gc_verify(self, verifier: &mut crate::gc::verify::HeapVerifier) {
+        if !verifier.check_object(self.ptr.get(), Self::METADATA_SIZE_BLOCKS) {
+            return;
+        }
+
+        verifier.check_not_forwarded(self.ptr.get());
+
+        let raw_metadata = unsafe {
+            verifier
+                .gc()
+                .block_ptr(self.ptr)
+                .cast::<[usize; 2]>()
+                .read()
+        };
+
+        if raw_metadata[0] == 0 || raw_metadata[1] & (usize::MAX >> 1) == 0 {
+            verifier.fail("rational has an empty numerator or denominator");
+        }
+
+        let [numerator, denominator] = self.numerator_and_denominator_from_metadata(
+            RationalMetadata::from_raw_unchecked(raw_metadata),
+        );
+
+        verifier.check_object(
+            self.ptr.get(),
+            Self::METADATA_SIZE_BLOCKS + numerator.size_blocks() + denominator.size_blocks(),
+        );
+    }
 }
 
 impl GCDebug for GCRational {
@@ -592,14 +1076,14 @@ impl GCDebug for GCRational {
             write!(f, "-")?;
         }
 
-        let numerator = unsafe { numerator.to_naive_string(gc) };
+        let numerator = unsafe { numerator.to_decimal_string(gc) };
 
         write!(f, "{}", numerator)?;
 
         if unsafe { GCWrap::new(&denominator, gc) != (1 as limb_t) } {
             write!(f, "/")?;
 
-            let denominator = unsafe { denominator.to_naive_string(gc) };
+            let denominator = unsafe { denominator.to_decimal_string(gc) };
 
             write!(f, "{}", denominator)?
         }
@@ -626,6 +1110,12 @@ impl GCEq<GCRational> for GCRational {
     }
 }
 
+impl GCEq<isize> for GCRational {
+    unsafe fn gc_eq(&self, gc: &GarbageCollector, rhs: &isize) -> bool {
+        unsafe { self.as_isize(gc) }.is_some_and(|val| val == *rhs)
+    }
+}
+
 impl GCEq<usize> for GCRational {
     unsafe fn gc_eq(&self, gc: &GarbageCollector, rhs: &usize) -> bool {
         let metadata = unsafe { self.metadata(gc) };
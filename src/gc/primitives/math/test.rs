@@ -0,0 +1,552 @@
+use super::mpn::limb_t;
+use copyspan::Span;
+use proptest::prelude::*;
+
+use crate::{
+    error::PartialSpanned,
+    gc::{
+        GCNumber, GarbageCollector,
+        math::{
+            ArithmeticError, ConversionError, DecimalFormat, DivisionMode, RoundingMode,
+            decimal::format_decimal,
+        },
+        safety::GC,
+    },
+};
+
+fn decimal(literal: &str, format: DecimalFormat) -> String {
+    let gc = GarbageCollector::new();
+
+    let number =
+        GCNumber::parse_from_literal(&gc, PartialSpanned(literal, Span::from(0..0)), None).unwrap();
+
+    unsafe { number.to_decimal_string(&gc, format) }
+}
+
+fn rounded(literal: &str, precision: usize, rounding: RoundingMode) -> String {
+    decimal(
+        literal,
+        DecimalFormat::Rounded {
+            precision,
+            rounding,
+        },
+    )
+}
+
+#[test]
+fn finite_decimals() {
+    let format = DecimalFormat::default();
+
+    assert_eq!(decimal("0", format), "0");
+    assert_eq!(decimal("42", format), "42");
+    assert_eq!(decimal("1/4", format), "0.25");
+    assert_eq!(decimal("3.125", format), "3.125");
+    assert_eq!(decimal("1/1024", format), "0.0009765625");
+    assert_eq!(decimal("1e-30", format), "0.000000000000000000000000000001");
+    assert_eq!(
+        decimal("123456789012345678901234567890.5", format),
+        "123456789012345678901234567890.5"
+    );
+}
+
+#[test]
+fn repeating_decimals() {
+    let format = DecimalFormat::default();
+
+    assert_eq!(decimal("1/3", format), "0.(3)");
+    assert_eq!(decimal("1/6", format), "0.1(6)");
+    assert_eq!(decimal("22/7", format), "3.(142857)");
+    assert_eq!(decimal("1/12", format), "0.08(3)");
+    assert_eq!(decimal("7/74", format), "0.0(945)");
+    assert_eq!(decimal("1/81", format), "0.(012345679)");
+
+    // 1/97 has a period of 96 digits
+    assert_eq!(
+        decimal("1/97", DecimalFormat::Repeating { max_period: 95 }),
+        "1/97"
+    );
+    assert_eq!(
+        decimal("1/97", DecimalFormat::Repeating { max_period: 96 }),
+        "0.(010309278350515463917525773195876288659793814432989690721649484536082474226804123711340206185567)"
+    );
+}
+
+#[test]
+fn fractions() {
+    assert_eq!(decimal("1/3", DecimalFormat::Fraction), "1/3");
+    assert_eq!(decimal("10/4", DecimalFormat::Fraction), "2.5");
+    assert_eq!(decimal("7", DecimalFormat::Fraction), "7");
+}
+
+#[test]
+fn half_even_rounding() {
+    use RoundingMode::HalfEven;
+
+    assert_eq!(rounded("2/3", 3, HalfEven), "0.667");
+    assert_eq!(rounded("0.125", 2, HalfEven), "0.12");
+    assert_eq!(rounded("0.375", 2, HalfEven), "0.38");
+    assert_eq!(rounded("2.5", 0, HalfEven), "2");
+    assert_eq!(rounded("3.5", 0, HalfEven), "4");
+    assert_eq!(rounded("9.996", 2, HalfEven), "10.00");
+    assert_eq!(rounded("1", 3, HalfEven), "1.000");
+    assert_eq!(rounded("0.0001", 2, HalfEven), "0.00");
+}
+
+#[test]
+fn floor_and_ceil_rounding() {
+    use RoundingMode::{Ceil, Floor};
+
+    assert_eq!(rounded("2/3", 2, Floor), "0.66");
+    assert_eq!(rounded("2/3", 2, Ceil), "0.67");
+    assert_eq!(rounded("1/3", 0, Ceil), "1");
+    assert_eq!(rounded("0.5", 1, Floor), "0.5");
+    assert_eq!(rounded("0.5", 1, Ceil), "0.5");
+}
+
+#[test]
+fn negative_decimals() {
+    let limbs = |n: limb_t| [n];
+
+    let format = DecimalFormat::default();
+    assert_eq!(format_decimal(true, &limbs(1), &limbs(3), format), "-0.(3)");
+    assert_eq!(format_decimal(true, &limbs(5), &limbs(4), format), "-1.25");
+    assert_eq!(format_decimal(true, &limbs(0), &limbs(1), format), "0");
+    assert_eq!(
+        format_decimal(true, &limbs(1), &limbs(3), DecimalFormat::Fraction),
+        "-1/3"
+    );
+
+    let rounded = |n, d, rounding| {
+        let format = DecimalFormat::Rounded {
+            precision: 1,
+            rounding,
+        };
+
+        format_decimal(true, &limbs(n), &limbs(d), format)
+    };
+
+    assert_eq!(rounded(1, 3, RoundingMode::Floor), "-0.4");
+    assert_eq!(rounded(1, 3, RoundingMode::Ceil), "-0.3");
+    assert_eq!(rounded(1, 4, RoundingMode::HalfEven), "-0.2");
+    assert_eq!(rounded(1, 1000, RoundingMode::Ceil), "0.0");
+    assert_eq!(rounded(1, 1000, RoundingMode::Floor), "-0.1");
+}
+
+#[test]
+fn unreduced_fractions() {
+    let limbs = |n: limb_t| [n];
+
+    let format = DecimalFormat::default();
+    assert_eq!(
+        format_decimal(false, &limbs(2), &limbs(12), format),
+        "0.1(6)"
+    );
+    assert_eq!(
+        format_decimal(false, &limbs(30), &limbs(40), format),
+        "0.75"
+    );
+    assert_eq!(
+        format_decimal(true, &limbs(22), &limbs(14), format),
+        "-1.(571428)"
+    );
+    assert_eq!(format_decimal(false, &limbs(0), &limbs(12), format), "0");
+    assert_eq!(
+        format_decimal(false, &limbs(2), &limbs(12), DecimalFormat::Fraction),
+        "1/6"
+    );
+    assert_eq!(
+        format_decimal(false, &limbs(10), &limbs(4), DecimalFormat::Fraction),
+        "2.5"
+    );
+}
+
+/// Parses a number literal. A leading `-` negates the number.
+fn number(gc: &GarbageCollector, literal: &str) -> GCNumber {
+    let (is_negative, literal) = match literal.strip_prefix('-') {
+        Some(literal) => (true, literal),
+        None => (false, literal),
+    };
+
+    let number =
+        GCNumber::parse_from_literal(gc, PartialSpanned(literal, Span::from(0..0)), None).unwrap();
+
+    if is_negative {
+        unsafe { number.neg(gc) }
+    } else {
+        number
+    }
+}
+
+fn show(gc: &GarbageCollector, number: GCNumber) -> String {
+    unsafe { number.to_decimal_string(gc, DecimalFormat::Fraction) }
+}
+
+fn div_rem(lhs: &str, rhs: &str, mode: DivisionMode) -> Result<(String, String), ArithmeticError> {
+    let gc = GarbageCollector::new();
+
+    let (quotient, remainder) = unsafe { number(&gc, lhs).div_rem(&gc, number(&gc, rhs), mode)? };
+
+    Ok((show(&gc, quotient), show(&gc, remainder)))
+}
+
+fn assert_div_rem(lhs: &str, rhs: &str, mode: DivisionMode, quotient: &str, remainder: &str) {
+    assert_eq!(
+        div_rem(lhs, rhs, mode),
+        Ok((quotient.to_owned(), remainder.to_owned())),
+        "{lhs} / {rhs} ({mode:?})"
+    );
+}
+
+const BIG: &str = "340282366920938463463374607431768211456"; // 2^128
+const BIG_PLUS_ONE: &str = "340282366920938463463374607431768211457";
+
+#[test]
+fn floored_division() {
+    use DivisionMode::Floored;
+
+    assert_div_rem("7", "2", Floored, "3", "1");
+    assert_div_rem("-7", "2", Floored, "-4", "1");
+    assert_div_rem("7", "-2", Floored, "-4", "-1");
+    assert_div_rem("-7", "-2", Floored, "3", "-1");
+    assert_div_rem("6", "-3", Floored, "-2", "0");
+    assert_div_rem("7.5", "2", Floored, "3", "1.5");
+    assert_div_rem("-7.5", "2", Floored, "-4", "0.5");
+    assert_div_rem("1/3", "1/4", Floored, "1", "1/12");
+    assert_div_rem(
+        BIG_PLUS_ONE,
+        "2",
+        Floored,
+        "170141183460469231731687303715884105728",
+        "1",
+    );
+    assert_div_rem(
+        &format!("-{BIG}"),
+        "3",
+        Floored,
+        "-113427455640312821154458202477256070486",
+        "2",
+    );
+}
+
+#[test]
+fn truncated_division() {
+    use DivisionMode::Truncated;
+
+    assert_div_rem("7", "2", Truncated, "3", "1");
+    assert_div_rem("-7", "2", Truncated, "-3", "-1");
+    assert_div_rem("7", "-2", Truncated, "-3", "1");
+    assert_div_rem("-7", "-2", Truncated, "3", "-1");
+    assert_div_rem("-7.5", "2", Truncated, "-3", "-1.5");
+    assert_div_rem(
+        &format!("-{BIG}"),
+        "3",
+        Truncated,
+        "-113427455640312821154458202477256070485",
+        "-1",
+    );
+    assert_div_rem("3", BIG, Truncated, "0", "3");
+}
+
+#[test]
+fn division_by_zero() {
+    for mode in [DivisionMode::Floored, DivisionMode::Truncated] {
+        assert_eq!(
+            div_rem("1", "0", mode),
+            Err(ArithmeticError::DivisionByZero)
+        );
+        assert_eq!(
+            div_rem("1/2", "0", mode),
+            Err(ArithmeticError::DivisionByZero)
+        );
+        assert_eq!(
+            div_rem(BIG, "0.0", mode),
+            Err(ArithmeticError::DivisionByZero)
+        );
+    }
+}
+
+#[test]
+fn rounding_to_integers() {
+    let gc = GarbageCollector::new();
+
+    let round = |literal, mode| show(&gc, unsafe { number(&gc, literal).round(&gc, mode) });
+
+    for (literal, floor, ceil, round_half_even, trunc) in [
+        ("2.5", "2", "3", "2", "2"),
+        ("3.5", "3", "4", "4", "3"),
+        ("-2.5", "-3", "-2", "-2", "-2"),
+        ("-2.6", "-3", "-2", "-3", "-2"),
+        ("1/3", "0", "1", "0", "0"),
+        ("-1/3", "-1", "0", "0", "0"),
+        ("7", "7", "7", "7", "7"),
+        ("-7", "-7", "-7", "-7", "-7"),
+    ] {
+        assert_eq!(
+            round(literal, RoundingMode::Floor),
+            floor,
+            "floor({literal})"
+        );
+        assert_eq!(round(literal, RoundingMode::Ceil), ceil, "ceil({literal})");
+        assert_eq!(
+            round(literal, RoundingMode::HalfEven),
+            round_half_even,
+            "round({literal})"
+        );
+        assert_eq!(
+            round(literal, RoundingMode::Trunc),
+            trunc,
+            "trunc({literal})"
+        );
+    }
+}
+
+#[test]
+fn abs_min_max() {
+    let gc = GarbageCollector::new();
+    let num = |literal| number(&gc, literal);
+    let negative_big = format!("-{BIG}");
+
+    unsafe {
+        assert_eq!(show(&gc, num("-5").abs(&gc)), "5");
+        assert_eq!(show(&gc, num("-5/2").abs(&gc)), "2.5");
+        assert_eq!(show(&gc, num(&negative_big).abs(&gc)), BIG);
+
+        assert_eq!(show(&gc, num("-5").min(&gc, num("3"))), "-5");
+        assert_eq!(show(&gc, num("-5").max(&gc, num("3"))), "3");
+        assert_eq!(show(&gc, num("1/3").min(&gc, num("0.33"))), "0.33");
+        assert_eq!(show(&gc, num("-1/3").min(&gc, num("-0.33"))), "-1/3");
+        assert_eq!(
+            show(&gc, num(BIG).max(&gc, num(BIG_PLUS_ONE))),
+            BIG_PLUS_ONE
+        );
+        assert_eq!(show(&gc, num(BIG).min(&gc, num("-1"))), "-1");
+    }
+}
+
+#[test]
+fn gcd_and_lcm() {
+    let gc = GarbageCollector::new();
+    let num = |literal| number(&gc, literal);
+
+    unsafe {
+        assert_eq!(show(&gc, num("12").gcd(&gc, num("18"))), "6");
+        assert_eq!(show(&gc, num("-12").gcd(&gc, num("18"))), "6");
+        assert_eq!(show(&gc, num("0").gcd(&gc, num("5"))), "5");
+        assert_eq!(show(&gc, num("0").gcd(&gc, num("0"))), "0");
+        assert_eq!(show(&gc, num("12").lcm(&gc, num("18"))), "36");
+        assert_eq!(show(&gc, num("-4").lcm(&gc, num("6"))), "12");
+        assert_eq!(show(&gc, num("0").lcm(&gc, num("6"))), "0");
+
+        assert_eq!(show(&gc, num("1/2").gcd(&gc, num("1/3"))), "1/6");
+        assert_eq!(show(&gc, num("1/2").lcm(&gc, num("1/3"))), "1");
+
+        assert_eq!(show(&gc, num(BIG).gcd(&gc, num("96"))), "32");
+        assert_eq!(
+            show(&gc, num(BIG).lcm(&gc, num("3"))),
+            "1020847100762815390390123822295304634368"
+        );
+        assert_eq!(
+            show(
+                &gc,
+                num("4611686018427387903").lcm(&gc, num("4611686018427387902"))
+            ),
+            "21267647932558653952625854909203349506"
+        );
+    }
+}
+
+#[test]
+fn inline_boundaries() {
+    let gc = GarbageCollector::new();
+
+    for value in [0, 1, -1, isize::MAX >> 1, isize::MIN >> 1] {
+        let number = GCNumber::from_isize(value).unwrap();
+        assert_eq!(show(&gc, number), value.to_string());
+    }
+
+    assert!(GCNumber::from_isize((isize::MAX >> 1) + 1).is_none());
+    assert!(GCNumber::from_isize((isize::MIN >> 1) - 1).is_none());
+
+    // `-(isize::MIN >> 1)` can't be stored inline
+    let min = GCNumber::from_isize(isize::MIN >> 1).unwrap();
+    let negated = unsafe { min.neg(&gc) };
+    assert_eq!(show(&gc, negated), (-(isize::MIN >> 1)).to_string());
+    assert_eq!(
+        show(&gc, unsafe { negated.neg(&gc) }),
+        (isize::MIN >> 1).to_string()
+    );
+
+    let (quotient, remainder) = unsafe {
+        min.div_rem(
+            &gc,
+            GCNumber::from_isize(-1).unwrap(),
+            DivisionMode::Floored,
+        )
+        .unwrap()
+    };
+    assert_eq!(show(&gc, quotient), (-(isize::MIN >> 1)).to_string());
+    assert_eq!(show(&gc, remainder), "0");
+}
+
+#[test]
+fn integer_conversions() {
+    let gc = GarbageCollector::new();
+    let num = |literal| unsafe { GC::from_raw_parts(&gc, number(&gc, literal)) };
+    let u64_max = u64::MAX.to_string();
+    let u128_max = u128::MAX.to_string();
+    let u128_max_plus_one = format!("{u128_max}1");
+
+    assert_eq!(num("-7").to_i64(), Some(-7));
+    assert_eq!(num("-7").to_u64(), None);
+    assert_eq!(num("-0.0").to_u64(), Some(0));
+    assert_eq!(num("-9223372036854775808").to_i64(), Some(i64::MIN));
+    assert_eq!(num("9223372036854775807").to_i64(), Some(i64::MAX));
+    assert_eq!(num(&u64_max).to_u64(), Some(u64::MAX));
+    assert_eq!(num(&u128_max).to_u128(), Some(u128::MAX));
+    assert_eq!(num("1e3").to_u64(), Some(1000));
+
+    assert_eq!(
+        i64::try_from(num("9223372036854775808")),
+        Err(ConversionError::OutOfRange)
+    );
+    assert_eq!(
+        i64::try_from(num("-9223372036854775809")),
+        Err(ConversionError::OutOfRange)
+    );
+    assert_eq!(u64::try_from(num(BIG)), Err(ConversionError::OutOfRange));
+    assert_eq!(
+        u128::try_from(num(&u128_max_plus_one)),
+        Err(ConversionError::OutOfRange)
+    );
+    assert_eq!(u64::try_from(num("-1")), Err(ConversionError::OutOfRange));
+    assert_eq!(
+        i64::try_from(num("1/2")),
+        Err(ConversionError::NotAnInteger)
+    );
+    assert_eq!(
+        u128::try_from(num(&format!("{BIG}.5"))),
+        Err(ConversionError::NotAnInteger)
+    );
+}
+
+#[test]
+fn float_conversions() {
+    let gc = GarbageCollector::new();
+    let to_f64 = |literal| unsafe { GC::from_raw_parts(&gc, number(&gc, literal)) }.to_f64();
+
+    assert_eq!(to_f64("0"), Ok(0.0));
+    assert_eq!(to_f64("-2.5"), Ok(-2.5));
+    assert_eq!(to_f64("1/3"), Ok(1.0 / 3.0));
+    assert_eq!(to_f64("-2/3"), Ok(-2.0 / 3.0));
+    assert_eq!(to_f64(BIG), Ok(2f64.powi(128)));
+
+    // Ties are rounded to an even mantissa
+    assert_eq!(to_f64("9007199254740993"), Ok(9007199254740992.0));
+    assert_eq!(to_f64("9007199254740995"), Ok(9007199254740996.0));
+
+    assert_eq!(to_f64("4.9406564584124654e-324"), Ok(f64::from_bits(1)));
+    assert_eq!(to_f64("2e-324"), Ok(0.0));
+    assert_eq!(to_f64("3e-324"), Ok(f64::from_bits(1)));
+    assert_eq!(to_f64("1e-400"), Ok(0.0));
+
+    assert_eq!(to_f64("1.7976931348623157e308"), Ok(f64::MAX));
+    assert_eq!(to_f64("1.797693134862315807e308"), Ok(f64::MAX));
+    assert_eq!(to_f64("1.8e308"), Err(ConversionError::OutOfRange));
+    assert_eq!(to_f64("-1e400"), Err(ConversionError::OutOfRange));
+}
+
+#[test]
+fn fixed_point_conversions() {
+    let gc = GarbageCollector::new();
+    let to_fixed_point_string = |literal, scale| {
+        unsafe { GC::from_raw_parts(&gc, number(&gc, literal)) }.to_fixed_point_string(scale)
+    };
+
+    assert_eq!(to_fixed_point_string("2.5", 2), Ok("2.50".to_string()));
+    assert_eq!(to_fixed_point_string("-2.5", 1), Ok("-2.5".to_string()));
+    assert_eq!(to_fixed_point_string("7", 0), Ok("7".to_string()));
+    assert_eq!(to_fixed_point_string("1/8", 3), Ok("0.125".to_string()));
+    assert_eq!(to_fixed_point_string("-1/8", 5), Ok("-0.12500".to_string()));
+    assert_eq!(to_fixed_point_string(BIG, 1), Ok(format!("{BIG}.0")));
+
+    assert_eq!(
+        to_fixed_point_string("1/8", 2),
+        Err(ConversionError::Inexact)
+    );
+    assert_eq!(
+        to_fixed_point_string("1/3", 10),
+        Err(ConversionError::Inexact)
+    );
+    assert_eq!(
+        to_fixed_point_string("0.5", 0),
+        Err(ConversionError::Inexact)
+    );
+}
+
+#[test]
+fn numbers_from_rust_types() {
+    let gc = GarbageCollector::new();
+    let assert_same = |value: GCNumber, literal: &str| {
+        assert_eq!(
+            unsafe { value.cmp(&gc, number(&gc, literal)) },
+            std::cmp::Ordering::Equal,
+            "{literal}"
+        )
+    };
+
+    assert_same(GCNumber::from_i128(&gc, -7), "-7");
+    assert_same(GCNumber::from_i128(&gc, i128::MIN), &i128::MIN.to_string());
+    assert_same(GCNumber::from_u128(&gc, u128::MAX), &u128::MAX.to_string());
+    assert_same(
+        GCNumber::from_u128(&gc, 1 << 100),
+        &(1u128 << 100).to_string(),
+    );
+
+    let from_f64 = |value| GCNumber::from_f64(&gc, value).unwrap();
+
+    assert_same(from_f64(0.0), "0");
+    assert_same(from_f64(-0.0), "0");
+    assert_same(from_f64(-2.5), "-2.5");
+    assert_same(
+        from_f64(0.1),
+        "0.1000000000000000055511151231257827021181583404541015625",
+    );
+    assert_same(from_f64(2f64.powi(128)), BIG);
+
+    assert!(GCNumber::from_f64(&gc, f64::INFINITY).is_none());
+    assert!(GCNumber::from_f64(&gc, f64::NAN).is_none());
+}
+
+proptest! {
+    /// Rust's float parsing is correctly rounded, so parsing the same literal should give the same
+    /// value
+    #[test]
+    fn float_conversions_match_parsing(
+        mantissa in "[1-9][0-9]{0,40}(\\.[0-9]{1,40})?",
+        exponent in -360i32..330,
+    ) {
+        let literal = format!("{mantissa}e{exponent}");
+
+        let gc = GarbageCollector::new();
+        let number = unsafe { GC::from_raw_parts(&gc, number(&gc, &literal)) };
+
+        let expected = literal.parse::<f64>().unwrap();
+
+        if expected.is_infinite() {
+            prop_assert_eq!(number.to_f64(), Err(ConversionError::OutOfRange));
+        } else {
+            prop_assert_eq!(number.to_f64(), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn f64_round_trips(bits in any::<u64>()) {
+        let value = f64::from_bits(bits);
+        prop_assume!(value.is_finite());
+
+        let gc = GarbageCollector::new();
+        let number = unsafe { GC::from_raw_parts(&gc, GCNumber::from_f64(&gc, value).unwrap()) };
+
+        prop_assert_eq!(number.to_f64(), Ok(value));
+    }
+}
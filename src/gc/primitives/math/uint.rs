@@ -2,15 +2,15 @@ use std::ffi::c_uint;
 
 use super::PowerOfTenFactorization;
 
-use gmp_mpfr_sys::gmp::{
-    limb_t, mpn_addmul_1, mpn_copyd, mpn_copyi, mpn_divmod_1, mpn_get_str, mpn_lshift, mpn_mul_1,
-    mpn_rshift, mpn_zero, mpn_zero_p, size_t,
+use super::mpn::{
+    limb_t, mpn_add, mpn_copyd, mpn_copyi, mpn_divmod_1, mpn_gcd, mpn_get_str, mpn_lshift, mpn_mul,
+    mpn_mul_1, mpn_rshift, mpn_set_str, mpn_sub, mpn_tdiv_qr, mpn_zero, mpn_zero_p, size_t,
 };
 
 use crate::gc::{
     GCBuffer, GCEq, GarbageCollector,
     math::{self, maximum_limbs_from_num_digits, num_limbs_from_pow10_factorization},
-    primitives::math::{Digit, POW_5_MULTIPLIER},
+    primitives::math::Digit,
 };
 
 /// An unsigned bigint stored in GC space. Note: this doesn't implement `GCPtr`. It is meant to be
@@ -39,12 +39,15 @@ impl GCUInt {
         let data_ptr = self.data.as_mut_ptr(gc);
 
         unsafe {
-            mpn_lshift(
-                data_ptr,
-                data_ptr,
-                (self.data.len() - shift_limbs) as size_t,
-                shift_bits as c_uint,
-            );
+            // `mpn_lshift` requires a non-zero shift amount
+            if shift_bits != 0 {
+                mpn_lshift(
+                    data_ptr,
+                    data_ptr,
+                    (self.data.len() - shift_limbs) as size_t,
+                    shift_bits as c_uint,
+                );
+            }
 
             mpn_copyd(
                 data_ptr.add(shift_limbs),
@@ -65,41 +68,88 @@ impl GCUInt {
         digits: impl IntoIterator<Item = Digit>,
         num_digits: usize,
     ) -> Self {
-        let digits = digits.into_iter();
+        let digits: Vec<u8> = digits.into_iter().map(|digit| digit.to_u8()).collect();
+
+        debug_assert!(digits.len() <= num_digits);
 
-        let len = maximum_limbs_from_num_digits(num_digits);
+        // `mpn_set_str` requires an extra limb
+        let len = maximum_limbs_from_num_digits(num_digits) + 1;
 
         let mut output = Self {
             data: GCBuffer::new_uninit(gc, len),
         };
 
-        let buffer = GCBuffer::<limb_t>::new_uninit(gc, len);
+        let data_ptr = output.data.as_mut_ptr(gc);
 
-        let mut out_ptr = output.data.as_mut_ptr(gc);
-        let mut tmp_ptr = buffer.as_mut_ptr(gc);
+        unsafe {
+            // `mpn_set_str` requires at-least one digit
+            let written = if digits.is_empty() {
+                0
+            } else {
+                mpn_set_str(data_ptr, digits.as_ptr(), digits.len(), 10) as usize
+            };
 
-        unsafe { mpn_zero(out_ptr, len as size_t) };
+            mpn_zero(data_ptr.add(written), (len - written) as size_t);
 
-        for digit in digits {
-            unsafe { mpn_zero(tmp_ptr, len as size_t) };
-            unsafe { tmp_ptr.write(digit as limb_t) };
+            output.trim_leading_zero_limbs_at_end(gc);
+        }
 
-            unsafe { mpn_addmul_1(tmp_ptr, out_ptr, len as i64, 10) };
+        output
+    }
 
-            std::mem::swap(&mut out_ptr, &mut tmp_ptr);
-        }
+    /// Parses a `GCUInt` from the digits of a power-of-two radix (ie hexadecimal, octal, or
+    /// binary). `digits` should yield the value of each digit starting with the most significant
+    /// one.
+    ///
+    /// # Safety
+    /// - `num_digits` must be greater-than or equal-to `digits.count()`
+    /// - every digit must be less than `2^bits_per_digit`
+    pub unsafe fn parse_from_pow2_radix_digits(
+        gc: &GarbageCollector,
+        digits: impl DoubleEndedIterator<Item = limb_t>,
+        bits_per_digit: u32,
+        num_digits: usize,
+    ) -> Self {
+        const LIMB_BITS: usize = limb_t::BITS as usize;
+
+        let bits_per_digit = bits_per_digit as usize;
+        let len = (num_digits * bits_per_digit).div_ceil(LIMB_BITS).max(1);
+
+        let mut output = Self {
+            data: GCBuffer::new_uninit(gc, len),
+        };
+
+        let data_ptr = output.data.as_mut_ptr(gc);
 
-        if out_ptr.cast_const() != output.data.as_ptr(gc) {
-            unsafe { std::ptr::copy_nonoverlapping(out_ptr, output.data.as_mut_ptr(gc), len) };
+        unsafe { mpn_zero(data_ptr, len as size_t) };
+
+        // Because the digits don't need to be multiplied, we can just pack their bits into the
+        // limbs starting with the least significant digit
+        for (i, digit) in digits.rev().enumerate() {
+            debug_assert!(digit >> bits_per_digit == 0);
+
+            let bit = i * bits_per_digit;
+            let limb = bit / LIMB_BITS;
+            let offset = bit % LIMB_BITS;
+
+            unsafe { *data_ptr.add(limb) |= digit << offset };
+
+            // Octal digits can be split across two limbs
+            if offset + bits_per_digit > LIMB_BITS {
+                unsafe { *data_ptr.add(limb + 1) |= digit >> (LIMB_BITS - offset) };
+            }
         }
 
-        unsafe { buffer.deallocate_from_end(gc) };
         unsafe { output.trim_leading_zero_limbs_at_end(gc) };
 
         output
     }
 
-    pub unsafe fn to_naive_string(self, gc: &GarbageCollector) -> String {
+    /// Renders `self` in base 10
+    ///
+    /// # Safety
+    /// `self` must be valid
+    pub unsafe fn to_decimal_string(self, gc: &GarbageCollector) -> String {
         let digits_required = math::util::maximum_digits_from_num_limbs(self.data.len());
 
         let mut string = Vec::<u8>::with_capacity(digits_required + 1);
@@ -214,17 +264,17 @@ impl GCUInt {
 
     /// Writes a power of five to `self`. Requires that `self` is big enough and is set to zero.
     unsafe fn write_pow_5(self, gc: &GarbageCollector, pow_5: usize) {
-        let limbs_required = (pow_5 * POW_5_MULTIPLIER).div_ceil(256) / (limb_t::BITS as usize) + 1;
-
-        let data_ptr = self.data.as_mut_ptr(gc);
+        let pow_5 = limbs_pow(5, pow_5);
 
-        unsafe { data_ptr.write(1) };
+        debug_assert!(pow_5.len() <= self.data.len());
 
-        // This can be simplified significantly if we use other powers of 5 in the `mpn_mul_1`
-        // function, but there's not really a point in optimizing that right now.
-        for _ in 0..pow_5 {
-            unsafe { mpn_mul_1(data_ptr, data_ptr, limbs_required as size_t, 5) };
-        }
+        unsafe {
+            mpn_copyi(
+                self.data.as_mut_ptr(gc),
+                pow_5.as_ptr(),
+                pow_5.len() as size_t,
+            )
+        };
     }
 
     /// Reduces the fraction `self/(2^a * 5^b)` where `a` and `b` are given by `denominator`.
@@ -249,6 +299,56 @@ impl GCUInt {
         unsafe { self.trim_leading_zero_limbs_at_end(gc) };
     }
 
+    /// Multiplies `self` by `5^a * 2^b` where `a` and `b` are given by `factorization`. Requires
+    /// that the current allocation is the last allocation in the `GCSpace`.
+    ///
+    /// This is used for parsing decimals with positive exponents.
+    pub unsafe fn mul_pow10_factorization_at_end(
+        &mut self,
+        gc: &GarbageCollector,
+        factorization: PowerOfTenFactorization,
+    ) {
+        let pow_5 = limbs_pow(5, factorization.pow_5);
+        let old_len = self.data.len();
+
+        // `mpn_mul` requires that the output doesn't overlap with the inputs, so we have to
+        // multiply into a temporary buffer
+        let mut product = vec![0; old_len + pow_5.len()];
+
+        unsafe {
+            let (lhs, rhs) = (self.data.as_slice(gc), pow_5.as_slice());
+            let (lhs, rhs) = if lhs.len() >= rhs.len() {
+                (lhs, rhs)
+            } else {
+                (rhs, lhs)
+            };
+
+            mpn_mul(
+                product.as_mut_ptr(),
+                lhs.as_ptr(),
+                lhs.len() as size_t,
+                rhs.as_ptr(),
+                rhs.len() as size_t,
+            );
+        }
+
+        let len = product.len() + factorization.pow_2.div_ceil(limb_t::BITS as usize);
+
+        unsafe {
+            self.data.set_length_at_end(gc, len);
+
+            let data_ptr = self.data.as_mut_ptr(gc);
+
+            mpn_copyi(data_ptr, product.as_ptr(), product.len() as size_t);
+            mpn_zero(data_ptr.add(product.len()), (len - product.len()) as size_t);
+        }
+
+        unsafe {
+            self.shift_left_unchecked(gc, factorization.pow_2);
+            self.trim_leading_zero_limbs_at_end(gc);
+        }
+    }
+
     unsafe fn count_required_digits(self, gc: &GarbageCollector) -> usize {
         unsafe { self.data.as_slice(gc) }
             .iter()
@@ -311,12 +411,260 @@ impl GCUInt {
         unsafe {
             mpn_copyi(data_ptr, data_ptr.add(shr_limbs), limb_count as size_t);
 
-            mpn_rshift(data_ptr, data_ptr, limb_count as size_t, shr_bits as c_uint);
+            // `mpn_rshift` requires a non-zero shift amount
+            if shr_bits != 0 {
+                mpn_rshift(data_ptr, data_ptr, limb_count as size_t, shr_bits as c_uint);
+            }
         }
 
         limb_count
     }
 
+    /// Writes `lhs * rhs` to `output`.
+    ///
+    /// # Safety
+    /// - `output.len()` must be equal to `lhs.data.len() + rhs.data.len()`
+    /// - `output` must not overlap with `lhs` or `rhs`
+    pub unsafe fn mul_into(gc: &GarbageCollector, output: GCBuffer<limb_t>, lhs: Self, rhs: Self) {
+        debug_assert_eq!(output.len(), lhs.data.len() + rhs.data.len());
+
+        // `mpn_mul` requires that the first operand is at-least as long as the second
+        let (lhs, rhs) = if lhs.data.len() >= rhs.data.len() {
+            (lhs, rhs)
+        } else {
+            (rhs, lhs)
+        };
+
+        unsafe {
+            mpn_mul(
+                output.as_mut_ptr(gc),
+                lhs.data.as_ptr(gc),
+                lhs.data.len() as size_t,
+                rhs.data.as_ptr(gc),
+                rhs.data.len() as size_t,
+            )
+        };
+    }
+
+    pub unsafe fn is_odd(self, gc: &GarbageCollector) -> bool {
+        unsafe { self.data.as_slice(gc) }
+            .first()
+            .is_some_and(|limb| limb & 1 != 0)
+    }
+
+    /// Allocates a `GCUInt` with a single limb
+    pub fn from_limb(gc: &GarbageCollector, limb: limb_t) -> Self {
+        let data = GCBuffer::<limb_t>::new_uninit(gc, 1);
+        unsafe { data.as_mut_ptr(gc).write(limb) };
+
+        Self { data }
+    }
+
+    /// Copies `self` into a new allocation
+    pub unsafe fn copy(self, gc: &GarbageCollector) -> Self {
+        let data = GCBuffer::<limb_t>::new_uninit(gc, self.data.len());
+
+        unsafe {
+            mpn_copyi(
+                data.as_mut_ptr(gc),
+                self.data.as_ptr(gc),
+                data.len() as size_t,
+            )
+        };
+
+        Self { data }
+    }
+
+    /// Calculates `self + rhs`. The result is allocated at the end of the GC heap.
+    ///
+    /// # Safety
+    /// `self` and `rhs` must be valid and must not have leading zero limbs
+    pub unsafe fn add(self, gc: &GarbageCollector, rhs: Self) -> Self {
+        // `mpn_add` requires that the first operand is at-least as long as the second
+        let (lhs, rhs) = if self.data.len() >= rhs.data.len() {
+            (self, rhs)
+        } else {
+            (rhs, self)
+        };
+
+        let mut output = Self {
+            data: GCBuffer::new_uninit(gc, lhs.data.len() + 1),
+        };
+
+        unsafe {
+            let carry = mpn_add(
+                output.data.as_mut_ptr(gc),
+                lhs.data.as_ptr(gc),
+                lhs.data.len() as size_t,
+                rhs.data.as_ptr(gc),
+                rhs.data.len() as size_t,
+            );
+
+            output.data.element_ptr(gc, lhs.data.len()).write(carry);
+            output.trim_leading_zero_limbs_at_end(gc);
+        }
+
+        output
+    }
+
+    /// Calculates `self - rhs`. The result is allocated at the end of the GC heap.
+    ///
+    /// # Safety
+    /// - `self` and `rhs` must be valid and must not have leading zero limbs
+    /// - `self` must be greater-than or equal-to `rhs`
+    pub unsafe fn sub(self, gc: &GarbageCollector, rhs: Self) -> Self {
+        debug_assert!(unsafe { self.cmp(gc, rhs) }.is_ge());
+
+        let mut output = Self {
+            data: GCBuffer::new_uninit(gc, self.data.len()),
+        };
+
+        unsafe {
+            mpn_sub(
+                output.data.as_mut_ptr(gc),
+                self.data.as_ptr(gc),
+                self.data.len() as size_t,
+                rhs.data.as_ptr(gc),
+                rhs.data.len() as size_t,
+            );
+
+            output.trim_leading_zero_limbs_at_end(gc);
+        }
+
+        output
+    }
+
+    /// Calculates `self * rhs`. The result is allocated at the end of the GC heap.
+    ///
+    /// # Safety
+    /// `self` and `rhs` must be valid
+    pub unsafe fn mul(self, gc: &GarbageCollector, rhs: Self) -> Self {
+        let mut output = Self {
+            data: GCBuffer::new_uninit(gc, self.data.len() + rhs.data.len()),
+        };
+
+        unsafe {
+            Self::mul_into(gc, output.data, self, rhs);
+            output.trim_leading_zero_limbs_at_end(gc);
+        }
+
+        output
+    }
+
+    /// Calculates the quotient and remainder of `self / rhs`. The results are allocated in the GC
+    /// heap.
+    ///
+    /// # Safety
+    /// - `self` and `rhs` must be valid and must not have leading zero limbs
+    /// - `rhs` must not be zero
+    pub unsafe fn div_rem(self, gc: &GarbageCollector, rhs: Self) -> (Self, Self) {
+        debug_assert!(!unsafe { rhs.is_zero(gc) });
+
+        if self.data.len() < rhs.data.len() {
+            return (Self::from_limb(gc, 0), unsafe { self.copy(gc) });
+        }
+
+        let quotient = Self {
+            data: GCBuffer::new_uninit(gc, self.data.len() - rhs.data.len() + 1),
+        };
+
+        let mut remainder = Self {
+            data: GCBuffer::new_uninit(gc, rhs.data.len()),
+        };
+
+        unsafe {
+            mpn_tdiv_qr(
+                quotient.data.as_mut_ptr(gc),
+                remainder.data.as_mut_ptr(gc),
+                0,
+                self.data.as_ptr(gc),
+                self.data.len() as size_t,
+                rhs.data.as_ptr(gc),
+                rhs.data.len() as size_t,
+            );
+
+            remainder.trim_leading_zero_limbs_at_end(gc);
+
+            (quotient.without_leading_zero_limbs(gc), remainder)
+        }
+    }
+
+    /// Calculates the greatest common divisor of `self` and `rhs`. The result is allocated at the
+    /// end of the GC heap.
+    ///
+    /// # Safety
+    /// `self` and `rhs` must be valid and must not have leading zero limbs
+    pub unsafe fn gcd(self, gc: &GarbageCollector, rhs: Self) -> Self {
+        unsafe {
+            if self.is_zero(gc) {
+                return rhs.copy(gc);
+            }
+
+            if rhs.is_zero(gc) {
+                return self.copy(gc);
+            }
+        }
+
+        // `mpn_gcd` requires that at-least one input is odd, so we remove the common factors of two
+        // before-hand and add them back afterwards. It also destroys its inputs, so we need copies.
+        let shift = unsafe { self.trailing_zeroes(gc).min(rhs.trailing_zeroes(gc)) };
+
+        let [mut greater, mut less] = [self, rhs].map(|value| unsafe {
+            let mut value = value.copy(gc);
+            let len = value.shr_unchecked(gc, shift);
+            value.data.set_length(len);
+
+            value.without_leading_zero_limbs(gc)
+        });
+
+        if unsafe { greater.cmp(gc, less) }.is_lt() {
+            std::mem::swap(&mut greater, &mut less);
+        }
+
+        let output_len = less.data.len() + shift / limb_t::BITS as usize + 1;
+
+        let mut output = Self {
+            data: GCBuffer::new_uninit(gc, output_len),
+        };
+
+        unsafe {
+            let output_ptr = output.data.as_mut_ptr(gc);
+
+            let gcd_len = mpn_gcd(
+                output_ptr,
+                greater.data.as_mut_ptr(gc),
+                greater.data.len() as size_t,
+                less.data.as_mut_ptr(gc),
+                less.data.len() as size_t,
+            ) as usize;
+
+            mpn_zero(output_ptr.add(gcd_len), (output_len - gcd_len) as size_t);
+
+            output.shift_left_unchecked(gc, shift);
+            output.trim_leading_zero_limbs_at_end(gc);
+        }
+
+        output
+    }
+
+    /// Calculates the least common multiple of `self` and `rhs`. The result is allocated at the end
+    /// of the GC heap.
+    ///
+    /// # Safety
+    /// `self` and `rhs` must be valid and must not have leading zero limbs
+    pub unsafe fn lcm(self, gc: &GarbageCollector, rhs: Self) -> Self {
+        unsafe {
+            if self.is_zero(gc) || rhs.is_zero(gc) {
+                return Self::from_limb(gc, 0);
+            }
+
+            let gcd = self.gcd(gc, rhs);
+            let (quotient, _) = self.div_rem(gc, gcd);
+
+            quotient.mul(gc, rhs)
+        }
+    }
+
     /// Compares `self` with `other`.
     ///
     /// Requires there to be no leading zeroes on `self` or `other`
@@ -377,6 +725,41 @@ impl GCUInt {
     }
 }
 
+/// Calculates `base^exponent` by repeated squaring. The result has no leading zero limbs.
+fn limbs_pow(base: limb_t, exponent: usize) -> Vec<limb_t> {
+    let mut result: Vec<limb_t> = vec![1];
+
+    for bit in (0..usize::BITS - exponent.leading_zeros()).rev() {
+        let mut square = vec![0; 2 * result.len()];
+
+        unsafe {
+            mpn_mul(
+                square.as_mut_ptr(),
+                result.as_ptr(),
+                result.len() as size_t,
+                result.as_ptr(),
+                result.len() as size_t,
+            )
+        };
+
+        if exponent >> bit & 1 != 0 {
+            let ptr = square.as_mut_ptr();
+            let carry = unsafe { mpn_mul_1(ptr, ptr, square.len() as size_t, base) };
+            square.push(carry);
+        }
+
+        let len = square
+            .iter()
+            .rposition(|limb| *limb != 0)
+            .map_or(0, |i| i + 1);
+        square.truncate(len);
+
+        result = square;
+    }
+
+    result
+}
+
 impl From<GCBuffer<limb_t>> for GCUInt {
     fn from(value: GCBuffer<limb_t>) -> Self {
         Self { data: value }
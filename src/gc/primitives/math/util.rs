@@ -1,5 +1,5 @@
+use super::mpn::limb_t;
 use copyspan::Span;
-use gmp_mpfr_sys::gmp::limb_t;
 use mulch_macros::FromToU8;
 
 use crate::{
@@ -7,11 +7,6 @@ use crate::{
     parser,
 };
 
-/// Equal to `ceil(log2(5) * 256)`
-///
-/// This is used for certain calculations when parsing decimals
-pub(super) const POW_5_MULTIPLIER: usize = 595;
-
 #[derive(Clone, Copy, Debug)]
 pub(super) struct PowerOfTenFactorization {
     pub pow_5: usize,
@@ -33,10 +28,44 @@ pub enum Digit {
     Nine,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArithmeticError {
+    DivisionByZero,
+}
+
+/// Determines how integer division rounds its quotient
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DivisionMode {
+    /// Rounds the quotient towards negative infinity. The remainder has the same sign as the
+    /// divisor (eg `-7 mod 2 = 1`).
+    Floored,
+    /// Rounds the quotient towards zero. The remainder has the same sign as the dividend (eg
+    /// `-7 mod 2 = -1`).
+    Truncated,
+}
+
+/// The largest exponent magnitude allowed in scientific notation (eg `1e65535`)
+pub(super) const MAX_EXPONENT: usize = u16::MAX as usize;
+
 #[derive(Clone, Copy, FromToU8, Debug)]
 pub(super) enum NumLiteralType {
     Decimal,
     Fraction,
+    Hexadecimal,
+    Octal,
+    Binary,
+}
+
+impl NumLiteralType {
+    /// Gets the number of bits per digit for `Hexadecimal`, `Octal`, and `Binary` literals
+    pub(super) fn bits_per_digit(self) -> Option<u32> {
+        match self {
+            NumLiteralType::Hexadecimal => Some(4),
+            NumLiteralType::Octal => Some(3),
+            NumLiteralType::Binary => Some(1),
+            NumLiteralType::Decimal | NumLiteralType::Fraction => None,
+        }
+    }
 }
 
 pub(super) fn strip_integer_zeroes(str: &str) -> &str {
@@ -79,6 +108,13 @@ pub(super) fn strip_decimal_zeroes(str: &str) -> &str {
 }
 
 pub(super) fn literal_type(literal: PartialSpanned<&str>) -> PDResult<NumLiteralType> {
+    match literal.get(..2) {
+        Some("0x") => return Ok(NumLiteralType::Hexadecimal),
+        Some("0o") => return Ok(NumLiteralType::Octal),
+        Some("0b") => return Ok(NumLiteralType::Binary),
+        _ => {}
+    }
+
     let mut iter = literal.chars().filter(|c| matches!(c, '.' | '/'));
 
     let num_type = iter
@@ -128,6 +164,53 @@ pub(super) fn decimal_literal_info(decimal: PartialSpanned<&str>) -> PDResult<(u
     Ok((digits_after_decimal_point, num_digits))
 }
 
+/// Splits a decimal literal into its mantissa and its exponent.
+///
+/// `1.5e-3` -> (`1.5`, -3)
+///
+/// `2_500` -> (`2_500`, 0)
+pub(super) fn split_exponent(
+    literal: PartialSpanned<&str>,
+) -> PDResult<(PartialSpanned<&str>, isize)> {
+    let Some(e) = literal.find(['e', 'E']) else {
+        return Ok((literal, 0));
+    };
+
+    let mantissa = literal.map(|str| &str[..e]);
+
+    let exponent = &literal[e + 1..];
+    let (is_negative, exponent) = match exponent.strip_prefix('-') {
+        Some(exponent) => (true, exponent),
+        None => (false, exponent.strip_prefix('+').unwrap_or(exponent)),
+    };
+
+    let mut magnitude = 0usize;
+
+    for c in exponent.chars() {
+        match c {
+            '0'..='9' => {
+                magnitude = magnitude
+                    .saturating_mul(10)
+                    .saturating_add(c as usize - '0' as usize);
+            }
+            '_' => {}
+            _ => return Err(parser::error::unexpected_character_in_number(c, literal.1)),
+        }
+    }
+
+    if magnitude > MAX_EXPONENT {
+        return Err(parser::error::exponent_too_large(literal.1, MAX_EXPONENT));
+    }
+
+    let exponent = if is_negative {
+        -(magnitude as isize)
+    } else {
+        magnitude as isize
+    };
+
+    Ok((mantissa, exponent))
+}
+
 pub(super) fn num_integer_digits(integer: &str, span: Span) -> PDResult<usize> {
     let mut ret = 0;
 
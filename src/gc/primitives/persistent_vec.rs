@@ -0,0 +1,576 @@
+//! A garbage collected persistent vector.
+//!
+//! The elements are stored in chunks of up to [`CHUNK_SIZE`] elements, which are the leaves of a
+//! height-balanced binary tree. Every branch stores the number of elements below it, so indexing
+//! is `O(log n)`. Nodes are never modified after they are created, so the result of an operation
+//! shares every node that it does not change with its inputs:
+//! - Appending copies the last chunk and the path to it.
+//! - Concatenation joins the two trees along the spine of the taller one (like joining AVL
+//!   trees), which is `O(log n)`.
+//! - Slicing splits the tree twice. Every split is a sequence of joins whose costs add up to
+//!   `O(log n)`.
+
+use std::ops::Range;
+
+use mulch_macros::GCPtr;
+
+use crate::gc::{
+    GCBox, GCDebug, GCPtr, GCVec, GarbageCollector,
+    safety::{GC, GCCtx},
+    util::GCWrap,
+};
+
+#[cfg(test)]
+mod test;
+
+/// The maximum number of elements in a leaf
+const CHUNK_SIZE: usize = 32;
+
+/// A garbage collected persistent vector. Appending, concatenation and slicing are `O(log n)` and
+/// return a new vector that shares most of its memory with the old ones.
+#[derive(Clone, Copy, GCPtr)]
+pub struct GCPersistentVec<T: GCPtr> {
+    /// `None` if the vector is empty
+    root: Option<GCBox<Node<T>>>,
+}
+
+#[derive(Clone, Copy, GCPtr)]
+struct Node<T: GCPtr> {
+    /// The number of elements in this subtree
+    len: usize,
+    /// The height of this subtree. Leaves have a height of 0.
+    height: usize,
+    /// The elements of a leaf. This is `None` for branches.
+    chunk: Option<GCVec<T>>,
+    left: Option<GCBox<Node<T>>>,
+    right: Option<GCBox<Node<T>>>,
+}
+
+type NodeRef<T> = GCBox<Node<T>>;
+
+/// An iterator over the elements of a [`GCPersistentVec`]
+pub struct SafeGCPersistentVecIter<'a, T: GCPtr> {
+    gc: &'a GarbageCollector,
+    /// The chunk that is being iterated over
+    chunk: Option<GCVec<T>>,
+    /// The index of the next element in `chunk`
+    idx: usize,
+    /// The subtrees that come after `chunk`, from last to first
+    stack: Vec<NodeRef<T>>,
+}
+
+impl<'a, T: GCPtr> GC<'a, GCPersistentVec<T>> {
+    pub fn len(&self) -> usize {
+        // SAFETY: `self` is valid because it's wrapped in `GC`
+        unsafe { self.raw().len(self.gc()) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.raw().root.is_none()
+    }
+
+    pub fn get(&self, idx: usize) -> Option<GC<'a, T>> {
+        // SAFETY: `self` is valid because it's wrapped in `GC`
+        let element = unsafe { self.raw().get(self.gc(), idx)? };
+
+        Some(unsafe { GC::from_raw_parts(self.gc(), element) })
+    }
+
+    pub fn iter(&self) -> SafeGCPersistentVecIter<'a, T> {
+        let mut iter = SafeGCPersistentVecIter {
+            gc: self.gc(),
+            chunk: None,
+            idx: 0,
+            stack: Vec::new(),
+        };
+
+        iter.stack.extend(self.raw().root);
+
+        iter
+    }
+
+    /// Returns a vector with `value` appended to the end of `self`
+    pub fn push(self, ctx: &'a GCCtx, value: GC<'a, T>) -> GC<'a, GCPersistentVec<T>> {
+        // SAFETY: `self` and `value` are valid because they're wrapped in `GC`
+        unsafe {
+            let leaf = new_leaf(ctx, &[value.raw()]);
+            let root = join_opt(ctx, self.raw().root, Some(leaf));
+
+            GC::new(ctx, GCPersistentVec { root })
+        }
+    }
+
+    /// Returns the elements of `self` followed by the elements of `other`
+    pub fn concat(
+        self,
+        ctx: &'a GCCtx,
+        other: GC<'a, GCPersistentVec<T>>,
+    ) -> GC<'a, GCPersistentVec<T>> {
+        // SAFETY: `self` and `other` are valid because they're wrapped in `GC`
+        unsafe {
+            let root = join_opt(ctx, self.raw().root, other.raw().root);
+
+            GC::new(ctx, GCPersistentVec { root })
+        }
+    }
+
+    /// Returns the elements in `range`, or `None` if `range` is out of bounds
+    pub fn slice(self, ctx: &'a GCCtx, range: Range<usize>) -> Option<GC<'a, GCPersistentVec<T>>> {
+        if range.start > range.end || range.end > self.len() {
+            return None;
+        }
+
+        // SAFETY: `self` is valid because it's wrapped in `GC`
+        unsafe {
+            let (rest, _) = split_opt(ctx, self.raw().root, range.end);
+            let (_, root) = split_opt(ctx, rest, range.start);
+
+            Some(GC::new(ctx, GCPersistentVec { root }))
+        }
+    }
+}
+
+impl<'a, T: GCPtr> Iterator for SafeGCPersistentVecIter<'a, T> {
+    type Item = GC<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(chunk) = self.chunk {
+                // SAFETY: `chunk` is referenced by the vector that is being iterated over
+                let element = unsafe { chunk.as_slice(self.gc).get(self.idx).copied() };
+
+                if let Some(element) = element {
+                    self.idx += 1;
+
+                    return Some(unsafe { GC::from_raw_parts(self.gc, element) });
+                }
+            }
+
+            // Descend to the leftmost leaf of the next subtree
+            let mut node = unsafe { self.stack.pop()?.get(self.gc) };
+
+            while let (Some(left), Some(right)) = (node.left, node.right) {
+                self.stack.push(right);
+                node = unsafe { left.get(self.gc) };
+            }
+
+            self.chunk = node.chunk;
+            self.idx = 0;
+        }
+    }
+}
+
+impl<T: GCPtr> GCPersistentVec<T> {
+    pub fn new_empty<'c>(ctx: &'c GCCtx) -> GC<'c, Self> {
+        unsafe { GC::new(ctx, Self { root: None }) }
+    }
+
+    /// Creates a balanced vector from `elements`
+    ///
+    /// # Safety
+    /// - All `elements` must be valid and alive
+    pub unsafe fn new(gc: &GarbageCollector, elements: &[T]) -> Self {
+        let leaves: Vec<NodeRef<T>> = elements
+            .chunks(CHUNK_SIZE)
+            .map(|chunk| unsafe { new_leaf(gc, chunk) })
+            .collect();
+
+        Self {
+            root: unsafe { build_balanced(gc, &leaves) },
+        }
+    }
+
+    pub(crate) fn from_iter_and_len<'a, 'b, I>(
+        ctx: &'a GCCtx<'_>,
+        elements: I,
+        len: usize,
+    ) -> GC<'a, Self>
+    where
+        I: Iterator<Item = GC<'b, T>>,
+    {
+        let elements: Vec<T> = elements
+            .inspect(|element| assert!(std::ptr::eq(element.gc(), **ctx)))
+            .map(|element| element.raw())
+            .collect();
+
+        assert_eq!(elements.len(), len);
+
+        // SAFETY: the elements are valid because they were wrapped in `GC`
+        unsafe { GC::new(ctx, Self::new(ctx, &elements)) }
+    }
+
+    /// # Safety
+    /// `self` must be valid and alive
+    pub unsafe fn len(&self, gc: &GarbageCollector) -> usize {
+        self.root.map_or(0, |root| unsafe { root.get(gc) }.len)
+    }
+
+    /// Gets the element at `idx`
+    ///
+    /// # Safety
+    /// `self` must be valid and alive
+    pub unsafe fn get(&self, gc: &GarbageCollector, mut idx: usize) -> Option<T> {
+        let mut node = unsafe { self.root?.get(gc) };
+
+        if idx >= node.len {
+            return None;
+        }
+
+        loop {
+            if let Some(chunk) = node.chunk {
+                return unsafe { chunk.as_slice(gc).get(idx).copied() };
+            }
+
+            let left = unsafe { node.left.unwrap().get(gc) };
+
+            if idx < left.len {
+                node = left;
+            } else {
+                idx -= left.len;
+                node = unsafe { node.right.unwrap().get(gc) };
+            }
+        }
+    }
+
+    /// Iterates over the elements without the safe garbage-collection API
+    ///
+    /// # Safety
+    /// - `self` must be valid and alive
+    /// - No garbage-collection cycle can happen while the iterator is in use
+    unsafe fn raw_iter<'a>(&self, gc: &'a GarbageCollector) -> impl Iterator<Item = T> + 'a
+    where
+        T: 'a,
+    {
+        let iter = SafeGCPersistentVecIter {
+            gc,
+            chunk: None,
+            idx: 0,
+            stack: self.root.into_iter().collect(),
+        };
+
+        iter.map(|element| element.raw())
+    }
+}
+
+/// Allocates a leaf. `elements` must not be empty.
+///
+/// # Safety
+/// - All `elements` must be valid and alive
+unsafe fn new_leaf<T: GCPtr>(gc: &GarbageCollector, elements: &[T]) -> NodeRef<T> {
+    debug_assert!(!elements.is_empty() && elements.len() <= CHUNK_SIZE);
+
+    unsafe {
+        let chunk = GCVec::new(gc, elements);
+
+        GCBox::new_raw(
+            gc,
+            Node {
+                len: elements.len(),
+                height: 0,
+                chunk: Some(chunk),
+                left: None,
+                right: None,
+            },
+        )
+    }
+}
+
+/// Allocates a branch without rebalancing
+///
+/// # Safety
+/// - `left` and `right` must be valid and alive
+unsafe fn new_branch<T: GCPtr>(
+    gc: &GarbageCollector,
+    left: NodeRef<T>,
+    right: NodeRef<T>,
+) -> NodeRef<T> {
+    unsafe {
+        let (l, r) = (left.get(gc), right.get(gc));
+
+        GCBox::new_raw(
+            gc,
+            Node {
+                len: l.len + r.len,
+                height: l.height.max(r.height) + 1,
+                chunk: None,
+                left: Some(left),
+                right: Some(right),
+            },
+        )
+    }
+}
+
+/// Builds a balanced tree from leaves
+///
+/// # Safety
+/// - All `leaves` must be valid and alive
+unsafe fn build_balanced<T: GCPtr>(
+    gc: &GarbageCollector,
+    leaves: &[NodeRef<T>],
+) -> Option<NodeRef<T>> {
+    match leaves {
+        [] => None,
+        [leaf] => Some(*leaf),
+        _ => {
+            let (left, right) = leaves.split_at(leaves.len() / 2);
+
+            unsafe {
+                Some(new_branch(
+                    gc,
+                    build_balanced(gc, left)?,
+                    build_balanced(gc, right)?,
+                ))
+            }
+        }
+    }
+}
+
+fn height<T: GCPtr>(gc: &GarbageCollector, node: NodeRef<T>) -> usize {
+    // SAFETY: every node that is passed around in this module is valid
+    unsafe { node.get(gc).height }
+}
+
+/// Concatenates two trees. A leaf that fits into the adjacent leaf of the other tree is merged
+/// into it, so that appending one element at a time produces full chunks.
+///
+/// # Safety
+/// - `left` and `right` must be valid and alive
+unsafe fn join<T: GCPtr>(gc: &GarbageCollector, left: NodeRef<T>, right: NodeRef<T>) -> NodeRef<T> {
+    let (hl, hr) = (height(gc, left), height(gc, right));
+
+    unsafe {
+        if let Some(joined) = absorb_leaf(gc, left, right, Edge::Last) {
+            return joined;
+        } else if let Some(joined) = absorb_leaf(gc, right, left, Edge::First) {
+            return joined;
+        }
+
+        if hl > hr + 1 {
+            join_right(gc, left, right)
+        } else if hr > hl + 1 {
+            join_left(gc, left, right)
+        } else {
+            new_branch(gc, left, right)
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Edge {
+    First,
+    Last,
+}
+
+/// Adds the elements of `leaf` to the first or last leaf of `node`, if `leaf` is a leaf and there
+/// is room for them. This does not change the shape of the tree.
+///
+/// # Safety
+/// - `node` and `leaf` must be valid and alive
+unsafe fn absorb_leaf<T: GCPtr>(
+    gc: &GarbageCollector,
+    node_ref: NodeRef<T>,
+    leaf_ref: NodeRef<T>,
+    edge: Edge,
+) -> Option<NodeRef<T>> {
+    unsafe {
+        let (node, leaf) = (node_ref.get(gc), leaf_ref.get(gc));
+        let leaf_chunk = leaf.chunk?;
+
+        if let Some(chunk) = node.chunk {
+            if node.len + leaf.len > CHUNK_SIZE {
+                return None;
+            }
+
+            let (first, last) = match edge {
+                Edge::First => (leaf_chunk, chunk),
+                Edge::Last => (chunk, leaf_chunk),
+            };
+            let elements = [first.as_slice(gc), last.as_slice(gc)].concat();
+
+            return Some(new_leaf(gc, &elements));
+        }
+
+        let (left, right) = (node.left.unwrap(), node.right.unwrap());
+
+        Some(match edge {
+            Edge::First => new_branch(gc, absorb_leaf(gc, left, leaf_ref, edge)?, right),
+            Edge::Last => new_branch(gc, left, absorb_leaf(gc, right, leaf_ref, edge)?),
+        })
+    }
+}
+
+/// Concatenates two trees where `left` is more than one level taller than `right`, by inserting
+/// `right` along the right spine of `left`
+///
+/// # Safety
+/// - `left` and `right` must be valid and alive
+unsafe fn join_right<T: GCPtr>(
+    gc: &GarbageCollector,
+    left: NodeRef<T>,
+    right: NodeRef<T>,
+) -> NodeRef<T> {
+    unsafe {
+        let node = left.get(gc);
+        let (l, c) = (node.left.unwrap(), node.right.unwrap());
+
+        if height(gc, c) <= height(gc, right) + 1 {
+            let joined = new_branch(gc, c, right);
+
+            if height(gc, joined) <= height(gc, l) + 1 {
+                new_branch(gc, l, joined)
+            } else {
+                rotate_left(gc, new_branch(gc, l, rotate_right(gc, joined)))
+            }
+        } else {
+            let joined = join_right(gc, c, right);
+            let branch = new_branch(gc, l, joined);
+
+            if height(gc, joined) <= height(gc, l) + 1 {
+                branch
+            } else {
+                rotate_left(gc, branch)
+            }
+        }
+    }
+}
+
+/// The mirror image of [`join_right`]
+///
+/// # Safety
+/// - `left` and `right` must be valid and alive
+unsafe fn join_left<T: GCPtr>(
+    gc: &GarbageCollector,
+    left: NodeRef<T>,
+    right: NodeRef<T>,
+) -> NodeRef<T> {
+    unsafe {
+        let node = right.get(gc);
+        let (c, r) = (node.left.unwrap(), node.right.unwrap());
+
+        if height(gc, c) <= height(gc, left) + 1 {
+            let joined = new_branch(gc, left, c);
+
+            if height(gc, joined) <= height(gc, r) + 1 {
+                new_branch(gc, joined, r)
+            } else {
+                rotate_right(gc, new_branch(gc, rotate_left(gc, joined), r))
+            }
+        } else {
+            let joined = join_left(gc, left, c);
+            let branch = new_branch(gc, joined, r);
+
+            if height(gc, joined) <= height(gc, r) + 1 {
+                branch
+            } else {
+                rotate_right(gc, branch)
+            }
+        }
+    }
+}
+
+/// Turns `(a, (b, c))` into `((a, b), c)`
+///
+/// # Safety
+/// - `node` must be a valid branch whose right child is a branch
+unsafe fn rotate_left<T: GCPtr>(gc: &GarbageCollector, node: NodeRef<T>) -> NodeRef<T> {
+    unsafe {
+        let node = node.get(gc);
+        let right = node.right.unwrap().get(gc);
+
+        let left = new_branch(gc, node.left.unwrap(), right.left.unwrap());
+
+        new_branch(gc, left, right.right.unwrap())
+    }
+}
+
+/// Turns `((a, b), c)` into `(a, (b, c))`
+///
+/// # Safety
+/// - `node` must be a valid branch whose left child is a branch
+unsafe fn rotate_right<T: GCPtr>(gc: &GarbageCollector, node: NodeRef<T>) -> NodeRef<T> {
+    unsafe {
+        let node = node.get(gc);
+        let left = node.left.unwrap().get(gc);
+
+        let right = new_branch(gc, left.right.unwrap(), node.right.unwrap());
+
+        new_branch(gc, left.left.unwrap(), right)
+    }
+}
+
+/// Concatenates two trees that may be empty
+///
+/// # Safety
+/// - `left` and `right` must be valid and alive
+unsafe fn join_opt<T: GCPtr>(
+    gc: &GarbageCollector,
+    left: Option<NodeRef<T>>,
+    right: Option<NodeRef<T>>,
+) -> Option<NodeRef<T>> {
+    match (left, right) {
+        (Some(left), Some(right)) => Some(unsafe { join(gc, left, right) }),
+        (left, right) => left.or(right),
+    }
+}
+
+/// Splits a tree into the first `idx` elements and the rest
+///
+/// # Safety
+/// - `node` must be valid and alive
+/// - `idx` must not be greater than the length of `node`
+unsafe fn split_opt<T: GCPtr>(
+    gc: &GarbageCollector,
+    node: Option<NodeRef<T>>,
+    idx: usize,
+) -> (Option<NodeRef<T>>, Option<NodeRef<T>>) {
+    let Some(node_ref) = node else {
+        return (None, None);
+    };
+
+    let node = unsafe { node_ref.get(gc) };
+
+    if idx == 0 {
+        return (None, Some(node_ref));
+    } else if idx == node.len {
+        return (Some(node_ref), None);
+    }
+
+    unsafe {
+        if let Some(chunk) = node.chunk {
+            // Allocating may invalidate the slice, so the elements are copied out first
+            let elements = chunk.as_slice(gc).to_vec();
+            let (left, right) = elements.split_at(idx);
+
+            return (Some(new_leaf(gc, left)), Some(new_leaf(gc, right)));
+        }
+
+        let (left, right) = (node.left.unwrap(), node.right.unwrap());
+        let left_len = left.get(gc).len;
+
+        if idx <= left_len {
+            let (a, b) = split_opt(gc, Some(left), idx);
+
+            (a, join_opt(gc, b, Some(right)))
+        } else {
+            let (a, b) = split_opt(gc, Some(right), idx - left_len);
+
+            (join_opt(gc, Some(left), a), b)
+        }
+    }
+}
+
+impl<T: GCDebug + GCPtr> GCDebug for GCPersistentVec<T> {
+    unsafe fn gc_debug(
+        &self,
+        gc: &GarbageCollector,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        let mut list = f.debug_list();
+
+        for element in unsafe { self.raw_iter(gc) } {
+            list.entry(&unsafe { GCWrap::new(&element, gc) });
+        }
+
+        list.finish()
+    }
+}
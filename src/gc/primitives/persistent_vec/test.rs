@@ -0,0 +1,168 @@
+use crate::gc::{
+    GCPersistentVec, GCString, GarbageCollector,
+    primitives::persistent_vec::{CHUNK_SIZE, NodeRef},
+    safety::{GC, GCCtx, let_gc_and_context, root},
+};
+
+/// Creates a vector of `range` by concatenating single-element vectors
+fn new_vec<'c>(ctx: &'c GCCtx, range: std::ops::Range<usize>) -> GC<'c, GCPersistentVec<usize>> {
+    range.fold(GCPersistentVec::new_empty(ctx), |vec, element| {
+        // SAFETY: `usize`s are always valid
+        let single = unsafe { GC::new(ctx, GCPersistentVec::new(ctx, &[element])) };
+
+        vec.concat(ctx, single)
+    })
+}
+
+fn contents(vec: GC<GCPersistentVec<usize>>) -> Vec<usize> {
+    vec.iter().map(|element| element.raw()).collect()
+}
+
+/// Checks that the tree is balanced and that the lengths and heights are correct. Returns the
+/// number of leaves.
+fn check_invariants(gc: &GarbageCollector, vec: GC<GCPersistentVec<usize>>) -> usize {
+    fn check_node(gc: &GarbageCollector, node: NodeRef<usize>) -> usize {
+        let node = unsafe { node.get(gc) };
+
+        if let Some(chunk) = node.chunk {
+            let len = unsafe { chunk.len(gc) };
+
+            assert!(len > 0 && len <= CHUNK_SIZE);
+            assert_eq!(node.len, len);
+            assert_eq!(node.height, 0);
+
+            return 1;
+        }
+
+        let (left, right) = (node.left.unwrap(), node.right.unwrap());
+        let (l, r) = unsafe { (left.get(gc), right.get(gc)) };
+
+        assert!(l.height.abs_diff(r.height) <= 1, "the tree is unbalanced");
+        assert_eq!(node.height, l.height.max(r.height) + 1);
+        assert_eq!(node.len, l.len + r.len);
+
+        check_node(gc, left) + check_node(gc, right)
+    }
+
+    vec.raw().root.map_or(0, |root| check_node(gc, root))
+}
+
+#[test]
+fn push() {
+    let_gc_and_context!(gc, ctx);
+
+    let mut vec = GCPersistentVec::new_empty(ctx);
+    let empty = vec;
+
+    for i in 0..1000 {
+        vec = vec.push(ctx, GC::new_non_gc(ctx, i));
+    }
+
+    assert_eq!(contents(vec), (0..1000).collect::<Vec<_>>());
+    assert_eq!(vec.len(), 1000);
+    assert_eq!(vec.get(999).map(|element| element.raw()), Some(999));
+    assert!(vec.get(1000).is_none());
+
+    // Appending one element at a time fills up every chunk
+    assert_eq!(check_invariants(&gc, vec), 1000usize.div_ceil(CHUNK_SIZE));
+
+    // Older versions are not modified
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn bulk_build() {
+    let_gc_and_context!(gc, ctx);
+
+    for len in [0, 1, CHUNK_SIZE, CHUNK_SIZE + 1, 1000] {
+        let elements: Vec<usize> = (0..len).collect();
+
+        // SAFETY: `usize`s are always valid
+        let vec = unsafe { GC::new(ctx, GCPersistentVec::new(ctx, &elements)) };
+
+        assert_eq!(contents(vec), elements);
+        assert_eq!(check_invariants(&gc, vec), len.div_ceil(CHUNK_SIZE));
+    }
+}
+
+#[test]
+fn concat() {
+    let_gc_and_context!(gc, ctx);
+
+    let lens = [0, 1, 5, CHUNK_SIZE, CHUNK_SIZE + 1, 100, 1000, 5000];
+
+    for lhs_len in lens {
+        for rhs_len in lens {
+            let lhs = new_vec(ctx, 0..lhs_len);
+            let rhs = new_vec(ctx, lhs_len..lhs_len + rhs_len);
+
+            let vec = lhs.concat(ctx, rhs);
+
+            assert_eq!(contents(vec), (0..lhs_len + rhs_len).collect::<Vec<_>>());
+            check_invariants(&gc, vec);
+
+            assert_eq!(contents(lhs), (0..lhs_len).collect::<Vec<_>>());
+        }
+    }
+
+    // Repeatedly concatenating small vectors keeps the tree balanced
+    let mut vec = GCPersistentVec::new_empty(ctx);
+
+    for i in 0..200 {
+        vec = vec.concat(ctx, new_vec(ctx, i * 7..(i + 1) * 7));
+    }
+
+    assert_eq!(contents(vec), (0..1400).collect::<Vec<_>>());
+    check_invariants(&gc, vec);
+}
+
+#[test]
+fn slice() {
+    let_gc_and_context!(gc, ctx);
+
+    let vec = new_vec(ctx, 0..300);
+
+    for start in (0..=300).step_by(13) {
+        for end in (start..=300).step_by(11) {
+            let slice = vec.slice(ctx, start..end).unwrap();
+
+            assert_eq!(contents(slice), (start..end).collect::<Vec<_>>());
+            check_invariants(&gc, slice);
+        }
+    }
+
+    assert!(vec.slice(ctx, 0..301).is_none());
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = 20..10;
+    assert!(vec.slice(ctx, reversed).is_none());
+
+    assert_eq!(contents(vec), (0..300).collect::<Vec<_>>());
+}
+
+#[test]
+fn survives_collection() {
+    let_gc_and_context!(gc, ctx);
+
+    let strings: Vec<String> = (0..100)
+        .map(|i| format!("a string that is too long to be stored inline: {i}"))
+        .collect();
+
+    let mut vec = GCPersistentVec::new_empty(ctx);
+
+    for string in &strings {
+        vec = vec.push(ctx, GCString::new(ctx, string));
+    }
+
+    let vec = root!(ctx, vec);
+    let half = root!(ctx, vec.get(ctx).slice(ctx, 50..100).unwrap());
+
+    ctx.force_minor_collect();
+    ctx.force_collect();
+
+    let read = |vec: GC<GCPersistentVec<GCString>>| -> Vec<String> {
+        vec.iter().map(|string| string.read().to_owned()).collect()
+    };
+
+    assert_eq!(read(vec.get(ctx)), strings);
+    assert_eq!(read(half.get(ctx)), strings[50..]);
+}
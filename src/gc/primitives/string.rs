@@ -43,7 +43,36 @@ impl GCString {
 
     /// Creates a new garbage collected string.
     pub fn new_raw(gc: &GarbageCollector, string: &str) -> Self {
-        Self::new_in_space(&gc.from_space, string)
+        Self::new_in_space(&gc.nursery, string)
+    }
+
+    /// Creates an interned string that can be used with the safe garbage-collection API. See
+    /// [`GCString::intern_raw`].
+    pub fn intern<'c, 'gc>(ctx: &'c GCCtx<'gc>, string: &'_ str) -> GC<'c, Self> {
+        unsafe { GC::new(ctx, Self::intern_raw(ctx, string)) }
+    }
+
+    /// Creates an interned string. Interned strings with the same contents share their storage
+    /// for as long as any of them is alive, so they can be compared with [`GCString::ptr_eq`].
+    /// This is meant for names, which tend to be repeated many times.
+    pub fn intern_raw(gc: &GarbageCollector, string: &str) -> Self {
+        if Self::fits_inline(string) {
+            return Self::new_raw(gc, string);
+        }
+
+        gc.intern(string)
+    }
+
+    /// Whether `self` and `other` are the same string. This is always true for interned strings
+    /// with the same contents, but it may be false for equal strings that were not interned.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        // Unused bytes of inline strings are always zero
+        self.ptr == other.ptr && self.len == other.len
+    }
+
+    /// Whether `string` is stored inline rather than on the GC heap
+    pub(in crate::gc) fn fits_inline(string: &str) -> bool {
+        string.len() < std::mem::size_of::<GCString>() && string.len() <= 127
     }
 
     /// Gets the string if it is less than 2 * sizeof(usize) bytes long. Otherwise returns `None`
@@ -68,14 +97,18 @@ impl GCString {
     /// # Safety
     /// `self` must be valid, and a garbage-collection cycle must not be in progress
     pub unsafe fn get<'a>(&'a self, gc: &'a GarbageCollector) -> &'a str {
-        unsafe { self.get_in_space(&gc.from_space) }
+        if let Some(string) = self.get_inline() {
+            return string;
+        }
+
+        unsafe { self.get_in_space(gc.space_of(self.ptr.get())) }
     }
 
     /// Creates a garbage collected string in a given `GCSpace`
     fn new_in_space(space: &GCSpace, string: &str) -> Self {
         // If the string is small enough, it can be stored inline rather than on the GC Heap
 
-        if string.len() < std::mem::size_of::<GCString>() && string.len() <= 127 {
+        if Self::fits_inline(string) {
             let discriminant: usize = (0b1000_0000 | string.len()) << (usize::BITS - 8); // The MSB being set signifies that the string is stored inline
 
             let mut retval = GCString {
@@ -132,12 +165,14 @@ impl GCString {
 }
 
 impl GCString {
-    unsafe fn get_forwarded_value(&self, gc: &GarbageCollector) -> Option<Self> {
-        if self.get_inline().is_some() {
+    /// Gets the new location of the string if it has been copied by the current collection, or
+    /// `self` if it is not moved by it. Returns `None` if the string has not been copied yet.
+    pub(in crate::gc) unsafe fn get_forwarded_value(&self, gc: &GarbageCollector) -> Option<Self> {
+        if self.get_inline().is_some() || !gc.is_evacuating(self.ptr.get()) {
             return Some(*self);
         }
 
-        let ptr = gc.from_space.block_ptr(self.ptr);
+        let ptr = gc.block_ptr(self.ptr);
         let discriminant = unsafe { ptr.cast::<usize>().read() };
 
         // If the most-significant-byte is not `0xFF`, it is not a forwarded value.
@@ -166,19 +201,61 @@ unsafe impl GCPtr for GCString {
             return forward;
         }
 
-        let to_value =
-            Self::new_in_space(&gc.to_space, unsafe { self.get_in_space(&gc.from_space) });
+        let to_value = Self::new_in_space(gc.copy_target(), unsafe { self.get(gc) });
 
-        let forward = to_value.ptr.get() | 0xFF;
+        let forward = to_value.ptr.get() | (0xFF << (usize::BITS - 8));
         unsafe {
-            gc.from_space
-                .block_ptr(self.ptr)
-                .cast::<usize>()
-                .write(forward);
+            gc.block_ptr(self.ptr).cast::<usize>().write(forward);
         }
 
         to_value
     }
+
+    unsafe fn gc_trace(self, tracer: &mut crate::gc::dump::HeapTracer) {
+        if self.get_inline().is_some() {
+            return;
+        }
+
+        tracer.object(
+            self.ptr.get(),
+            std::any::type_name::<Self>(),
+            self.len.div_ceil(GarbageCollector::BLOCK_SIZE),
+            |_, _| {},
+        );
+    }
+
+    #[cfg(feature = "verify-heap")]
+    unsafe fn gc_verify(self, verifier: &mut crate::gc::verify::HeapVerifier) {
+        let string = if let Some(string) = self.get_inline() {
+            let len = (self.ptr.get() >> (usize::BITS - 8)) & 0b0111_1111;
+
+            if len >= std::mem::size_of::<GCString>() {
+                verifier.fail(format_args!("inline string has an invalid length of {len}"));
+            }
+
+            string
+        } else {
+            let size_blocks = self.len.div_ceil(GarbageCollector::BLOCK_SIZE);
+
+            if !verifier.check_object(self.ptr.get(), size_blocks) {
+                return;
+            }
+
+            let discriminant = unsafe { verifier.gc().block_ptr(self.ptr).cast::<usize>().read() };
+
+            if discriminant >> (usize::BITS - 8) == 0xFF {
+                verifier.fail(format_args!(
+                    "reachable forward pointer ({discriminant:#x})"
+                ));
+            }
+
+            unsafe { self.get(verifier.gc()) }
+        };
+
+        if std::str::from_utf8(string.as_bytes()).is_err() {
+            verifier.fail("string is not valid UTF-8");
+        }
+    }
 }
 
 impl GCGet for GCString {
@@ -207,6 +284,6 @@ impl GCEq<str> for GCString {
 
 impl GCEq<GCString> for GCString {
     unsafe fn gc_eq(&self, gc: &GarbageCollector, rhs: &GCString) -> bool {
-        unsafe { self.get(gc) == rhs.get(gc) }
+        self.ptr_eq(rhs) || unsafe { self.get(gc) == rhs.get(gc) }
     }
 }
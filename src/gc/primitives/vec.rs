@@ -2,6 +2,8 @@ use std::{marker::PhantomData, num::NonZeroUsize, ptr};
 
 use crate::gc::{
     GCDebug, GCEq, GCGet, GCPtr, GCSpace, GarbageCollector,
+    dump::HeapTracer,
+    incremental::UnscannedEntry,
     primitives::buffer::GCBuffer,
     roots::GCRootInfo,
     safety::{GC, GCCtx},
@@ -23,9 +25,13 @@ pub struct GCVec<T: GCPtr> {
     _phantomdata: PhantomData<*mut T>,
 }
 
+/// An iterator over the elements of a [`GCVec`]. Elements are read one at a time, so the loop body
+/// may allocate (which can move the space that the vector is in).
 pub struct SafeGCVecIter<'a, T: GCPtr> {
     gc: &'a GarbageCollector,
-    data: &'a [T],
+    buffer: GCBuffer<T>,
+    /// The index of the next element
+    idx: usize,
 }
 
 impl<'a, T: GCPtr> GC<'a, GCVec<T>> {
@@ -42,11 +48,11 @@ impl<'a, T: GCPtr> GC<'a, GCVec<T>> {
     }
 
     pub fn iter(&self) -> SafeGCVecIter<'a, T> {
-        unsafe {
-            SafeGCVecIter {
-                gc: self.gc(),
-                data: (*self).raw().as_buffer(self.gc()).as_slice(self.gc()),
-            }
+        SafeGCVecIter {
+            gc: self.gc(),
+            // SAFETY: `self` is valid because it's wrapped in `GC`
+            buffer: unsafe { self.raw().as_buffer(self.gc()) },
+            idx: 0,
         }
     }
 }
@@ -55,14 +61,27 @@ impl<'a, T: GCPtr> Iterator for SafeGCVecIter<'a, T> {
     type Item = GC<'a, T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let (item, remaining) = self.data.split_first()?;
+        if self.idx == self.buffer.len() {
+            return None;
+        }
 
-        self.data = remaining;
+        // SAFETY: the pointer is recalculated for every element, so it is valid even if the space
+        // was reallocated since the last element
+        let item = unsafe { self.buffer.element_ptr(self.gc, self.idx).read() };
+        self.idx += 1;
 
-        Some(unsafe { GC::from_raw_parts(self.gc, *item) })
+        Some(unsafe { GC::from_raw_parts(self.gc, item) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.buffer.len() - self.idx;
+
+        (remaining, Some(remaining))
     }
 }
 
+impl<T: GCPtr> ExactSizeIterator for SafeGCVecIter<'_, T> {}
+
 impl<T: GCPtr> GCVec<T> {
     /// # Safety
     /// - All `elements` must be valid and alive
@@ -78,7 +97,7 @@ impl<T: GCPtr> GCVec<T> {
     /// # Safety
     /// A garbage-collection cycle cannot be triggered before this is fully initialized.
     pub unsafe fn new_uninit(gc: &GarbageCollector, len: usize) -> Self {
-        unsafe { Self::new_uninit_in_space(&gc.from_space, len) }
+        unsafe { Self::new_uninit_in_space(&gc.nursery, len) }
     }
 
     pub fn new_empty<'c>(ctx: &'c GCCtx) -> GC<'c, Self> {
@@ -93,8 +112,7 @@ impl<T: GCPtr> GCVec<T> {
     where
         I: Iterator<Item = GC<'b, T>>,
     {
-        let vec = unsafe { Self::new_uninit_in_space(&ctx.from_space, len) };
-        let mut ptr = vec.element_ptr(ctx, 0);
+        let vec = unsafe { Self::new_uninit_in_space(&ctx.nursery, len) };
 
         let mut count = 0;
 
@@ -102,9 +120,10 @@ impl<T: GCPtr> GCVec<T> {
             assert!(count < len);
             assert!(ptr::eq(element.gc(), **ctx));
 
-            unsafe { ptr.write(element.raw()) };
+            // The iterator may allocate, which can move from-space, so the pointer has to be
+            // recalculated for every element
+            unsafe { vec.element_ptr(ctx, count).write(element.raw()) };
 
-            ptr = ptr.wrapping_add(1);
             count += 1;
         }
 
@@ -161,7 +180,7 @@ impl<T: GCPtr> GCVec<T> {
     /// # Safety
     /// `vec` must be a valid, non-frozen `GCVec` in `Self`
     fn element_ptr(&self, gc: &GarbageCollector, index: usize) -> *mut T {
-        self.element_ptr_in_space(&gc.from_space, index)
+        self.element_ptr_in_space(gc.space_of(self.ptr.get()), index)
     }
 
     /// Allocates an unitialized garbage-collected dynamically-sized array
@@ -192,7 +211,7 @@ impl<T: GCPtr> GCVec<T> {
     }
 
     unsafe fn get_forwarded_value(&self, gc: &GarbageCollector) -> Option<Self> {
-        let discriminant = unsafe { gc.from_space.block_ptr(self.ptr).cast::<usize>().read() };
+        let discriminant = unsafe { gc.block_ptr(self.ptr).cast::<usize>().read() };
         if discriminant & 1usize.rotate_right(1) == 0 {
             return None;
         }
@@ -205,6 +224,27 @@ impl<T: GCPtr> GCVec<T> {
     }
 }
 
+/// Copies the objects that the elements of a `GCVec` in the old generation reference. This is used
+/// for vecs that a major collection copied without scanning.
+unsafe fn scan<T: GCPtr>(ptr: NonZeroUsize, gc: &GarbageCollector) {
+    let vec = GCVec::<T> {
+        ptr,
+        _phantomdata: PhantomData,
+    };
+    let len = unsafe { gc.copy_target().block_ptr(ptr).cast::<usize>().read() };
+
+    for i in 0..len {
+        let old_element = unsafe { vec.element_ptr_in_space(gc.copy_target(), i).read() };
+        let new_element = unsafe { old_element.gc_copy(gc) };
+
+        // Copying may have grown the old generation, so the pointer is recalculated
+        unsafe {
+            vec.element_ptr_in_space(gc.copy_target(), i)
+                .write(new_element)
+        };
+    }
+}
+
 unsafe impl<T> GCPtr for GCVec<T>
 where
     T: GCPtr,
@@ -212,26 +252,47 @@ where
     const MSB_RESERVED: bool = true;
 
     unsafe fn gc_copy(self, gc: &GarbageCollector) -> Self {
+        if !gc.is_evacuating(self.ptr.get()) {
+            return self;
+        }
+
         if let Some(fwd) = unsafe { self.get_forwarded_value(gc) } {
             return fwd;
         }
 
-        let from_base_ptr = gc.from_space.block_ptr(self.ptr);
+        let from_base_ptr = gc.block_ptr(self.ptr);
         let len = unsafe { from_base_ptr.cast::<usize>().read() };
 
         // We must allocate the vec and write the forward pointer before copying the elements
         // because they may contain references to `self`
-        let new_vec = unsafe { Self::new_uninit_in_space(&gc.to_space, len) };
+        let new_vec = unsafe { Self::new_uninit_in_space(gc.copy_target(), len) };
         let discriminant = new_vec.ptr | 1usize.rotate_right(1);
         unsafe { from_base_ptr.cast::<usize>().write(discriminant.get()) };
 
+        if gc.defers_scanning() {
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    self.element_ptr(gc, 0),
+                    new_vec.element_ptr_in_space(gc.copy_target(), 0),
+                    len,
+                )
+            };
+
+            gc.defer_scan(UnscannedEntry {
+                ptr: new_vec.ptr,
+                scan_fn: scan::<T>,
+            });
+
+            return new_vec;
+        }
+
         for i in 0..len {
-            let old_element = unsafe { self.element_ptr_in_space(&gc.from_space, i).read() };
+            let old_element = unsafe { self.element_ptr(gc, i).read() };
             let new_element = unsafe { old_element.gc_copy(gc) };
 
             unsafe {
                 new_vec
-                    .element_ptr_in_space(&gc.to_space, i)
+                    .element_ptr_in_space(gc.copy_target(), i)
                     .write(new_element)
             };
         }
@@ -239,6 +300,52 @@ where
         new_vec
     }
 
+    unsafe fn gc_trace(self, tracer: &mut HeapTracer) {
+        unsafe fn trace_references<T: GCPtr>(ptr: usize, tracer: &mut HeapTracer) {
+            let gc = tracer.gc();
+            let vec = GCVec::<T> {
+                ptr: NonZeroUsize::new(ptr).unwrap(),
+                _phantomdata: PhantomData,
+            };
+
+            for i in 0..unsafe { vec.as_buffer(gc) }.len() {
+                unsafe { vec.element_ptr(gc, i).read().gc_trace(tracer) };
+            }
+        }
+
+        let buffer = unsafe { self.as_buffer(tracer.gc()) };
+
+        // The length is stored in the block before the buffer
+        tracer.object(
+            self.ptr.get(),
+            std::any::type_name::<Self>(),
+            1 + buffer.size_blocks(),
+            trace_references::<T>,
+        );
+    }
+
+    #[cfg(feature = "verify-heap")]
+    unsafe fn gc_verify(self, verifier: &mut crate::gc::verify::HeapVerifier) {
+        if !verifier.check_object(self.ptr.get(), 1) {
+            return;
+        }
+
+        verifier.check_not_forwarded(self.ptr.get());
+
+        let gc = verifier.gc();
+        let buffer = unsafe { self.as_buffer(gc) };
+
+        verifier.check_object(buffer.gc_ptr().get(), buffer.size_blocks());
+
+        for i in 0..buffer.len() {
+            let element = unsafe { self.element_ptr(gc, i).read() };
+
+            verifier.segment(format_args!("[{i}]"), |verifier| unsafe {
+                element.gc_verify(verifier)
+            });
+        }
+    }
+
     unsafe fn to_gc_root_entry(self, _gc: &GarbageCollector) -> GCRootInfo {
         unsafe fn copy_fn<T: GCPtr>(data: NonZeroUsize, gc: &GarbageCollector) -> NonZeroUsize {
             let old = GCVec::<T>::from_ptr(data);
@@ -246,8 +353,13 @@ where
             new.ptr()
         }
 
+        unsafe fn trace_fn<T: GCPtr>(data: NonZeroUsize, tracer: &mut HeapTracer) {
+            unsafe { GCVec::<T>::from_ptr(data).gc_trace(tracer) };
+        }
+
         GCRootInfo {
             copy_fn: copy_fn::<T>,
+            trace_fn: trace_fn::<T>,
             data_ptr: self.ptr(),
         }
     }
@@ -1,6 +1,6 @@
 use std::{cell::UnsafeCell, marker::PhantomData, num::NonZeroUsize};
 
-use crate::gc::{GCPtr, GarbageCollector};
+use crate::gc::{GCPtr, GarbageCollector, incremental::RootSlot};
 
 pub use rootlist::GCRootList;
 
@@ -45,6 +45,14 @@ mod rootlist {
             unsafe { self.roots.get().as_ref_unchecked().len() }
         }
 
+        /// The number of roots that haven't been freed
+        pub fn num_live(&self) -> usize {
+            unsafe { self.roots.get().as_ref_unchecked() }
+                .iter()
+                .filter(|root| root.is_some())
+                .count()
+        }
+
         pub(super) fn push(&self, entry: GCRootEntry) {
             unsafe { self.roots.get().as_mut_unchecked().push(Some(entry)) };
         }
@@ -65,6 +73,10 @@ pub struct GCRootInfo {
     /// The function that the garbage collector calls to copy this entry. Its first argument is `data_ptr`.
     pub(crate) copy_fn: unsafe fn(NonZeroUsize, &GarbageCollector) -> NonZeroUsize,
 
+    /// The function that heap dumps call to trace the objects that this entry references,
+    /// including `data_ptr` itself if it is an object
+    pub(crate) trace_fn: unsafe fn(NonZeroUsize, &mut crate::gc::dump::HeapTracer),
+
     /// For most types, this is a `GCBox<Self>`. This data is determined by a type's `GCPtr`
     /// implementation.
     pub(crate) data_ptr: NonZeroUsize,
@@ -79,9 +91,21 @@ pub(super) struct GCRootEntry {
     /// implementation.
     pub(crate) data_ptr: NonZeroUsize,
 
+    /// Whether this still points into the from-space of the major collection that is in progress.
+    /// Pending entries are evacuated before they are read.
+    pub(crate) pending: bool,
+
     /// The name of the type stored. This is only used for debug assertions.
     #[cfg(debug_assertions)]
     pub(crate) type_name: &'static str,
+
+    /// The function that heap dumps call to trace the objects that this entry references,
+    /// including `data_ptr` itself if it is an object
+    pub(crate) trace_fn: unsafe fn(NonZeroUsize, &mut crate::gc::dump::HeapTracer),
+
+    /// The function that the heap verifier calls to check this entry
+    #[cfg(feature = "verify-heap")]
+    pub(crate) verify_fn: unsafe fn(NonZeroUsize, &mut crate::gc::verify::HeapVerifier),
 }
 
 /// A raw reference to a GC root.
@@ -94,14 +118,20 @@ pub struct GCRootRef<T> {
 }
 
 impl<T: GCPtr> GCRootRef<T> {
-    /// Gets the value of a GC root without removing it.
+    /// Gets the value of a GC root without removing it. If a major collection is in progress and
+    /// the root has not been evacuated (or scanned) yet, that is finished first.
     ///
     /// # Safety
     /// - `self` must point to a valid root in `gc`
     pub unsafe fn get(&self, gc: &GarbageCollector) -> Option<T> {
         debug_assert!(self.index < gc.roots.len());
 
-        let entry = unsafe { gc.roots.get_unchecked(self.index) }?;
+        let mut entry = unsafe { gc.roots.get_unchecked(self.index) }?;
+
+        if entry.pending || gc.is_scanning(RootSlot::Root(self.index)) {
+            entry = unsafe { gc.evacuate_entry(entry) };
+            gc.roots.set(self.index, Some(entry));
+        }
 
         #[cfg(debug_assertions)]
         assert_eq!(entry.type_name, core::any::type_name::<T>());
@@ -165,9 +195,14 @@ impl GarbageCollector {
         let entry = GCRootEntry {
             copy_fn: info.copy_fn,
             data_ptr: info.data_ptr,
+            pending: false,
+            trace_fn: info.trace_fn,
 
             #[cfg(debug_assertions)]
             type_name: ::core::any::type_name::<T>(),
+
+            #[cfg(feature = "verify-heap")]
+            verify_fn: crate::gc::verify::verify_root::<T>,
         };
 
         self.roots.push(entry);
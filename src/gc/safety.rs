@@ -9,7 +9,9 @@ use std::{
     pin::Pin,
 };
 
-use crate::gc::{GCDebug, GCGet, GCProject, GCPtr, GCRootRef, GarbageCollector, NonGC};
+use crate::gc::{
+    GCDebug, GCGet, GCProject, GCPtr, GCRootRef, GarbageCollector, NonGC, dump::HeapDump,
+};
 
 mod function_call;
 pub use function_call::*;
@@ -17,9 +19,16 @@ pub use function_call::*;
 /// Creates a garbage-collector object and a context object.
 ///
 /// This is the only safe way to create a context.
+#[macro_export]
 macro_rules! let_gc_and_context {
     ($gc_name:ident, $ctx_name:ident) => {
-        let $gc_name = $crate::gc::GarbageCollector::new();
+        $crate::gc::safety::let_gc_and_context!(
+            $gc_name = $crate::gc::GarbageCollector::new(),
+            $ctx_name
+        );
+    };
+    ($gc_name:ident = $gc:expr, $ctx_name:ident) => {
+        let $gc_name = $gc;
 
         #[allow(unused_mut)]
         let mut $ctx_name = unsafe { $crate::gc::safety::GCCtx::new(&$gc_name) };
@@ -28,7 +37,7 @@ macro_rules! let_gc_and_context {
     };
 }
 
-pub(crate) use let_gc_and_context;
+pub use let_gc_and_context;
 
 /// The garbage collection context. This is used for the safe garbage collection API. If a function
 /// takes in a mutable reference to the context, it can trigger a garbage-collection cycle. If a
@@ -46,7 +55,7 @@ impl<'gc> GCCtx<'gc> {
         Self { gc }
     }
 
-    /// Forcefully performs a garbage collection cycle.
+    /// Forcefully performs a major garbage collection cycle.
     ///
     /// This only makes sense to use for testing purposes.
     pub fn force_collect(&mut self) {
@@ -55,12 +64,34 @@ impl<'gc> GCCtx<'gc> {
         }
     }
 
+    /// Forcefully performs a minor garbage collection cycle, which only collects the nursery.
+    ///
+    /// This only makes sense to use for testing purposes.
+    pub fn force_minor_collect(&mut self) {
+        unsafe {
+            self.gc.force_minor_collect();
+        }
+    }
+
     /// Performs a garbage collection cycle if it makes sense to at the given moment.
     pub fn collect(&mut self) {
         unsafe {
             self.gc.collect();
         }
     }
+
+    /// Records every object that is reachable from a root or a handle. See
+    /// [`GarbageCollector::heap_dump`].
+    pub fn heap_dump(&self) -> HeapDump {
+        // SAFETY: the safe API never runs during a garbage-collection cycle
+        unsafe { self.gc.heap_dump() }
+    }
+
+    /// Does a bounded amount of garbage-collection work. Returns whether a major collection is
+    /// still in progress. See [`GarbageCollector::collect_slice`].
+    pub fn collect_slice(&mut self, budget: usize) -> bool {
+        unsafe { self.gc.collect_slice(budget) }
+    }
 }
 
 impl<'gc> Deref for GCCtx<'gc> {
@@ -238,6 +269,7 @@ macro_rules! rebind {
 /// functions that can trigger GC cycles but also take in unmanaged garbage-collected data.
 ///
 /// See [`#[gc_fn]`](mulch_macros::gc_fn) for more information.
+#[macro_export]
 macro_rules! gc_args {
     ($context:ident, $($args:expr),+ $(,)?) => {
         {
@@ -275,7 +307,7 @@ pub(crate) use root;
 
 pub(crate) use rebind;
 
-pub(crate) use gc_args;
+pub use gc_args;
 
 impl<'c, T: GCPtr + NonGC> GC<'c, T> {
     pub fn new_non_gc(gc: &'c GarbageCollector, value: T) -> Self {
@@ -1,4 +1,4 @@
-use crate::gc::safety::GCCtx;
+use crate::gc::{GCPtr, GarbageCollector, safety::GCCtx};
 
 /// A bundle of garbage-collected arguments. These are used for calling functions that
 /// can trigger GC cycles but also take in unmanaged garbage-collected data.
@@ -23,7 +23,70 @@ impl<'gc, 'ctx, B> GCArgs<'gc, 'ctx, B> {
     }
 
     /// Splits `GCArgs` into a mutable context reference and its raw GC pointers.
-    pub fn split(self) -> (&'ctx mut GCCtx<'gc>, B) {
-        (self.context, self.inner_tuple)
+    ///
+    /// This is a safe point: if the garbage collector is in stress mode, a collection is done
+    /// here.
+    pub fn split(self) -> (&'ctx mut GCCtx<'gc>, B)
+    where
+        B: GCArgTuple,
+    {
+        let mut inner_tuple = self.inner_tuple;
+
+        if self.context.is_stressed() {
+            // SAFETY: the caller gave up its context, so the arguments are the only values that
+            // are allowed to be unrooted
+            inner_tuple = unsafe { inner_tuple.collect_rooted(self.context) };
+        }
+
+        (self.context, inner_tuple)
+    }
+}
+
+/// A tuple of raw garbage-collected values that can be passed with [`GCArgs`]
+pub trait GCArgTuple: Sized {
+    /// Roots the values, does a garbage-collection cycle, and returns the values that were moved
+    /// by it.
+    ///
+    /// # Safety
+    /// - The values must be valid and alive
+    /// - This must not be called while the safe garbage-collection API holds unrooted values
+    unsafe fn collect_rooted(self, gc: &GarbageCollector) -> Self;
+}
+
+impl GCArgTuple for () {
+    unsafe fn collect_rooted(self, gc: &GarbageCollector) -> Self {
+        unsafe { gc.force_collect() };
     }
 }
+
+macro_rules! impl_gc_arg_tuple {
+    ($first_ty:ident $first:ident $(, $ty:ident $value:ident)*) => {
+        impl<$first_ty: GCPtr, $($ty: GCPtr),*> GCArgTuple for ($first_ty, $($ty,)*) {
+            unsafe fn collect_rooted(self, gc: &GarbageCollector) -> Self {
+                let ($first, $($value,)*) = self;
+
+                let $first = unsafe { gc.push_root($first) };
+                $(let $value = unsafe { gc.push_root($value) };)*
+
+                unsafe { gc.force_collect() };
+
+                let values = unsafe {
+                    ($first.get(gc).unwrap(), $($value.get(gc).unwrap(),)*)
+                };
+
+                // Every root after `first` is removed along with it
+                $($value.forget();)*
+                unsafe { gc.truncate_roots($first) };
+
+                values
+            }
+        }
+    };
+}
+
+impl_gc_arg_tuple!(A a);
+impl_gc_arg_tuple!(A a, B b);
+impl_gc_arg_tuple!(A a, B b, C c);
+impl_gc_arg_tuple!(A a, B b, C c, D d);
+impl_gc_arg_tuple!(A a, B b, C c, D d, E e);
+impl_gc_arg_tuple!(A a, B b, C c, D d, E e, F f);
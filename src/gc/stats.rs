@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use crate::gc::GarbageCollector;
+
+/// The kind of a garbage-collection cycle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollectionKind {
+    /// Only the nursery was collected. Surviving objects were promoted to the old generation.
+    Minor,
+    /// Every generation was collected
+    Major,
+}
+
+/// Information about a single garbage-collection cycle. This is passed to the callback set with
+/// [`GarbageCollector::set_collection_callback`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollectionEvent {
+    pub kind: CollectionKind,
+    /// The number of bytes that were copied out of the collected generations
+    pub bytes_copied: usize,
+    /// The number of occupied blocks after the cycle
+    pub live_blocks: usize,
+    /// The number of roots and handles that the cycle started from
+    pub roots: usize,
+    /// How long the cycle took. For major collections that were done in slices, this only
+    /// includes the time spent in slices.
+    pub duration: Duration,
+}
+
+/// Statistics about a garbage collector. These are obtained with [`GarbageCollector::stats`].
+///
+/// Capacities are in blocks of [`GarbageCollector::BLOCK_SIZE`] bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GCStats {
+    pub minor_collections: usize,
+    pub major_collections: usize,
+    /// The total number of bytes copied by every cycle
+    pub bytes_copied: usize,
+    /// The number of occupied blocks after the last cycle
+    pub live_blocks: usize,
+    pub nursery_capacity: usize,
+    pub old_capacity: usize,
+    /// The capacity of the space that holds the old generation while a major collection
+    /// evacuates it
+    pub to_space_capacity: usize,
+    /// The number of roots and handles that are currently alive. A number that keeps growing
+    /// between evaluations usually means that roots are being forgotten.
+    pub roots: usize,
+    /// The total time spent collecting garbage
+    pub time: Duration,
+}
+
+/// A function that is called after every garbage-collection cycle
+pub type CollectionCallback = Box<dyn FnMut(&CollectionEvent)>;
+
+impl GarbageCollector {
+    /// Gets statistics about the collections that have been run and the current size of the heap
+    pub fn stats(&self) -> GCStats {
+        GCStats {
+            nursery_capacity: self.nursery.capacity(),
+            old_capacity: self.old.capacity(),
+            to_space_capacity: self.to_space.capacity(),
+            roots: self.num_roots(),
+            ..self.stats.get()
+        }
+    }
+
+    /// Sets a function that is called after every garbage-collection cycle. This replaces the
+    /// previous callback. The callback cannot use the garbage collector.
+    pub fn set_collection_callback(&self, callback: Option<CollectionCallback>) {
+        *self.collection_callback.borrow_mut() = callback;
+    }
+
+    pub(super) fn num_roots(&self) -> usize {
+        self.roots.num_live() + self.handles.num_live()
+    }
+
+    /// Records a finished cycle and reports it to the collection callback
+    pub(super) fn finish_cycle(
+        &self,
+        kind: CollectionKind,
+        duration: Duration,
+        bytes_copied: usize,
+        roots: usize,
+    ) {
+        let event = CollectionEvent {
+            kind,
+            bytes_copied,
+            live_blocks: self.old.used() + self.nursery.used(),
+            roots,
+            duration,
+        };
+
+        let mut stats = self.stats.get();
+
+        match kind {
+            CollectionKind::Minor => stats.minor_collections += 1,
+            CollectionKind::Major => stats.major_collections += 1,
+        }
+
+        stats.bytes_copied += event.bytes_copied;
+        stats.live_blocks = event.live_blocks;
+        stats.time += event.duration;
+
+        self.stats.set(stats);
+
+        if let Some(callback) = self.collection_callback.borrow_mut().as_mut() {
+            callback(&event);
+        }
+    }
+}
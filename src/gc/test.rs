@@ -1,4 +1,7 @@
-use crate::gc::{GCString, safety::let_gc_and_context};
+use crate::gc::{
+    GCString, GCVec,
+    safety::{GC, let_gc_and_context},
+};
 
 #[test]
 fn gcspace_string_test() {
@@ -6,9 +9,9 @@ fn gcspace_string_test() {
 
     let_gc_and_context!(gc, ctx);
 
-    let string0 = GCString::new(&ctx, strings[0]);
-    let string1 = GCString::new(&ctx, strings[1]);
-    let string2 = GCString::new(&ctx, strings[2]);
+    let string0 = GCString::new(ctx, strings[0]);
+    let string1 = GCString::new(ctx, strings[1]);
+    let string2 = GCString::new(ctx, strings[2]);
 
     assert_eq!(string0.raw().get_inline(), Some(strings[0]));
     assert_eq!(string1.raw().get_inline(), None);
@@ -18,3 +21,21 @@ fn gcspace_string_test() {
     assert_eq!(string1.read(), strings[1]);
     assert_eq!(string2.read(), strings[2]);
 }
+
+#[test]
+fn vec_iteration_while_allocating() {
+    let_gc_and_context!(gc, ctx);
+
+    let numbers: Vec<usize> = (0..2000).collect();
+    let vec = unsafe { GC::new(ctx, GCVec::new(ctx, &numbers)) };
+
+    // Allocating grows the nursery, which can move it
+    for (i, number) in vec.iter().enumerate() {
+        let _ = GCString::new(
+            ctx,
+            &"a string that is too long to be stored inline".repeat(8),
+        );
+
+        assert_eq!(number.raw(), i);
+    }
+}
@@ -1,6 +1,8 @@
 use std::{fmt::Formatter, num::NonZeroUsize};
 
-use crate::gc::{GCBox, GarbageCollector, roots::GCRootInfo, safety::GC, util::GCWrap};
+use crate::gc::{
+    GCBox, GarbageCollector, dump::HeapTracer, roots::GCRootInfo, safety::GC, util::GCWrap,
+};
 
 /// Represents a pointer to a garbage-collectable object.
 ///
@@ -27,6 +29,31 @@ pub unsafe trait GCPtr: Sized + Copy {
     #[must_use]
     unsafe fn gc_copy(self, gc: &GarbageCollector) -> Self;
 
+    /// Checks the invariants of `self` and of every object that it references. Violations are
+    /// reported with [`HeapVerifier::fail`]. Types that don't contain pointers can use the default
+    /// implementation.
+    ///
+    /// # Safety
+    /// `self` must be reachable from a root, and the heap must not have been modified since the
+    /// last collection.
+    ///
+    /// [`HeapVerifier::fail`]: crate::gc::verify::HeapVerifier::fail
+    #[cfg(feature = "verify-heap")]
+    unsafe fn gc_verify(self, verifier: &mut crate::gc::verify::HeapVerifier) {
+        let _ = verifier;
+    }
+
+    /// Records the objects that `self` references in a heap dump with [`HeapTracer::object`].
+    /// Types that don't contain pointers can use the default implementation.
+    ///
+    /// # Safety
+    /// `self` must be reachable from a root, and a major collection must not be in progress.
+    ///
+    /// [`HeapTracer::object`]: crate::gc::dump::HeapTracer::object
+    unsafe fn gc_trace(self, tracer: &mut HeapTracer) {
+        let _ = tracer;
+    }
+
     /// Wraps `self` with a reference to the garbage collector. This wrapper may implement `Debug`,
     /// `PartialEq`, and similar traits.
     ///
@@ -51,10 +78,15 @@ pub unsafe trait GCPtr: Sized + Copy {
             new_box.ptr()
         }
 
+        unsafe fn trace_fn<Self_: GCPtr>(data: NonZeroUsize, tracer: &mut HeapTracer) {
+            unsafe { GCBox::<Self_>::from_ptr(data).gc_trace(tracer) };
+        }
+
         let data = unsafe { GCBox::<Self>::new_raw(gc, self) };
 
         GCRootInfo {
             copy_fn: copy_fn::<Self>,
+            trace_fn: trace_fn::<Self>,
             data_ptr: data.ptr(),
         }
     }
@@ -80,7 +112,7 @@ pub unsafe trait GCPtr: Sized + Copy {
 /// a struct or want to destructure an enum.
 ///
 /// If we have the following struct that implements `GCProject`:
-/// ```
+/// ```ignore
 /// #[derive(Clone, Copy, GCPtr, GCProject)]
 /// struct GCFoo {
 ///     a: GCVec<u32>,
@@ -88,7 +120,7 @@ pub unsafe trait GCPtr: Sized + Copy {
 /// }
 /// ```
 /// We could use projection to safely implement the following functions like so:
-/// ```
+/// ```ignore
 /// fn extract_b<'a>(val: GC<'a, Foo>) -> GC<'a, ast::Expression> {
 ///     val.project().b
 /// }
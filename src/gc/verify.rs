@@ -0,0 +1,137 @@
+//! A debug pass that checks the invariants of every reachable object after a collection. This is
+//! only compiled with the `verify-heap` feature.
+
+use std::{collections::HashSet, fmt::Display, num::NonZeroUsize};
+
+use crate::gc::{GCPtr, GarbageCollector, incremental::RootSlot, roots::GCRootEntry};
+
+#[cfg(test)]
+mod test;
+
+/// Walks the heap from the garbage-collection roots. Violations panic with the path of the
+/// offending object, eg `roots[2].inner::Evaluated.0[3]`.
+pub struct HeapVerifier<'gc> {
+    gc: &'gc GarbageCollector,
+    path: Vec<String>,
+    /// The objects that have already been checked. Objects can be referenced multiple times and
+    /// may contain cycles.
+    visited: HashSet<usize>,
+}
+
+impl<'gc> HeapVerifier<'gc> {
+    pub fn gc(&self) -> &'gc GarbageCollector {
+        self.gc
+    }
+
+    /// Runs `f` with `segment` appended to the current path
+    pub fn segment(&mut self, segment: impl Display, f: impl FnOnce(&mut Self)) {
+        self.path.push(segment.to_string());
+        f(self);
+        self.path.pop();
+    }
+
+    /// Panics with a message that includes the current path
+    #[track_caller]
+    pub fn fail(&self, message: impl Display) -> ! {
+        panic!(
+            "heap verification failed at `{}`: {message}",
+            self.path.concat()
+        )
+    }
+
+    /// Checks that the `size_blocks` blocks starting at `ptr` are occupied blocks of the nursery or
+    /// the old generation. The nursery is empty after a minor collection, but objects can be
+    /// allocated in it while a major collection is done in slices. Returns `false` if the object
+    /// has already been checked.
+    #[track_caller]
+    pub fn check_object(&mut self, ptr: usize, size_blocks: usize) -> bool {
+        if ptr == 0 {
+            self.fail("pointer to the reserved block 0");
+        }
+
+        if ptr + size_blocks > self.gc.space_of(ptr).len() {
+            let space = if self.gc.is_young(ptr) {
+                "the nursery"
+            } else {
+                "the old generation"
+            };
+
+            self.fail(format_args!(
+                "object at block {ptr:#x} ({size_blocks} blocks) is in unoccupied memory of {space}"
+            ));
+        }
+
+        self.visited.insert(ptr)
+    }
+
+    /// Checks that the most-significant-bit of the `usize` at `ptr` is clear. This bit marks a
+    /// forward pointer in objects that reserve it.
+    #[track_caller]
+    pub fn check_not_forwarded(&self, ptr: usize) {
+        let word = unsafe { self.gc.block_ptr(ptr).cast::<usize>().read() };
+
+        if word & 1usize.rotate_right(1) != 0 {
+            self.fail(format_args!("reachable forward pointer ({word:#x})"));
+        }
+    }
+}
+
+impl GarbageCollector {
+    /// Checks every object that is reachable from a root or a handle, and every interned string.
+    ///
+    /// # Panics
+    /// Panics if an invariant of the heap is violated
+    ///
+    /// # Safety
+    /// - This must be called directly after a collection, before anything is allocated
+    pub unsafe fn verify_heap(&self) {
+        unsafe fn verify_entry(
+            verifier: &mut HeapVerifier,
+            entry: Option<GCRootEntry>,
+            slot: RootSlot,
+        ) {
+            // Pending entries point into the from-space of an unfinished major collection, and so
+            // do the objects that the entry that is being scanned references
+            let skip = |entry: &GCRootEntry| entry.pending || verifier.gc.is_scanning(slot);
+
+            if let Some(entry) = entry.filter(|entry| !skip(entry)) {
+                unsafe { (entry.verify_fn)(entry.data_ptr, verifier) };
+            }
+        }
+
+        let mut verifier = HeapVerifier {
+            gc: self,
+            path: Vec::new(),
+            visited: HashSet::new(),
+        };
+
+        for i in 0..self.roots.len() {
+            verifier.segment(format_args!("roots[{i}]"), |verifier| unsafe {
+                verify_entry(verifier, self.roots.get_unchecked(i), RootSlot::Root(i))
+            });
+        }
+
+        for i in 0..self.handles.len() {
+            verifier.segment(format_args!("handles[{i}]"), |verifier| unsafe {
+                verify_entry(verifier, self.handles.get_unchecked(i), RootSlot::Handle(i))
+            });
+        }
+
+        let mut i = 0;
+
+        self.for_each_interned(|string| {
+            verifier.segment(format_args!("interned[{i}]"), |verifier| unsafe {
+                string.gc_verify(verifier)
+            });
+
+            i += 1;
+        });
+    }
+}
+
+/// The `verify_fn` of a root entry that stores a `T`
+pub(super) unsafe fn verify_root<T: GCPtr>(data_ptr: NonZeroUsize, verifier: &mut HeapVerifier) {
+    let value = unsafe { T::from_gc_root_entry(verifier.gc(), data_ptr) };
+
+    unsafe { value.gc_verify(verifier) };
+}
@@ -0,0 +1,94 @@
+use crate::{
+    eval::{MValue, Scope, Set},
+    gc::{
+        GCPtr, GCString, GCVec, GarbageCollector,
+        safety::{GC, Projected, let_gc_and_context, root},
+    },
+};
+
+#[test]
+fn shared_strings() {
+    let_gc_and_context!(gc, ctx);
+
+    let string: GC<MValue> = Projected::<MValue>::String(GCString::new(
+        ctx,
+        "a string that is too long to be stored inline",
+    ))
+    .into();
+
+    // The string is copied through both elements, so the second copy has to follow its forward
+    let list = root!(
+        ctx,
+        GCVec::from_iter_and_len(ctx, [string, string].into_iter(), 2)
+    );
+
+    ctx.force_minor_collect();
+    ctx.force_collect();
+
+    for element in list.get(ctx).iter() {
+        let Projected::<MValue>::String(string) = element.project() else {
+            panic!()
+        };
+
+        assert_eq!(
+            string.read(),
+            "a string that is too long to be stored inline"
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "heap verification failed at `roots[0][0]`: reachable forward pointer")]
+fn reachable_forward() {
+    let_gc_and_context!(gc, ctx);
+
+    let inner: GC<GCVec<MValue>> = GCVec::new_empty(ctx);
+    let outer = root!(
+        ctx,
+        GCVec::from_iter_and_len(ctx, std::iter::once(inner), 1)
+    );
+
+    ctx.force_collect();
+
+    let inner = outer.get(ctx).get(0).unwrap();
+
+    // Corrupt the inner vector by making it look like it has been forwarded
+    unsafe {
+        gc.block_ptr(inner.raw().ptr())
+            .cast::<usize>()
+            .write(1 | 1usize.rotate_right(1))
+    };
+
+    unsafe { gc.verify_heap() };
+}
+
+#[test]
+#[should_panic(
+    expected = "heap verification failed at `roots[0].parent`: reachable forward pointer"
+)]
+fn forward_behind_option() {
+    let_gc_and_context!(gc, ctx);
+
+    let parent = Scope::new_global(ctx);
+    let scope = root!(ctx, Scope::new_child(ctx, parent, Set::new_empty(ctx)));
+
+    ctx.force_collect();
+
+    let parent = scope.get(ctx).raw().parent.unwrap();
+
+    // Corrupt the parent by making it look like it has been forwarded. Types that don't reserve
+    // the most-significant bit of their first word store the forward pointer after the value.
+    let forward_block = if Scope::MSB_RESERVED {
+        parent.ptr().get()
+    } else {
+        parent.ptr().get() + size_of::<Scope>().div_ceil(GarbageCollector::BLOCK_SIZE)
+    };
+
+    unsafe {
+        gc.block_ptr(forward_block)
+            .cast::<usize>()
+            .write(1 | 1usize.rotate_right(1))
+    };
+
+    unsafe { gc.verify_heap() };
+}
@@ -1,4 +1,5 @@
 mod error;
+mod lint;
 mod types;
 
 #[cfg(test)]
@@ -7,7 +8,9 @@ mod test;
 use std::{borrow::Cow, str::CharIndices};
 
 use copyspan::Span;
+pub use lint::lint_identifiers;
 pub use types::*;
+use unicode_normalization::UnicodeNormalization;
 
 use crate::{
     error::{DResult, FullSpan, PartialSpanned},
@@ -17,8 +20,11 @@ use crate::{
 #[derive(Clone, Debug)]
 pub struct Lexer<'a> {
     src: &'a str,
-    remaining: MultiPeekable<CharIndices<'a>, 2>,
+    remaining: MultiPeekable<CharIndices<'a>, 3>,
     file_id: usize,
+    normalize: bool,
+    /// Whether the previous token was a `.`, so that a string literal is the name of an attribute
+    after_dot: bool,
 }
 
 impl<'a> Lexer<'a> {
@@ -27,9 +33,21 @@ impl<'a> Lexer<'a> {
             src,
             remaining: MultiPeekable::new(src.char_indices()),
             file_id,
+            normalize: false,
+            after_dot: false,
         }
     }
 
+    /// Enables or disables NFC normalization of identifiers (disabled by default).
+    ///
+    /// String literals that are used as attribute names (eg `a."é"` or `"é" = 1;`) are normalized
+    /// as well, so that names written as either refer to the same attribute. Other string
+    /// literals are values and are left as they are.
+    pub fn with_nfc_normalization(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
     pub fn lex(self) -> DResult<Vec<PartialSpanned<Token<'a>>>> {
         self.collect()
     }
@@ -41,6 +59,11 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Gets the byte index of the next character (or the length of the source if there is none)
+    fn current_idx(&self) -> usize {
+        self.remaining.peek(0).map_or(self.src.len(), |&(i, _)| i)
+    }
+
     fn full_span_at(&self, idx: usize) -> FullSpan {
         let rem = &self.src[idx..];
         let char = rem.chars().next().unwrap();
@@ -48,6 +71,15 @@ impl<'a> Lexer<'a> {
 
         self.full_span(Span::at(idx).with_len(len))
     }
+
+    /// Applies NFC normalization to `str` if it is enabled and `str` is not already normalized
+    fn normalize(&self, str: Cow<'a, str>) -> Cow<'a, str> {
+        if !self.normalize || unicode_normalization::is_nfc(&str) {
+            return str;
+        }
+
+        Cow::Owned(str.nfc().collect())
+    }
 }
 
 impl<'a> Iterator for Lexer<'a> {
@@ -90,7 +122,8 @@ impl<'a> Iterator for Lexer<'a> {
                 Err(err) => return Some(Err(err)),
             };
 
-            let end = self.remaining.peek(0).map_or(self.src.len(), |&(i, _)| i);
+            let end = self.current_idx();
+            self.after_dot = token == T!(.);
 
             return Some(Ok(PartialSpanned::new(token, Span::from(start..end))));
         }
@@ -101,7 +134,7 @@ impl<'a> Lexer<'a> {
     fn try_lex_identifier(&mut self) -> Option<DResult<Token<'a>>> {
         let (start, first_char) = *self.remaining.peek(0)?;
 
-        if first_char.is_ascii_alphabetic() || first_char == '_' {
+        if unicode_ident::is_xid_start(first_char) || first_char == '_' {
             self.remaining.next();
         } else {
             return None;
@@ -111,7 +144,7 @@ impl<'a> Lexer<'a> {
             let v = self.remaining.peek(0).copied();
 
             if let Some((_, c)) = v
-                && (c.is_ascii_alphanumeric() || c == '_')
+                && unicode_ident::is_xid_continue(c)
             {
                 self.remaining.next();
             } else {
@@ -119,7 +152,9 @@ impl<'a> Lexer<'a> {
             }
         };
 
-        Some(Ok(Token::Identifier(Cow::Borrowed(&self.src[start..end]))))
+        Some(Ok(Token::Identifier(
+            self.normalize(Cow::Borrowed(&self.src[start..end])),
+        )))
     }
 
     fn try_lex_symbol(&mut self) -> Option<DResult<Token<'a>>> {
@@ -169,35 +204,21 @@ impl<'a> Lexer<'a> {
         self.remaining.next();
         let string_start = start + '"'.len_utf8();
 
-        let mut escape = false;
         let mut has_escapes = false;
         let mut buf = String::new();
 
         let mut end = None;
 
-        for (i, c) in self.remaining.by_ref() {
-            if escape {
-                escape = false;
-
-                let char = match c {
-                    'r' => '\r',
-                    'n' => '\n',
-                    't' => '\t',
-                    '"' => '"',
-                    '\\' => '\\',
-                    '\n' => continue,
-                    _ => return Some(Err(error::invalid_escape(c, self.full_span_at(i)))),
-                };
-
-                buf.push(char);
-                continue;
-            }
-
+        while let Some((i, c)) = self.remaining.next() {
             match c {
                 '\\' => {
-                    escape = true;
                     has_escapes = true;
-                    continue;
+
+                    match self.lex_escape(i) {
+                        Ok(Some(char)) => buf.push(char),
+                        Ok(None) => {}
+                        Err(err) => return Some(Err(err)),
+                    }
                 }
                 '"' => {
                     end = Some(i);
@@ -218,13 +239,130 @@ impl<'a> Lexer<'a> {
             )));
         };
 
-        if has_escapes {
-            return Some(Ok(Token::StringLiteral(buf.into())));
+        let string = if has_escapes {
+            Cow::Owned(buf)
+        } else {
+            Cow::Borrowed(&self.src[string_start..end])
+        };
+
+        // The name of a member access, or the name of an attribute or variable that is being
+        // defined
+        let is_name = self.after_dot
+            || self.src[self.current_idx()..]
+                .trim_start_matches(|c: char| c.is_ascii_whitespace())
+                .starts_with('=');
+
+        let string = if is_name {
+            self.normalize(string)
+        } else {
+            string
+        };
+
+        Some(Ok(Token::StringLiteral(string)))
+    }
+
+    /// Lexes the remainder of an escape sequence in a string literal. `backslash` is the index of
+    /// the backslash that started the escape sequence.
+    ///
+    /// Returns `None` if the escape sequence does not produce a character (ie an escaped newline).
+    /// This also returns `None` if the end of the file was reached; in that case, the caller will
+    /// report the missing end quote.
+    fn lex_escape(&mut self, backslash: usize) -> DResult<Option<char>> {
+        let Some((i, c)) = self.remaining.next() else {
+            return Ok(None);
+        };
+
+        let char = match c {
+            'r' => '\r',
+            'n' => '\n',
+            't' => '\t',
+            '0' => '\0',
+            '"' => '"',
+            '\\' => '\\',
+            '$' => '$',
+            '\n' => return Ok(None),
+            'x' => self.lex_hex_escape(backslash)?,
+            'u' => self.lex_unicode_escape(backslash)?,
+            _ => return Err(error::invalid_escape(c, self.full_span_at(i))),
+        };
+
+        Ok(Some(char))
+    }
+
+    /// Lexes the digits of a `\xXX` escape sequence. The `\x` should already be consumed.
+    fn lex_hex_escape(&mut self, backslash: usize) -> DResult<char> {
+        let mut value = 0;
+
+        for _ in 0..2 {
+            let Some(digit) = self.remaining.peek(0).and_then(|(_, c)| c.to_digit(16)) else {
+                return Err(error::invalid_hex_escape(
+                    self.full_span(backslash..self.current_idx()),
+                ));
+            };
+
+            self.remaining.next();
+            value = value * 16 + digit;
+        }
+
+        let span = backslash..self.current_idx();
+
+        if value > 0x7F {
+            return Err(error::hex_escape_out_of_range(
+                &self.src[span.clone()],
+                self.full_span(span),
+            ));
+        }
+
+        Ok(char::from(value as u8))
+    }
+
+    /// Lexes the `{XXXX}` part of a `\u{XXXX}` escape sequence. The `\u` should already be
+    /// consumed.
+    fn lex_unicode_escape(&mut self, backslash: usize) -> DResult<char> {
+        if !matches!(self.remaining.peek(0), Some((_, '{'))) {
+            return Err(error::invalid_unicode_escape(
+                self.full_span(backslash..self.current_idx()),
+            ));
+        }
+
+        self.remaining.next();
+
+        let mut value = 0u32;
+        let mut num_digits = 0;
+
+        loop {
+            match self.remaining.peek(0).copied() {
+                Some((_, '}')) if num_digits > 0 => {
+                    self.remaining.next();
+                    break;
+                }
+                Some((_, c)) if c.is_ascii_hexdigit() && num_digits < 6 => {
+                    self.remaining.next();
+
+                    value = value * 16 + c.to_digit(16).unwrap();
+                    num_digits += 1;
+                }
+                _ => {
+                    return Err(error::invalid_unicode_escape(
+                        self.full_span(backslash..self.current_idx()),
+                    ));
+                }
+            }
         }
 
-        Some(Ok(Token::StringLiteral(Cow::Borrowed(
-            &self.src[string_start..end],
-        ))))
+        let span = backslash..self.current_idx();
+
+        match char::from_u32(value) {
+            Some(char) => Ok(char),
+            None if (0xD800..=0xDFFF).contains(&value) => Err(error::surrogate_code_point(
+                &self.src[span.clone()],
+                self.full_span(span),
+            )),
+            None => Err(error::invalid_code_point(
+                &self.src[span.clone()],
+                self.full_span(span),
+            )),
+        }
     }
 
     fn try_lex_numeric_literal(&mut self) -> Option<DResult<Token<'a>>> {
@@ -234,20 +372,38 @@ impl<'a> Lexer<'a> {
             return None;
         }
 
+        if let Some(radix) = self.radix_prefix() {
+            return Some(self.lex_radix_literal(start, radix));
+        }
+
         let mut hit_decimal = false;
+        let mut hit_slash = false;
+        let mut hit_exponent = false;
 
         let end = loop {
             match self.remaining.peek_all() {
                 [(_, '0'..='9' | '_'), ..] => {}
-                [(_, '.' | '/'), (_, '0'..='9')] if !hit_decimal => {
+                [(_, c @ ('.' | '/')), (_, '0'..='9'), ..] if !hit_decimal && !hit_exponent => {
                     hit_decimal = true;
+                    hit_slash = *c == '/';
+                }
+                // Exponents are not allowed in fractions
+                [(_, 'e' | 'E'), (_, '0'..='9'), ..] if !hit_exponent && !hit_slash => {
+                    hit_exponent = true;
+                }
+                [(_, 'e' | 'E'), (_, '+' | '-'), (_, '0'..='9')] if !hit_exponent && !hit_slash => {
+                    hit_exponent = true;
+                    self.remaining.next();
                 }
-                [(idx, c @ ('a'..='z' | 'A'..='Z')), ..] => {
+                [(idx, c @ ('e' | 'E')), (_, '0'..='9' | '+' | '-'), ..] if hit_slash => {
                     return Some(Err(error::unexpected_character_in_numeric_literal(
                         *c,
                         self.full_span_at(*idx),
                     )));
                 }
+                [(_, 'a'..='z' | 'A'..='Z'), ..] => {
+                    break self.lex_unit_suffix();
+                }
                 tokens => {
                     let end = tokens.first().map_or(self.src.len(), |&(idx, _)| idx);
 
@@ -260,4 +416,68 @@ impl<'a> Lexer<'a> {
 
         Some(Ok(Token::Number(Cow::Borrowed(&self.src[start..end]))))
     }
+
+    /// Lexes the unit suffix of a numeric literal (eg the `ms` in `10ms`). Returns the end index of
+    /// the suffix.
+    ///
+    /// The suffix is validated by the parser, so this will also consume any trailing digits (eg
+    /// `10ms2`) in order to give a better error message.
+    fn lex_unit_suffix(&mut self) -> usize {
+        while self
+            .remaining
+            .peek(0)
+            .is_some_and(|(_, c)| c.is_ascii_alphanumeric() || *c == '_')
+        {
+            self.remaining.next();
+        }
+
+        self.current_idx()
+    }
+
+    /// Gets the radix of the `0x`, `0o`, or `0b` prefix at the current position (if any)
+    fn radix_prefix(&self) -> Option<u32> {
+        match self.remaining.peek_all() {
+            [(_, '0'), (_, 'x'), ..] => Some(16),
+            [(_, '0'), (_, 'o'), ..] => Some(8),
+            [(_, '0'), (_, 'b'), ..] => Some(2),
+            _ => None,
+        }
+    }
+
+    /// Lexes a hexadecimal, octal, or binary literal. The prefix should not yet be consumed.
+    fn lex_radix_literal(&mut self, start: usize, radix: u32) -> DResult<Token<'a>> {
+        self.remaining.next();
+        self.remaining.next();
+
+        let mut has_digits = false;
+
+        loop {
+            match self.remaining.peek(0).copied() {
+                Some((_, '_')) => {}
+                Some((_, c)) if c.is_digit(radix) => has_digits = true,
+                Some((idx, c)) if c.is_ascii_alphanumeric() => {
+                    return Err(error::unexpected_character_in_numeric_literal(
+                        c,
+                        self.full_span_at(idx),
+                    ));
+                }
+                _ => break,
+            }
+
+            self.remaining.next();
+        }
+
+        if !has_digits {
+            let literal = &self.src[start..self.current_idx()];
+
+            return Err(error::missing_digits_in_numeric_literal(
+                literal,
+                self.full_span(start..self.current_idx()),
+            ));
+        }
+
+        Ok(Token::Number(Cow::Borrowed(
+            &self.src[start..self.current_idx()],
+        )))
+    }
 }
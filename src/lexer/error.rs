@@ -1,4 +1,4 @@
-use crate::error::{Diagnostic, FullSpan, error};
+use crate::error::{Diagnostic, FullSpan, error, warning};
 
 pub fn unexpected_character(c: char, span: FullSpan) -> Diagnostic {
     error!("EL0001", format!("Unexpected character {c:?}"), [{"character here", span, primary}])
@@ -15,3 +15,44 @@ pub fn no_end_quote(span: FullSpan) -> Diagnostic {
 pub fn unexpected_character_in_numeric_literal(c: char, span: FullSpan) -> Diagnostic {
     error!("EL0004", format!("Unexpected character {c:?} in numeric literal"), [{"character here", span, primary}])
 }
+
+pub fn invalid_unicode_escape(span: FullSpan) -> Diagnostic {
+    error!("EL0005", "Invalid unicode escape; expected `\\u{XXXX}` with 1 to 6 hex digits", [{"here", span, primary}])
+}
+
+pub fn invalid_code_point(escape: &str, span: FullSpan) -> Diagnostic {
+    error!("EL0006", format!("Escape sequence `{escape}` is not a valid unicode code point"), [{"here", span, primary}])
+}
+
+pub fn surrogate_code_point(escape: &str, span: FullSpan) -> Diagnostic {
+    error!("EL0007", format!("Escape sequence `{escape}` is a surrogate code point"), [{"here", span, primary}])
+}
+
+pub fn invalid_hex_escape(span: FullSpan) -> Diagnostic {
+    error!("EL0008", "Invalid hex escape; expected `\\xXX` with exactly 2 hex digits", [{"here", span, primary}])
+}
+
+pub fn hex_escape_out_of_range(escape: &str, span: FullSpan) -> Diagnostic {
+    error!("EL0009", format!("Hex escape `{escape}` is out of range; hex escapes must be in the range `\\x00`-`\\x7F`"), [{"here", span, primary}])
+}
+
+pub fn missing_digits_in_numeric_literal(literal: &str, span: FullSpan) -> Diagnostic {
+    error!("EL0010", format!("Numeric literal `{literal}` has no digits"), [{"here", span, primary}])
+}
+
+pub fn mixed_script_identifier(ident: &str, span: FullSpan) -> Diagnostic {
+    warning!("WL0001", format!("Identifier `{ident}` contains characters from multiple scripts"), [{"identifier here", span, primary}])
+}
+
+pub fn confusable_identifiers(
+    ident: &str,
+    span: FullSpan,
+    other: &str,
+    other_span: FullSpan,
+) -> Diagnostic {
+    warning!(
+        "WL0002",
+        format!("Identifier `{ident}` is visually confusable with `{other}`"),
+        [{"identifier here", span, primary}, {format!("`{other}` used here"), other_span, secondary}]
+    )
+}
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use copyspan::Span;
+use unicode_security::{MixedScript, confusable_detection::skeleton};
+
+use crate::{
+    error::{Diagnostic, FullSpan, PartialSpanned},
+    lexer::{Token, error},
+};
+
+/// Checks the identifiers in `tokens` for characters that could visually mislead a reader.
+///
+/// This warns about identifiers that mix characters from multiple scripts (eg `pаypal` with a
+/// cyrillic `а`) and about distinct identifiers that look the same (eg `scope` and `ѕcope`). Pairs of
+/// ASCII-only identifiers are never reported as confusable (eg `m` and `rn`).
+pub fn lint_identifiers(tokens: &[PartialSpanned<Token>], file_id: usize) -> Vec<Diagnostic> {
+    let full_span = |span: Span| FullSpan { span, file_id };
+
+    let mut warnings = Vec::new();
+    let mut skeletons: HashMap<String, (&str, Span)> = HashMap::new();
+
+    for PartialSpanned(token, span) in tokens {
+        let Token::Identifier(ident) = token else {
+            continue;
+        };
+
+        let ident: &str = ident;
+        let span = *span;
+
+        if !ident.is_ascii() && !ident.is_single_script() {
+            warnings.push(error::mixed_script_identifier(ident, full_span(span)));
+        }
+
+        let ident_skeleton: String = skeleton(ident).collect();
+
+        match skeletons.get(&ident_skeleton) {
+            Some(&(other, other_span))
+                if other != ident && !(ident.is_ascii() && other.is_ascii()) =>
+            {
+                warnings.push(error::confusable_identifiers(
+                    ident,
+                    full_span(span),
+                    other,
+                    full_span(other_span),
+                ));
+            }
+            Some(_) => {}
+            None => {
+                skeletons.insert(ident_skeleton, (ident, span));
+            }
+        }
+    }
+
+    warnings
+}
@@ -57,3 +57,153 @@ lexer_test! {
         [{"here", FullSpan {span: Span::from(19..20), file_id: 0}, primary}]
     )
 }
+
+lexer_test! {
+    surrogate_code_point,
+    "s = \"\\u{D800}\"",
+    error!(
+        "EL0007",
+        "Escape sequence `\\u{D800}` is a surrogate code point",
+        [{"here", FullSpan {span: Span::from(5..13), file_id: 0}, primary}]
+    )
+}
+
+lexer_test! {
+    invalid_code_point,
+    "s = \"ab\\u{110000}\"",
+    error!(
+        "EL0006",
+        "Escape sequence `\\u{110000}` is not a valid unicode code point",
+        [{"here", FullSpan {span: Span::from(7..17), file_id: 0}, primary}]
+    )
+}
+
+lexer_test! {
+    invalid_unicode_escape1,
+    "s = \"\\u{12G}\"",
+    error!(
+        "EL0005",
+        "Invalid unicode escape; expected `\\u{XXXX}` with 1 to 6 hex digits",
+        [{"here", FullSpan {span: Span::from(5..10), file_id: 0}, primary}]
+    )
+}
+
+lexer_test! {
+    invalid_unicode_escape2,
+    "s = \"\\u1234\"",
+    error!(
+        "EL0005",
+        "Invalid unicode escape; expected `\\u{XXXX}` with 1 to 6 hex digits",
+        [{"here", FullSpan {span: Span::from(5..7), file_id: 0}, primary}]
+    )
+}
+
+lexer_test! {
+    invalid_unicode_escape3,
+    "s = \"\\u{}\"",
+    error!(
+        "EL0005",
+        "Invalid unicode escape; expected `\\u{XXXX}` with 1 to 6 hex digits",
+        [{"here", FullSpan {span: Span::from(5..8), file_id: 0}, primary}]
+    )
+}
+
+lexer_test! {
+    invalid_hex_escape,
+    "s = \"\\x4\"",
+    error!(
+        "EL0008",
+        "Invalid hex escape; expected `\\xXX` with exactly 2 hex digits",
+        [{"here", FullSpan {span: Span::from(5..8), file_id: 0}, primary}]
+    )
+}
+
+lexer_test! {
+    hex_escape_out_of_range,
+    "s = \"\\xFF\"",
+    error!(
+        "EL0009",
+        "Hex escape `\\xFF` is out of range; hex escapes must be in the range `\\x00`-`\\x7F`",
+        [{"here", FullSpan {span: Span::from(5..9), file_id: 0}, primary}]
+    )
+}
+
+lexer_test! {
+    invalid_hex_digit,
+    "0x1fg",
+    error!(
+        "EL0004",
+        "Unexpected character 'g' in numeric literal",
+        [{"character here", FullSpan {span: Span::from(4..5), file_id: 0}, primary}]
+    )
+}
+
+lexer_test! {
+    invalid_binary_digit,
+    "0b1021",
+    error!(
+        "EL0004",
+        "Unexpected character '2' in numeric literal",
+        [{"character here", FullSpan {span: Span::from(4..5), file_id: 0}, primary}]
+    )
+}
+
+lexer_test! {
+    hex_without_digits,
+    "a = 0x;",
+    error!(
+        "EL0010",
+        "Numeric literal `0x` has no digits",
+        [{"here", FullSpan {span: Span::from(4..6), file_id: 0}, primary}]
+    )
+}
+
+lexer_test! {
+    octal_without_digits,
+    "a = 0o;",
+    error!(
+        "EL0010",
+        "Numeric literal `0o` has no digits",
+        [{"here", FullSpan {span: Span::from(4..6), file_id: 0}, primary}]
+    )
+}
+
+lexer_test! {
+    binary_without_digits,
+    "a = 0b;",
+    error!(
+        "EL0010",
+        "Numeric literal `0b` has no digits",
+        [{"here", FullSpan {span: Span::from(4..6), file_id: 0}, primary}]
+    )
+}
+
+lexer_test! {
+    radix_prefix_with_only_underscores,
+    "0x__",
+    error!(
+        "EL0010",
+        "Numeric literal `0x__` has no digits",
+        [{"here", FullSpan {span: Span::from(0..4), file_id: 0}, primary}]
+    )
+}
+
+lexer_test! {
+    exponent_in_fraction,
+    "1/2e3",
+    error!(
+        "EL0004",
+        "Unexpected character 'e' in numeric literal",
+        [{"character here", FullSpan {span: Span::from(3..4), file_id: 0}, primary}]
+    )
+}
+
+lexer_test! {
+    signed_exponent_in_fraction,
+    "1/2E-3",
+    error!(
+        "EL0004",
+        "Unexpected character 'E' in numeric literal",
+        [{"character here", FullSpan {span: Span::from(3..4), file_id: 0}, primary}]
+    )
+}
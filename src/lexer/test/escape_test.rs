@@ -0,0 +1,21 @@
+use crate::lexer::{Lexer, Token};
+
+macro_rules! escape_test {
+    {$name:ident, $string:literal, $expected:literal $(,)?} => {
+        #[test]
+        fn $name() {
+            let tokens = Lexer::new($string, 0).lex().unwrap();
+
+            assert_eq!(tokens.len(), 1);
+            assert_eq!(tokens[0].0, Token::StringLiteral($expected.into()));
+        }
+    };
+}
+
+escape_test! {simple_escapes, r#""\r\n\t\"\\\0""#, "\r\n\t\"\\\0"}
+escape_test! {escaped_newline, "\"a\\\nb\"", "ab"}
+escape_test! {dollar_escape, r#""\${not_special}""#, "${not_special}"}
+escape_test! {hex_escape, r#""\x41\x7f\x00""#, "A\x7f\0"}
+escape_test! {unicode_escape1, r#""\u{e9}t\u{E9}""#, "\u{e9}t\u{e9}"}
+escape_test! {unicode_escape2, r#""\u{1F602}|\u{10FFFF}""#, "\u{1f602}|\u{10ffff}"}
+escape_test! {unicode_escape3, r#""\u{00002C}""#, ","}
@@ -6,11 +6,11 @@ use itertools::Itertools;
 use proptest::prelude::*;
 use std::iter;
 
-const WHITESPACE: &'static str = "[ \t\r\n]+";
-const OPT_WHITESPACE: &'static str = "[ \t\r\n]*";
+const WHITESPACE: &str = "[ \t\r\n]+";
+const OPT_WHITESPACE: &str = "[ \t\r\n]*";
 
-const IDENT: &'static str = "[A-Za-z_][a-zA-Z0-9_]*";
-const NUMBER: &'static str = "[0-9]+(\\.[0-9]+)?";
+const IDENT: &str = "[A-Za-z_][a-zA-Z0-9_]*";
+const NUMBER: &str = "[0-9]+(\\.[0-9]+)?";
 
 fn arb_token() -> impl Strategy<Value = (Token<'static>, String)> + Clone {
     prop_oneof! {
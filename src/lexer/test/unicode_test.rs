@@ -0,0 +1,104 @@
+use copyspan::Span;
+
+use crate::{
+    error::{FullSpan, warning},
+    lexer::{Lexer, Token, lint_identifiers},
+};
+
+fn identifiers(src: &str, normalize: bool) -> Vec<String> {
+    Lexer::new(src, 0)
+        .with_nfc_normalization(normalize)
+        .lex()
+        .unwrap()
+        .into_iter()
+        .filter_map(|token| match token.0 {
+            Token::Identifier(ident) => Some(ident.into_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn unicode_identifiers() {
+    assert_eq!(
+        identifiers("café = 日本語; _x9 = ñ_ü.Ωmega;", false),
+        ["café", "日本語", "_x9", "ñ_ü", "Ωmega"]
+    );
+}
+
+#[test]
+fn non_xid_start() {
+    // U+0301 (combining acute accent) can continue an identifier but cannot start one
+    assert_eq!(identifiers("e\u{301}", false), ["e\u{301}"]);
+    assert!(Lexer::new("\u{301}e", 0).lex().is_err());
+}
+
+#[test]
+fn nfc_normalization() {
+    assert_eq!(
+        identifiers("e\u{301} \u{e9}", false),
+        ["e\u{301}", "\u{e9}"]
+    );
+    assert_eq!(identifiers("e\u{301} \u{e9}", true), ["\u{e9}", "\u{e9}"]);
+}
+
+#[test]
+fn nfc_normalization_of_strings() {
+    let strings = |src: &str| -> Vec<String> {
+        Lexer::new(src, 0)
+            .with_nfc_normalization(true)
+            .lex()
+            .unwrap()
+            .into_iter()
+            .filter_map(|token| match token.0 {
+                Token::StringLiteral(string) => Some(string.into_owned()),
+                _ => None,
+            })
+            .collect()
+    };
+
+    // Attribute names are normalized, values are not
+    assert_eq!(
+        strings("{ \"e\u{301}\" = \"e\u{301}\"; }.\"e\u{301}\""),
+        ["\u{e9}", "e\u{301}", "\u{e9}"]
+    );
+    assert_eq!(
+        strings("let \"e\u{301}\"\n= 1; in [\"e\u{301}\", a . \"e\u{301}\"]"),
+        ["\u{e9}", "e\u{301}", "\u{e9}"]
+    );
+}
+
+#[test]
+fn mixed_script_lint() {
+    // `а` is cyrillic
+    let src = "p\u{430}ypal = 1; κόσμε = 2;";
+    let tokens = Lexer::new(src, 0).lex().unwrap();
+
+    assert_eq!(
+        lint_identifiers(&tokens, 0),
+        [warning!(
+            "WL0001",
+            "Identifier `p\u{430}ypal` contains characters from multiple scripts",
+            [{"identifier here", FullSpan {span: Span::from(0..7), file_id: 0}, primary}]
+        )]
+    );
+}
+
+#[test]
+fn confusable_lint() {
+    // `ѕсоре` is entirely cyrillic
+    let src = "scope.m.rn.\u{455}\u{441}\u{43e}\u{440}\u{435}";
+    let tokens = Lexer::new(src, 0).lex().unwrap();
+
+    assert_eq!(
+        lint_identifiers(&tokens, 0),
+        [warning!(
+            "WL0002",
+            "Identifier `\u{455}\u{441}\u{43e}\u{440}\u{435}` is visually confusable with `scope`",
+            [
+                {"identifier here", FullSpan {span: Span::from(11..21), file_id: 0}, primary},
+                {"`scope` used here", FullSpan {span: Span::from(0..5), file_id: 0}, secondary},
+            ]
+        )]
+    );
+}
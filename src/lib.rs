@@ -0,0 +1,27 @@
+#![allow(clippy::enum_clike_unportable_variant)]
+#![allow(clippy::type_complexity)]
+
+extern crate self as mulch;
+
+pub mod error;
+pub mod eval;
+pub mod evaluator;
+pub mod gc;
+pub mod lexer;
+pub mod parser;
+
+mod util;
+
+pub use evaluator::Evaluator;
+pub use mulch_macros::mulch_builtin;
+
+// TODO:
+// - Replace `From` impl with `deproject` method for `GCProject` trait
+// - Add `phantom` annotations
+// - Use `zst` and `phantom` annotations for `GCPtr` optimizations
+// - Add more parser tests for:
+//     - Set and list lambda arguments
+//     - Default lambda arguments and argument bindings
+//     - Method calls
+//     - Member access
+// - Add logic for printing recursively-defined values.
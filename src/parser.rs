@@ -14,6 +14,7 @@ mod keyword;
 mod punct;
 mod separatedlist;
 mod traits;
+mod unit;
 pub mod util;
 
 #[cfg(test)]
@@ -49,6 +50,9 @@ pub use traits::ParseRight;
 
 pub use keyword::Keyword;
 
+pub use unit::DEFAULT_UNIT_SUFFIXES;
+pub use unit::UnitSuffix;
+
 /// The [`Keyword`] type. Takes a string literal as input.
 ///
 /// The string must be less than 16 characters long.
@@ -56,27 +60,38 @@ pub use mulch_macros::keyword;
 
 pub type TokenStream<'src> = [PartialSpanned<Token<'src>>];
 
-/// Contains parser state. Currently only contains a reference to the garbage collector, but that
-/// will change if or when custom parsers are added.
+/// Contains parser state. Currently only contains a reference to the garbage collector and the
+/// unit suffixes for number literals, but that will change if or when custom parsers are added.
 pub struct Parser<'a> {
     gc: &'a GarbageCollector,
+    unit_suffixes: &'a [UnitSuffix],
 }
 
 impl<'a> Parser<'a> {
     pub fn new_default(gc: &'a GarbageCollector) -> Self {
-        Self { gc }
+        Self {
+            gc,
+            unit_suffixes: DEFAULT_UNIT_SUFFIXES,
+        }
+    }
+
+    /// Sets the unit suffixes that are allowed on number literals (eg the `ms` in `10ms`). This
+    /// defaults to [`DEFAULT_UNIT_SUFFIXES`].
+    pub fn with_unit_suffixes(mut self, unit_suffixes: &'a [UnitSuffix]) -> Self {
+        self.unit_suffixes = unit_suffixes;
+        self
     }
 }
 
 single_token_parse_type! {
     error_function = parser::error::expected_identifier;
 
-    #[derive(Clone, Copy, GCPtr, GCDebug, GCEq)]
+    #[derive(Clone, Copy, GCPtr, GCDebug, GCEq, GCProject)]
     #[debug_direct]
     pub struct Ident(pub GCString);
 
     |parser| {
-        PartialSpanned(Token::Identifier(ident), _) => Self(GCString::new_raw(parser.gc, ident))
+        PartialSpanned(Token::Identifier(ident), _) => Self(GCString::intern_raw(parser.gc, ident))
     }
 }
 
@@ -88,7 +103,7 @@ single_token_parse_type! {
     pub struct IdentOrString(pub GCString);
 
     |parser| {
-        PartialSpanned(Token::Identifier(ident), _) => Self(GCString::new_raw(parser.gc, ident)),
-        PartialSpanned(Token::StringLiteral(lit), _) => Self(GCString::new_raw(parser.gc, lit)),
+        PartialSpanned(Token::Identifier(ident), _) => Self(GCString::intern_raw(parser.gc, ident)),
+        PartialSpanned(Token::StringLiteral(lit), _) => Self(GCString::intern_raw(parser.gc, lit)),
     }
 }
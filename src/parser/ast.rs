@@ -100,7 +100,7 @@ pub struct MemberAccess {
     pub rhs: IdentOrString,
 }
 
-#[derive(GCPtr, GCDebug, GCEq, Parse, Clone, Copy)]
+#[derive(GCPtr, GCDebug, GCEq, Parse, Clone, Copy, GCProject)]
 #[parse_direction(Right)]
 #[mulch_parse_error(|_| unimplemented!())]
 pub struct FunctionCall {
@@ -170,7 +170,7 @@ pub struct NamedValue {
     pub value: PartialSpanned<Expression>,
 }
 
-#[derive(GCPtr, GCDebug, GCEq, ParseRight, Clone, Copy)]
+#[derive(GCPtr, GCDebug, GCEq, ParseRight, Clone, Copy, GCProject)]
 #[mulch_parse_error(|_| unimplemented!())]
 #[parse_hook(function_call_args_set_hook)]
 #[debug_direct_with_name]
@@ -103,3 +103,16 @@ pub fn multiple_decimals_or_slashes_in_number(span: Span) -> ParseDiagnostic {
 pub fn denominator_of_zero(span: Span) -> ParseDiagnostic {
     parse_error!("EP0019", format!("Fraction literals cannot have a denominator of zero"), [{"here", span, primary}])
 }
+
+pub fn exponent_too_large(span: Span, max: usize) -> ParseDiagnostic {
+    parse_error!("EP0020", format!("Exponent in number literal is too large; the maximum magnitude is {max}"), [{"here", span, primary}])
+}
+
+pub fn unknown_unit_suffix(suffix: &str, closest: Option<&str>, span: Span) -> ParseDiagnostic {
+    let hint = match closest {
+        Some(closest) => format!("did you mean `{closest}`?"),
+        None => "here".to_owned(),
+    };
+
+    parse_error!("EP0021", format!("Unknown unit suffix `{suffix}` in number literal"), [{hint, span, primary}])
+}
@@ -5,7 +5,7 @@ use indoc::indoc;
 
 mod util;
 
-#[cfg(any(not(miri), rust_analyzer))]
+#[cfg(any(not(all(miri, feature = "gmp")), rust_analyzer))] // GMP does not work under MIRI
 mod numeric;
 
 parse_test! {nested_set, "{ x = a; b={x=cat; y=dog}; hi=foo;}",
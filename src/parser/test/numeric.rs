@@ -1,4 +1,11 @@
-use crate::parser::test::util::parse_test;
+use copyspan::Span;
+
+use crate::{
+    error::parse::{ParseDiagnostic, parse_error},
+    gc::GarbageCollector,
+    lexer::Lexer,
+    parser::{Parse, Parser, ast::Expression, test::util::parse_test},
+};
 
 parse_test! {decimal_red1, "3.125", NumericLiteral(25/8)}
 parse_test! {decimal_red2, "3.4", NumericLiteral(17/5)}
@@ -72,3 +79,80 @@ parse_test! {let_in1, "let pi = 3.14159 in e^(i * pi)",
         ),
     }
 }
+
+parse_test! {hex1, "0xFF", NumericLiteral(255)}
+parse_test! {hex2, "0xdead_BEEF", NumericLiteral(3735928559)}
+parse_test! {hex3, "0x1_0000_0000_0000_0000_0000_0000_0000_0000", NumericLiteral(340282366920938463463374607431768211456)}
+parse_test! {octal1, "0o755", NumericLiteral(493)}
+parse_test! {octal2, "0o7777777777777777777777777", NumericLiteral(37778931862957161709567)}
+parse_test! {binary1, "0b1010", NumericLiteral(10)}
+parse_test! {binary2, "0b0000_0000", NumericLiteral(0)}
+
+parse_test! {scientific1, "1.5e-3", NumericLiteral(3/2000)}
+parse_test! {scientific2, "1.5E3", NumericLiteral(1500)}
+parse_test! {scientific3, "25e+2", NumericLiteral(2500)}
+parse_test! {scientific4, "0.0e99", NumericLiteral(0)}
+parse_test! {scientific5, "12.5e1", NumericLiteral(125)}
+parse_test! {scientific6, "6.02214076e23", NumericLiteral(602214076000000000000000)}
+parse_test! {scientific7, "1e-40", NumericLiteral(1/10000000000000000000000000000000000000000)}
+parse_test! {scientific8, "3e0_1", NumericLiteral(30)}
+
+parse_test! {unit_suffix1, "10ms", NumericLiteral(1/100)}
+parse_test! {unit_suffix2, "2h", NumericLiteral(7200)}
+parse_test! {unit_suffix3, "4KiB", NumericLiteral(4096)}
+parse_test! {unit_suffix4, "1.5GB", NumericLiteral(1500000000)}
+parse_test! {unit_suffix5, "512MiB", NumericLiteral(536870912)}
+parse_test! {unit_suffix6, "30s", NumericLiteral(30)}
+parse_test! {unit_suffix7, "250ns", NumericLiteral(1/4000000)}
+parse_test! {unit_suffix8, "1/3min", NumericLiteral(20)}
+parse_test! {unit_suffix9, "1.5e3ms", NumericLiteral(3/2)}
+parse_test! {unit_suffix10, "16EiB", NumericLiteral(18446744073709551616)}
+parse_test! {unit_suffix11, "0d", NumericLiteral(0)}
+
+fn parse_number_error(src: &str) -> ParseDiagnostic {
+    let gc = GarbageCollector::new();
+    let parser = Parser::new_default(&gc);
+
+    let tokens = Lexer::new(src, 0).lex().unwrap();
+
+    match Expression::parse(&parser, &tokens) {
+        Err(err) => err,
+        Ok(_) => panic!("Test failed: no error occured"),
+    }
+}
+
+#[test]
+fn unknown_unit_suffix() {
+    assert_eq!(
+        parse_number_error("64mb"),
+        parse_error!("EP0021", "Unknown unit suffix `mb` in number literal", [{"did you mean `MB`?", Span::from(2..4), primary}])
+    );
+
+    assert_eq!(
+        parse_number_error("10mins"),
+        parse_error!("EP0021", "Unknown unit suffix `mins` in number literal", [{"did you mean `min`?", Span::from(2..6), primary}])
+    );
+
+    assert_eq!(
+        parse_number_error("1e3e4"),
+        parse_error!("EP0021", "Unknown unit suffix `e4` in number literal", [{"here", Span::from(3..5), primary}])
+    );
+
+    assert_eq!(
+        parse_number_error("1eb"),
+        parse_error!("EP0021", "Unknown unit suffix `eb` in number literal", [{"did you mean `EB`?", Span::from(1..3), primary}])
+    );
+
+    assert_eq!(
+        parse_number_error("5xyz"),
+        parse_error!("EP0021", "Unknown unit suffix `xyz` in number literal", [{"here", Span::from(1..4), primary}])
+    );
+}
+
+#[test]
+fn exponent_too_large() {
+    assert_eq!(
+        parse_number_error("1e65536"),
+        parse_error!("EP0020", "Exponent in number literal is too large; the maximum magnitude is 65535", [{"here", Span::from(0..7), primary}])
+    );
+}
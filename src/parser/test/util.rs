@@ -8,17 +8,21 @@ macro_rules! parse_test {
                 $src.into(),
             );
 
-            let gc = $crate::gc::GarbageCollector::new();
+            let gc = $crate::gc::GarbageCollector::builder().stress(true).build();
             let parser = $crate::parser::Parser::new_default(&gc);
 
-            let tokens = $crate::dresult_unwrap($crate::lexer::Lexer::new($src, 0).lex(), &db);
+            let tokens = $crate::error::dresult_unwrap($crate::lexer::Lexer::new($src, 0).lex(), &db);
 
-            let expr  = $crate::pdresult_unwrap(
+            let expr  = $crate::error::pdresult_unwrap(
                 <$crate::parser::ast::Expression as $crate::parser::Parse>::parse(&parser, &tokens),
                 0,
                 &db,
             ).unwrap();
 
+            // The parser has no safe points, so collect once with only the parsed expression
+            // rooted to check that every node survives being moved
+            let (expr,) = unsafe { $crate::gc::safety::GCArgTuple::collect_rooted((expr,), &gc) };
+
             let ast = $crate::parser::test::util::ast!(&gc, $($expected_ast)+);
 
             let expr = unsafe { $crate::gc::util::GCWrap::new(&expr, &gc) };
@@ -0,0 +1,144 @@
+use copyspan::Span;
+
+use crate::{
+    error::{PartialSpanned, parse::PDResult},
+    gc::GCNumber,
+    parser::{self, Parser},
+};
+
+/// A unit suffix for numeric literals (eg the `ms` in `10ms`).
+///
+/// A literal with a unit suffix is converted to the unit's base unit by multiplying it by the
+/// fraction `numerator/denominator`.
+#[derive(Clone, Copy, Debug)]
+pub struct UnitSuffix {
+    pub suffix: &'static str,
+    pub numerator: u64,
+    pub denominator: u64,
+}
+
+impl UnitSuffix {
+    pub const fn new(suffix: &'static str, numerator: u64, denominator: u64) -> Self {
+        Self {
+            suffix,
+            numerator,
+            denominator,
+        }
+    }
+}
+
+/// The unit suffixes used by [`Parser::new_default`]. Durations are in seconds, and sizes are in
+/// bytes.
+pub const DEFAULT_UNIT_SUFFIXES: &[UnitSuffix] = &[
+    // Durations
+    UnitSuffix::new("ns", 1, 1_000_000_000),
+    UnitSuffix::new("us", 1, 1_000_000),
+    UnitSuffix::new("ms", 1, 1_000),
+    UnitSuffix::new("s", 1, 1),
+    UnitSuffix::new("min", 60, 1),
+    UnitSuffix::new("h", 60 * 60, 1),
+    UnitSuffix::new("d", 24 * 60 * 60, 1),
+    UnitSuffix::new("w", 7 * 24 * 60 * 60, 1),
+    // Sizes
+    UnitSuffix::new("B", 1, 1),
+    UnitSuffix::new("KB", 1_000, 1),
+    UnitSuffix::new("MB", 1_000_000, 1),
+    UnitSuffix::new("GB", 1_000_000_000, 1),
+    UnitSuffix::new("TB", 1_000_000_000_000, 1),
+    UnitSuffix::new("PB", 1_000_000_000_000_000, 1),
+    UnitSuffix::new("EB", 1_000_000_000_000_000_000, 1),
+    UnitSuffix::new("KiB", 1 << 10, 1),
+    UnitSuffix::new("MiB", 1 << 20, 1),
+    UnitSuffix::new("GiB", 1 << 30, 1),
+    UnitSuffix::new("TiB", 1 << 40, 1),
+    UnitSuffix::new("PiB", 1 << 50, 1),
+    UnitSuffix::new("EiB", 1 << 60, 1),
+];
+
+impl Parser<'_> {
+    /// Parses a number literal with an optional unit suffix
+    pub(crate) fn parse_number_literal(&self, literal: PartialSpanned<&str>) -> PDResult<GCNumber> {
+        let (number, suffix) = split_unit_suffix(literal.0);
+
+        let multiplier = if suffix.is_empty() {
+            None
+        } else {
+            let Some(unit) = self.unit_suffixes.iter().find(|u| u.suffix == suffix) else {
+                let suffix_span = Span::from(literal.1.start + number.len()..literal.1.end);
+
+                return Err(parser::error::unknown_unit_suffix(
+                    suffix,
+                    closest_unit_suffix(self.unit_suffixes, suffix),
+                    suffix_span,
+                ));
+            };
+
+            Some((unit.numerator, unit.denominator))
+        };
+
+        GCNumber::parse_from_literal(self.gc, PartialSpanned(number, literal.1), multiplier)
+    }
+}
+
+/// Splits a number literal into the number and the unit suffix (which may be empty).
+///
+/// `1.5e3ms` -> (`1.5e3`, `ms`)
+///
+/// Hexadecimal, octal, and binary literals cannot have suffixes.
+fn split_unit_suffix(literal: &str) -> (&str, &str) {
+    if matches!(literal.get(..2), Some("0x" | "0o" | "0b")) {
+        return (literal, "");
+    }
+
+    let mut hit_slash = false;
+    let mut hit_exponent = false;
+
+    for (i, c) in literal.char_indices() {
+        match c {
+            '/' => hit_slash = true,
+            'e' | 'E' if !hit_exponent && !hit_slash => {
+                let exponent = &literal[i + 1..];
+                let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+
+                if !exponent.starts_with(|c: char| c.is_ascii_digit()) {
+                    return literal.split_at(i);
+                }
+
+                hit_exponent = true;
+            }
+            _ if c.is_ascii_alphabetic() => return literal.split_at(i),
+            _ => {}
+        }
+    }
+
+    (literal, "")
+}
+
+/// Finds the known suffix that is most similar to `suffix`. Differences in case are considered
+/// less significant than other differences (ie `mb` is closer to `MB` than to `ms`).
+///
+/// Suffixes that look like an exponent (eg the `e4` in `1e3e4`) get no suggestion, because they
+/// are almost certainly not a misspelled unit. Neither do suffixes that need more than one edit
+/// per three characters to turn into a known suffix (eg `xyz`).
+fn closest_unit_suffix(suffixes: &[UnitSuffix], suffix: &str) -> Option<&'static str> {
+    if let [b'e' | b'E', b'0'..=b'9', ..] = suffix.as_bytes() {
+        return None;
+    }
+
+    let lowercase = suffix.to_ascii_lowercase();
+    let max_distance = suffix.chars().count().div_ceil(3);
+
+    suffixes
+        .iter()
+        .map(|unit| {
+            let distance = (
+                strsim::levenshtein(&unit.suffix.to_ascii_lowercase(), &lowercase),
+                strsim::levenshtein(unit.suffix, suffix),
+            );
+
+            (distance, unit.suffix)
+        })
+        .min_by_key(|&(distance, _)| distance)
+        .filter(|&((distance, _), _)| distance <= max_distance)
+        .map(|(_, suffix)| suffix)
+}
@@ -71,6 +71,15 @@ unsafe impl<T: GCPtr, P> GCPtr for NotPrecededBy<T, P> {
             _phantomdata: PhantomData,
         }
     }
+
+    unsafe fn gc_trace(self, tracer: &mut crate::gc::dump::HeapTracer) {
+        unsafe { self.value.gc_trace(tracer) };
+    }
+
+    #[cfg(feature = "verify-heap")]
+    unsafe fn gc_verify(self, verifier: &mut crate::gc::verify::HeapVerifier) {
+        unsafe { self.value.gc_verify(verifier) };
+    }
 }
 
 impl<T: GCDebug, P> GCDebug for NotPrecededBy<T, P> {